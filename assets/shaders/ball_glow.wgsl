@@ -0,0 +1,21 @@
+#import bevy_pbr::mesh_vertex_output MeshVertexOutput
+#import bevy_pbr::mesh_view_bindings view, globals
+
+struct BallGlowMaterial {
+    color: vec4<f32>,
+    pulse_speed: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: BallGlowMaterial;
+
+// A rim of light around the ball, strongest where the shell is seen edge-on.
+@fragment
+fn fragment(mesh: MeshVertexOutput) -> @location(0) vec4<f32> {
+    let to_camera = normalize(view.world_position.xyz - mesh.world_position.xyz);
+    let facing = abs(dot(normalize(mesh.world_normal), to_camera));
+    let rim = pow(1.0 - facing, 2.0);
+    let pulse = 0.75 + 0.25 * sin(globals.time * material.pulse_speed * 6.2831853);
+
+    return vec4<f32>(material.color.rgb, rim * pulse * material.color.a);
+}
@@ -0,0 +1,48 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader FullscreenVertexOutput
+
+@group(0) @binding(0)
+var screen_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var texture_sampler: sampler;
+#ifdef MULTISAMPLED
+@group(0) @binding(2)
+var depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(2)
+var depth_texture: texture_depth_2d;
+#endif
+
+struct DepthOfField {
+    focus_distance: f32,
+    strength: f32,
+    near: f32,
+};
+
+@group(0) @binding(3)
+var<uniform> settings: DepthOfField;
+
+// Largest blur in pixels, for things far out of focus at full strength.
+const MAX_RADIUS: f32 = 8.0;
+const TAPS: i32 = 12;
+const TAU: f32 = 6.2831853;
+
+// Averages the picture over two rings of taps that grow the further the pixel is from the focus.
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(screen_texture));
+    // The last argument is the mip level, or the sample when multisampled
+    let depth = textureLoad(depth_texture, vec2<i32>(in.uv * size), 0);
+    // Bevy uses an infinite reversed-z projection, where depth is near / distance
+    let distance = settings.near / max(depth, 0.000001);
+    let defocus = clamp(abs(distance - settings.focus_distance) / settings.focus_distance, 0.0, 1.0);
+    let radius = defocus * settings.strength * MAX_RADIUS / size;
+
+    var color = textureSample(screen_texture, texture_sampler, in.uv);
+    for (var i = 0; i < TAPS; i += 1) {
+        let angle = f32(i) * TAU / f32(TAPS);
+        let offset = vec2<f32>(cos(angle), sin(angle)) * radius;
+        color += textureSample(screen_texture, texture_sampler, in.uv + offset);
+        color += textureSample(screen_texture, texture_sampler, in.uv + offset * 0.5);
+    }
+    return color / f32(TAPS * 2 + 1);
+}
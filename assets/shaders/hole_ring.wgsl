@@ -0,0 +1,26 @@
+#import bevy_pbr::mesh_vertex_output MeshVertexOutput
+#import bevy_pbr::mesh_view_bindings globals
+
+struct HoleRingMaterial {
+    color: vec4<f32>,
+    pulse_speed: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: HoleRingMaterial;
+
+// A soft ring around the middle of the quad that grows and fades out over and over.
+@fragment
+fn fragment(mesh: MeshVertexOutput) -> @location(0) vec4<f32> {
+    let distance = length(mesh.uv - vec2<f32>(0.5, 0.5)) * 2.0;
+    let pulse = fract(globals.time * material.pulse_speed);
+
+    // A steady ring at the edge of the cup
+    let ring = smoothstep(0.5, 0.55, distance) * (1.0 - smoothstep(0.6, 0.7, distance));
+    // A wave moving outwards from the cup
+    let wave_radius = mix(0.55, 1.0, pulse);
+    let wave = (1.0 - smoothstep(0.0, 0.06, abs(distance - wave_radius))) * (1.0 - pulse);
+
+    let alpha = max(ring * 0.8, wave * 0.6) * material.color.a;
+    return vec4<f32>(material.color.rgb, alpha);
+}
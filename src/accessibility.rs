@@ -0,0 +1,315 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{locale::Locale, save};
+
+const SAVE_FILE: &str = "accessibility.ron";
+
+/// Sizes of the UI to pick from, relative to the usual size.
+const UI_SCALES: [f32; 4] = [1.0, 1.25, 1.5, 0.75];
+/// Text is made at least this light with high contrast, and gets a dark backdrop.
+const HIGH_CONTRAST_LIGHTNESS: f32 = 0.8;
+const BACKDROP_ALPHA: f32 = 0.85;
+
+const MARKINGS_WIDTH: usize = 64;
+const MARKINGS_HEIGHT: usize = 32;
+/// Pixels of a digit glyph, and how much each pixel is scaled up on the ball.
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const GLYPH_SCALE: usize = 3;
+/// Digits 0 to 9 as rows of 3 bits, from the top.
+const GLYPHS: [[u8; GLYPH_HEIGHT]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+/// Brightness of the markings. The ball color shows through where the texture is white.
+const MARK: u8 = 40;
+const PATTERN: u8 = 150;
+
+/// Settings that make the game easier to play for more people, saved between runs.
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<Accessibility>(SAVE_FILE))
+            .add_systems(
+                Update,
+                (save_accessibility, apply_ui_scale).run_if(resource_changed::<Accessibility>()),
+            )
+            // After the UI systems have picked their colors for this frame
+            .add_systems(PostUpdate, apply_high_contrast);
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Accessibility {
+    #[serde(default)]
+    pub palette: PlayerPalette,
+    /// Numbers and patterns on the balls, so that they can be told apart without colors.
+    #[serde(default)]
+    pub ball_markings: bool,
+    /// Size of all text and other UI, relative to the usual size.
+    #[serde(default = "default_ui_scale")]
+    pub ui_scale: f32,
+    /// Light text on dark backdrops everywhere.
+    #[serde(default)]
+    pub high_contrast: bool,
+    /// No camera shake, and no confetti or flying debris.
+    #[serde(default)]
+    pub reduced_motion: bool,
+    /// The aim snaps to straight directions and to the hole when the aim keys are let go close
+    /// to them.
+    #[serde(default)]
+    pub aim_snapping: bool,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self {
+            palette: PlayerPalette::default(),
+            ball_markings: false,
+            ui_scale: default_ui_scale(),
+            high_contrast: false,
+            reduced_motion: false,
+            aim_snapping: false,
+        }
+    }
+}
+
+impl Accessibility {
+    /// The next of the preset UI sizes after the current one.
+    pub fn next_ui_scale(&self) -> f32 {
+        let next = UI_SCALES
+            .iter()
+            .position(|&scale| scale == self.ui_scale)
+            .map_or(0, |i| (i + 1) % UI_SCALES.len());
+        UI_SCALES[next]
+    }
+}
+
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
+/// Where the colors of the balls come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PlayerPalette {
+    /// The colors the players picked, or random ones.
+    #[default]
+    Free,
+    /// The palette by Okabe and Ito, told apart with all common kinds of color blindness.
+    OkabeIto,
+    /// Paul Tol's bright palette, which also stays apart when printed in grayscale.
+    Tol,
+}
+
+impl PlayerPalette {
+    pub fn next(self) -> Self {
+        match self {
+            PlayerPalette::Free => PlayerPalette::OkabeIto,
+            PlayerPalette::OkabeIto => PlayerPalette::Tol,
+            PlayerPalette::Tol => PlayerPalette::Free,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            PlayerPalette::Free => "palette.free",
+            PlayerPalette::OkabeIto => "palette.okabe_ito",
+            PlayerPalette::Tol => "palette.tol",
+        })
+    }
+
+    /// The color for the given player or team, unless the players pick their own.
+    pub fn color(self, index: u32) -> Option<Color> {
+        let colors: &[Color] = match self {
+            PlayerPalette::Free => return None,
+            PlayerPalette::OkabeIto => &[
+                Color::rgb_u8(0xE6, 0x9F, 0x00),
+                Color::rgb_u8(0x56, 0xB4, 0xE9),
+                Color::rgb_u8(0x00, 0x9E, 0x73),
+                Color::rgb_u8(0xF0, 0xE4, 0x42),
+                Color::rgb_u8(0x00, 0x72, 0xB2),
+                Color::rgb_u8(0xD5, 0x5E, 0x00),
+                Color::rgb_u8(0xCC, 0x79, 0xA7),
+            ],
+            PlayerPalette::Tol => &[
+                Color::rgb_u8(0x44, 0x77, 0xAA),
+                Color::rgb_u8(0xEE, 0x66, 0x77),
+                Color::rgb_u8(0x22, 0x88, 0x33),
+                Color::rgb_u8(0xCC, 0xBB, 0x44),
+                Color::rgb_u8(0x66, 0xCC, 0xEE),
+                Color::rgb_u8(0xAA, 0x33, 0x77),
+                Color::rgb_u8(0xBB, 0xBB, 0xBB),
+            ],
+        };
+        Some(colors[index as usize % colors.len()])
+    }
+}
+
+fn save_accessibility(accessibility: Res<Accessibility>) {
+    // Only remember settings the player picked
+    if !accessibility.is_added() {
+        save::store(SAVE_FILE, &*accessibility);
+    }
+}
+
+fn apply_ui_scale(accessibility: Res<Accessibility>, mut ui_scale: ResMut<UiScale>) {
+    ui_scale.scale = accessibility.ui_scale as f64;
+}
+
+/// Colors a text had before high contrast changed them, to put back when it is turned off.
+#[derive(Component)]
+struct ContrastOriginal {
+    text: Vec<Color>,
+    background: Color,
+}
+
+/// Lightens all dark text and puts it on a dark backdrop while high contrast is on. Runs every
+/// frame, since the UI systems keep changing their colors.
+fn apply_high_contrast(
+    mut commands: Commands,
+    mut q_text: Query<(
+        Entity,
+        &mut Text,
+        &mut BackgroundColor,
+        Option<&mut ContrastOriginal>,
+    )>,
+    accessibility: Res<Accessibility>,
+) {
+    if !accessibility.high_contrast {
+        if accessibility.is_changed() {
+            for (entity, mut text, mut background, original) in q_text.iter_mut() {
+                let Some(original) = original else {
+                    continue;
+                };
+                for (section, &color) in text.sections.iter_mut().zip(&original.text) {
+                    section.style.color = color;
+                }
+                background.0 = original.background;
+                commands.entity(entity).remove::<ContrastOriginal>();
+            }
+        }
+        return;
+    }
+
+    for (entity, mut text, mut background, original) in q_text.iter_mut() {
+        match original {
+            Some(mut original) => raise_contrast(&mut text, &mut original.text),
+            None => {
+                let mut original = ContrastOriginal {
+                    text: Vec::new(),
+                    background: background.0,
+                };
+                raise_contrast(&mut text, &mut original.text);
+                commands.entity(entity).insert(original);
+            }
+        }
+
+        // Fades along with the text
+        let alpha = text
+            .sections
+            .iter()
+            .map(|section| section.style.color.a())
+            .fold(0.0, f32::max);
+        let backdrop = Color::BLACK.with_a(BACKDROP_ALPHA * alpha);
+        if background.0 != backdrop {
+            background.0 = backdrop;
+        }
+    }
+}
+
+/// Lightens the sections of a text that are too dark, remembering the colors they had.
+fn raise_contrast(text: &mut Mut<Text>, original: &mut Vec<Color>) {
+    while original.len() < text.sections.len() {
+        original.push(text.sections[original.len()].style.color);
+    }
+    for i in 0..text.sections.len() {
+        let color = text.sections[i].style.color;
+        let [hue, saturation, lightness, alpha] = color.as_hsla_f32();
+        if lightness < HIGH_CONTRAST_LIGHTNESS {
+            original[i] = color;
+            text.sections[i].style.color =
+                Color::hsla(hue, saturation, HIGH_CONTRAST_LIGHTNESS, alpha);
+        }
+    }
+}
+
+/// A texture for the ball of a player with their number on two sides and a pattern of their own,
+/// to be tinted by the color of the ball.
+pub fn ball_markings(player_id: u32) -> Image {
+    let mut pixels = vec![255u8; MARKINGS_WIDTH * MARKINGS_HEIGHT];
+
+    for y in 0..MARKINGS_HEIGHT {
+        for x in 0..MARKINGS_WIDTH {
+            let marked = match player_id % 4 {
+                0 => false,
+                1 => y % 8 < 2,
+                2 => x % 8 < 3 && y % 8 < 3,
+                _ => (x / 8 + y / 8) % 2 == 0,
+            };
+            if marked {
+                pixels[x + y * MARKINGS_WIDTH] = PATTERN;
+            }
+        }
+    }
+
+    // The texture wraps around the ball, so the number is put on opposite sides
+    let digits: Vec<usize> = (player_id + 1)
+        .to_string()
+        .bytes()
+        .map(|b| (b - b'0') as usize)
+        .collect();
+    let number_width = (digits.len() * (GLYPH_WIDTH + 1) - 1) * GLYPH_SCALE;
+    let number_height = GLYPH_HEIGHT * GLYPH_SCALE;
+    for center_x in [MARKINGS_WIDTH / 4, MARKINGS_WIDTH * 3 / 4] {
+        let left = center_x - number_width / 2;
+        let top = (MARKINGS_HEIGHT - number_height) / 2;
+
+        // A clear box around the number so that the pattern doesn't run into it
+        for y in top - 2..top + number_height + 2 {
+            for x in left - 2..left + number_width + 2 {
+                pixels[x + y * MARKINGS_WIDTH] = 255;
+            }
+        }
+        for (i, &digit) in digits.iter().enumerate() {
+            for (row, bits) in GLYPHS[digit].iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+                    let glyph_x = left + (i * (GLYPH_WIDTH + 1) + column) * GLYPH_SCALE;
+                    let glyph_y = top + row * GLYPH_SCALE;
+                    for y in glyph_y..glyph_y + GLYPH_SCALE {
+                        for x in glyph_x..glyph_x + GLYPH_SCALE {
+                            pixels[x + y * MARKINGS_WIDTH] = MARK;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let data = pixels.iter().flat_map(|&v| [v, v, v, 255]).collect();
+    Image::new(
+        Extent3d {
+            width: MARKINGS_WIDTH as u32,
+            height: MARKINGS_HEIGHT as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
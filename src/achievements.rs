@@ -0,0 +1,142 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    level::CurrentLevel,
+    locale::Locale,
+    save,
+    scoring::{self, BallHoled, GameState},
+    ui,
+};
+
+const SAVE_FILE: &str = "achievements.ron";
+
+/// Unlocks achievements when balls are holed and remembers them between games.
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<UnlockedAchievements>(SAVE_FILE))
+            .add_event::<AchievementUnlocked>()
+            .add_systems(
+                Update,
+                (check_hole_achievements, unlock_achievements)
+                    .chain()
+                    .after(scoring::check_ball_in_hole),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Achievement {
+    HoleInOne,
+    UnderPar,
+    /// Holed with a shot that bounced off at least three walls.
+    BankShot,
+    /// Holed with a shot that included a jump.
+    JumpIn,
+    /// Holed without putting spin on any shot.
+    NoSpin,
+}
+
+impl Achievement {
+    pub fn title(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            Achievement::HoleInOne => "achievement.hole_in_one",
+            Achievement::UnderPar => "achievement.under_par",
+            Achievement::BankShot => "achievement.bank_shot",
+            Achievement::JumpIn => "achievement.jump_in",
+            Achievement::NoSpin => "achievement.no_spin",
+        })
+    }
+}
+
+/// Achievements that have been unlocked by anybody playing on this machine.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct UnlockedAchievements(HashSet<Achievement>);
+
+/// Sent when a player earns an achievement, whether or not it was already unlocked.
+#[derive(Event)]
+pub struct AchievementUnlocked {
+    pub achievement: Achievement,
+    pub player_id: u32,
+}
+
+fn check_hole_achievements(
+    mut holed: EventReader<BallHoled>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+    game_state: Res<GameState>,
+    current_level: Res<CurrentLevel>,
+) {
+    for event in holed.iter() {
+        let shots = &game_state.players[event.player_id as usize].shots;
+        let last_shot = shots.last();
+
+        let earned = [
+            (Achievement::HoleInOne, event.strokes == 1),
+            (
+                Achievement::UnderPar,
+                event.score < current_level.0.par_for(&event.hole),
+            ),
+            (
+                Achievement::BankShot,
+                last_shot.map_or(false, |shot| shot.wall_bounces >= 3),
+            ),
+            (
+                Achievement::JumpIn,
+                last_shot.map_or(false, |shot| shot.jumped),
+            ),
+            (
+                Achievement::NoSpin,
+                !shots.is_empty() && shots.iter().all(|shot| shot.spin == 0.0),
+            ),
+        ];
+
+        for (achievement, condition) in earned {
+            if condition {
+                unlocked.send(AchievementUnlocked {
+                    achievement,
+                    player_id: event.player_id,
+                });
+            }
+        }
+    }
+}
+
+fn unlock_achievements(
+    mut commands: Commands,
+    mut events: EventReader<AchievementUnlocked>,
+    mut achievements: ResMut<UnlockedAchievements>,
+    locale: Res<Locale>,
+) {
+    let mut row = 1;
+    let mut changed = false;
+
+    for event in events.iter() {
+        if !achievements.0.insert(event.achievement) {
+            continue;
+        }
+        changed = true;
+        info!("Achievement unlocked: {:?}", event.achievement);
+
+        ui::spawn_toast(
+            &mut commands,
+            locale.format(
+                "achievement.unlocked",
+                &[
+                    ("player", &(event.player_id + 1)),
+                    ("achievement", &event.achievement.title(&locale)),
+                ],
+            ),
+            Color::GOLD,
+            row,
+        );
+        row += 1;
+    }
+
+    if changed {
+        save::store(SAVE_FILE, &*achievements);
+    }
+}
@@ -0,0 +1,162 @@
+use bevy::{
+    pbr::NotShadowCaster,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{Ball, BallColor, ShootSettings},
+    collision,
+    level::LevelEntity,
+    scoring::GameState,
+    AppState,
+};
+
+/// Length of the arrow at full power.
+const MAX_LENGTH: f32 = 0.1;
+const SHAFT_WIDTH: f32 = 0.02;
+const HEAD_WIDTH: f32 = 0.05;
+/// Part of the arrow taken up by its head.
+const HEAD_PART: f32 = 0.3;
+/// Points along the shaft and along the head where the ground below is looked up.
+const SHAFT_SAMPLES: usize = 8;
+const HEAD_SAMPLES: usize = 4;
+/// How far above and below the ball the ground is looked for.
+const PROBE_HEIGHT: f32 = 0.3;
+/// Lifts the arrow off the ground so that it doesn't flicker through it.
+const GROUND_OFFSET: f32 = 0.002;
+
+/// An arrow on the ground in front of the aiming ball, as long as the shot is strong and
+/// following the floor over slopes and bumps.
+pub struct AimDecalPlugin;
+
+impl Plugin for AimDecalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), spawn_aim_decal)
+            .add_systems(Update, update_aim_decal.run_if(in_state(AppState::InGame)));
+    }
+}
+
+#[derive(Component)]
+struct AimDecal;
+
+fn spawn_aim_decal(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        LevelEntity,
+        AimDecal,
+        PbrBundle {
+            mesh: meshes.add(Mesh::new(PrimitiveTopology::TriangleList)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::CYAN.with_a(0.8),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                cull_mode: None,
+                ..default()
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NotShadowCaster,
+    ));
+}
+
+/// Rebuilds the arrow every frame, since the ground below it changes as it turns and grows.
+fn update_aim_decal(
+    mut q_decal: Query<(&Handle<Mesh>, &Handle<StandardMaterial>, &mut Visibility), With<AimDecal>>,
+    q_ball: Query<(Entity, &Transform, &ShootSettings, &Ball, &BallColor)>,
+    rapier_context: Res<RapierContext>,
+    game_state: Res<GameState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Ok((mesh, material, mut visibility)) = q_decal.get_single_mut() else {
+        return;
+    };
+    let aiming = q_ball
+        .iter()
+        .find(|(_, _, _, ball, _)| ball.player_id == game_state.current_player)
+        .filter(|(_, _, shoot_settings, _, _)| shoot_settings.power > 0.0);
+
+    let new_visibility = if aiming.is_some() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != new_visibility {
+        *visibility = new_visibility;
+    }
+    let Some((ball_entity, ball_transform, shoot_settings, _, color)) = aiming else {
+        return;
+    };
+
+    if let Some(material) = materials.get_mut(material) {
+        let tint = color.0.with_a(0.8);
+        if material.base_color != tint {
+            material.base_color = tint;
+        }
+    }
+
+    let length = shoot_settings.power * MAX_LENGTH;
+    let forward = Quat::from_rotation_y(shoot_settings.angle) * Vec3::X;
+    let side = Vec3::Y.cross(forward);
+    let start = ball_transform.translation;
+
+    // Only the floor and walls count as ground, not balls, debris or sensors. The ball is also
+    // in its player's group, so it has to be left out by itself.
+    let filter = QueryFilter::new()
+        .exclude_sensors()
+        .exclude_rigid_body(ball_entity)
+        .groups(CollisionGroups::new(
+            Group::ALL,
+            Group::ALL.difference(collision::BALL_GROUP | collision::DEBRIS_GROUP),
+        ));
+    let mut last_height = start.y - 0.025;
+    let mut ground = |point: Vec3| {
+        let origin = Vec3::new(point.x, start.y + PROBE_HEIGHT, point.z);
+        if let Some((_, toi)) =
+            rapier_context.cast_ray(origin, Vec3::NEG_Y, PROBE_HEIGHT * 2.0, true, filter)
+        {
+            last_height = origin.y - toi;
+        }
+        Vec3::new(point.x, last_height + GROUND_OFFSET, point.z)
+    };
+
+    // Rows of a point on each side of the arrow, from the ball to the tip. The shaft and the
+    // head both have a row where they meet, with a zero-length quad between them.
+    let head_start = length * (1.0 - HEAD_PART);
+    let shaft = (0..=SHAFT_SAMPLES).map(|i| {
+        let along = head_start * i as f32 / SHAFT_SAMPLES as f32;
+        (along, SHAFT_WIDTH / 2.0)
+    });
+    let head = (0..=HEAD_SAMPLES).map(|i| {
+        let part = i as f32 / HEAD_SAMPLES as f32;
+        let along = head_start + (length - head_start) * part;
+        (along, HEAD_WIDTH / 2.0 * (1.0 - part))
+    });
+    let mut positions = Vec::with_capacity((SHAFT_SAMPLES + HEAD_SAMPLES + 2) * 2);
+    for (along, half_width) in shaft.chain(head) {
+        let center = start + forward * along;
+        positions.push(ground(center - side * half_width).to_array());
+        positions.push(ground(center + side * half_width).to_array());
+    }
+
+    let rows = positions.len() as u32 / 2;
+    let mut indices = Vec::with_capacity((rows as usize - 1) * 6);
+    for i in 0..rows - 1 {
+        let (a, b, c, d) = (i * 2, i * 2 + 1, i * 2 + 2, i * 2 + 3);
+        indices.extend([a, c, b, b, c, d]);
+    }
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+
+    if let Some(mesh) = meshes.get_mut(mesh) {
+        *mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.set_indices(Some(Indices::U32(indices)));
+    }
+}
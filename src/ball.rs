@@ -0,0 +1,893 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility::{self, Accessibility},
+    collision::{self, BallCollisionRule},
+    customize::BallStyles,
+    level::{Checkpoint, CurrentLevel, GravityZone, Hole, LevelEntity},
+    locale::Locale,
+    online::OnlineStyles,
+    powerup::{self, ActivePowerUp},
+    scoring::{GameState, StrokeAdded, TeamMode, TEAMS},
+    surface::{self, SurfaceContacts},
+    tuning::ShotTuning,
+    AppState, GameMode, GameSeed, NeedsColorChange,
+};
+
+/// Spawns the balls and keeps track of their physical state: moving, resting, in a hole or
+/// past a checkpoint.
+pub struct BallPlugin;
+
+impl Plugin for BallPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BallStopped>()
+            .add_event::<ShootEvent>()
+            .add_event::<JumpEvent>()
+            .init_resource::<BallCollisionRule>()
+            .add_systems(OnEnter(AppState::InGame), spawn_balls)
+            .add_systems(
+                Update,
+                (
+                    apply_shot,
+                    apply_jumps.after(apply_shot),
+                    draw_spin_arrows,
+                    stop_ball_from_spinning_forever,
+                    apply_ball_collision_rule,
+                    (
+                        surface::track_ball_surfaces,
+                        surface::apply_surface_physics,
+                        apply_shape_rolling,
+                    )
+                        .chain(),
+                    (
+                        track_gravity_zones,
+                        apply_gravity,
+                        hold_balls_on_slopes,
+                        update_ball_state,
+                        track_balls_in_holes,
+                        track_checkpoints,
+                    )
+                        .chain(),
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Ball {
+    pub player_id: u32,
+    pub hits: u32,
+}
+
+/// Below this speed a ball is considered to be at rest.
+pub const BALL_REST_SPEED: f32 = 0.01;
+
+/// A ball rolling slower than this at a steady speed is considered to be creeping down a slope.
+const CREEP_SPEED: f32 = 0.05;
+/// How much the speed of a creeping ball may change from one frame to the next.
+const CREEP_SPEED_TOLERANCE: f32 = 0.002;
+/// Frames a ball has to creep before it is held in place.
+const CREEP_FRAMES: u32 = 30;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BallState {
+    /// At rest and owned by the current player.
+    Aiming,
+    /// Freshly spawned balls start out moving since they are dropped onto the course.
+    #[default]
+    Moving,
+    /// At rest while waiting for another player's turn.
+    Resting,
+    Holed,
+}
+
+impl BallState {
+    pub fn is_at_rest(self) -> bool {
+        matches!(self, BallState::Aiming | BallState::Resting)
+    }
+}
+
+/// Sent once when a ball goes from moving to resting.
+#[derive(Event)]
+pub struct BallStopped {
+    pub entity: Entity,
+    pub player_id: u32,
+}
+
+/// Sideways spin put on the ball when it is shot, with a strength between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BallSpin {
+    Left(f32),
+    Right(f32),
+}
+
+impl BallSpin {
+    /// Spin from a value between -1 (full left) and 1 (full right), or `None` when it is zero.
+    pub fn from_signed(value: f32) -> Option<Self> {
+        let value = value.clamp(-1.0, 1.0);
+        if value < 0.0 {
+            Some(BallSpin::Left(-value))
+        } else if value > 0.0 {
+            Some(BallSpin::Right(value))
+        } else {
+            None
+        }
+    }
+
+    /// The spin as a value between -1 (full left) and 1 (full right).
+    pub fn signed(self) -> f32 {
+        match self {
+            BallSpin::Left(strength) => -strength,
+            BallSpin::Right(strength) => strength,
+        }
+    }
+
+    pub fn strength(self) -> f32 {
+        self.signed().abs()
+    }
+}
+
+#[derive(Component, Debug, Clone, PartialEq, Default)]
+pub struct ShootSettings {
+    pub power: f32,
+    pub angle: f32,
+    pub spin: Option<BallSpin>,
+}
+
+/// Lets a ball jump once per shot while it is rolling. Holding the jump key charges the jump,
+/// and releasing it jumps higher the longer it was held.
+#[derive(Component, Debug, Default)]
+pub struct Jump {
+    /// From 0 to 1 while the jump key is held.
+    pub charge: f32,
+    /// Set after jumping and cleared by the next shot.
+    pub used: bool,
+}
+
+impl Jump {
+    /// Time it takes to charge a full jump.
+    pub const CHARGE_SECONDS: f32 = 0.8;
+
+    pub fn can_jump(&self, state: BallState) -> bool {
+        !self.used && state == BallState::Moving
+    }
+}
+
+/// Asks for a player's ball to be shot, no matter if the shot comes from the keyboard, a
+/// gamepad, a script or somewhere else.
+///
+/// Ignored unless the ball is currently aiming.
+#[derive(Event, Debug, Clone)]
+pub struct ShootEvent {
+    pub player_id: u32,
+    pub power: f32,
+    pub angle: f32,
+    pub spin: Option<BallSpin>,
+}
+
+impl ShootEvent {
+    pub fn new(player_id: u32, shoot: &ShootSettings) -> Self {
+        Self {
+            player_id,
+            power: shoot.power,
+            angle: shoot.angle,
+            spin: shoot.spin,
+        }
+    }
+}
+
+/// Asks for a rolling ball to jump, with a charge from 0 to 1.
+///
+/// Ignored unless the ball can jump.
+#[derive(Event, Debug, Clone)]
+pub struct JumpEvent {
+    pub player_id: u32,
+    pub charge: f32,
+}
+
+/// Holds a ball in place when it would otherwise keep creeping down a slope forever, so that
+/// its player can aim.
+#[derive(Component, Default)]
+pub struct SlopeHold {
+    last_speed: f32,
+    creeping_frames: u32,
+    /// The gravity scale to give back to the ball once it is released.
+    held_gravity: Option<f32>,
+}
+
+impl SlopeHold {
+    pub fn is_holding(&self) -> bool {
+        self.held_gravity.is_some()
+    }
+}
+
+/// Marks a ball that is currently inside a gravity zone, along with the zone entity.
+#[derive(Component)]
+pub struct InGravityZone(pub Entity);
+
+/// Marks a ball that is currently inside a hole sensor, along with the hole entity.
+#[derive(Component)]
+pub struct InHole(pub Entity);
+
+/// Where a ball is put back after going out of bounds, set by the last checkpoint it passed.
+///
+/// Balls without one are put back where they were last hit.
+#[derive(Component)]
+pub struct RespawnPoint(pub Vec3);
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_balls(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_state: Res<GameState>,
+    seed: Res<GameSeed>,
+    styles: Res<BallStyles>,
+    online_styles: Res<OnlineStyles>,
+    team_mode: Res<TeamMode>,
+    accessibility: Res<Accessibility>,
+    current_level: Res<CurrentLevel>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let mut rng = seed.rng(GameSeed::BALLS_STREAM);
+    for player_id in 0..game_state.num_players {
+        let shape = match rng.gen_range(0..=2) {
+            0 => BallShape::Sphere,
+            1 => BallShape::Cube,
+            _ => BallShape::Cone,
+        };
+        let offset_sideways = rng.gen_range(-0.4..0.4);
+        let offset_along = rng.gen_range(-0.4..0.0);
+        let hue = rng.gen_range(0.0..360.0);
+
+        // The random values are drawn either way, so the seed places the balls the same
+        let mut style = online_styles
+            .get(player_id)
+            .or_else(|| styles.get(player_id))
+            .unwrap_or(BallStyle {
+                shape,
+                hue,
+                ..default()
+            });
+        // Teammates get shades of their team's color
+        if let Some(team) = team_mode.team_of(player_id) {
+            let (_, team_hue) = TEAMS[team as usize];
+            style.hue = (team_hue + (player_id / 2) as f32 * 25.0) % 360.0;
+        }
+        // Teammates share a palette color, and can be told apart by their markings
+        let palette_index = team_mode.team_of(player_id).unwrap_or(player_id);
+        if let Some(Color::Hsla {
+            hue,
+            saturation,
+            lightness,
+            ..
+        }) = accessibility
+            .palette
+            .color(palette_index)
+            .map(|c| c.as_hsla())
+        {
+            style.hue = hue;
+            style.saturation = saturation;
+            style.lightness = lightness;
+        }
+
+        // Placed the same as before tee zones in the default zone
+        let zone = current_level.0.tee_zone(player_id);
+        let position = zone.point(offset_along / 0.4 + 1.0, offset_sideways / 0.8 + 0.5);
+        let ball = spawn_ball(&mut commands, &asset_server, player_id, position, style);
+        commands.entity(ball).insert(zone);
+        if accessibility.ball_markings {
+            let markings = images.add(accessibility::ball_markings(player_id));
+            commands.entity(ball).insert(BallMarkings(markings));
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BallShape {
+    Sphere,
+    Cube,
+    Cone,
+}
+
+impl BallShape {
+    pub fn next(self) -> BallShape {
+        match self {
+            BallShape::Sphere => BallShape::Cube,
+            BallShape::Cube => BallShape::Cone,
+            BallShape::Cone => BallShape::Sphere,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            BallShape::Sphere => "shape.sphere",
+            BallShape::Cube => "shape.cube",
+            BallShape::Cone => "shape.cone",
+        })
+    }
+
+    /// Short description of how the shape plays, shown when picking it.
+    pub fn hint(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            BallShape::Sphere => "shape.sphere.hint",
+            BallShape::Cube => "shape.cube.hint",
+            BallShape::Cone => "shape.cone.hint",
+        })
+    }
+
+    /// Whether spin put on a shot has any effect.
+    pub fn takes_spin(self) -> bool {
+        self != BallShape::Cube
+    }
+
+    pub fn scene_path(self) -> String {
+        let model_file = match self {
+            BallShape::Sphere => "sphere",
+            BallShape::Cube => "cube",
+            BallShape::Cone => "cone",
+        };
+        format!("models/{}.gltf#Scene0", model_file)
+    }
+}
+
+/// How the surface of a ball reflects light.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BallFinish {
+    #[default]
+    Glossy,
+    Metallic,
+    Matte,
+}
+
+impl BallFinish {
+    pub fn next(self) -> BallFinish {
+        match self {
+            BallFinish::Glossy => BallFinish::Metallic,
+            BallFinish::Metallic => BallFinish::Matte,
+            BallFinish::Matte => BallFinish::Glossy,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            BallFinish::Glossy => "finish.glossy",
+            BallFinish::Metallic => "finish.metallic",
+            BallFinish::Matte => "finish.matte",
+        })
+    }
+
+    pub fn apply(self, material: &mut StandardMaterial) {
+        let (metallic, roughness, reflectance) = match self {
+            BallFinish::Glossy => (0.0, 0.15, 0.7),
+            BallFinish::Metallic => (1.0, 0.3, 0.5),
+            BallFinish::Matte => (0.0, 1.0, 0.2),
+        };
+        material.metallic = metallic;
+        material.perceptual_roughness = roughness;
+        material.reflectance = reflectance;
+    }
+}
+
+/// The color a player picked for their ball, for showing it elsewhere.
+#[derive(Component)]
+pub struct BallColor(pub Color);
+
+/// A texture from `assets/skins/` that is drawn on a ball, tinted by its color.
+#[derive(Component)]
+pub struct BallSkin(pub Handle<Image>);
+
+/// A texture with the number and pattern of a player, drawn on their ball instead of its skin.
+#[derive(Component)]
+pub struct BallMarkings(pub Handle<Image>);
+
+/// The look of a ball: its shape, color, finish and skin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BallStyle {
+    pub shape: BallShape,
+    /// In degrees.
+    pub hue: f32,
+    pub saturation: f32,
+    pub lightness: f32,
+    pub finish: BallFinish,
+    /// File name of the skin texture in `assets/skins/`.
+    #[serde(default)]
+    pub skin: Option<String>,
+}
+
+impl Default for BallStyle {
+    fn default() -> Self {
+        Self {
+            shape: BallShape::Sphere,
+            hue: 0.0,
+            saturation: 1.0,
+            lightness: 0.5,
+            finish: BallFinish::default(),
+            skin: None,
+        }
+    }
+}
+
+impl BallStyle {
+    pub fn color(&self) -> Color {
+        Color::hsl(self.hue, self.saturation, self.lightness)
+    }
+
+    pub fn skin(&self, asset_server: &AssetServer) -> Option<BallSkin> {
+        let file = self.skin.as_ref()?;
+        Some(BallSkin(asset_server.load(format!("skins/{}", file))))
+    }
+}
+
+pub fn spawn_ball(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    player_id: u32,
+    position: Vec3,
+    style: BallStyle,
+) -> Entity {
+    let scene_handle = asset_server.load(style.shape.scene_path());
+
+    let rr = 0.01; // rounding radius
+
+    let collider = match style.shape {
+        BallShape::Sphere => Collider::ball(0.025),
+        BallShape::Cube => Collider::round_cuboid(0.025 - rr, 0.025 - rr, 0.025 - rr, rr),
+        BallShape::Cone => Collider::round_cone(0.025 - rr, 0.025 - rr, rr),
+    };
+
+    let model_oversize = 1.0;
+
+    let r = 0.025;
+    let density = 4.0;
+    let mass = r * r * r * 8.0 * density;
+
+    let principal_inertia = Vec3::new(1.0, 1.0, 1.0) * 3.0 / 10.0 * r * r * mass;
+
+    let ball = commands
+        .spawn((
+            RigidBody::Dynamic,
+            collider,
+            ExternalImpulse::default(),
+            ExternalForce::default(),
+            Restitution {
+                coefficient: 0.5,
+                combine_rule: CoefficientCombineRule::Max,
+            },
+            // The ball's friction follows the surface, so it must win over the tiles' friction
+            Friction {
+                coefficient: 1.0,
+                combine_rule: CoefficientCombineRule::Min,
+            },
+            ColliderMassProperties::MassProperties(MassProperties {
+                local_center_of_mass: Vec3::ZERO,
+                mass,
+                principal_inertia_local_frame: Quat::IDENTITY,
+                principal_inertia,
+            }),
+            ReadMassProperties::default(),
+            Damping {
+                linear_damping: 0.6,
+                angular_damping: 0.9,
+            },
+            Ccd::enabled(),
+            Sleeping::default(),
+            GravityScale(1.0),
+            CollisionGroups::new(collision::BALL_GROUP, Group::ALL),
+            // Ball-ball contacts only raise events if one of the balls asks for them
+            ActiveEvents::COLLISION_EVENTS,
+            surface::SurfaceContacts::default(),
+        ))
+        .insert(Velocity {
+            linvel: Vec3::new(0.0, 0.0, 0.0),
+            angvel: Vec3::new(0.0, 0.0, 0.0),
+        })
+        .insert(SceneBundle {
+            scene: scene_handle,
+            transform: Transform::from_translation(position).with_scale(Vec3::ONE / model_oversize),
+            ..default()
+        })
+        .insert(NeedsColorChange(style.color()))
+        .insert(BallColor(style.color()))
+        .insert(style.finish)
+        .insert(style.shape)
+        .insert(Ball { player_id, hits: 0 })
+        .insert(LevelEntity)
+        .insert(BallState::default())
+        .insert(ShootSettings::default())
+        .insert(Jump::default())
+        .insert(SlopeHold::default())
+        .id();
+
+    if let Some(skin) = style.skin(asset_server) {
+        commands.entity(ball).insert(skin);
+    }
+    ball
+}
+
+fn apply_ball_collision_rule(
+    mut q_ball: Query<(&Ball, &mut CollisionGroups, Option<&ActivePowerUp>)>,
+    rule: Res<BallCollisionRule>,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+) {
+    for (ball, mut groups, power_up) in q_ball.iter_mut() {
+        let active = *game_mode == GameMode::Party || ball.player_id == game_state.current_player;
+        let mut new_groups = rule.ball_groups(ball.player_id, active);
+        if powerup::is_ghost(power_up) {
+            new_groups.filters.remove(collision::WALL_GROUP);
+        }
+        if *groups != new_groups {
+            *groups = new_groups;
+        }
+    }
+}
+
+/// How quickly a cube loses its speed while tumbling along the floor, per second.
+const CUBE_BRAKING: f32 = 1.5;
+/// Radius of the circle a cone rolls in when lying flat on its side.
+const CONE_TURN_RADIUS: f32 = 1.2;
+/// A cone tilted further than this from upright, as the up component of its axis, rolls on its
+/// side.
+const CONE_SIDE_TILT: f32 = 0.5;
+
+/// Gives each shape its own way of rolling: cubes tumble to a stop quickly, cones on their side
+/// curve towards their tip, and spheres roll as usual.
+fn apply_shape_rolling(
+    mut q_ball: Query<
+        (
+            &BallShape,
+            &Transform,
+            &Velocity,
+            &SurfaceContacts,
+            &ReadMassProperties,
+            &mut ExternalImpulse,
+        ),
+        With<Ball>,
+    >,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (shape, transform, velocity, contacts, mass, mut impulse) in q_ball.iter_mut() {
+        let rolling = velocity.linvel * Vec3::new(1.0, 0.0, 1.0);
+        let speed = rolling.length();
+        if contacts.is_airborne() || speed < BALL_REST_SPEED {
+            continue;
+        }
+
+        match shape {
+            BallShape::Sphere => {}
+            BallShape::Cube => {
+                impulse.impulse -= rolling * (CUBE_BRAKING * dt).min(1.0) * mass.0.mass;
+            }
+            BallShape::Cone => {
+                let axis = transform.rotation * Vec3::Y;
+                if axis.y.abs() > CONE_SIDE_TILT {
+                    continue;
+                }
+                // The part of the axis across the direction of travel points to where the tip is
+                let forward = rolling / speed;
+                let across = (axis - forward * axis.dot(forward)) * Vec3::new(1.0, 0.0, 1.0);
+                let flatness = 1.0 - axis.y.abs() / CONE_SIDE_TILT;
+                let acceleration = speed * speed / CONE_TURN_RADIUS * flatness;
+                impulse.impulse += across.normalize_or_zero() * acceleration * dt * mass.0.mass;
+            }
+        }
+    }
+}
+
+fn stop_ball_from_spinning_forever(
+    mut q_ball: Query<(&mut ExternalImpulse, &Velocity, &ReadMassProperties), With<Ball>>,
+) {
+    for (mut f, vel, mass) in q_ball.iter_mut() {
+        if vel.linvel.length() < 5.0 * BALL_REST_SPEED {
+            f.impulse -= vel.linvel * mass.0.mass * 0.9;
+            f.torque_impulse = -vel.angvel * mass.0.principal_inertia * 0.9;
+        }
+    }
+}
+
+fn track_gravity_zones(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_zone: Query<(), With<GravityZone>>,
+    q_ball: Query<Option<&InGravityZone>, With<Ball>>,
+) {
+    for event in collision_events.iter() {
+        let (e1, e2, entered) = match *event {
+            CollisionEvent::Started(e1, e2, _) => (e1, e2, true),
+            CollisionEvent::Stopped(e1, e2, _) => (e1, e2, false),
+        };
+        let (ball_entity, zone_entity) = if q_zone.contains(e1) && q_ball.contains(e2) {
+            (e2, e1)
+        } else if q_zone.contains(e2) && q_ball.contains(e1) {
+            (e1, e2)
+        } else {
+            continue;
+        };
+
+        let Some(mut ball) = commands.get_entity(ball_entity) else {
+            continue;
+        };
+        if entered {
+            ball.insert(InGravityZone(zone_entity));
+        } else if q_ball
+            .get(ball_entity)
+            .is_ok_and(|zone| zone.is_some_and(|zone| zone.0 == zone_entity))
+        {
+            // Only when leaving the zone the ball is in, since zones may overlap
+            ball.remove::<InGravityZone>();
+        }
+    }
+}
+
+/// Gives every ball the gravity of the zone it is in, or else that of the level.
+fn apply_gravity(
+    mut q_ball: Query<(&mut GravityScale, &SlopeHold, Option<&InGravityZone>)>,
+    q_zone: Query<&GravityZone>,
+    current_level: Res<CurrentLevel>,
+) {
+    for (mut gravity, hold, in_zone) in q_ball.iter_mut() {
+        if hold.is_holding() {
+            continue;
+        }
+        let scale = in_zone
+            .and_then(|in_zone| q_zone.get(in_zone.0).ok())
+            .map_or(current_level.0.gravity_scale, |zone| zone.gravity_scale);
+        if gravity.0 != scale {
+            gravity.0 = scale;
+        }
+    }
+}
+
+fn hold_balls_on_slopes(
+    mut q_ball: Query<(&mut Velocity, &mut GravityScale, &mut SlopeHold, &BallState)>,
+) {
+    for (mut velocity, mut gravity, mut hold, state) in q_ball.iter_mut() {
+        let speed = velocity.linvel.length();
+        let steady = (speed - hold.last_speed).abs() < CREEP_SPEED_TOLERANCE;
+        hold.last_speed = speed;
+
+        if let Some(held_gravity) = hold.held_gravity {
+            // Anything that gets a held ball moving, like a shot or another ball, releases it
+            if speed > BALL_REST_SPEED {
+                gravity.0 = held_gravity;
+                hold.held_gravity = None;
+            } else {
+                velocity.linvel = Vec3::ZERO;
+                velocity.angvel = Vec3::ZERO;
+            }
+            continue;
+        }
+
+        if *state == BallState::Moving && speed < CREEP_SPEED && steady {
+            hold.creeping_frames += 1;
+        } else {
+            hold.creeping_frames = 0;
+        }
+
+        if hold.creeping_frames >= CREEP_FRAMES {
+            hold.creeping_frames = 0;
+            hold.held_gravity = Some(gravity.0);
+            gravity.0 = 0.0;
+            velocity.linvel = Vec3::ZERO;
+            velocity.angvel = Vec3::ZERO;
+        }
+    }
+}
+
+fn update_ball_state(
+    mut q_ball: Query<
+        (Entity, &Velocity, &Sleeping, &Ball, &mut BallState),
+        // Balls that are not yet known to Rapier have not started moving
+        With<RapierRigidBodyHandle>,
+    >,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    mut stopped: EventWriter<BallStopped>,
+) {
+    for (entity, velocity, sleeping, ball, mut state) in q_ball.iter_mut() {
+        if *state == BallState::Holed {
+            continue;
+        }
+
+        let new_state = if sleeping.sleeping || velocity.linvel.length() < BALL_REST_SPEED {
+            if ball.player_id == game_state.current_player || *game_mode == GameMode::Party {
+                BallState::Aiming
+            } else {
+                BallState::Resting
+            }
+        } else {
+            BallState::Moving
+        };
+
+        if *state == BallState::Moving && new_state.is_at_rest() {
+            stopped.send(BallStopped {
+                entity,
+                player_id: ball.player_id,
+            });
+        }
+        if *state != new_state {
+            *state = new_state;
+        }
+    }
+}
+
+fn track_balls_in_holes(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_hole: Query<(), With<Hole>>,
+    q_ball: Query<(), With<Ball>>,
+) {
+    for event in collision_events.iter() {
+        let (e1, e2, entered) = match *event {
+            CollisionEvent::Started(e1, e2, _) => (e1, e2, true),
+            CollisionEvent::Stopped(e1, e2, _) => (e1, e2, false),
+        };
+        let (ball_entity, hole_entity) = if q_hole.contains(e1) && q_ball.contains(e2) {
+            (e2, e1)
+        } else if q_hole.contains(e2) && q_ball.contains(e1) {
+            (e1, e2)
+        } else {
+            continue;
+        };
+
+        if let Some(mut ball) = commands.get_entity(ball_entity) {
+            if entered {
+                ball.insert(InHole(hole_entity));
+            } else {
+                ball.remove::<InHole>();
+            }
+        }
+    }
+}
+
+pub fn track_checkpoints(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_checkpoint: Query<&Checkpoint>,
+    q_ball: Query<(), With<Ball>>,
+) {
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *event else {
+            continue;
+        };
+        let (checkpoint, ball_entity) = match (q_checkpoint.get(e1), q_checkpoint.get(e2)) {
+            (Ok(checkpoint), _) if q_ball.contains(e2) => (checkpoint, e2),
+            (_, Ok(checkpoint)) if q_ball.contains(e1) => (checkpoint, e1),
+            _ => continue,
+        };
+
+        if let Some(mut ball) = commands.get_entity(ball_entity) {
+            ball.insert(RespawnPoint(checkpoint.position));
+        }
+    }
+}
+
+/// Draws a curved arrow around the aiming ball that shows the direction and strength of its
+/// spin. The arrow starts behind the ball and grows around it as the spin gets stronger.
+fn draw_spin_arrows(
+    mut gizmos: Gizmos,
+    q_ball: Query<(&Transform, &ShootSettings, &BallState, &BallShape)>,
+) {
+    const RADIUS: f32 = 0.06;
+    const SEGMENTS: usize = 24;
+
+    for (transform, shoot, state, shape) in q_ball.iter() {
+        let Some(spin) = shoot.spin else {
+            continue;
+        };
+        if *state != BallState::Aiming || !shape.takes_spin() {
+            continue;
+        }
+
+        let color = match spin {
+            BallSpin::Left(_) => Color::ORANGE,
+            BallSpin::Right(_) => Color::FUCHSIA,
+        };
+        let center = transform.translation;
+        let start = shoot.angle + PI;
+        let sweep = spin.signed() * PI * 1.5;
+        let point_at = |angle: f32| center + Quat::from_rotation_y(angle) * Vec3::X * RADIUS;
+
+        let points: Vec<Vec3> = (0..=SEGMENTS)
+            .map(|i| point_at(start + sweep * i as f32 / SEGMENTS as f32))
+            .collect();
+        gizmos.linestrip(points.iter().copied(), color);
+
+        // Arrow head at the end of the arc
+        let tip = points[SEGMENTS];
+        let back = (points[SEGMENTS - 1] - tip).normalize_or_zero() * RADIUS * 0.5;
+        let outward = (tip - center).normalize_or_zero() * RADIUS * 0.3;
+        gizmos.line(tip, tip + back + outward, color);
+        gizmos.line(tip, tip + back - outward, color);
+    }
+}
+
+/// Applies requested shots to the balls and counts the strokes.
+pub fn apply_shot(
+    mut shots: EventReader<ShootEvent>,
+    mut q_ball: Query<(
+        &mut ExternalImpulse,
+        &ReadMassProperties,
+        &Transform,
+        &BallState,
+        &mut ShootSettings,
+        &mut Ball,
+        &mut Jump,
+        &BallShape,
+    )>,
+    mut game_state: ResMut<GameState>,
+    mut strokes: EventWriter<StrokeAdded>,
+    tuning: Res<ShotTuning>,
+) {
+    let mut has_shot: Vec<u32> = Vec::new();
+
+    for shot in shots.iter() {
+        if shot.power <= 0.0 || has_shot.contains(&shot.player_id) {
+            continue;
+        }
+        let Some((mut ball_impulse, ball_mass, transform, _, mut shoot, mut ball, mut jump, shape)) =
+            q_ball.iter_mut().find(|(_, _, _, state, _, ball, _, _)| {
+                ball.player_id == shot.player_id && **state == BallState::Aiming
+            })
+        else {
+            continue;
+        };
+        has_shot.push(shot.player_id);
+
+        let rot = Quat::from_euler(EulerRot::XYZ, 0.0, shot.angle, 0.0);
+        let dir = Transform::from_rotation(rot) * Vec3::X;
+
+        let power_multiplier = 1.0 * ball_mass.0.mass;
+        ball_impulse.impulse += dir * tuning.shot_speed(shot.power) * power_multiplier;
+
+        let torqe_magnitude = tuning.spin_torque * ball_mass.0.mass;
+        let spin = shot.spin.filter(|_| shape.takes_spin());
+        let torque_amount = spin.map_or(0.0, BallSpin::signed) * torqe_magnitude;
+        ball_impulse.torque_impulse.y += torque_amount;
+        ball_impulse.torque_impulse.x += torque_amount;
+
+        ball.hits += 1;
+        strokes.send(StrokeAdded {
+            player_id: ball.player_id,
+            penalty: false,
+        });
+        game_state.shot_in_progress = true;
+        game_state.players[ball.player_id as usize].last_pos = transform.translation;
+
+        *shoot = ShootSettings {
+            angle: shot.angle,
+            ..ShootSettings::default()
+        };
+        *jump = Jump::default();
+    }
+}
+
+pub fn apply_jumps(
+    mut jumps: EventReader<JumpEvent>,
+    mut q_ball: Query<(
+        &mut ExternalImpulse,
+        &ReadMassProperties,
+        &BallState,
+        &Ball,
+        &mut Jump,
+    )>,
+    tuning: Res<ShotTuning>,
+) {
+    for event in jumps.iter() {
+        let Some((mut ball_impulse, ball_mass, _, _, mut jump)) =
+            q_ball.iter_mut().find(|(_, _, state, ball, jump)| {
+                ball.player_id == event.player_id && jump.can_jump(**state)
+            })
+        else {
+            continue;
+        };
+        ball_impulse.impulse.y += tuning.jump_impulse(event.charge) * ball_mass.0.mass;
+        jump.used = true;
+    }
+}
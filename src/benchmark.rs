@@ -0,0 +1,238 @@
+use std::path::PathBuf;
+
+use bevy::{app::AppExit, prelude::*, utils::Instant};
+use bevy_rapier3d::prelude::*;
+use rand::{rngs::StdRng, Rng};
+use serde::Serialize;
+
+use crate::{
+    ball::{self, Ball, BallState, ShootEvent},
+    cli::CliArgs,
+    level::{CurrentLevel, Level},
+    procgen::{self, CourseSettings},
+    scoring::GameState,
+    tuning::ShotTuning,
+    AppState, GameSeed,
+};
+
+/// Used unless `--seed` is given, so that reports from different builds are comparable.
+pub const SEED: u64 = 0xB3AC;
+pub const NUM_PLAYERS: u32 = 32;
+const DEFAULT_REPORT_PATH: &str = "benchmark.ron";
+/// Time to let the course and balls load in before measuring.
+const WARMUP_SECONDS: f32 = 2.0;
+
+/// A long course with many turns, hazards and surfaces, to give the physics plenty to do.
+pub fn stress_course(seed: u64) -> Level {
+    let settings = CourseSettings {
+        min_blocks: 40,
+        max_blocks: 40,
+        turn_chance: 0.5,
+        hazard_chance: 0.5,
+        surface_chance: 0.4,
+    };
+    Level {
+        name: "Benchmark".to_string(),
+        ..procgen::generate_level(seed, &settings)
+    }
+}
+
+/// Plays the stress course with random shots from every ball at once, measures the frame and
+/// physics step times for a number of seconds and then writes a report and exits.
+pub struct BenchmarkPlugin {
+    pub seconds: f32,
+    pub report_path: PathBuf,
+}
+
+impl BenchmarkPlugin {
+    pub fn from_args(args: &CliArgs) -> Self {
+        Self {
+            seconds: args.benchmark.unwrap_or_default(),
+            report_path: args
+                .benchmark_report
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_REPORT_PATH)),
+        }
+    }
+}
+
+impl Plugin for BenchmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Benchmark {
+            seconds: self.seconds,
+            report_path: self.report_path.clone(),
+            ..default()
+        })
+        .add_systems(OnEnter(AppState::InGame), start_benchmark)
+        .add_systems(
+            Update,
+            (
+                play_random_shots.before(ball::apply_shot),
+                record_frame_time,
+                finish_benchmark,
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                start_physics_timer
+                    .after(PhysicsSet::SyncBackendFlush)
+                    .before(PhysicsSet::StepSimulation),
+                stop_physics_timer
+                    .after(PhysicsSet::StepSimulation)
+                    .before(PhysicsSet::Writeback),
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Benchmark {
+    seconds: f32,
+    report_path: PathBuf,
+    /// When measuring starts, in real seconds since startup.
+    start: Option<f32>,
+    frame_times: Vec<f32>,
+    physics_times: Vec<f32>,
+    physics_started: Option<Instant>,
+}
+
+impl Benchmark {
+    fn is_measuring(&self, time: &Time) -> bool {
+        self.start
+            .is_some_and(|start| time.raw_elapsed_seconds() >= start)
+    }
+}
+
+#[derive(Resource)]
+struct ShotRng(StdRng);
+
+#[derive(Serialize)]
+struct Report {
+    course: String,
+    players: u32,
+    seconds: f32,
+    frames: usize,
+    frame_ms: Summary,
+    physics_ms: Summary,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    average: f32,
+    p50: f32,
+    p95: f32,
+    p99: f32,
+    max: f32,
+}
+
+impl Summary {
+    fn of_seconds(samples: &[f32]) -> Self {
+        let mut ms: Vec<f32> = samples.iter().map(|s| s * 1000.0).collect();
+        ms.sort_by(f32::total_cmp);
+        let percentile = |p: f32| {
+            let index = ((ms.len() as f32 - 1.0) * p).round() as usize;
+            ms.get(index).copied().unwrap_or(0.0)
+        };
+        Self {
+            average: ms.iter().sum::<f32>() / ms.len().max(1) as f32,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: ms.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+fn start_benchmark(
+    mut commands: Commands,
+    mut benchmark: ResMut<Benchmark>,
+    time: Res<Time>,
+    seed: Res<GameSeed>,
+) {
+    benchmark.start = Some(time.raw_elapsed_seconds() + WARMUP_SECONDS);
+    commands.insert_resource(ShotRng(seed.rng(GameSeed::BENCHMARK_STREAM)));
+}
+
+/// Shoots every ball that is waiting for a shot in a random direction.
+fn play_random_shots(
+    q_ball: Query<(&Ball, &BallState)>,
+    mut rng: ResMut<ShotRng>,
+    mut shots: EventWriter<ShootEvent>,
+    tuning: Res<ShotTuning>,
+) {
+    for (ball, state) in q_ball.iter() {
+        if *state != BallState::Aiming {
+            continue;
+        }
+        shots.send(ShootEvent {
+            player_id: ball.player_id,
+            power: rng.0.gen_range(0.3..1.0) * tuning.max_power,
+            angle: rng.0.gen_range(0.0..std::f32::consts::TAU),
+            spin: None,
+        });
+    }
+}
+
+fn record_frame_time(mut benchmark: ResMut<Benchmark>, time: Res<Time>) {
+    if benchmark.is_measuring(&time) {
+        benchmark.frame_times.push(time.raw_delta_seconds());
+    }
+}
+
+fn start_physics_timer(mut benchmark: ResMut<Benchmark>) {
+    benchmark.physics_started = Some(Instant::now());
+}
+
+fn stop_physics_timer(mut benchmark: ResMut<Benchmark>, time: Res<Time>) {
+    let Some(started) = benchmark.physics_started.take() else {
+        return;
+    };
+    if benchmark.is_measuring(&time) {
+        benchmark
+            .physics_times
+            .push(started.elapsed().as_secs_f32());
+    }
+}
+
+fn finish_benchmark(
+    benchmark: Res<Benchmark>,
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    current_level: Res<CurrentLevel>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(start) = benchmark.start else {
+        return;
+    };
+    if time.raw_elapsed_seconds() < start + benchmark.seconds {
+        return;
+    }
+
+    let report = Report {
+        course: current_level.0.name.clone(),
+        players: game_state.num_players,
+        seconds: benchmark.seconds,
+        frames: benchmark.frame_times.len(),
+        frame_ms: Summary::of_seconds(&benchmark.frame_times),
+        physics_ms: Summary::of_seconds(&benchmark.physics_times),
+    };
+
+    for (name, summary) in [("Frame", &report.frame_ms), ("Physics", &report.physics_ms)] {
+        println!(
+            "{:<8} avg {:.2} ms, p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+            name, summary.average, summary.p50, summary.p95, summary.p99, summary.max
+        );
+    }
+
+    let written = ron::ser::to_string_pretty(&report, default())
+        .map_err(|e| e.to_string())
+        .and_then(|text| std::fs::write(&benchmark.report_path, text).map_err(|e| e.to_string()));
+    match written {
+        Ok(()) => println!("Report written to {}", benchmark.report_path.display()),
+        Err(e) => eprintln!("Could not write {}: {}", benchmark.report_path.display(), e),
+    }
+
+    exit.send(AppExit);
+}
@@ -0,0 +1,141 @@
+use bevy::{pbr::NotShadowCaster, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{Ball, BallState},
+    collision,
+    graphics::GraphicsQuality,
+    level::LevelEntity,
+    AppState,
+};
+
+const BLOB_RADIUS: f32 = 0.03;
+const BLOB_ALPHA: f32 = 0.5;
+/// Height above the ground at which the blob has faded out completely.
+const FADE_HEIGHT: f32 = 0.5;
+/// Lifts the blob off the ground so that it doesn't flicker through it.
+const GROUND_OFFSET: f32 = 0.001;
+
+/// Dark blobs on the ground under the balls when the graphics quality has no shadow maps, so
+/// that it is still possible to tell how high a jumping ball is.
+pub struct BlobShadowPlugin;
+
+impl Plugin for BlobShadowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_blob_shadows, move_blob_shadows)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// The shadow of a ball, with the ball it belongs to.
+#[derive(Component)]
+struct BlobShadow(Entity);
+
+fn spawn_blob_shadows(
+    mut commands: Commands,
+    q_ball: Query<Entity, Added<Ball>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut mesh: Local<Option<Handle<Mesh>>>,
+) {
+    for ball in q_ball.iter() {
+        let mesh = mesh.get_or_insert_with(|| {
+            meshes.add(
+                shape::Circle {
+                    radius: BLOB_RADIUS,
+                    vertices: 16,
+                }
+                .into(),
+            )
+        });
+        commands.spawn((
+            LevelEntity,
+            BlobShadow(ball),
+            PbrBundle {
+                mesh: mesh.clone(),
+                // Each blob fades on its own as its ball goes up
+                material: materials.add(StandardMaterial {
+                    base_color: Color::BLACK.with_a(BLOB_ALPHA),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            NotShadowCaster,
+        ));
+    }
+}
+
+/// Puts each blob on the ground right below its ball, turned along the ground.
+fn move_blob_shadows(
+    mut commands: Commands,
+    mut q_blob: Query<(
+        Entity,
+        &BlobShadow,
+        &Handle<StandardMaterial>,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    q_ball: Query<(&GlobalTransform, &BallState), With<Ball>>,
+    rapier_context: Res<RapierContext>,
+    quality: Res<GraphicsQuality>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let groups = CollisionGroups::new(
+        Group::ALL,
+        Group::ALL.difference(collision::BALL_GROUP | collision::DEBRIS_GROUP),
+    );
+
+    for (entity, blob, material, mut transform, mut visibility) in q_blob.iter_mut() {
+        let Ok((ball_transform, state)) = q_ball.get(blob.0) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        let ball_position = ball_transform.translation();
+        let hit = if quality.shadows() || *state == BallState::Holed {
+            None
+        } else {
+            // The ball is also in its player's group, so it has to be left out by itself
+            let filter = QueryFilter::new()
+                .exclude_sensors()
+                .exclude_rigid_body(blob.0)
+                .groups(groups);
+            rapier_context.cast_ray_and_get_normal(
+                ball_position,
+                Vec3::NEG_Y,
+                FADE_HEIGHT,
+                true,
+                filter,
+            )
+        };
+
+        let new_visibility = if hit.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+        let Some((_, intersection)) = hit else {
+            continue;
+        };
+
+        transform.translation = intersection.point + intersection.normal * GROUND_OFFSET;
+        // Circles face +Z
+        transform.rotation = Quat::from_rotation_arc(Vec3::Z, intersection.normal);
+        // Smaller and lighter the higher the ball is
+        let fade = 1.0 - intersection.toi / FADE_HEIGHT;
+        transform.scale = Vec3::splat(0.5 + 0.5 * fade);
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color.set_a(BLOB_ALPHA * fade);
+        }
+    }
+}
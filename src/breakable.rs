@@ -0,0 +1,272 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility::Accessibility,
+    ball::Ball,
+    collision,
+    level::{tile_center, Direction, LevelEntity},
+    AppState,
+};
+
+const HEIGHT: f32 = 0.1;
+/// How far in front of an obstacle a fast ball is noticed, so that it is smashed before the ball
+/// bounces off it.
+const TRIGGER_MARGIN: f32 = 0.1;
+const NUM_DEBRIS: usize = 12;
+const DEBRIS_SECONDS: f32 = 2.0;
+
+/// Obstacles that smash when a ball hits them hard enough, like glass panes and stacked blocks.
+pub struct BreakablePlugin;
+
+impl Plugin for BreakablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (smash_breakables, clean_up_debris).run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// An obstacle standing on the edge of a tile. Also the component on its collider.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Breakable {
+    pub position: (i32, i32, i32),
+    /// The edge of the tile the obstacle stands on.
+    pub side: Direction,
+    pub kind: BreakableKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BreakableKind {
+    Glass,
+    Blocks,
+}
+
+impl BreakableKind {
+    /// The speed a ball needs to smash the obstacle.
+    fn strength(self) -> f32 {
+        match self {
+            BreakableKind::Glass => 1.5,
+            BreakableKind::Blocks => 2.5,
+        }
+    }
+
+    fn thickness(self) -> f32 {
+        match self {
+            BreakableKind::Glass => 0.01,
+            BreakableKind::Blocks => 0.05,
+        }
+    }
+
+    fn material(self) -> StandardMaterial {
+        match self {
+            BreakableKind::Glass => StandardMaterial {
+                base_color: Color::rgba(0.7, 0.9, 1.0, 0.35),
+                alpha_mode: AlphaMode::Blend,
+                perceptual_roughness: 0.05,
+                reflectance: 0.8,
+                ..default()
+            },
+            BreakableKind::Blocks => StandardMaterial {
+                base_color: Color::rgb(0.6, 0.4, 0.25),
+                perceptual_roughness: 0.9,
+                ..default()
+            },
+        }
+    }
+}
+
+/// Sensor in front of and behind an obstacle.
+#[derive(Component)]
+struct BreakTrigger;
+
+/// A piece of a smashed obstacle, removed after a while.
+#[derive(Component)]
+struct Debris {
+    timer: Timer,
+}
+
+pub fn spawn_breakable(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    breakable: Breakable,
+) {
+    let (x, y, z) = breakable.position;
+    let side = breakable.side.vector();
+    let thickness = breakable.kind.thickness();
+    let position = tile_center(x, y, z) + side * 0.2 + Vec3::Y * (0.05 + HEIGHT / 2.0);
+    let material = materials.add(breakable.kind.material());
+
+    commands
+        .spawn((
+            LevelEntity,
+            breakable,
+            SpatialBundle::from_transform(
+                Transform::from_translation(position).looking_to(side, Vec3::Y),
+            ),
+            Collider::cuboid(0.2, HEIGHT / 2.0, thickness / 2.0),
+            ActiveEvents::COLLISION_EVENTS,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                BreakTrigger,
+                Collider::cuboid(0.2, HEIGHT / 2.0, thickness / 2.0 + TRIGGER_MARGIN),
+                TransformBundle::IDENTITY,
+                Sensor,
+                ActiveEvents::COLLISION_EVENTS,
+            ));
+
+            match breakable.kind {
+                BreakableKind::Glass => {
+                    parent.spawn(PbrBundle {
+                        mesh: meshes.add(shape::Box::new(0.4, HEIGHT, thickness).into()),
+                        material,
+                        ..default()
+                    });
+                }
+                BreakableKind::Blocks => {
+                    let block = meshes.add(shape::Cube::new(HEIGHT / 2.0).into());
+                    for column in 0..8 {
+                        for row in 0..2 {
+                            parent.spawn(PbrBundle {
+                                mesh: block.clone(),
+                                material: material.clone(),
+                                transform: Transform::from_xyz(
+                                    (column as f32 + 0.5) * HEIGHT / 2.0 - 0.2,
+                                    (row as f32 - 0.5) * HEIGHT / 2.0,
+                                    0.0,
+                                ),
+                                ..default()
+                            });
+                        }
+                    }
+                }
+            }
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn smash_breakables(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_ball: Query<&Velocity, With<Ball>>,
+    q_trigger: Query<&Parent, With<BreakTrigger>>,
+    q_breakable: Query<(&Breakable, &GlobalTransform)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    accessibility: Res<Accessibility>,
+) {
+    let mut smashed = Vec::new();
+
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *event else {
+            continue;
+        };
+
+        for (ball, other) in [(e1, e2), (e2, e1)] {
+            let Ok(velocity) = q_ball.get(ball) else {
+                continue;
+            };
+            let target = q_trigger.get(other).map_or(other, |parent| parent.get());
+            let Ok((breakable, transform)) = q_breakable.get(target) else {
+                continue;
+            };
+            if velocity.linvel.length() < breakable.kind.strength() || smashed.contains(&target) {
+                continue;
+            }
+
+            smashed.push(target);
+            commands.entity(target).despawn_recursive();
+            if accessibility.reduced_motion {
+                continue;
+            }
+            spawn_debris(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                breakable.kind,
+                transform.translation(),
+                velocity.linvel,
+            );
+        }
+    }
+}
+
+fn spawn_debris(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    kind: BreakableKind,
+    position: Vec3,
+    ball_velocity: Vec3,
+) {
+    let mut rng = rand::thread_rng();
+    let size = 0.02;
+    let mesh = meshes.add(shape::Box::new(size, size, kind.thickness().min(size)).into());
+    let material = materials.add(kind.material());
+
+    for _ in 0..NUM_DEBRIS {
+        let offset = Vec3::new(
+            rng.gen_range(-0.15..0.15),
+            rng.gen_range(-HEIGHT / 2.0..HEIGHT / 2.0),
+            rng.gen_range(-0.15..0.15),
+        );
+        let velocity = ball_velocity * rng.gen_range(0.2..0.6)
+            + Vec3::new(
+                rng.gen_range(-0.5..0.5),
+                rng.gen_range(0.0..0.8),
+                rng.gen_range(-0.5..0.5),
+            );
+        commands.spawn((
+            LevelEntity,
+            Debris {
+                timer: Timer::from_seconds(DEBRIS_SECONDS, TimerMode::Once),
+            },
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(position + offset).with_rotation(
+                    Quat::from_euler(
+                        EulerRot::XYZ,
+                        rng.gen_range(0.0..PI),
+                        rng.gen_range(0.0..PI),
+                        0.0,
+                    ),
+                ),
+                ..default()
+            },
+            RigidBody::Dynamic,
+            Collider::cuboid(size / 2.0, size / 2.0, kind.thickness().min(size) / 2.0),
+            CollisionGroups::new(
+                collision::DEBRIS_GROUP,
+                Group::ALL.difference(collision::any_ball_group() | collision::DEBRIS_GROUP),
+            ),
+            Velocity {
+                linvel: velocity,
+                angvel: Vec3::new(
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                ),
+            },
+        ));
+    }
+}
+
+fn clean_up_debris(
+    mut commands: Commands,
+    mut q_debris: Query<(Entity, &mut Debris)>,
+    time: Res<Time>,
+) {
+    for (entity, mut debris) in q_debris.iter_mut() {
+        if debris.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility::Accessibility,
+    ball::{Ball, BallState, ShootEvent, ShootSettings},
+    chat,
+    controls::{CommonAction, KeyBindings},
+    director::Director,
+    flythrough::Flythrough,
+    level::Wall,
+    online::NetRole,
+    save,
+    scoring::GameState,
+    sound::Listener,
+    spectator::FreeCamera,
+    tuning::ShotTuning,
+    AppState, GameMode,
+};
+
+/// Largest distance the camera is moved by a shake.
+const MAX_SHAKE_OFFSET: f32 = 0.03;
+/// How much shake wears off per second.
+const SHAKE_DECAY: f32 = 1.5;
+/// Shake added by a shot with the highest possible power.
+const SHOT_SHAKE: f32 = 0.5;
+/// Balls hitting a wall faster than this shake the camera.
+const WALL_IMPACT_SPEED: f32 = 3.0;
+/// Angles the camera looks down at the ball at. Never below the floor, and not quite straight
+/// down, where the view would spin around.
+const MIN_PITCH: f32 = 0.05;
+const MAX_PITCH: f32 = 1.5;
+const DEFAULT_PITCH: f32 = 0.29;
+const SAVE_FILE: &str = "camera.ron";
+/// Ranges of distances to the ball that the camera setting goes through, the middle one being
+/// the default.
+const DISTANCE_RANGES: [(f32, f32); 3] = [(0.15, 4.0), (0.25, 8.0), (0.5, 16.0)];
+/// Closest the camera ever comes to the ball, so that it stays well outside of it.
+const CLOSEST_DISTANCE: f32 = 0.1;
+/// Zoom per notch of a mouse wheel that scrolls in lines.
+const LINE_ZOOM: f32 = 0.15;
+/// Zoom per pixel of scrolling on touchpads and in browsers.
+const PIXEL_ZOOM: f32 = 0.003;
+/// How quickly the zoom eases towards the picked zoom, as the fraction left after one second.
+const ZOOM_SMOOTHING: f32 = 0.0001;
+/// Space kept between the camera and a wall between it and the ball.
+const WALL_MARGIN: f32 = 0.05;
+
+/// A camera that follows the current player's ball and can be rotated and zoomed with the mouse.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<CameraSettings>(SAVE_FILE))
+            .add_systems(Startup, setup_camera)
+            .add_systems(
+                Update,
+                (save_camera_settings, apply_camera_settings)
+                    .run_if(resource_changed::<CameraSettings>()),
+            )
+            .add_systems(
+                Update,
+                (
+                    camera_input,
+                    camera_presets.run_if(in_state(AppState::InGame).and_then(chat::not_typing)),
+                    (shake_on_shot, shake_on_wall_impact),
+                    (remove_camera_shake, move_camera_to_ball, apply_camera_shake).chain(),
+                ),
+            );
+    }
+}
+
+/// How close to and far from the ball the camera can be zoomed, saved between runs.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraSettings {
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        let (min_distance, max_distance) = DISTANCE_RANGES[1];
+        Self {
+            min_distance,
+            max_distance,
+        }
+    }
+}
+
+impl CameraSettings {
+    /// The next of the preset distance ranges after the current one.
+    pub fn next_range(&self) -> Self {
+        let next = DISTANCE_RANGES
+            .iter()
+            .position(|&range| range == (self.min_distance, self.max_distance))
+            .map_or(0, |i| (i + 1) % DISTANCE_RANGES.len());
+        let (min_distance, max_distance) = DISTANCE_RANGES[next];
+        Self {
+            min_distance,
+            max_distance,
+        }
+    }
+
+    /// The range in meters, e.g. "0.25–8 m".
+    pub fn range_name(&self) -> String {
+        format!("{}–{} m", self.min_distance, self.max_distance)
+    }
+}
+
+fn save_camera_settings(settings: Res<CameraSettings>) {
+    // Only remember settings the player picked
+    if !settings.is_added() {
+        save::store(SAVE_FILE, &*settings);
+    }
+}
+
+fn apply_camera_settings(
+    settings: Res<CameraSettings>,
+    mut q_controller: Query<&mut CameraController>,
+) {
+    for mut controller in q_controller.iter_mut() {
+        controller.min_distance = settings.min_distance.max(CLOSEST_DISTANCE);
+        controller.max_distance = settings.max_distance.max(controller.min_distance);
+    }
+}
+
+fn setup_camera(mut commands: Commands) {
+    // Replaced by the saved range on the first frame
+    let (min_distance, max_distance) = DISTANCE_RANGES[1];
+    commands.spawn((
+        CameraController {
+            yaw: PI,
+            pitch: DEFAULT_PITCH,
+            zoom: 0.0,
+            target_zoom: 0.0,
+            min_distance,
+            max_distance,
+        },
+        CameraShake::default(),
+        Listener,
+        Camera3dBundle {
+            camera: Camera {
+                hdr: true,
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 1.5, 1.0)
+                .looking_at(Vec3::new(0.0, 0.0, 0.0), Vec3::Y),
+            ..default()
+        },
+    ));
+}
+
+#[derive(Component)]
+pub struct CameraController {
+    /// Turn around the vertical axis. At 0 the camera is on the +z side of what it looks at.
+    pub yaw: f32,
+    /// Angle above the ground that the camera looks down at, between `MIN_PITCH` and `MAX_PITCH`.
+    /// There is never any roll.
+    pub pitch: f32,
+    /// The camera is `(-zoom).exp()` away from what it looks at. Eases towards `target_zoom`.
+    pub zoom: f32,
+    /// The zoom picked with the mouse wheel.
+    pub target_zoom: f32,
+    /// Closest the camera can be zoomed in to the ball, also when a wall is in the way. Set from
+    /// `CameraSettings`.
+    pub min_distance: f32,
+    /// Farthest the camera can be zoomed out from the ball.
+    pub max_distance: f32,
+}
+
+impl CameraController {
+    /// Orientation of a camera looking the way the controller is turned.
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, -self.pitch, 0.0)
+    }
+
+    /// Unit vector from what the camera looks at towards the camera.
+    pub fn offset(&self) -> Vec3 {
+        self.rotation() * Vec3::Z
+    }
+
+    /// Unit vector along the ground from what the camera looks at towards the camera.
+    pub fn ground_offset(&self) -> Vec3 {
+        Quat::from_rotation_y(self.yaw) * Vec3::Z
+    }
+}
+
+/// Views of the ball to line up a shot with, relative to where it is aimed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CameraPreset {
+    /// Behind the ball, looking along the aim line.
+    BehindBall,
+    /// Straight above the ball, with the aim line pointing up in the view.
+    TopDown,
+    /// Low down to the right of the aim line, to see how the ground slopes.
+    Side,
+}
+
+impl CameraPreset {
+    const ALL: [CameraPreset; 3] = [
+        CameraPreset::BehindBall,
+        CameraPreset::TopDown,
+        CameraPreset::Side,
+    ];
+
+    fn action(self) -> CommonAction {
+        match self {
+            CameraPreset::BehindBall => CommonAction::CameraBehindBall,
+            CameraPreset::TopDown => CommonAction::CameraTopDown,
+            CameraPreset::Side => CommonAction::CameraSide,
+        }
+    }
+
+    fn pitch(self) -> f32 {
+        match self {
+            CameraPreset::BehindBall => DEFAULT_PITCH,
+            CameraPreset::TopDown => MAX_PITCH,
+            CameraPreset::Side => MIN_PITCH,
+        }
+    }
+}
+
+/// Shakes the camera on top of wherever it is placed, to make hard hits feel hard.
+#[derive(Component, Default)]
+pub struct CameraShake {
+    /// From 0 (still) to 1 (shaking as much as possible).
+    pub trauma: f32,
+    /// The offset applied this frame, so it can be taken away again.
+    offset: Vec3,
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+}
+
+fn camera_input(
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    buttons: Res<Input<MouseButton>>,
+    mut query: Query<&mut CameraController>,
+    time: Res<Time>,
+) {
+    let scroll: f32 = mouse_wheel
+        .iter()
+        .map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y * LINE_ZOOM,
+            MouseScrollUnit::Pixel => wheel.y * PIXEL_ZOOM,
+        })
+        .sum();
+    for mut controller in query.iter_mut() {
+        // Zooming in makes the distance smaller, so the closest distance gives the largest zoom
+        let (min_zoom, max_zoom) = (-controller.max_distance.ln(), -controller.min_distance.ln());
+        controller.target_zoom = (controller.target_zoom + scroll).clamp(min_zoom, max_zoom);
+        let ease = 1.0 - ZOOM_SMOOTHING.powf(time.delta_seconds());
+        controller.zoom += (controller.target_zoom - controller.zoom) * ease;
+        if buttons.pressed(MouseButton::Left) {
+            for mouse in mouse_motion.iter() {
+                let delta = mouse.delta * time.delta_seconds() * 0.3;
+                controller.yaw -= delta.x;
+                controller.pitch = (controller.pitch + delta.y).clamp(MIN_PITCH, MAX_PITCH);
+            }
+        }
+    }
+}
+
+/// Snaps the camera to a preset view of the current player's ball on the preset keys, 1 to 3 by
+/// default.
+fn camera_presets(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut q_camera: Query<&mut CameraController>,
+    q_ball: Query<(&Ball, &ShootSettings)>,
+    game_state: Res<GameState>,
+) {
+    let Some(preset) = CameraPreset::ALL
+        .into_iter()
+        .find(|&preset| bindings.common.just_pressed(&keys, preset.action()))
+    else {
+        return;
+    };
+    let Some((_, shoot)) = q_ball
+        .iter()
+        .find(|(ball, _)| ball.player_id == game_state.current_player)
+    else {
+        return;
+    };
+
+    let aim = Quat::from_rotation_y(shoot.angle) * Vec3::X;
+    // Where the camera is placed from the ball, along the ground
+    let from_ball = match preset {
+        CameraPreset::BehindBall | CameraPreset::TopDown => -aim,
+        CameraPreset::Side => aim.cross(Vec3::Y),
+    };
+    for mut controller in q_camera.iter_mut() {
+        controller.yaw = f32::atan2(from_ball.x, from_ball.z);
+        controller.pitch = preset.pitch();
+    }
+}
+
+pub fn move_camera_to_ball(
+    mut query: Query<
+        (&CameraController, &mut Transform),
+        (
+            Without<Ball>,
+            Without<FreeCamera>,
+            Without<Director>,
+            Without<Flythrough>,
+        ),
+    >,
+    q_ball: Query<(&Transform, &Ball, &BallState)>,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    role: Res<NetRole>,
+    rapier_context: Res<RapierContext>,
+) {
+    if *game_mode == GameMode::Party {
+        if let Ok((controller, mut transform)) = query.get_single_mut() {
+            frame_all_balls(
+                controller,
+                &mut transform,
+                q_ball.iter().map(|(t, _, _)| t.translation),
+            );
+        }
+        return;
+    }
+
+    if let Ok((controller, mut transform)) = query.get_single_mut() {
+        // Spectators would rather see whatever is happening right now
+        let moving_ball = q_ball
+            .iter()
+            .find(|(_, _, state)| *role == NetRole::Spectator && **state == BallState::Moving);
+        if let Some((ball_transform, _, _)) = moving_ball.or_else(|| {
+            q_ball
+                .iter()
+                .find(|(_, ball, _)| ball.player_id == game_state.current_player)
+        }) {
+            let ball_pos = ball_transform.translation;
+            let look = controller.offset();
+
+            // Come closer instead of looking through a wall that is in the way
+            let mut distance = (-controller.zoom).exp();
+            let filter = QueryFilter::new()
+                .exclude_sensors()
+                .predicate(&|entity| !q_ball.contains(entity));
+            if let Some((_, toi)) = rapier_context.cast_ray(ball_pos, look, distance, true, filter)
+            {
+                distance = (toi - WALL_MARGIN).max(controller.min_distance);
+            }
+            transform.translation = ball_pos + look * distance;
+            transform.look_at(ball_pos, Vec3::Y);
+        }
+    }
+}
+
+/// Places the camera so that every ball is in view, used when everybody plays at once.
+fn frame_all_balls(
+    controller: &CameraController,
+    transform: &mut Transform,
+    ball_positions: impl Iterator<Item = Vec3>,
+) {
+    let positions: Vec<Vec3> = ball_positions.collect();
+    if positions.is_empty() {
+        return;
+    }
+
+    let center = positions.iter().sum::<Vec3>() / positions.len() as f32;
+    let spread = positions
+        .iter()
+        .map(|p| p.distance(center))
+        .fold(0.0, f32::max);
+
+    let mut look = controller.ground_offset();
+    look.y = 0.8;
+    look = look.normalize();
+    let distance = (spread * 2.0 + 0.5) * (-controller.zoom).exp();
+    transform.translation = center + look * distance;
+    transform.look_at(center, Vec3::Y);
+}
+
+fn shake_on_shot(
+    mut shots: EventReader<ShootEvent>,
+    mut q_shake: Query<&mut CameraShake>,
+    tuning: Res<ShotTuning>,
+) {
+    for shot in shots.iter() {
+        for mut shake in q_shake.iter_mut() {
+            shake.add_trauma(SHOT_SHAKE * shot.power / tuning.max_power);
+        }
+    }
+}
+
+fn shake_on_wall_impact(
+    mut collision_events: EventReader<CollisionEvent>,
+    q_ball: Query<&Velocity, With<Ball>>,
+    q_wall: Query<(), With<Wall>>,
+    mut q_shake: Query<&mut CameraShake>,
+) {
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *event else {
+            continue;
+        };
+
+        for (ball, wall) in [(e1, e2), (e2, e1)] {
+            if !q_wall.contains(wall) {
+                continue;
+            }
+            let Ok(velocity) = q_ball.get(ball) else {
+                continue;
+            };
+            let speed = velocity.linvel.length();
+            if speed > WALL_IMPACT_SPEED {
+                for mut shake in q_shake.iter_mut() {
+                    shake.add_trauma((speed - WALL_IMPACT_SPEED) * 0.1);
+                }
+            }
+        }
+    }
+}
+
+/// Takes away last frame's shake, so that it doesn't pile up when the camera stays put.
+fn remove_camera_shake(mut q_camera: Query<(&mut CameraShake, &mut Transform)>) {
+    for (mut shake, mut transform) in q_camera.iter_mut() {
+        transform.translation -= shake.offset;
+        shake.offset = Vec3::ZERO;
+    }
+}
+
+pub fn apply_camera_shake(
+    mut q_camera: Query<(&mut CameraShake, &mut Transform)>,
+    accessibility: Res<Accessibility>,
+    time: Res<Time>,
+) {
+    let t = time.elapsed_seconds();
+
+    for (mut shake, mut transform) in q_camera.iter_mut() {
+        if accessibility.reduced_motion {
+            shake.trauma = 0.0;
+        }
+        if shake.trauma <= 0.0 {
+            continue;
+        }
+
+        // Cheap smooth noise, out of phase on each axis
+        let noise = Vec3::new(
+            (t * 37.0).sin() * (t * 13.0).cos(),
+            (t * 41.0 + 1.0).sin() * (t * 17.0).cos(),
+            (t * 29.0 + 2.0).sin() * (t * 11.0).cos(),
+        );
+        shake.offset = noise * shake.trauma * shake.trauma * MAX_SHAKE_OFFSET;
+        transform.translation += shake.offset;
+
+        shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_seconds()).max(0.0);
+    }
+}
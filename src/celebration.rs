@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    accessibility::Accessibility, camera::CameraController, level::LevelEntity, locale::Locale,
+    scoring::BallHoled, time_control::TimeControl,
+};
+
+const DURATION: f32 = 4.0;
+const NUM_CONFETTI: usize = 150;
+const ORBIT_RADIUS: f32 = 0.8;
+const ORBIT_SPEED: f32 = 0.8;
+
+/// Present while a hole-in-one is being celebrated.
+#[derive(Resource)]
+pub struct Celebration {
+    timer: Timer,
+    hole_position: Vec3,
+}
+
+#[derive(Component)]
+pub struct CelebrationEntity;
+
+#[derive(Component)]
+pub struct Confetti {
+    velocity: Vec3,
+    spin: Vec3,
+}
+
+pub fn start_hole_in_one_celebration(
+    mut commands: Commands,
+    mut holed: EventReader<BallHoled>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut time_control: ResMut<TimeControl>,
+    accessibility: Res<Accessibility>,
+    locale: Res<Locale>,
+) {
+    let Some(event) = holed.iter().find(|e| e.strokes == 1) else {
+        return;
+    };
+
+    commands.insert_resource(Celebration {
+        timer: Timer::from_seconds(DURATION, TimerMode::Once),
+        hole_position: event.position,
+    });
+    time_control.slow_motion = true;
+
+    commands
+        .spawn((
+            LevelEntity,
+            CelebrationEntity,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Percent(30.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("celebration.hole_in_one"),
+                TextStyle {
+                    font_size: 96.0,
+                    color: Color::GOLD,
+                    ..default()
+                },
+            ));
+        });
+
+    if accessibility.reduced_motion {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    let mesh = meshes.add(shape::Quad::new(Vec2::splat(0.02)).into());
+    for _ in 0..NUM_CONFETTI {
+        let material = materials.add(StandardMaterial {
+            base_color: Color::hsl(rng.gen_range(0.0..360.0), 1.0, 0.6),
+            unlit: true,
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        });
+        let velocity = Vec3::new(
+            rng.gen_range(-0.6..0.6),
+            rng.gen_range(1.5..3.0),
+            rng.gen_range(-0.6..0.6),
+        );
+        commands.spawn((
+            LevelEntity,
+            CelebrationEntity,
+            Confetti {
+                velocity,
+                spin: Vec3::new(
+                    rng.gen_range(-8.0..8.0),
+                    rng.gen_range(-8.0..8.0),
+                    rng.gen_range(-8.0..8.0),
+                ),
+            },
+            PbrBundle {
+                mesh: mesh.clone(),
+                material,
+                transform: Transform::from_translation(event.position),
+                ..default()
+            },
+        ));
+    }
+}
+
+pub fn animate_confetti(
+    mut q_confetti: Query<(&mut Confetti, &mut Transform)>,
+    time_control: Res<TimeControl>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds() * time_control.scale();
+
+    for (mut confetti, mut transform) in q_confetti.iter_mut() {
+        // Confetti falls slowly because of air resistance
+        confetti.velocity.y = (confetti.velocity.y - 4.0 * dt).max(-0.3);
+        confetti.velocity.x *= 1.0 - dt;
+        confetti.velocity.z *= 1.0 - dt;
+
+        transform.translation += confetti.velocity * dt;
+        let spin = confetti.spin * dt;
+        transform.rotate(Quat::from_euler(EulerRot::XYZ, spin.x, spin.y, spin.z));
+    }
+}
+
+/// Slowly circles the camera around the hole, overriding the usual ball-following camera.
+pub fn orbit_camera_around_hole(
+    mut q_camera: Query<&mut Transform, With<CameraController>>,
+    celebration: Res<Celebration>,
+) {
+    let angle = celebration.timer.elapsed_secs() * ORBIT_SPEED;
+    let offset = Quat::from_rotation_y(angle) * Vec3::new(ORBIT_RADIUS, ORBIT_RADIUS * 0.5, 0.0);
+    for mut transform in q_camera.iter_mut() {
+        transform.translation = celebration.hole_position + offset;
+        transform.look_at(celebration.hole_position, Vec3::Y);
+    }
+}
+
+pub fn end_celebration(
+    mut commands: Commands,
+    q_entities: Query<Entity, With<CelebrationEntity>>,
+    mut celebration: ResMut<Celebration>,
+    mut time_control: ResMut<TimeControl>,
+    time: Res<Time>,
+) {
+    celebration.timer.tick(time.delta());
+    if !celebration.timer.finished() {
+        return;
+    }
+
+    time_control.slow_motion = false;
+    for entity in q_entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Celebration>();
+}
@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+
+use crate::{
+    cli::CliArgs,
+    online::{NetMessage, NetRole, ReceivedMessage, SendMessage},
+};
+
+/// How long a chat message stays on screen.
+const MESSAGE_SECONDS: f32 = 10.0;
+const MAX_MESSAGE_LENGTH: usize = 120;
+
+/// Text chat between everybody in an online game. Enter opens the input box and sends.
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatInput>()
+            .add_systems(Startup, setup_chat)
+            .add_systems(
+                Update,
+                (type_chat_message, show_chat_messages, fade_chat_messages)
+                    .chain()
+                    .run_if(not(resource_equals(NetRole::Offline))),
+            );
+    }
+}
+
+/// The message being typed, if the input box is open.
+#[derive(Resource, Default)]
+pub struct ChatInput {
+    pub open: bool,
+    text: String,
+}
+
+/// Run condition for input that should be ignored while a chat message is typed.
+pub fn not_typing(chat: Res<ChatInput>) -> bool {
+    !chat.open
+}
+
+#[derive(Component)]
+struct ChatLog;
+
+#[derive(Component)]
+struct ChatInputBox;
+
+#[derive(Component)]
+struct ChatLine {
+    timer: Timer,
+}
+
+fn setup_chat(mut commands: Commands) {
+    commands
+        .spawn((
+            ChatLog,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(50.0),
+                    left: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            // Messages are added above the input box
+            parent.spawn((
+                ChatInputBox,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+                Visibility::Hidden,
+            ));
+        });
+}
+
+/// A name for this game's messages, from `--name` or the role.
+pub fn sender_name(args: &CliArgs, role: NetRole) -> String {
+    args.name.clone().unwrap_or_else(|| match role {
+        NetRole::Host => "Host".to_string(),
+        NetRole::Spectator => "Spectator".to_string(),
+        NetRole::Player | NetRole::Offline => "Guest".to_string(),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn type_chat_message(
+    mut chars: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut chat: ResMut<ChatInput>,
+    mut q_input: Query<(&mut Text, &mut Visibility), With<ChatInputBox>>,
+    mut messages: EventWriter<SendMessage>,
+    mut received: EventWriter<ReceivedMessage>,
+    args: Res<CliArgs>,
+    role: Res<NetRole>,
+) {
+    if !chat.open {
+        chars.clear();
+        if keys.just_pressed(KeyCode::Return) {
+            chat.open = true;
+        }
+    } else if keys.just_pressed(KeyCode::Return) {
+        let text = std::mem::take(&mut chat.text);
+        let text = text.trim();
+        if !text.is_empty() {
+            let message = NetMessage::Chat {
+                from: sender_name(&args, *role),
+                text: text.to_string(),
+            };
+            // Show our own message as if it came back from the others
+            received.send(ReceivedMessage(message.clone()));
+            messages.send(SendMessage(message));
+        }
+        chat.open = false;
+    } else if keys.just_pressed(KeyCode::Escape) {
+        chat.text.clear();
+        chat.open = false;
+    } else {
+        for c in chars.iter() {
+            if !c.char.is_control() && chat.text.len() < MAX_MESSAGE_LENGTH {
+                chat.text.push(c.char);
+            }
+        }
+        if keys.just_pressed(KeyCode::Back) {
+            chat.text.pop();
+        }
+    }
+
+    if let Ok((mut text, mut visibility)) = q_input.get_single_mut() {
+        text.sections[0].value = format!("> {}_", chat.text);
+        *visibility = if chat.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn show_chat_messages(
+    mut commands: Commands,
+    mut received: EventReader<ReceivedMessage>,
+    q_log: Query<(Entity, &Children), With<ChatLog>>,
+) {
+    let Ok((log, children)) = q_log.get_single() else {
+        return;
+    };
+    // The input box is always the last child
+    let mut index = children.len().saturating_sub(1);
+
+    for ReceivedMessage(message) in received.iter() {
+        let NetMessage::Chat { from, text } = message else {
+            continue;
+        };
+        let line = commands
+            .spawn((
+                ChatLine {
+                    timer: Timer::from_seconds(MESSAGE_SECONDS, TimerMode::Once),
+                },
+                TextBundle::from_section(
+                    format!("{}: {}", from, text),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ))
+            .id();
+        commands.entity(log).insert_children(index, &[line]);
+        index += 1;
+    }
+}
+
+fn fade_chat_messages(
+    mut commands: Commands,
+    mut q_line: Query<(Entity, &mut ChatLine, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (entity, mut line, mut text) in q_line.iter_mut() {
+        line.timer.tick(time.delta());
+        if line.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        // Only fade during the last second
+        let alpha = line.timer.remaining_secs().min(1.0);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+use bevy::prelude::Resource;
+
+const USAGE: &str = "Usage: golf [OPTIONS]
+
+Options:
+  --players <N>     Number of players (default 4)
+  --level <PATH>    Play a level from a .ron file instead of showing the menu
+  --seed <SEED>     Seed for all gameplay randomness
+  --headless        Run the simulation without a window
+  --shots <PATH>    Shots to play in headless mode, one `<power> <angle> [left|right]` per line
+  --expect-strokes <N>
+                    Exit with an error unless every player finishes in N strokes
+  --dev             Rebuild the lane whenever the file given with --level changes, and
+                    reload assets/tuning.ron whenever it changes
+  --debug-render    Show Rapier collider outlines from the start (toggle with F4)
+  --stats-csv <PATH>
+                    Write the statistics of every shot to a CSV file when a ball is holed
+  --host <PORT>     Host an online game that others can join
+  --join <ADDRESS>  Play in an online game hosted at ADDRESS (host:port or a join code)
+  --spectate <ADDRESS>
+                    Watch an online game hosted at ADDRESS (host:port or a join code)
+                    without playing
+  --name <NAME>     Name shown to others in online games
+  --benchmark <SECONDS>
+                    Play a stress-test course with many balls, measure frame and physics times
+                    and exit
+  --benchmark-report <PATH>
+                    Where to write the benchmark report (default benchmark.ron)
+  --help            Print this message";
+
+#[derive(Resource, Debug, Default, Clone)]
+pub struct CliArgs {
+    pub players: Option<u32>,
+    pub level: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub headless: bool,
+    pub shots: Option<PathBuf>,
+    pub expect_strokes: Option<u32>,
+    pub dev: bool,
+    pub debug_render: bool,
+    pub stats_csv: Option<PathBuf>,
+    pub host: Option<u16>,
+    pub join: Option<String>,
+    pub spectate: Option<String>,
+    pub name: Option<String>,
+    pub benchmark: Option<f32>,
+    pub benchmark_report: Option<PathBuf>,
+}
+
+impl CliArgs {
+    /// Parses the arguments of the current process, exiting with a usage message on errors.
+    pub fn from_env() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(message) => {
+                eprintln!("{}\n\n{}", message, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut result = CliArgs::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+
+            match arg.as_str() {
+                "--players" => {
+                    let n = value("--players")?;
+                    let n: u32 = n
+                        .parse()
+                        .map_err(|_| format!("Invalid player count: {}", n))?;
+                    if n == 0 {
+                        return Err("There must be at least one player".to_string());
+                    }
+                    result.players = Some(n);
+                }
+                "--level" => result.level = Some(PathBuf::from(value("--level")?)),
+                "--seed" => {
+                    let seed = value("--seed")?;
+                    result.seed = Some(
+                        seed.parse()
+                            .map_err(|_| format!("Invalid seed: {}", seed))?,
+                    );
+                }
+                "--headless" => result.headless = true,
+                "--shots" => result.shots = Some(PathBuf::from(value("--shots")?)),
+                "--expect-strokes" => {
+                    let n = value("--expect-strokes")?;
+                    result.expect_strokes = Some(
+                        n.parse()
+                            .map_err(|_| format!("Invalid stroke count: {}", n))?,
+                    );
+                }
+                "--dev" => result.dev = true,
+                "--debug-render" => result.debug_render = true,
+                "--stats-csv" => result.stats_csv = Some(PathBuf::from(value("--stats-csv")?)),
+                "--host" => {
+                    let port = value("--host")?;
+                    result.host = Some(
+                        port.parse()
+                            .map_err(|_| format!("Invalid port: {}", port))?,
+                    );
+                }
+                "--join" => result.join = Some(value("--join")?),
+                "--spectate" => result.spectate = Some(value("--spectate")?),
+                "--name" => result.name = Some(value("--name")?),
+                "--benchmark" => {
+                    let seconds = value("--benchmark")?;
+                    result.benchmark = Some(
+                        seconds
+                            .parse()
+                            .ok()
+                            .filter(|s: &f32| *s > 0.0)
+                            .ok_or_else(|| format!("Invalid duration: {}", seconds))?,
+                    );
+                }
+                "--benchmark-report" => {
+                    result.benchmark_report = Some(PathBuf::from(value("--benchmark-report")?))
+                }
+                "--help" | "-h" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(result)
+    }
+}
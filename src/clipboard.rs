@@ -0,0 +1,47 @@
+/// Puts text on the clipboard. Returns whether it worked.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn copy(text: &str) -> bool {
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+        Ok(()) => true,
+        Err(e) => {
+            bevy::log::warn!("Could not copy to the clipboard: {}", e);
+            false
+        }
+    }
+}
+
+/// Puts text on the clipboard. Returns whether it worked.
+#[cfg(target_arch = "wasm32")]
+pub fn copy(text: &str) -> bool {
+    // The promise is not waited for, the copy practically never fails after a key press
+    js::write_text(text);
+    true
+}
+
+/// The text on the clipboard, if there is any.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn paste() -> Option<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .ok()
+}
+
+/// Asks for text to be pasted in a dialog, since browsers only hand out the clipboard
+/// asynchronously.
+#[cfg(target_arch = "wasm32")]
+pub fn paste_with_prompt(message: &str) -> Option<String> {
+    js::prompt(message)
+}
+
+#[cfg(target_arch = "wasm32")]
+mod js {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = ["navigator", "clipboard"], js_name = writeText)]
+        pub fn write_text(text: &str) -> JsValue;
+
+        pub fn prompt(message: &str) -> Option<String>;
+    }
+}
@@ -6,6 +6,9 @@ use bevy_rapier3d::{
     prelude::*,
     rapier::prelude::{Isometry, SharedShape},
 };
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
 
 pub fn create_collider_from_gltf_node(
     node: &GltfNode,
@@ -34,3 +37,80 @@ pub fn create_collider_from_gltf_node(
 
     Collider::from(SharedShape::new(trimesh))
 }
+
+/// Collision group that all balls belong to.
+pub const BALL_GROUP: Group = Group::GROUP_2;
+/// Collision group for balls that may hit other balls under `BallCollisionRule::ActiveOnly`.
+pub const ACTIVE_BALL_GROUP: Group = Group::GROUP_3;
+/// Collision group of the pieces of smashed obstacles, which only collide with the course.
+pub const DEBRIS_GROUP: Group = Group::GROUP_12;
+/// Collision group of wall tiles, so that balls can be made to pass through them.
+pub const WALL_GROUP: Group = Group::GROUP_13;
+
+/// Collision groups for player-owned lanes, one per player.
+const PLAYER_GROUPS: [Group; 8] = [
+    Group::GROUP_4,
+    Group::GROUP_5,
+    Group::GROUP_6,
+    Group::GROUP_7,
+    Group::GROUP_8,
+    Group::GROUP_9,
+    Group::GROUP_10,
+    Group::GROUP_11,
+];
+
+/// The group of a player's ball and of the lane geometry that only that ball collides with.
+pub fn player_group(player_id: u32) -> Group {
+    PLAYER_GROUPS[player_id as usize % PLAYER_GROUPS.len()]
+}
+
+fn all_player_groups() -> Group {
+    PLAYER_GROUPS.iter().fold(Group::NONE, |acc, &g| acc | g)
+}
+
+/// Every group a ball may be a member of, for things that must never touch any ball.
+pub fn any_ball_group() -> Group {
+    BALL_GROUP | ACTIVE_BALL_GROUP | all_player_groups()
+}
+
+/// Whether balls can knock each other around.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BallCollisionRule {
+    #[default]
+    Collide,
+    PassThrough,
+    /// Only the ball in play collides with the other balls.
+    ActiveOnly,
+}
+
+impl BallCollisionRule {
+    pub fn next(self) -> Self {
+        match self {
+            BallCollisionRule::Collide => BallCollisionRule::PassThrough,
+            BallCollisionRule::PassThrough => BallCollisionRule::ActiveOnly,
+            BallCollisionRule::ActiveOnly => BallCollisionRule::Collide,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            BallCollisionRule::Collide => "collisions.on",
+            BallCollisionRule::PassThrough => "collisions.off",
+            BallCollisionRule::ActiveOnly => "collisions.active_only",
+        })
+    }
+
+    pub fn ball_groups(self, player_id: u32, active: bool) -> CollisionGroups {
+        let (memberships, filters) = match self {
+            BallCollisionRule::Collide => (BALL_GROUP, Group::ALL),
+            BallCollisionRule::PassThrough => (BALL_GROUP, Group::ALL.difference(BALL_GROUP)),
+            BallCollisionRule::ActiveOnly if active => (BALL_GROUP | ACTIVE_BALL_GROUP, Group::ALL),
+            BallCollisionRule::ActiveOnly => (BALL_GROUP, Group::ALL.difference(BALL_GROUP)),
+        };
+
+        // Never collide with lanes owned by other players
+        let own_group = player_group(player_id);
+        let other_players = all_player_groups().difference(own_group);
+        CollisionGroups::new(memberships | own_group, filters.difference(other_players))
+    }
+}
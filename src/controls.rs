@@ -0,0 +1,688 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{locale::Locale, save};
+
+const SAVE_FILE: &str = "key_bindings.ron";
+
+/// Something a player can do with their ball that a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BallAction {
+    PowerUp,
+    PowerDown,
+    AimLeft,
+    AimRight,
+    SpinLeft,
+    SpinRight,
+    Shoot,
+    Jump,
+    Reset,
+    UsePowerUp,
+    FineAim,
+    CoarseAim,
+}
+
+impl BallAction {
+    pub const ALL: [BallAction; 12] = [
+        BallAction::PowerUp,
+        BallAction::PowerDown,
+        BallAction::AimLeft,
+        BallAction::AimRight,
+        BallAction::SpinLeft,
+        BallAction::SpinRight,
+        BallAction::Shoot,
+        BallAction::Jump,
+        BallAction::Reset,
+        BallAction::UsePowerUp,
+        BallAction::FineAim,
+        BallAction::CoarseAim,
+    ];
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            BallAction::PowerUp => "action.power_up",
+            BallAction::PowerDown => "action.power_down",
+            BallAction::AimLeft => "action.aim_left",
+            BallAction::AimRight => "action.aim_right",
+            BallAction::SpinLeft => "action.spin_left",
+            BallAction::SpinRight => "action.spin_right",
+            BallAction::Shoot => "action.shoot",
+            BallAction::Jump => "action.jump",
+            BallAction::Reset => "action.reset",
+            BallAction::UsePowerUp => "action.use_power_up",
+            BallAction::FineAim => "action.fine_aim",
+            BallAction::CoarseAim => "action.coarse_aim",
+        })
+    }
+}
+
+/// Keys that one player uses to control their ball.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerKeys {
+    pub power_up: KeyCode,
+    pub power_down: KeyCode,
+    pub aim_left: KeyCode,
+    pub aim_right: KeyCode,
+    pub spin_left: KeyCode,
+    pub spin_right: KeyCode,
+    pub shoot: KeyCode,
+    pub jump: KeyCode,
+    pub reset: KeyCode,
+    pub use_power_up: KeyCode,
+    pub fine_aim: KeyCode,
+    pub coarse_aim: KeyCode,
+}
+
+impl PlayerKeys {
+    pub fn key(&self, action: BallAction) -> KeyCode {
+        match action {
+            BallAction::PowerUp => self.power_up,
+            BallAction::PowerDown => self.power_down,
+            BallAction::AimLeft => self.aim_left,
+            BallAction::AimRight => self.aim_right,
+            BallAction::SpinLeft => self.spin_left,
+            BallAction::SpinRight => self.spin_right,
+            BallAction::Shoot => self.shoot,
+            BallAction::Jump => self.jump,
+            BallAction::Reset => self.reset,
+            BallAction::UsePowerUp => self.use_power_up,
+            BallAction::FineAim => self.fine_aim,
+            BallAction::CoarseAim => self.coarse_aim,
+        }
+    }
+
+    pub fn key_mut(&mut self, action: BallAction) -> &mut KeyCode {
+        match action {
+            BallAction::PowerUp => &mut self.power_up,
+            BallAction::PowerDown => &mut self.power_down,
+            BallAction::AimLeft => &mut self.aim_left,
+            BallAction::AimRight => &mut self.aim_right,
+            BallAction::SpinLeft => &mut self.spin_left,
+            BallAction::SpinRight => &mut self.spin_right,
+            BallAction::Shoot => &mut self.shoot,
+            BallAction::Jump => &mut self.jump,
+            BallAction::Reset => &mut self.reset,
+            BallAction::UsePowerUp => &mut self.use_power_up,
+            BallAction::FineAim => &mut self.fine_aim,
+            BallAction::CoarseAim => &mut self.coarse_aim,
+        }
+    }
+}
+
+/// Something the camera or the game can be told to do from the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommonAction {
+    SkipTurn,
+    FreeCamera,
+    CameraForward,
+    CameraBack,
+    CameraLeft,
+    CameraRight,
+    CameraUp,
+    CameraDown,
+    CameraBehindBall,
+    CameraTopDown,
+    CameraSide,
+    Director,
+    ResetStuck,
+    EmoteNiceShot,
+    EmoteLaughing,
+    EmoteCrying,
+    TeeForward,
+    TeeBack,
+    TeeLeft,
+    TeeRight,
+    TogglePaths,
+}
+
+impl CommonAction {
+    pub const ALL: [CommonAction; 21] = [
+        CommonAction::SkipTurn,
+        CommonAction::FreeCamera,
+        CommonAction::CameraForward,
+        CommonAction::CameraBack,
+        CommonAction::CameraLeft,
+        CommonAction::CameraRight,
+        CommonAction::CameraUp,
+        CommonAction::CameraDown,
+        CommonAction::CameraBehindBall,
+        CommonAction::CameraTopDown,
+        CommonAction::CameraSide,
+        CommonAction::Director,
+        CommonAction::ResetStuck,
+        CommonAction::EmoteNiceShot,
+        CommonAction::EmoteLaughing,
+        CommonAction::EmoteCrying,
+        CommonAction::TeeForward,
+        CommonAction::TeeBack,
+        CommonAction::TeeLeft,
+        CommonAction::TeeRight,
+        CommonAction::TogglePaths,
+    ];
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            CommonAction::SkipTurn => "action.skip_turn",
+            CommonAction::FreeCamera => "action.free_camera",
+            CommonAction::CameraForward => "action.camera_forward",
+            CommonAction::CameraBack => "action.camera_back",
+            CommonAction::CameraLeft => "action.camera_left",
+            CommonAction::CameraRight => "action.camera_right",
+            CommonAction::CameraUp => "action.camera_up",
+            CommonAction::CameraDown => "action.camera_down",
+            CommonAction::CameraBehindBall => "action.camera_behind_ball",
+            CommonAction::CameraTopDown => "action.camera_top_down",
+            CommonAction::CameraSide => "action.camera_side",
+            CommonAction::Director => "action.director",
+            CommonAction::ResetStuck => "action.reset_stuck",
+            CommonAction::EmoteNiceShot => "action.emote_nice_shot",
+            CommonAction::EmoteLaughing => "action.emote_laughing",
+            CommonAction::EmoteCrying => "action.emote_crying",
+            CommonAction::TeeForward => "action.tee_forward",
+            CommonAction::TeeBack => "action.tee_back",
+            CommonAction::TeeLeft => "action.tee_left",
+            CommonAction::TeeRight => "action.tee_right",
+            CommonAction::TogglePaths => "action.toggle_paths",
+        })
+    }
+
+    /// Whether the key may also be bound to a ball action. The free camera is meant to be flown
+    /// with the same keys as the first player's ball.
+    fn may_share_ball_key(self) -> bool {
+        matches!(
+            self,
+            CommonAction::CameraForward
+                | CommonAction::CameraBack
+                | CommonAction::CameraLeft
+                | CommonAction::CameraRight
+                | CommonAction::CameraUp
+                | CommonAction::CameraDown
+        )
+    }
+
+    /// Whether the action is only used in turn-based play, where everybody uses the keys of the
+    /// first player.
+    fn turn_based_only(self) -> bool {
+        matches!(
+            self,
+            CommonAction::SkipTurn
+                | CommonAction::TeeForward
+                | CommonAction::TeeBack
+                | CommonAction::TeeLeft
+                | CommonAction::TeeRight
+        )
+    }
+}
+
+/// Keys that aren't tied to a player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommonKeys {
+    pub skip_turn: KeyCode,
+    pub free_camera: KeyCode,
+    pub camera_forward: KeyCode,
+    pub camera_back: KeyCode,
+    pub camera_left: KeyCode,
+    pub camera_right: KeyCode,
+    pub camera_up: KeyCode,
+    pub camera_down: KeyCode,
+    #[serde(default = "CommonKeys::default_camera_behind_ball")]
+    pub camera_behind_ball: KeyCode,
+    #[serde(default = "CommonKeys::default_camera_top_down")]
+    pub camera_top_down: KeyCode,
+    #[serde(default = "CommonKeys::default_camera_side")]
+    pub camera_side: KeyCode,
+    #[serde(default = "CommonKeys::default_director")]
+    pub director: KeyCode,
+    #[serde(default = "CommonKeys::default_reset_stuck")]
+    pub reset_stuck: KeyCode,
+    #[serde(default = "CommonKeys::default_emote_nice_shot")]
+    pub emote_nice_shot: KeyCode,
+    #[serde(default = "CommonKeys::default_emote_laughing")]
+    pub emote_laughing: KeyCode,
+    #[serde(default = "CommonKeys::default_emote_crying")]
+    pub emote_crying: KeyCode,
+    #[serde(default = "CommonKeys::default_tee_forward")]
+    pub tee_forward: KeyCode,
+    #[serde(default = "CommonKeys::default_tee_back")]
+    pub tee_back: KeyCode,
+    #[serde(default = "CommonKeys::default_tee_left")]
+    pub tee_left: KeyCode,
+    #[serde(default = "CommonKeys::default_tee_right")]
+    pub tee_right: KeyCode,
+    #[serde(default = "CommonKeys::default_toggle_paths")]
+    pub toggle_paths: KeyCode,
+}
+
+impl Default for CommonKeys {
+    fn default() -> Self {
+        Self {
+            skip_turn: KeyCode::C,
+            free_camera: KeyCode::F,
+            camera_forward: KeyCode::W,
+            camera_back: KeyCode::S,
+            camera_left: KeyCode::A,
+            camera_right: KeyCode::D,
+            camera_up: KeyCode::E,
+            camera_down: KeyCode::Q,
+            camera_behind_ball: Self::default_camera_behind_ball(),
+            camera_top_down: Self::default_camera_top_down(),
+            camera_side: Self::default_camera_side(),
+            director: Self::default_director(),
+            reset_stuck: Self::default_reset_stuck(),
+            emote_nice_shot: Self::default_emote_nice_shot(),
+            emote_laughing: Self::default_emote_laughing(),
+            emote_crying: Self::default_emote_crying(),
+            tee_forward: Self::default_tee_forward(),
+            tee_back: Self::default_tee_back(),
+            tee_left: Self::default_tee_left(),
+            tee_right: Self::default_tee_right(),
+            toggle_paths: Self::default_toggle_paths(),
+        }
+    }
+}
+
+impl CommonKeys {
+    fn default_camera_behind_ball() -> KeyCode {
+        KeyCode::Key1
+    }
+
+    fn default_camera_top_down() -> KeyCode {
+        KeyCode::Key2
+    }
+
+    fn default_camera_side() -> KeyCode {
+        KeyCode::Key3
+    }
+
+    fn default_director() -> KeyCode {
+        KeyCode::V
+    }
+
+    fn default_reset_stuck() -> KeyCode {
+        KeyCode::R
+    }
+
+    fn default_emote_nice_shot() -> KeyCode {
+        KeyCode::Key7
+    }
+
+    fn default_emote_laughing() -> KeyCode {
+        KeyCode::Key8
+    }
+
+    fn default_emote_crying() -> KeyCode {
+        KeyCode::Key9
+    }
+
+    fn default_tee_forward() -> KeyCode {
+        KeyCode::Up
+    }
+
+    fn default_tee_back() -> KeyCode {
+        KeyCode::Down
+    }
+
+    fn default_tee_left() -> KeyCode {
+        KeyCode::Left
+    }
+
+    fn default_tee_right() -> KeyCode {
+        KeyCode::Right
+    }
+
+    fn default_toggle_paths() -> KeyCode {
+        KeyCode::T
+    }
+
+    pub fn key(&self, action: CommonAction) -> KeyCode {
+        match action {
+            CommonAction::SkipTurn => self.skip_turn,
+            CommonAction::FreeCamera => self.free_camera,
+            CommonAction::CameraForward => self.camera_forward,
+            CommonAction::CameraBack => self.camera_back,
+            CommonAction::CameraLeft => self.camera_left,
+            CommonAction::CameraRight => self.camera_right,
+            CommonAction::CameraUp => self.camera_up,
+            CommonAction::CameraDown => self.camera_down,
+            CommonAction::CameraBehindBall => self.camera_behind_ball,
+            CommonAction::CameraTopDown => self.camera_top_down,
+            CommonAction::CameraSide => self.camera_side,
+            CommonAction::Director => self.director,
+            CommonAction::ResetStuck => self.reset_stuck,
+            CommonAction::EmoteNiceShot => self.emote_nice_shot,
+            CommonAction::EmoteLaughing => self.emote_laughing,
+            CommonAction::EmoteCrying => self.emote_crying,
+            CommonAction::TeeForward => self.tee_forward,
+            CommonAction::TeeBack => self.tee_back,
+            CommonAction::TeeLeft => self.tee_left,
+            CommonAction::TeeRight => self.tee_right,
+            CommonAction::TogglePaths => self.toggle_paths,
+        }
+    }
+
+    pub fn key_mut(&mut self, action: CommonAction) -> &mut KeyCode {
+        match action {
+            CommonAction::SkipTurn => &mut self.skip_turn,
+            CommonAction::FreeCamera => &mut self.free_camera,
+            CommonAction::CameraForward => &mut self.camera_forward,
+            CommonAction::CameraBack => &mut self.camera_back,
+            CommonAction::CameraLeft => &mut self.camera_left,
+            CommonAction::CameraRight => &mut self.camera_right,
+            CommonAction::CameraUp => &mut self.camera_up,
+            CommonAction::CameraDown => &mut self.camera_down,
+            CommonAction::CameraBehindBall => &mut self.camera_behind_ball,
+            CommonAction::CameraTopDown => &mut self.camera_top_down,
+            CommonAction::CameraSide => &mut self.camera_side,
+            CommonAction::Director => &mut self.director,
+            CommonAction::ResetStuck => &mut self.reset_stuck,
+            CommonAction::EmoteNiceShot => &mut self.emote_nice_shot,
+            CommonAction::EmoteLaughing => &mut self.emote_laughing,
+            CommonAction::EmoteCrying => &mut self.emote_crying,
+            CommonAction::TeeForward => &mut self.tee_forward,
+            CommonAction::TeeBack => &mut self.tee_back,
+            CommonAction::TeeLeft => &mut self.tee_left,
+            CommonAction::TeeRight => &mut self.tee_right,
+            CommonAction::TogglePaths => &mut self.toggle_paths,
+        }
+    }
+
+    pub fn pressed(&self, keys: &Input<KeyCode>, action: CommonAction) -> bool {
+        keys.pressed(self.key(action))
+    }
+
+    pub fn just_pressed(&self, keys: &Input<KeyCode>, action: CommonAction) -> bool {
+        keys.just_pressed(self.key(action))
+    }
+}
+
+/// `PlayerKeys` as saved, where the keys that could only be bound later are missing from older
+/// saves.
+#[derive(Deserialize)]
+#[serde(rename = "PlayerKeys")]
+struct SavedPlayerKeys {
+    power_up: KeyCode,
+    power_down: KeyCode,
+    aim_left: KeyCode,
+    aim_right: KeyCode,
+    spin_left: KeyCode,
+    spin_right: KeyCode,
+    shoot: KeyCode,
+    jump: KeyCode,
+    reset: KeyCode,
+    use_power_up: KeyCode,
+    #[serde(default, deserialize_with = "some_key")]
+    fine_aim: Option<KeyCode>,
+    #[serde(default, deserialize_with = "some_key")]
+    coarse_aim: Option<KeyCode>,
+}
+
+fn some_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<KeyCode>, D::Error> {
+    KeyCode::deserialize(deserializer).map(Some)
+}
+
+/// Reads the keys of all players, giving each player their own default for keys missing from
+/// the save.
+fn deserialize_players<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<PlayerKeys>, D::Error> {
+    let defaults = KeyBindings::default();
+    let saved = Vec::<SavedPlayerKeys>::deserialize(deserializer)?;
+    let players = saved
+        .into_iter()
+        .enumerate()
+        .map(|(index, saved)| {
+            let default = defaults.get(index);
+            let missing = [
+                saved.fine_aim.is_none().then_some(BallAction::FineAim),
+                saved.coarse_aim.is_none().then_some(BallAction::CoarseAim),
+            ];
+            let mut keys = PlayerKeys {
+                power_up: saved.power_up,
+                power_down: saved.power_down,
+                aim_left: saved.aim_left,
+                aim_right: saved.aim_right,
+                spin_left: saved.spin_left,
+                spin_right: saved.spin_right,
+                shoot: saved.shoot,
+                jump: saved.jump,
+                reset: saved.reset,
+                use_power_up: saved.use_power_up,
+                fine_aim: saved.fine_aim.unwrap_or(default.fine_aim),
+                coarse_aim: saved.coarse_aim.unwrap_or(default.coarse_aim),
+            };
+            // Keys that the filled in ones took over, e.g. Shift from jumping, go to their new
+            // defaults
+            for filled in missing.into_iter().flatten() {
+                for action in BallAction::ALL {
+                    if action != filled && keys.key(action) == keys.key(filled) {
+                        *keys.key_mut(action) = default.key(action);
+                    }
+                }
+            }
+            keys
+        })
+        .collect();
+    Ok(players)
+}
+
+/// All keys the players have bound, saved between runs. In turn-based play everybody shares
+/// the keys of the first player.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct KeyBindings {
+    #[serde(deserialize_with = "deserialize_players")]
+    pub players: Vec<PlayerKeys>,
+    #[serde(default)]
+    pub common: CommonKeys,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let players = vec![
+            PlayerKeys {
+                power_up: KeyCode::W,
+                power_down: KeyCode::S,
+                aim_left: KeyCode::A,
+                aim_right: KeyCode::D,
+                spin_left: KeyCode::Q,
+                spin_right: KeyCode::E,
+                shoot: KeyCode::Space,
+                jump: KeyCode::Z,
+                reset: KeyCode::Escape,
+                use_power_up: KeyCode::X,
+                fine_aim: KeyCode::LShift,
+                coarse_aim: KeyCode::LControl,
+            },
+            PlayerKeys {
+                power_up: KeyCode::Up,
+                power_down: KeyCode::Down,
+                aim_left: KeyCode::Left,
+                aim_right: KeyCode::Right,
+                spin_left: KeyCode::Comma,
+                spin_right: KeyCode::Period,
+                shoot: KeyCode::RShift,
+                jump: KeyCode::RControl,
+                reset: KeyCode::Slash,
+                use_power_up: KeyCode::RAlt,
+                fine_aim: KeyCode::End,
+                coarse_aim: KeyCode::PageDown,
+            },
+            PlayerKeys {
+                power_up: KeyCode::I,
+                power_down: KeyCode::K,
+                aim_left: KeyCode::J,
+                aim_right: KeyCode::L,
+                spin_left: KeyCode::U,
+                spin_right: KeyCode::O,
+                shoot: KeyCode::H,
+                jump: KeyCode::B,
+                reset: KeyCode::Y,
+                use_power_up: KeyCode::M,
+                fine_aim: KeyCode::N,
+                coarse_aim: KeyCode::G,
+            },
+            PlayerKeys {
+                power_up: KeyCode::Numpad8,
+                power_down: KeyCode::Numpad5,
+                aim_left: KeyCode::Numpad4,
+                aim_right: KeyCode::Numpad6,
+                spin_left: KeyCode::Numpad7,
+                spin_right: KeyCode::Numpad9,
+                shoot: KeyCode::Numpad0,
+                jump: KeyCode::NumpadEnter,
+                reset: KeyCode::NumpadDecimal,
+                use_power_up: KeyCode::NumpadAdd,
+                fine_aim: KeyCode::Numpad1,
+                coarse_aim: KeyCode::Numpad3,
+            },
+        ];
+        Self {
+            players,
+            common: CommonKeys::default(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn load() -> Self {
+        save::load(SAVE_FILE)
+    }
+
+    pub fn store(&self) {
+        save::store(SAVE_FILE, self);
+    }
+
+    pub fn get(&self, index: usize) -> &PlayerKeys {
+        &self.players[index % self.players.len()]
+    }
+
+    /// The player and ball action that `key` is bound to, if binding it to the common `action`
+    /// as well would make one key do two things at once.
+    pub fn common_conflict(
+        &self,
+        action: CommonAction,
+        key: KeyCode,
+    ) -> Option<(usize, BallAction)> {
+        if action.may_share_ball_key() {
+            return None;
+        }
+        let num_players = if action.turn_based_only() {
+            1
+        } else {
+            self.players.len()
+        };
+        self.players
+            .iter()
+            .take(num_players)
+            .enumerate()
+            .find_map(|(index, player_keys)| {
+                let ball_action = BallAction::ALL
+                    .into_iter()
+                    .find(|&ball_action| player_keys.key(ball_action) == key)?;
+                Some((index, ball_action))
+            })
+    }
+
+    /// The common action that `key` is bound to, if binding it to a ball action of the player
+    /// at `index` as well would make one key do two things at once.
+    pub fn ball_conflict(&self, index: usize, key: KeyCode) -> Option<CommonAction> {
+        CommonAction::ALL.into_iter().find(|&action| {
+            !action.may_share_ball_key()
+                && (index == 0 || !action.turn_based_only())
+                && self.common.key(action) == key
+        })
+    }
+}
+
+/// What a player wants to do with their ball this frame, independent of the input device.
+#[derive(Debug, Default)]
+pub struct BallControls {
+    /// Change in power, from -1 to 1.
+    pub power: f32,
+    /// Change in aim angle, from -1 (right) to 1 (left).
+    pub aim: f32,
+    /// Turns the aim slower, for lining up precisely.
+    pub fine: bool,
+    /// Coarse steps to turn the aim this frame, -1 (right) or 1 (left).
+    pub aim_step: f32,
+    /// Change in spin while held, from -1 (left) to 1 (right).
+    pub spin: f32,
+    pub shoot: bool,
+    /// Held to charge a jump, which happens when it is released.
+    pub jump: bool,
+    pub reset: bool,
+    pub use_power_up: bool,
+}
+
+impl BallControls {
+    pub fn from_keys(keys: &Input<KeyCode>, map: &PlayerKeys) -> Self {
+        let axis = |pos: KeyCode, neg: KeyCode| {
+            keys.pressed(pos) as i32 as f32 - keys.pressed(neg) as i32 as f32
+        };
+        Self {
+            power: axis(map.power_up, map.power_down),
+            aim: axis(map.aim_left, map.aim_right),
+            spin: axis(map.spin_right, map.spin_left),
+            shoot: keys.just_pressed(map.shoot),
+            jump: keys.pressed(map.jump),
+            reset: keys.just_pressed(map.reset),
+            use_power_up: keys.just_pressed(map.use_power_up),
+            ..default()
+        }
+    }
+
+    /// The fine aim key to aim finely, and the coarse aim key to turn the aim in coarse steps
+    /// with the aim keys.
+    pub fn with_aim_modifiers(mut self, keys: &Input<KeyCode>, map: &PlayerKeys) -> Self {
+        self.fine = keys.pressed(map.fine_aim);
+        if keys.pressed(map.coarse_aim) {
+            self.aim_step = keys.just_pressed(map.aim_left) as i32 as f32
+                - keys.just_pressed(map.aim_right) as i32 as f32;
+            self.aim = 0.0;
+        }
+        self
+    }
+
+    /// Left stick for power and aim, shoulder buttons for spin, south/east to shoot/reset,
+    /// north to jump and west to use a power-up. Pressing the left stick aims finely and the
+    /// d-pad turns the aim in coarse steps.
+    pub fn from_gamepad(
+        gamepad: Gamepad,
+        axes: &Axis<GamepadAxis>,
+        buttons: &Input<GamepadButton>,
+    ) -> Self {
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        let pressed = |button_type| buttons.pressed(GamepadButton::new(gamepad, button_type));
+        let just_pressed =
+            |button_type| buttons.just_pressed(GamepadButton::new(gamepad, button_type));
+        Self {
+            power: axis(GamepadAxisType::LeftStickY),
+            aim: -axis(GamepadAxisType::LeftStickX),
+            fine: pressed(GamepadButtonType::LeftThumb),
+            aim_step: just_pressed(GamepadButtonType::DPadLeft) as i32 as f32
+                - just_pressed(GamepadButtonType::DPadRight) as i32 as f32,
+            spin: pressed(GamepadButtonType::RightTrigger) as i32 as f32
+                - pressed(GamepadButtonType::LeftTrigger) as i32 as f32,
+            shoot: just_pressed(GamepadButtonType::South),
+            jump: pressed(GamepadButtonType::North),
+            reset: just_pressed(GamepadButtonType::East),
+            use_power_up: just_pressed(GamepadButtonType::West),
+        }
+    }
+
+    pub fn merge(self, other: BallControls) -> Self {
+        Self {
+            power: (self.power + other.power).clamp(-1.0, 1.0),
+            aim: (self.aim + other.aim).clamp(-1.0, 1.0),
+            fine: self.fine || other.fine,
+            aim_step: (self.aim_step + other.aim_step).clamp(-1.0, 1.0),
+            spin: (self.spin + other.spin).clamp(-1.0, 1.0),
+            shoot: self.shoot || other.shoot,
+            jump: self.jump || other.jump,
+            reset: self.reset || other.reset,
+            use_power_up: self.use_power_up || other.use_power_up,
+        }
+    }
+}
@@ -0,0 +1,541 @@
+use bevy::prelude::*;
+
+use crate::{
+    clipboard,
+    level::{CurrentLevel, Difficulty, Direction, LaneConfig, LanePart, Level},
+    library,
+    locale::Locale,
+    menu::{BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    scoring::GameState,
+    surface::SurfaceType,
+    ui, AppState, GameMode,
+};
+
+const EXPORT_KEY: KeyCode = KeyCode::F6;
+
+/// Marks course codes, and which version of the code format they hold.
+const CODE_PREFIX: &str = "GOLF2-";
+/// Marks the first course codes, which held the whole level as RON. They can still be played.
+const RON_CODE_PREFIX: &str = "GOLF1-";
+/// The URL-safe base64 alphabet, so that codes survive being pasted into links and chats.
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Shares courses as text. The course being played is copied to the clipboard as a code, and a
+/// code pasted into the box in the menu is played right away.
+///
+/// A code holds the lanes and their surfaces, packed into a few bytes per tile, along with the
+/// par and the name, author, description and difficulty shown when the course is opened. The
+/// rest of the level, e.g. holes, tee zones, pickups and props, follows as RON, so that the
+/// course plays the same for whoever it is shared with.
+pub struct CourseCodePlugin;
+
+impl Plugin for CourseCodePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ImportBox>()
+            .add_systems(OnExit(AppState::Menu), close_import_box)
+            .add_systems(
+                Update,
+                (
+                    export_course.run_if(in_state(AppState::InGame)),
+                    (import_course_button, edit_import_box)
+                        .chain()
+                        .run_if(in_state(AppState::Menu)),
+                ),
+            );
+    }
+}
+
+/// Opens the box to paste a course code into.
+#[derive(Component)]
+pub struct ImportCourseButton;
+
+/// The course code being pasted or typed in the menu, if the box is open.
+#[derive(Resource, Default)]
+pub struct ImportBox {
+    open: bool,
+    text: String,
+}
+
+/// Run condition for menu input that should be ignored while a code is typed.
+pub fn not_importing(import: Res<ImportBox>) -> bool {
+    !import.open
+}
+
+#[derive(Component)]
+struct ImportBoxRoot;
+
+#[derive(Component)]
+struct ImportBoxText;
+
+/// A code that stands for the level, e.g. `GOLF2-BFRlc3QD...`.
+pub fn encode(level: &Level) -> String {
+    let mut bytes = Vec::new();
+    write_string(&mut bytes, &level.name);
+    write_string(&mut bytes, &level.author);
+    write_string(&mut bytes, &level.description);
+    // 0 for unrated courses
+    let difficulty = level
+        .difficulty
+        .and_then(|d| Difficulty::ALL.iter().position(|&other| other == d));
+    bytes.push(difficulty.map_or(0, |index| index as u8 + 1));
+    write_varint(&mut bytes, level.par as u64);
+    write_tiles(
+        &mut bytes,
+        level
+            .lanes
+            .0
+            .iter()
+            .map(|&(position, part)| (position, part_to_byte(part))),
+    );
+    write_tiles(
+        &mut bytes,
+        level.surfaces.iter().map(|&(position, surface)| {
+            let index = SurfaceType::ALL.iter().position(|&s| s == surface);
+            (position, index.unwrap_or(0) as u8)
+        }),
+    );
+    // Everything else, e.g. holes, tee zones and pickups, as RON without what is written above
+    let rest = Level {
+        name: String::new(),
+        author: String::new(),
+        difficulty: None,
+        description: String::new(),
+        lanes: LaneConfig::default(),
+        surfaces: Vec::new(),
+        ..level.clone()
+    };
+    let rest = ron::to_string(&rest).expect("levels can always be serialized");
+    write_string(&mut bytes, &rest);
+    format!("{}{}", CODE_PREFIX, to_base64(&bytes))
+}
+
+/// The level a code stands for. Whitespace in the code is ignored, since long codes are often
+/// broken into lines when shared.
+pub fn decode(code: &str) -> Result<Level, String> {
+    let code: String = code.split_whitespace().collect();
+    if let Some(data) = code.strip_prefix(RON_CODE_PREFIX) {
+        let bytes = from_base64(data).ok_or_else(|| "The course code is damaged".to_string())?;
+        let text = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+        return ron::from_str(&text).map_err(|e| format!("Could not read the course: {}", e));
+    }
+    let data = code
+        .strip_prefix(CODE_PREFIX)
+        .ok_or_else(|| "Not a course code".to_string())?;
+    let bytes = from_base64(data).ok_or_else(|| "The course code is damaged".to_string())?;
+    read_level(&mut Reader { bytes: &bytes })
+        .ok_or_else(|| "The course code is damaged".to_string())
+}
+
+fn read_level(reader: &mut Reader) -> Option<Level> {
+    let name = reader.string()?;
+    let author = reader.string()?;
+    let description = reader.string()?;
+    let difficulty = match reader.byte()? {
+        0 => None,
+        index => Some(*Difficulty::ALL.get(index as usize - 1)?),
+    };
+    let par = reader.varint()? as u32;
+    let lanes = read_tiles(reader, part_from_byte)?;
+    let surfaces = read_tiles(reader, |index| {
+        SurfaceType::ALL.get(index as usize).copied()
+    })?;
+
+    let rest: Level = ron::from_str(&reader.string()?).ok()?;
+
+    Some(Level {
+        name,
+        author,
+        difficulty,
+        description,
+        lanes: LaneConfig(lanes),
+        par,
+        surfaces,
+        ..rest
+    })
+}
+
+/// The lane part kind times four plus the direction, for the parts that have one.
+fn part_to_byte(part: LanePart) -> u8 {
+    let (kind, direction) = match part {
+        LanePart::BasicFloor => (0, Direction::Up),
+        LanePart::HoleFloor => (1, Direction::Up),
+        LanePart::Checkpoint => (2, Direction::Up),
+        LanePart::Wall(direction) => (3, direction),
+        LanePart::Tunnel(direction) => (4, direction),
+        LanePart::HalfPipe(direction) => (5, direction),
+        LanePart::Fan(direction) => (6, direction),
+    };
+    kind * 4 + direction as u8
+}
+
+fn part_from_byte(byte: u8) -> Option<LanePart> {
+    const DIRECTIONS: [Direction; 4] = [
+        Direction::Up,
+        Direction::Left,
+        Direction::Down,
+        Direction::Right,
+    ];
+    let direction = DIRECTIONS[(byte % 4) as usize];
+    Some(match byte / 4 {
+        0 => LanePart::BasicFloor,
+        1 => LanePart::HoleFloor,
+        2 => LanePart::Checkpoint,
+        3 => LanePart::Wall(direction),
+        4 => LanePart::Tunnel(direction),
+        5 => LanePart::HalfPipe(direction),
+        6 => LanePart::Fan(direction),
+        _ => return None,
+    })
+}
+
+/// Tiles as their count followed by each one's offset from the tile before it and a byte for
+/// what is there. Neighbouring tiles mostly follow each other, so the offsets take a byte each.
+fn write_tiles(bytes: &mut Vec<u8>, tiles: impl ExactSizeIterator<Item = ((i32, i32, i32), u8)>) {
+    write_varint(bytes, tiles.len() as u64);
+    let mut previous = (0, 0, 0);
+    for (position, value) in tiles {
+        for (coord, previous_coord) in [
+            (position.0, previous.0),
+            (position.1, previous.1),
+            (position.2, previous.2),
+        ] {
+            write_varint(bytes, zigzag(coord as i64 - previous_coord as i64));
+        }
+        bytes.push(value);
+        previous = position;
+    }
+}
+
+fn read_tiles<T>(
+    reader: &mut Reader,
+    value: impl Fn(u8) -> Option<T>,
+) -> Option<Vec<((i32, i32, i32), T)>> {
+    let count = reader.varint()? as usize;
+    // Every tile takes at least four bytes, so a damaged count can't make a huge vector
+    let mut tiles = Vec::with_capacity(count.min(reader.bytes.len() / 4));
+    let mut previous = (0, 0, 0);
+    for _ in 0..count {
+        let mut coord = |previous_coord: i32| -> Option<i32> {
+            i32::try_from(previous_coord as i64 + unzigzag(reader.varint()?)).ok()
+        };
+        let position = (coord(previous.0)?, coord(previous.1)?, coord(previous.2)?);
+        tiles.push((position, value(reader.byte()?)?));
+        previous = position;
+    }
+    Some(tiles)
+}
+
+/// Seven bits per byte, with the top bit set on all but the last.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// A varint length followed by the UTF-8 bytes.
+fn write_string(bytes: &mut Vec<u8>, text: &str) {
+    write_varint(bytes, text.len() as u64);
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+/// Small negative numbers as small positive ones, so that they fit in one varint byte.
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        if count > self.bytes.len() {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = usize::try_from(self.varint()?).ok()?;
+        String::from_utf8(self.take(length)?.to_vec()).ok()
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+pub fn to_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() * 4 / 3 + 3);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        // Without padding, n bytes take n + 1 letters
+        for i in 0..=chunk.len() {
+            text.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    text
+}
+
+pub fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u32> = text
+        .bytes()
+        .map(|c| {
+            BASE64_ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .map(|d| d as u32)
+        })
+        .collect::<Option<_>>()?;
+
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        if chunk.len() < 2 {
+            return None;
+        }
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &d)| n | d << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+fn export_course(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    current_level: Res<CurrentLevel>,
+    locale: Res<Locale>,
+) {
+    if !keys.just_pressed(EXPORT_KEY) {
+        return;
+    }
+    let code = encode(&current_level.0);
+    let message = if clipboard::copy(&code) {
+        locale.get("course_code.copied")
+    } else {
+        // The code can still be copied from the log
+        info!("Course code: {}", code);
+        locale.get("course_code.copy_failed")
+    };
+    ui::spawn_toast(&mut commands, message.to_string(), Color::WHITE, 1);
+}
+
+/// Starts playing the course of a code, or says what is wrong with it.
+fn play_code(
+    commands: &mut Commands,
+    next_state: &mut NextState<AppState>,
+    code: &str,
+    num_players: u32,
+    locale: &Locale,
+) -> bool {
+    match decode(code) {
+        Ok(level) => {
+            info!("Playing a shared course: {}", level.name);
+            if !level.name.is_empty() {
+                let details = library::details(&level, locale);
+                let message = if details.is_empty() {
+                    level.name.clone()
+                } else {
+                    format!("{}\n{}", level.name, details)
+                };
+                ui::spawn_toast(commands, message, Color::WHITE, 1);
+            }
+            commands.insert_resource(CurrentLevel(level));
+            commands.insert_resource(GameState::new(num_players));
+            commands.insert_resource(GameMode::Standard);
+            next_state.set(AppState::InGame);
+            true
+        }
+        Err(e) => {
+            info!("{}", e);
+            let message = locale.get("course_code.invalid").to_string();
+            ui::spawn_toast(commands, message, Color::RED, 1);
+            false
+        }
+    }
+}
+
+fn import_course_button(
+    mut commands: Commands,
+    mut q_button: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ImportCourseButton>),
+    >,
+    mut import: ResMut<ImportBox>,
+    #[cfg(target_arch = "wasm32")] game_state: Res<GameState>,
+    #[cfg(target_arch = "wasm32")] mut next_state: ResMut<NextState<AppState>>,
+    locale: Res<Locale>,
+) {
+    for (interaction, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed if !import.open => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    import.open = true;
+                    import.text.clear();
+                    spawn_import_box(&mut commands, &locale);
+                }
+                #[cfg(target_arch = "wasm32")]
+                if let Some(code) = clipboard::paste_with_prompt(locale.get("course_code.prompt")) {
+                    play_code(
+                        &mut commands,
+                        &mut next_state,
+                        &code,
+                        game_state.num_players,
+                        &locale,
+                    );
+                }
+            }
+            Interaction::Pressed => {}
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+fn spawn_import_box(commands: &mut Commands, locale: &Locale) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(ImportBoxRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("course_code.import_hint"),
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                ImportBoxText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn edit_import_box(
+    mut commands: Commands,
+    mut chars: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut import: ResMut<ImportBox>,
+    mut q_text: Query<&mut Text, With<ImportBoxText>>,
+    q_box: Query<Entity, With<ImportBoxRoot>>,
+    game_state: Res<GameState>,
+    mut next_state: ResMut<NextState<AppState>>,
+    locale: Res<Locale>,
+) {
+    if !import.open {
+        chars.clear();
+        return;
+    }
+
+    let ctrl = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if ctrl && keys.just_pressed(KeyCode::V) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(text) = clipboard::paste() {
+            import.text = text.trim().to_string();
+        }
+    } else {
+        for c in chars.iter().filter(|c| !c.char.is_control()) {
+            import.text.push(c.char);
+        }
+    }
+    chars.clear();
+    if keys.just_pressed(KeyCode::Back) {
+        import.text.pop();
+    }
+
+    let close = if keys.just_pressed(KeyCode::Escape) {
+        true
+    } else if keys.just_pressed(KeyCode::Return) {
+        let code = import.text.clone();
+        play_code(
+            &mut commands,
+            &mut next_state,
+            &code,
+            game_state.num_players,
+            &locale,
+        )
+    } else {
+        false
+    };
+    if close {
+        import.open = false;
+        for entity in q_box.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    if import.is_changed() {
+        // Codes are long, so only their end is shown
+        let shown: String = {
+            let count = import.text.chars().count();
+            import.text.chars().skip(count.saturating_sub(60)).collect()
+        };
+        for mut text in q_text.iter_mut() {
+            text.sections[0].value = format!("{}_", shown);
+        }
+    }
+}
+
+fn close_import_box(
+    mut commands: Commands,
+    mut import: ResMut<ImportBox>,
+    q_box: Query<Entity, With<ImportBoxRoot>>,
+) {
+    import.open = false;
+    for entity in q_box.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
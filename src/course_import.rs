@@ -0,0 +1,166 @@
+use bevy::{prelude::*, scene::SceneInstance};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::Ball,
+    collision,
+    level::{self, CurrentLevel, Hole, LevelEntity, Wall, FLOOR_TOP},
+    scoring::GameState,
+    surface::SurfaceType,
+    AppState,
+};
+
+/// Plays courses modeled as a whole in a glTF scene instead of being built from tiles.
+///
+/// What a node of the scene is for is told by the start of its name:
+/// - `floor_`: something to roll on, gets a collider from its mesh
+/// - `wall_`: something to bounce off, gets a collider from its mesh
+/// - `hole_`: an empty at the top center of a cup modeled into a floor
+/// - `tee_`: an empty where the balls start
+/// - `deco_`: only for looks, like any node without one of the names above
+pub struct CourseImportPlugin;
+
+impl Plugin for CourseImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), spawn_course_scene)
+            .add_systems(
+                Update,
+                (tag_course_nodes, build_course_nodes).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// The scene of the course, until its nodes have been tagged.
+#[derive(Component)]
+struct PendingCourse;
+
+/// A node of the course scene that has a purpose in the game.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum CourseNode {
+    Floor,
+    Wall,
+    Hole,
+    Tee,
+}
+
+impl CourseNode {
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            ("floor_", CourseNode::Floor),
+            ("wall_", CourseNode::Wall),
+            ("hole_", CourseNode::Hole),
+            ("tee_", CourseNode::Tee),
+        ]
+        .into_iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, node)| node)
+    }
+}
+
+fn spawn_course_scene(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    current_level: Res<CurrentLevel>,
+) {
+    if let Some(path) = &current_level.0.course_scene {
+        commands.spawn((
+            LevelEntity,
+            PendingCourse,
+            SceneBundle {
+                scene: asset_server.load(path.as_str()),
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Tags the named nodes once the scene has been spawned. They are built on the next frame, when
+/// their global transforms are known.
+fn tag_course_nodes(
+    mut commands: Commands,
+    q_pending: Query<(Entity, &SceneInstance), With<PendingCourse>>,
+    q_name: Query<&Name>,
+    scene_manager: Res<SceneSpawner>,
+) {
+    for (entity, instance) in q_pending.iter() {
+        if !scene_manager.instance_is_ready(**instance) {
+            continue;
+        }
+        commands.entity(entity).remove::<PendingCourse>();
+
+        for node in scene_manager.iter_instance_entities(**instance) {
+            if let Some(kind) = q_name
+                .get(node)
+                .ok()
+                .and_then(|name| CourseNode::from_name(name.as_str()))
+            {
+                commands.entity(node).insert(kind);
+            }
+        }
+    }
+}
+
+fn build_course_nodes(
+    mut commands: Commands,
+    q_node: Query<(Entity, &CourseNode, &GlobalTransform), Added<CourseNode>>,
+    q_children: Query<&Children>,
+    q_mesh: Query<&Handle<Mesh>>,
+    mut q_ball: Query<(&Ball, &mut Transform, &mut Velocity)>,
+    meshes: Res<Assets<Mesh>>,
+    mut game_state: ResMut<GameState>,
+) {
+    for (entity, &kind, transform) in q_node.iter() {
+        match kind {
+            CourseNode::Floor | CourseNode::Wall => {
+                // The meshes of a node are either on the node itself or on one child per primitive
+                let mesh_entities = std::iter::once(entity)
+                    .chain(q_children.get(entity).into_iter().flatten().copied());
+                for mesh_entity in mesh_entities {
+                    let Some(collider) = q_mesh
+                        .get(mesh_entity)
+                        .ok()
+                        .and_then(|handle| meshes.get(handle))
+                        .and_then(|mesh| {
+                            Collider::from_bevy_mesh(mesh, &ComputedColliderShape::TriMesh)
+                        })
+                    else {
+                        continue;
+                    };
+
+                    let mut node = commands.entity(mesh_entity);
+                    node.insert((collider, ActiveEvents::COLLISION_EVENTS));
+                    if kind == CourseNode::Floor {
+                        node.insert((SurfaceType::default(), Friction::new(1.0)));
+                    } else {
+                        node.insert((
+                            Wall,
+                            CollisionGroups::new(collision::WALL_GROUP, Group::ALL),
+                        ));
+                    }
+                }
+            }
+            CourseNode::Hole => {
+                let position = transform.translation();
+                let hole = Hole::at(level::nearest_tile(position));
+                let entities =
+                    level::spawn_hole(&mut commands, hole, position - Vec3::Y * FLOOR_TOP, None);
+                for entity in entities {
+                    commands.entity(entity).insert(LevelEntity);
+                }
+            }
+            CourseNode::Tee => {
+                let tee = transform.translation();
+                for (ball, mut ball_transform, mut velocity) in q_ball.iter_mut() {
+                    if ball.hits > 0 {
+                        continue;
+                    }
+                    // Keep the spread of the balls around the start
+                    let offset = ball_transform.translation * Vec3::new(1.0, 0.0, 1.0);
+                    ball_transform.translation = tee + offset + Vec3::Y * 0.1;
+                    *velocity = Velocity::zero();
+                    game_state.players[ball.player_id as usize].last_pos = tee;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::BallStyle,
+    camera::CameraController,
+    locale::Locale,
+    menu::{self, BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    save,
+    scoring::{GameState, Handicaps},
+    AppState, NeedsColorChange,
+};
+
+const SAVE_FILE: &str = "ball_styles.ron";
+
+/// How far in front of the camera the preview ball floats.
+const PREVIEW_DISTANCE: f32 = 0.5;
+const PREVIEW_SCALE: f32 = 2.0;
+/// In radians per second.
+const PREVIEW_TURN_SPEED: f32 = 1.0;
+
+/// Skins that come with the game. Natively, any image put in `assets/skins/` can be picked too.
+const BUILTIN_SKINS: &[&str] = &["stripes.png", "dots.png"];
+
+/// A screen before the game where every player picks the look of their ball and their handicap.
+pub struct CustomizePlugin;
+
+impl Plugin for CustomizePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<BallStyles>(SAVE_FILE))
+            .add_systems(OnEnter(AppState::Customize), setup_customize_screen)
+            .add_systems(OnExit(AppState::Customize), cleanup_customize_screen)
+            .add_systems(
+                Update,
+                (
+                    customize_buttons,
+                    update_style_text,
+                    update_preview,
+                    rotate_preview,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Customize)),
+            );
+    }
+}
+
+/// The style each player picked. Players without one get a random style from the seed.
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct BallStyles(Vec<Option<BallStyle>>);
+
+impl BallStyles {
+    pub fn get(&self, player_id: u32) -> Option<BallStyle> {
+        self.0.get(player_id as usize).cloned().flatten()
+    }
+
+    pub fn set(&mut self, player_id: u32, style: BallStyle) {
+        let index = player_id as usize;
+        if self.0.len() <= index {
+            self.0.resize(index + 1, None);
+        }
+        self.0[index] = Some(style);
+    }
+}
+
+/// The player whose ball is being edited.
+#[derive(Resource)]
+struct Selection {
+    player_id: u32,
+}
+
+#[derive(Component)]
+struct CustomizeRoot;
+
+#[derive(Component)]
+struct StyleText;
+
+#[derive(Component)]
+struct ColorSwatch;
+
+#[derive(Component)]
+struct PreviewBall;
+
+#[derive(Component, Clone, Copy)]
+enum CustomizeButton {
+    PreviousPlayer,
+    NextPlayer,
+    Shape,
+    Hue(f32),
+    Saturation(f32),
+    Lightness(f32),
+    Finish,
+    Skin,
+    Handicap(i32),
+    Done,
+}
+
+/// File names of the skins in `assets/skins/`, sorted by name.
+fn available_skins() -> Vec<String> {
+    let mut skins: Vec<String> = BUILTIN_SKINS.iter().map(|s| s.to_string()).collect();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(entries) = std::fs::read_dir("assets/skins") {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".png") && !skins.contains(&name) {
+                skins.push(name);
+            }
+        }
+    }
+
+    skins.sort();
+    skins
+}
+
+/// The skin after `current`, going through no skin after the last one.
+fn next_skin(current: Option<&String>) -> Option<String> {
+    let skins = available_skins();
+    let next_index = match current {
+        Some(current) => skins.iter().position(|s| s == current).map_or(0, |i| i + 1),
+        None => 0,
+    };
+    skins.get(next_index).cloned()
+}
+
+/// The style a player starts out with before changing anything.
+pub fn initial_style(player_id: u32) -> BallStyle {
+    BallStyle {
+        hue: (player_id * 90 % 360) as f32,
+        ..default()
+    }
+}
+
+fn setup_customize_screen(mut commands: Commands, locale: Res<Locale>) {
+    commands.insert_resource(Selection { player_id: 0 });
+
+    commands
+        .spawn((
+            CustomizeRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexStart,
+                    justify_content: JustifyContent::Center,
+                    padding: UiRect::left(Val::Px(40.0)),
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                StyleText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 28.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+            parent.spawn((
+                ColorSwatch,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(260.0),
+                        height: Val::Px(24.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+
+            spawn_row(parent, |row| {
+                spawn_small_button(row, CustomizeButton::PreviousPlayer, "<");
+                spawn_small_button(row, CustomizeButton::NextPlayer, ">");
+                spawn_label(row, locale.get("customize.player"));
+            });
+            menu::spawn_button(
+                parent,
+                CustomizeButton::Shape,
+                locale.get("customize.shape"),
+            );
+            for (label_key, minus, plus) in [
+                (
+                    "customize.hue",
+                    CustomizeButton::Hue(-15.0),
+                    CustomizeButton::Hue(15.0),
+                ),
+                (
+                    "customize.saturation",
+                    CustomizeButton::Saturation(-0.1),
+                    CustomizeButton::Saturation(0.1),
+                ),
+                (
+                    "customize.lightness",
+                    CustomizeButton::Lightness(-0.1),
+                    CustomizeButton::Lightness(0.1),
+                ),
+            ] {
+                spawn_row(parent, |row| {
+                    spawn_small_button(row, minus, "-");
+                    spawn_small_button(row, plus, "+");
+                    spawn_label(row, locale.get(label_key));
+                });
+            }
+            menu::spawn_button(
+                parent,
+                CustomizeButton::Finish,
+                locale.get("customize.finish"),
+            );
+            menu::spawn_button(parent, CustomizeButton::Skin, locale.get("customize.skin"));
+            spawn_row(parent, |row| {
+                spawn_small_button(row, CustomizeButton::Handicap(-1), "-");
+                spawn_small_button(row, CustomizeButton::Handicap(1), "+");
+                spawn_label(row, locale.get("customize.handicap"));
+            });
+            menu::spawn_button(parent, CustomizeButton::Done, locale.get("customize.done"));
+        });
+}
+
+fn spawn_row(parent: &mut ChildBuilder, children: impl FnOnce(&mut ChildBuilder)) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(children);
+}
+
+fn spawn_label(parent: &mut ChildBuilder, label: &str) {
+    parent.spawn(TextBundle::from_section(
+        label,
+        TextStyle {
+            font_size: 28.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    ));
+}
+
+fn spawn_small_button(parent: &mut ChildBuilder, button: CustomizeButton, label: &str) {
+    parent
+        .spawn((
+            button,
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(50.0),
+                    height: Val::Px(50.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            spawn_label(parent, label);
+        });
+}
+
+fn customize_buttons(
+    mut q_button: Query<
+        (&Interaction, &CustomizeButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    keys: Res<Input<KeyCode>>,
+    mut selection: ResMut<Selection>,
+    mut styles: ResMut<BallStyles>,
+    mut handicaps: ResMut<Handicaps>,
+    game_state: Res<GameState>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let player_id = selection.player_id;
+    let num_players = game_state.num_players.max(1);
+    let current = styles
+        .get(player_id)
+        .unwrap_or_else(|| initial_style(player_id));
+    let mut style = current.clone();
+
+    for (interaction, button, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match *button {
+                CustomizeButton::PreviousPlayer => {
+                    selection.player_id = (player_id + num_players - 1) % num_players;
+                }
+                CustomizeButton::NextPlayer => {
+                    selection.player_id = (player_id + 1) % num_players;
+                }
+                CustomizeButton::Shape => style.shape = style.shape.next(),
+                CustomizeButton::Hue(delta) => style.hue = (style.hue + delta).rem_euclid(360.0),
+                CustomizeButton::Saturation(delta) => {
+                    style.saturation = (style.saturation + delta).clamp(0.0, 1.0)
+                }
+                CustomizeButton::Lightness(delta) => {
+                    style.lightness = (style.lightness + delta).clamp(0.0, 1.0)
+                }
+                CustomizeButton::Finish => style.finish = style.finish.next(),
+                CustomizeButton::Skin => style.skin = next_skin(style.skin.as_ref()),
+                CustomizeButton::Handicap(delta) => handicaps.adjust(player_id, delta),
+                CustomizeButton::Done => next_state.set(AppState::Menu),
+            },
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+
+    if style != current {
+        styles.set(player_id, style);
+    }
+}
+
+fn update_style_text(
+    mut q_text: Query<&mut Text, With<StyleText>>,
+    mut q_swatch: Query<&mut BackgroundColor, With<ColorSwatch>>,
+    selection: Res<Selection>,
+    styles: Res<BallStyles>,
+    handicaps: Res<Handicaps>,
+    locale: Res<Locale>,
+) {
+    if !selection.is_changed() && !styles.is_changed() && !handicaps.is_changed() {
+        return;
+    }
+    let style = styles
+        .get(selection.player_id)
+        .unwrap_or_else(|| initial_style(selection.player_id));
+
+    for mut text in q_text.iter_mut() {
+        let skin = match &style.skin {
+            Some(skin) => skin.as_str(),
+            None => locale.get("customize.no_skin"),
+        };
+        text.sections[0].value = locale.format(
+            "customize.style",
+            &[
+                ("player", &(selection.player_id + 1)),
+                ("shape", &style.shape.name(&locale)),
+                ("shape_hint", &style.shape.hint(&locale)),
+                ("hue", &format!("{:.0}", style.hue)),
+                ("saturation", &format!("{:.0}", style.saturation * 100.0)),
+                ("lightness", &format!("{:.0}", style.lightness * 100.0)),
+                ("finish", &style.finish.name(&locale)),
+                ("skin", &skin),
+                ("handicap", &handicaps.get(selection.player_id)),
+            ],
+        );
+    }
+    for mut background in q_swatch.iter_mut() {
+        *background = style.color().into();
+    }
+}
+
+/// Replaces the preview ball whenever the edited style changes.
+fn update_preview(
+    mut commands: Commands,
+    q_preview: Query<(Entity, &Transform), With<PreviewBall>>,
+    q_camera: Query<&Transform, With<CameraController>>,
+    asset_server: Res<AssetServer>,
+    selection: Res<Selection>,
+    styles: Res<BallStyles>,
+) {
+    if !selection.is_changed() && !styles.is_changed() {
+        return;
+    }
+    let Ok(camera) = q_camera.get_single() else {
+        return;
+    };
+    let style = styles
+        .get(selection.player_id)
+        .unwrap_or_else(|| initial_style(selection.player_id));
+
+    let mut rotation = Quat::IDENTITY;
+    for (entity, transform) in q_preview.iter() {
+        rotation = transform.rotation;
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let position = camera.translation
+        + camera.forward() * PREVIEW_DISTANCE
+        + camera.right() * PREVIEW_DISTANCE * 0.3;
+    let preview = commands
+        .spawn((
+            PreviewBall,
+            SceneBundle {
+                scene: asset_server.load(style.shape.scene_path()),
+                transform: Transform::from_translation(position)
+                    .with_rotation(rotation)
+                    .with_scale(Vec3::splat(PREVIEW_SCALE)),
+                ..default()
+            },
+            NeedsColorChange(style.color()),
+            style.finish,
+        ))
+        .id();
+
+    if let Some(skin) = style.skin(&asset_server) {
+        commands.entity(preview).insert(skin);
+    }
+}
+
+fn rotate_preview(mut q_preview: Query<&mut Transform, With<PreviewBall>>, time: Res<Time>) {
+    for mut transform in q_preview.iter_mut() {
+        transform.rotate_y(PREVIEW_TURN_SPEED * time.delta_seconds());
+    }
+}
+
+fn cleanup_customize_screen(
+    mut commands: Commands,
+    q_entities: Query<Entity, Or<(With<CustomizeRoot>, With<PreviewBall>)>>,
+    styles: Res<BallStyles>,
+) {
+    for entity in q_entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Selection>();
+    save::store(SAVE_FILE, &*styles);
+}
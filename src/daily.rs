@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    level::CurrentLevel,
+    locale::Locale,
+    menu::{BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    procgen, save,
+    scoring::{self, BallHoled, GameState},
+    ui, AppState, GameMode, GameSeed,
+};
+
+const LEADERBOARD_FILE: &str = "leaderboard.ron";
+
+/// Scores kept per daily course.
+const LEADERBOARD_SIZE: usize = 10;
+
+/// A random course that is the same for everybody on the same day.
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<Leaderboard>(LEADERBOARD_FILE))
+            .add_systems(OnExit(AppState::InGame), end_daily_challenge)
+            .add_systems(
+                Update,
+                (
+                    daily_button.run_if(in_state(AppState::Menu)),
+                    submit_daily_scores
+                        .after(scoring::check_ball_in_hole)
+                        .run_if(in_state(AppState::InGame))
+                        .run_if(resource_exists::<DailyChallenge>()),
+                ),
+            );
+    }
+}
+
+/// Set while the daily course of `day` is played.
+#[derive(Resource)]
+pub struct DailyChallenge {
+    pub day: u64,
+}
+
+/// The best scores on each daily course, fewest strokes first, keyed by the day.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    daily: BTreeMap<u64, Vec<u32>>,
+}
+
+impl Leaderboard {
+    /// Adds a score to the bucket of `day`, returning its place if it made the list.
+    fn submit(&mut self, day: u64, score: u32) -> Option<usize> {
+        let scores = self.daily.entry(day).or_default();
+        let place = scores.partition_point(|&s| s <= score);
+        if place >= LEADERBOARD_SIZE {
+            return None;
+        }
+        scores.insert(place, score);
+        scores.truncate(LEADERBOARD_SIZE);
+        Some(place)
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct DailyButton;
+
+/// Days since 1970-01-01 in UTC, so that the day changes at the same moment everywhere.
+pub fn today() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_millis() as f64);
+    #[cfg(target_arch = "wasm32")]
+    let millis = js_date::now();
+
+    (millis / 86_400_000.0) as u64
+}
+
+#[cfg(target_arch = "wasm32")]
+mod js_date {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = Date)]
+        pub fn now() -> f64;
+    }
+}
+
+/// The seed of the course of `day`. It must never change, or old daily scores stop matching.
+pub fn seed_for_day(day: u64) -> u64 {
+    (day ^ 0x6461_696C_7900_0000).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+/// The date of `day` as year-month-day.
+pub fn date_label(day: u64) -> String {
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let d = day_of_year - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = year_of_era + era * 400 + i64::from(m <= 2);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+fn daily_button(
+    mut commands: Commands,
+    mut q_button: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<DailyButton>),
+    >,
+    game_state: Res<GameState>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                let day = today();
+                let seed = seed_for_day(day);
+                info!("Playing the daily course of {}", date_label(day));
+                let mut level = procgen::generate_level(seed, &procgen::CourseSettings::default());
+                level.name = format!("Daily {}", date_label(day));
+                commands.insert_resource(CurrentLevel(level));
+                // The balls come from the same seed, so that they are the same for everybody too
+                commands.insert_resource(GameSeed(seed));
+                commands.insert_resource(GameState::new(game_state.num_players));
+                commands.insert_resource(GameMode::Standard);
+                commands.insert_resource(DailyChallenge { day });
+                next_state.set(AppState::InGame);
+            }
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+/// Only scores from the game the daily course was started in count.
+fn end_daily_challenge(mut commands: Commands) {
+    commands.remove_resource::<DailyChallenge>();
+}
+
+fn submit_daily_scores(
+    mut commands: Commands,
+    mut holed: EventReader<BallHoled>,
+    daily: Res<DailyChallenge>,
+    mut leaderboard: ResMut<Leaderboard>,
+    locale: Res<Locale>,
+) {
+    let mut changed = false;
+    for event in holed.iter() {
+        let message = match leaderboard.submit(daily.day, event.score) {
+            Some(place) => {
+                changed = true;
+                locale.format(
+                    "daily.placed",
+                    &[
+                        ("player", &(event.player_id + 1)),
+                        ("place", &(place + 1)),
+                        ("date", &date_label(daily.day)),
+                    ],
+                )
+            }
+            None => locale.format(
+                "daily.not_placed",
+                &[
+                    ("player", &(event.player_id + 1)),
+                    ("count", &LEADERBOARD_SIZE),
+                ],
+            ),
+        };
+        ui::spawn_toast(&mut commands, message, Color::GOLD, 3);
+    }
+    if changed {
+        save::store(LEADERBOARD_FILE, &*leaderboard);
+    }
+}
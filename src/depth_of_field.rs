@@ -0,0 +1,287 @@
+use bevy::{
+    core_pipeline::{
+        core_3d, fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+        prepass::ViewPrepassTextures,
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, ViewNode, ViewNodeRunner,
+        },
+        render_resource::*,
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::ViewTarget,
+        RenderApp,
+    },
+};
+
+use crate::{ball::Ball, scoring::GameState, AppState};
+
+/// Blurs the picture of cameras with a `DepthOfField` more the further things are from the
+/// ball of the current player, which is kept in focus.
+pub struct DepthOfFieldPlugin;
+
+impl Plugin for DepthOfFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<DepthOfField>::default(),
+            UniformComponentPlugin::<DepthOfField>::default(),
+        ))
+        .add_systems(Update, focus_on_ball.run_if(in_state(AppState::InGame)));
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<DepthOfFieldNode>>(
+                core_3d::graph::NAME,
+                DepthOfFieldNode::NAME,
+            )
+            .add_render_graph_edges(
+                core_3d::graph::NAME,
+                &[
+                    core_3d::graph::node::TONEMAPPING,
+                    DepthOfFieldNode::NAME,
+                    core_3d::graph::node::END_MAIN_PASS_POST_PROCESSING,
+                ],
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<DepthOfFieldPipeline>();
+    }
+}
+
+/// Needs a `DepthPrepass` on the same camera.
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct DepthOfField {
+    /// Distance from the camera that is in focus.
+    focus_distance: f32,
+    /// From 0 to 1.
+    strength: f32,
+    /// Near plane of the camera, for turning depth into distance.
+    near: f32,
+}
+
+impl DepthOfField {
+    pub fn new(strength: f32) -> Self {
+        Self {
+            focus_distance: 1.0,
+            strength: strength.clamp(0.0, 1.0),
+            near: 0.1,
+        }
+    }
+}
+
+fn focus_on_ball(
+    mut q_camera: Query<(&mut DepthOfField, &GlobalTransform, &Projection)>,
+    q_ball: Query<(&Ball, &GlobalTransform)>,
+    game_state: Res<GameState>,
+) {
+    let Some((_, ball_transform)) = q_ball
+        .iter()
+        .find(|(ball, _)| ball.player_id == game_state.current_player)
+    else {
+        return;
+    };
+    for (mut depth_of_field, camera_transform, projection) in q_camera.iter_mut() {
+        depth_of_field.focus_distance = camera_transform
+            .translation()
+            .distance(ball_transform.translation());
+        if let Projection::Perspective(perspective) = projection {
+            depth_of_field.near = perspective.near;
+        }
+    }
+}
+
+#[derive(Default)]
+struct DepthOfFieldNode;
+
+impl DepthOfFieldNode {
+    const NAME: &str = "depth_of_field";
+}
+
+impl ViewNode for DepthOfFieldNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewPrepassTextures,
+        &'static DynamicUniformIndex<DepthOfField>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, prepass_textures, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let dof_pipeline = world.resource::<DepthOfFieldPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let settings = world.resource::<ComponentUniforms<DepthOfField>>();
+        let (Some(depth), Some(settings)) =
+            (&prepass_textures.depth, settings.uniforms().binding())
+        else {
+            return Ok(());
+        };
+
+        // The depth texture is multisampled when MSAA is on, which needs its own pipeline
+        let multisampled = (depth.texture.sample_count() > 1) as usize;
+        let hdr = view_target.is_hdr() as usize;
+        let layout = &dof_pipeline.layouts[multisampled];
+        let pipeline_id = dof_pipeline.pipeline_ids[multisampled][hdr];
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context
+            .render_device()
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("depth_of_field_bind_group"),
+                layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(post_process.source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&dof_pipeline.sampler),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: BindingResource::TextureView(&depth.default_view),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: settings,
+                    },
+                ],
+            });
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("depth_of_field_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
+
+/// Layouts by whether the depth is multisampled, and pipelines by that and whether the view is
+/// HDR, since both can change with the graphics quality.
+#[derive(Resource)]
+struct DepthOfFieldPipeline {
+    sampler: Sampler,
+    layouts: [BindGroupLayout; 2],
+    pipeline_ids: [[CachedRenderPipelineId; 2]; 2],
+}
+
+impl FromWorld for DepthOfFieldPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+        let layouts =
+            [false, true].map(|multisampled| bind_group_layout(render_device, multisampled));
+
+        let shader = world
+            .resource::<AssetServer>()
+            .load("shaders/depth_of_field.wgsl");
+        let descriptor = |multisampled: bool, hdr: bool| {
+            let shader_defs = if multisampled {
+                vec!["MULTISAMPLED".into()]
+            } else {
+                vec![]
+            };
+            RenderPipelineDescriptor {
+                label: Some("depth_of_field_pipeline".into()),
+                layout: vec![layouts[multisampled as usize].clone()],
+                vertex: fullscreen_shader_vertex_state(),
+                fragment: Some(FragmentState {
+                    shader: shader.clone(),
+                    shader_defs,
+                    entry_point: "fragment".into(),
+                    targets: vec![Some(ColorTargetState {
+                        format: if hdr {
+                            ViewTarget::TEXTURE_FORMAT_HDR
+                        } else {
+                            TextureFormat::bevy_default()
+                        },
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                push_constant_ranges: vec![],
+            }
+        };
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline_ids = [false, true].map(|multisampled| {
+            [false, true]
+                .map(|hdr| pipeline_cache.queue_render_pipeline(descriptor(multisampled, hdr)))
+        });
+
+        Self {
+            sampler,
+            layouts,
+            pipeline_ids,
+        }
+    }
+}
+
+fn bind_group_layout(render_device: &RenderDevice, multisampled: bool) -> BindGroupLayout {
+    let entry = |binding: u32, ty: BindingType| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::FRAGMENT,
+        ty,
+        count: None,
+    };
+    render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("depth_of_field_bind_group_layout"),
+        entries: &[
+            entry(
+                0,
+                BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            ),
+            entry(1, BindingType::Sampler(SamplerBindingType::Filtering)),
+            entry(
+                2,
+                BindingType::Texture {
+                    sample_type: TextureSampleType::Depth,
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled,
+                },
+            ),
+            entry(
+                3,
+                BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: Some(DepthOfField::min_size()),
+                },
+            ),
+        ],
+    })
+}
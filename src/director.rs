@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{Ball, BallState},
+    camera::{self, CameraController},
+    chat,
+    controls::{CommonAction, KeyBindings},
+    flythrough::Flythrough,
+    level::Hole,
+    online::NetRole,
+    replay::ReplayPlayback,
+    spectator::FreeCamera,
+    AppState, GameMode,
+};
+
+/// Shortest time between two cuts, so that the picture doesn't jump back and forth.
+const MIN_SHOT_SECONDS: f32 = 1.5;
+/// How many times as interesting another ball must be for the camera to cut to it.
+const CUT_MARGIN: f32 = 1.5;
+/// Balls moving closer than this to a hole might be about to drop in.
+const NEAR_HOLE_DISTANCE: f32 = 0.6;
+/// Interest of a ball right at a hole, compared to the speed of other balls.
+const NEAR_HOLE_INTEREST: f32 = 5.0;
+/// How quickly the camera glides to its next position, per second.
+const SMOOTHING: f32 = 3.0;
+
+/// A "TV director" camera for party play, replays and spectators, which picks the most
+/// interesting ball to look at and cuts between angles on its own.
+pub struct DirectorPlugin;
+
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            start_directing.run_if(resource_exists::<ReplayPlayback>()),
+        )
+        .add_systems(OnExit(AppState::InGame), stop_directing)
+        .add_systems(
+            Update,
+            (
+                toggle_director
+                    .run_if(director_allowed)
+                    .run_if(chat::not_typing),
+                direct_camera
+                    .after(camera::move_camera_to_ball)
+                    .before(camera::apply_camera_shake),
+            ),
+        );
+    }
+}
+
+/// Moves the camera in place of the usual controller.
+#[derive(Component)]
+pub struct Director {
+    subject: Option<Entity>,
+    angle: Angle,
+    /// The angle of the next cut to a ball that is not near a hole.
+    next_angle: Angle,
+    /// Runs out when the camera may cut again.
+    shot_timer: Timer,
+    /// Where the camera is looking, which glides just like the camera itself.
+    focus: Vec3,
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Self {
+            subject: None,
+            angle: Angle::Wide,
+            next_angle: Angle::Chase,
+            shot_timer: Timer::from_seconds(MIN_SHOT_SECONDS, TimerMode::Once),
+            focus: Vec3::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Angle {
+    /// Behind the ball, looking where it is going.
+    Chase,
+    /// Beside the ball as it passes by.
+    Side,
+    /// Looking down at the ball from above.
+    High,
+    /// Low behind the hole, looking at the ball coming towards it.
+    Hole,
+    /// Every ball in view, while none of them is moving.
+    Wide,
+}
+
+impl Angle {
+    fn next(self) -> Self {
+        match self {
+            Angle::Chase => Angle::Side,
+            Angle::Side => Angle::High,
+            _ => Angle::Chase,
+        }
+    }
+}
+
+fn director_allowed(
+    game_mode: Res<GameMode>,
+    role: Res<NetRole>,
+    playback: Option<Res<ReplayPlayback>>,
+) -> bool {
+    *game_mode == GameMode::Party || *role == NetRole::Spectator || playback.is_some()
+}
+
+fn start_directing(
+    mut commands: Commands,
+    q_camera: Query<(Entity, &Transform), With<CameraController>>,
+) {
+    for (entity, transform) in q_camera.iter() {
+        commands
+            .entity(entity)
+            .remove::<FreeCamera>()
+            .insert(Director {
+                focus: transform.translation + transform.forward(),
+                ..default()
+            });
+    }
+}
+
+fn stop_directing(mut commands: Commands, q_camera: Query<Entity, With<Director>>) {
+    for entity in q_camera.iter() {
+        commands.entity(entity).remove::<Director>();
+    }
+}
+
+fn toggle_director(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    q_camera: Query<(Entity, &Transform, Option<&Director>), With<CameraController>>,
+) {
+    if !bindings.common.just_pressed(&keys, CommonAction::Director) {
+        return;
+    }
+    for (entity, transform, director) in q_camera.iter() {
+        if director.is_some() {
+            commands.entity(entity).remove::<Director>();
+        } else {
+            commands
+                .entity(entity)
+                .remove::<FreeCamera>()
+                .insert(Director {
+                    focus: transform.translation + transform.forward(),
+                    ..default()
+                });
+        }
+    }
+}
+
+fn nearest_hole(holes: &[Vec3], position: Vec3) -> Option<Vec3> {
+    holes.iter().copied().min_by(|a, b| {
+        a.distance_squared(position)
+            .total_cmp(&b.distance_squared(position))
+    })
+}
+
+/// How much a ball is worth looking at: moving fast, and even more so close to a hole.
+fn interest(position: Vec3, velocity: &Velocity, state: BallState, holes: &[Vec3]) -> f32 {
+    if state != BallState::Moving {
+        return 0.0;
+    }
+    let near_hole = nearest_hole(holes, position).map_or(0.0, |hole| {
+        (1.0 - hole.distance(position) / NEAR_HOLE_DISTANCE).max(0.0)
+    });
+    velocity.linvel.length() + near_hole * NEAR_HOLE_INTEREST
+}
+
+fn direct_camera(
+    // The free camera takes over from the director, should both ever be on
+    mut q_camera: Query<
+        (&mut Director, &mut Transform),
+        (Without<Ball>, Without<Flythrough>, Without<FreeCamera>),
+    >,
+    q_ball: Query<(Entity, &Transform, &Velocity, &BallState), With<Ball>>,
+    q_hole: Query<&GlobalTransform, With<Hole>>,
+    time: Res<Time>,
+) {
+    let Ok((mut director, mut transform)) = q_camera.get_single_mut() else {
+        return;
+    };
+    director.shot_timer.tick(time.delta());
+
+    let holes: Vec<Vec3> = q_hole.iter().map(|hole| hole.translation()).collect();
+    let interest_of = |entity: Entity| {
+        q_ball
+            .get(entity)
+            .map_or(0.0, |(_, ball, velocity, &state)| {
+                interest(ball.translation, velocity, state, &holes)
+            })
+    };
+
+    let current = director.subject.map_or(0.0, &interest_of);
+    let best = q_ball
+        .iter()
+        .map(|(entity, ..)| (entity, interest_of(entity)))
+        .filter(|&(_, interest)| interest > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    if director.shot_timer.finished() {
+        let cut_to = match best {
+            Some((entity, interest))
+                if director.subject != Some(entity)
+                    && (current == 0.0 || interest > current * CUT_MARGIN) =>
+            {
+                Some(Some(entity))
+            }
+            None if current == 0.0 && director.subject.is_some() => Some(None),
+            _ => None,
+        };
+        if let Some(subject) = cut_to {
+            director.subject = subject;
+            director.angle = Angle::Wide;
+            if let Some((_, ball, ..)) = subject.and_then(|entity| q_ball.get(entity).ok()) {
+                let close_to_hole = nearest_hole(&holes, ball.translation).map_or(false, |hole| {
+                    hole.distance(ball.translation) < NEAR_HOLE_DISTANCE
+                });
+                director.angle = if close_to_hole {
+                    Angle::Hole
+                } else {
+                    let angle = director.next_angle;
+                    director.next_angle = angle.next();
+                    angle
+                };
+            }
+            director.shot_timer.reset();
+        }
+    }
+
+    let subject = director.subject.and_then(|entity| q_ball.get(entity).ok());
+    let (eye, focus) = match subject {
+        Some((_, ball, velocity, _)) => {
+            let position = ball.translation;
+            let heading = Vec3::new(velocity.linvel.x, 0.0, velocity.linvel.z)
+                .try_normalize()
+                .unwrap_or(Vec3::Z);
+            let eye = match director.angle {
+                Angle::Chase => position - heading * 0.8 + Vec3::Y * 0.4,
+                Angle::Side => position + heading.cross(Vec3::Y) * 0.8 + Vec3::Y * 0.25,
+                Angle::High => position - heading * 0.2 + Vec3::Y * 1.5,
+                Angle::Hole => match nearest_hole(&holes, position) {
+                    Some(hole) => {
+                        let away = Vec3::new(hole.x - position.x, 0.0, hole.z - position.z)
+                            .try_normalize()
+                            .unwrap_or(heading);
+                        hole + away * 0.6 + Vec3::Y * 0.2
+                    }
+                    None => position - heading * 0.8 + Vec3::Y * 0.4,
+                },
+                Angle::Wide => position + Vec3::new(0.0, 1.0, 0.6),
+            };
+            (eye, position)
+        }
+        None => {
+            let positions: Vec<Vec3> = q_ball.iter().map(|(_, t, ..)| t.translation).collect();
+            if positions.is_empty() {
+                return;
+            }
+            let center = positions.iter().sum::<Vec3>() / positions.len() as f32;
+            let spread = positions
+                .iter()
+                .map(|p| p.distance(center))
+                .fold(0.0, f32::max);
+            let eye = center + Vec3::new(0.0, 1.0, 0.6).normalize() * (spread * 2.0 + 1.0);
+            (eye, center)
+        }
+    };
+
+    let amount = 1.0 - (-SMOOTHING * time.delta_seconds()).exp();
+    transform.translation = transform.translation.lerp(eye, amount);
+    director.focus = director.focus.lerp(focus, amount);
+    transform.look_at(director.focus, Vec3::Y);
+}
@@ -0,0 +1,179 @@
+use bevy::{
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode, WindowMoved, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{locale::Locale, save};
+
+const SAVE_FILE: &str = "display.ron";
+
+/// Window sizes to pick from, also used as the resolution in fullscreen.
+const RESOLUTIONS: [(u32, u32); 5] = [
+    (1280, 720),
+    (1600, 900),
+    (1920, 1080),
+    (2560, 1440),
+    (3840, 2160),
+];
+
+/// How long the window has to stay put before its size and position are saved.
+const SAVE_DELAY_SECONDS: f32 = 1.0;
+
+/// Applies the window settings of the native game and remembers where the window was left.
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                apply_display_settings.run_if(resource_changed::<DisplaySettings>()),
+                track_window,
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn next(self) -> Self {
+        match self {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            DisplayMode::Borderless => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            DisplayMode::Windowed => "window.windowed",
+            DisplayMode::Borderless => "window.borderless",
+            DisplayMode::Fullscreen => "window.fullscreen",
+        })
+    }
+
+    fn window_mode(self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::SizedFullscreen,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplaySettings {
+    pub mode: DisplayMode,
+    /// Size of the window, or the resolution in fullscreen.
+    pub size: (u32, u32),
+    pub vsync: bool,
+    /// Where the window was last left, if it was moved.
+    #[serde(default)]
+    pub position: Option<(i32, i32)>,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::default(),
+            size: RESOLUTIONS[0],
+            vsync: true,
+            position: None,
+        }
+    }
+}
+
+impl DisplaySettings {
+    pub fn load() -> Self {
+        save::load(SAVE_FILE)
+    }
+
+    /// The next of the preset sizes after the current one.
+    pub fn next_size(&self) -> (u32, u32) {
+        let next = RESOLUTIONS
+            .iter()
+            .position(|&size| size == self.size)
+            .map_or(0, |i| (i + 1) % RESOLUTIONS.len());
+        RESOLUTIONS[next]
+    }
+
+    pub fn size_name(&self) -> String {
+        format!("{}x{}", self.size.0, self.size.1)
+    }
+
+    /// Sets up a window with these settings. The browser decides all of this on its own.
+    pub fn apply(&self, window: &mut Window) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        window.mode = self.mode.window_mode();
+        window
+            .resolution
+            .set(self.size.0 as f32, self.size.1 as f32);
+        window.present_mode = if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+        if let Some((x, y)) = self.position {
+            window.position = WindowPosition::At(IVec2::new(x, y));
+        }
+    }
+}
+
+fn apply_display_settings(
+    settings: Res<DisplaySettings>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    // The window was made with these settings already
+    if settings.is_added() {
+        return;
+    }
+    if let Ok(mut window) = q_window.get_single_mut() {
+        settings.apply(&mut window);
+    }
+    save::store(SAVE_FILE, &*settings);
+}
+
+/// Keeps the settings up to date with where the player puts the window, and saves them once
+/// the window has stopped moving.
+fn track_window(
+    mut resized: EventReader<WindowResized>,
+    mut moved: EventReader<WindowMoved>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut settings: ResMut<DisplaySettings>,
+    mut save_timer: Local<Option<Timer>>,
+    time: Res<Time>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    let changed = resized.iter().count() + moved.iter().count() > 0;
+    if changed && window.mode == WindowMode::Windowed {
+        // Not a change to apply, since it already happened to the window
+        let settings = settings.bypass_change_detection();
+        settings.size = (
+            window.resolution.width() as u32,
+            window.resolution.height() as u32,
+        );
+        if let WindowPosition::At(position) = window.position {
+            settings.position = Some((position.x, position.y));
+        }
+        *save_timer = Some(Timer::from_seconds(SAVE_DELAY_SECONDS, TimerMode::Once));
+    }
+
+    if let Some(timer) = save_timer.as_mut() {
+        if timer.tick(time.delta()).just_finished() {
+            save::store(SAVE_FILE, &*settings);
+            *save_timer = None;
+        }
+    }
+}
@@ -0,0 +1,808 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::CameraController,
+    fan::FanCycle,
+    level::{
+        tile_center, CurrentLevel, Difficulty, Direction, LaneChanged, LanePart, Level, TeeZone,
+        LEVEL_HEIGHT,
+    },
+    level_file, library,
+    locale::Locale,
+    scoring::GameState,
+    surface::SurfaceType,
+    thumbnail::{self, TakeThumbnail},
+    ui,
+    validation::{self, Problem},
+    AppState, GameMode,
+};
+
+/// Distance of the camera from the cursor before zooming.
+const CAMERA_DISTANCE: f32 = 3.0;
+const CURSOR_COLOR: Color = Color::YELLOW;
+/// Height above the floor that balls are dropped from in a play test, as in the default tee.
+const TEE_DROP_HEIGHT: f32 = 0.7;
+/// How many edits can be undone.
+const MAX_UNDO_STEPS: usize = 100;
+/// Starts playing from the cursor in the editor, and goes back to editing while playing.
+const PLAY_TEST_KEY: KeyCode = KeyCode::F5;
+/// Opens and closes the fields for the name, author, difficulty and description of the course.
+const METADATA_KEY: KeyCode = KeyCode::F2;
+
+/// A tile editor for the courses in the library. A cursor is moved over the tile grid with the
+/// keyboard, placing and removing tiles of the chosen kind, and the course is saved back to the
+/// library.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Editor), setup_editor)
+            .add_systems(OnExit(AppState::Editor), cleanup_editor)
+            .add_systems(
+                Update,
+                (
+                    move_cursor.run_if(not_editing_metadata),
+                    edit_tiles.run_if(not_editing_metadata),
+                    save_and_leave.run_if(not_editing_metadata),
+                    start_play_test.run_if(not_editing_metadata),
+                    edit_metadata,
+                    update_editor_text,
+                    move_editor_camera,
+                    draw_cursor.run_if(thumbnail::not_capturing),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Editor)),
+            )
+            .add_systems(
+                Update,
+                end_play_test
+                    .run_if(in_state(AppState::InGame).and_then(resource_exists::<PlayTest>())),
+            );
+    }
+}
+
+/// The course being edited.
+#[derive(Resource)]
+pub struct Editor {
+    /// The id of the course in the library.
+    pub id: String,
+    /// The level as authored, without the walls that are added around the lane when played.
+    pub level: Level,
+    pub cursor: (i32, i32, i32),
+    brush: Brush,
+    direction: Direction,
+    /// Whether there are changes that are not saved yet.
+    unsaved: bool,
+    /// Set after Escape was pressed once with unsaved changes.
+    leaving: bool,
+    /// What looks wrong with the level, checked after every change.
+    problems: Vec<Problem>,
+    /// Set after saving was asked for once while there are problems.
+    saving_anyway: bool,
+    /// The field being typed in, while the metadata is being edited.
+    metadata_field: Option<MetadataField>,
+    /// The metadata from before it was edited, to be able to undo it all at once.
+    metadata_before: Metadata,
+    /// The latest edit is last, and is the first to be undone.
+    undo_stack: VecDeque<EditCommand>,
+    redo_stack: Vec<EditCommand>,
+}
+
+impl Editor {
+    pub fn new(id: String, level: Level) -> Self {
+        Self {
+            id,
+            problems: validation::validate(&level),
+            metadata_before: Metadata::read(&level),
+            level,
+            cursor: (0, 0, 0),
+            brush: Brush::Floor,
+            direction: Direction::Up,
+            unsaved: false,
+            leaving: false,
+            saving_anyway: false,
+            metadata_field: None,
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// Makes a change to the level that can be undone.
+    fn perform(&mut self, command: EditCommand) {
+        command.apply(&mut self.level, false);
+        if self.undo_stack.len() == MAX_UNDO_STEPS {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(command);
+        self.redo_stack.clear();
+    }
+
+    /// Returns whether there was anything to undo.
+    fn undo(&mut self) -> bool {
+        let Some(command) = self.undo_stack.pop_back() else {
+            return false;
+        };
+        command.apply(&mut self.level, true);
+        self.redo_stack.push(command);
+        true
+    }
+
+    /// Returns whether there was anything to redo.
+    fn redo(&mut self) -> bool {
+        let Some(command) = self.redo_stack.pop() else {
+            return false;
+        };
+        command.apply(&mut self.level, false);
+        self.undo_stack.push_back(command);
+        true
+    }
+}
+
+/// One change to the level, with what it replaced so that it can be undone.
+enum EditCommand {
+    Tile {
+        pos: (i32, i32, i32),
+        before: Tile,
+        after: Tile,
+    },
+    Par {
+        before: u32,
+        after: u32,
+    },
+    Metadata {
+        before: Metadata,
+        after: Metadata,
+    },
+}
+
+impl EditCommand {
+    fn apply(&self, level: &mut Level, undo: bool) {
+        match self {
+            EditCommand::Tile { pos, before, after } => {
+                let tile = if undo { before } else { after };
+                tile.write(level, *pos);
+            }
+            EditCommand::Par { before, after } => {
+                level.par = if undo { *before } else { *after };
+            }
+            EditCommand::Metadata { before, after } => {
+                let metadata = if undo { before } else { after };
+                metadata.write(level);
+            }
+        }
+    }
+}
+
+/// What is shown about the course when picking it.
+#[derive(Clone, PartialEq)]
+struct Metadata {
+    name: String,
+    author: String,
+    difficulty: Option<Difficulty>,
+    description: String,
+}
+
+impl Metadata {
+    fn read(level: &Level) -> Metadata {
+        Metadata {
+            name: level.name.clone(),
+            author: level.author.clone(),
+            difficulty: level.difficulty,
+            description: level.description.clone(),
+        }
+    }
+
+    fn write(&self, level: &mut Level) {
+        level.name = self.name.clone();
+        level.author = self.author.clone();
+        level.difficulty = self.difficulty;
+        level.description = self.description.clone();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MetadataField {
+    Name,
+    Author,
+    Difficulty,
+    Description,
+}
+
+impl MetadataField {
+    const ALL: [MetadataField; 4] = [
+        MetadataField::Name,
+        MetadataField::Author,
+        MetadataField::Difficulty,
+        MetadataField::Description,
+    ];
+
+    /// The text of the field, for the fields that are typed in.
+    fn text(self, level: &mut Level) -> Option<&mut String> {
+        match self {
+            MetadataField::Name => Some(&mut level.name),
+            MetadataField::Author => Some(&mut level.author),
+            MetadataField::Difficulty => None,
+            MetadataField::Description => Some(&mut level.description),
+        }
+    }
+}
+
+/// Everything the level has at one position of the grid.
+#[derive(Default, Clone, PartialEq)]
+struct Tile {
+    parts: Vec<LanePart>,
+    surface: Option<SurfaceType>,
+    fan_cycle: Option<FanCycle>,
+}
+
+impl Tile {
+    fn read(level: &Level, pos: (i32, i32, i32)) -> Tile {
+        Tile {
+            parts: level
+                .lanes
+                .0
+                .iter()
+                .filter(|(p, _)| *p == pos)
+                .map(|&(_, part)| part)
+                .collect(),
+            surface: level
+                .surfaces
+                .iter()
+                .find(|(p, _)| *p == pos)
+                .map(|&(_, s)| s),
+            fan_cycle: level
+                .fan_cycles
+                .iter()
+                .find(|(p, _)| *p == pos)
+                .map(|&(_, c)| c),
+        }
+    }
+
+    fn write(&self, level: &mut Level, pos: (i32, i32, i32)) {
+        level.lanes.0.retain(|(p, _)| *p != pos);
+        level.surfaces.retain(|(p, _)| *p != pos);
+        level.fan_cycles.retain(|(p, _)| *p != pos);
+
+        level
+            .lanes
+            .0
+            .extend(self.parts.iter().map(|&part| (pos, part)));
+        // Surfaces and fan cycles only mean something with a tile to belong to
+        if !self.parts.is_empty() {
+            level.surfaces.extend(self.surface.map(|s| (pos, s)));
+            level.fan_cycles.extend(self.fan_cycle.map(|c| (pos, c)));
+        }
+    }
+}
+
+/// The kinds of tiles that can be placed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Brush {
+    Floor,
+    Hole,
+    Checkpoint,
+    Wall,
+    Tunnel,
+    HalfPipe,
+    Fan,
+}
+
+impl Brush {
+    fn next(self) -> Brush {
+        match self {
+            Brush::Floor => Brush::Hole,
+            Brush::Hole => Brush::Checkpoint,
+            Brush::Checkpoint => Brush::Wall,
+            Brush::Wall => Brush::Tunnel,
+            Brush::Tunnel => Brush::HalfPipe,
+            Brush::HalfPipe => Brush::Fan,
+            Brush::Fan => Brush::Floor,
+        }
+    }
+
+    fn part(self, direction: Direction) -> LanePart {
+        match self {
+            Brush::Floor => LanePart::BasicFloor,
+            Brush::Hole => LanePart::HoleFloor,
+            Brush::Checkpoint => LanePart::Checkpoint,
+            Brush::Wall => LanePart::Wall(direction),
+            Brush::Tunnel => LanePart::Tunnel(direction),
+            Brush::HalfPipe => LanePart::HalfPipe(direction),
+            Brush::Fan => LanePart::Fan(direction),
+        }
+    }
+
+    fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            Brush::Floor => "editor.brush.floor",
+            Brush::Hole => "editor.brush.hole",
+            Brush::Checkpoint => "editor.brush.checkpoint",
+            Brush::Wall => "editor.brush.wall",
+            Brush::Tunnel => "editor.brush.tunnel",
+            Brush::HalfPipe => "editor.brush.half_pipe",
+            Brush::Fan => "editor.brush.fan",
+        })
+    }
+}
+
+fn direction_name(direction: Direction, locale: &Locale) -> &str {
+    locale.get(match direction {
+        Direction::Up => "editor.direction.up",
+        Direction::Left => "editor.direction.left",
+        Direction::Down => "editor.direction.down",
+        Direction::Right => "editor.direction.right",
+    })
+}
+
+fn next_direction(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Right,
+        Direction::Right => Direction::Down,
+        Direction::Down => Direction::Left,
+        Direction::Left => Direction::Up,
+    }
+}
+
+/// A test of the course being edited, started from the cursor. The view, the number of players
+/// and the game mode are put back when returning to the editor.
+#[derive(Resource)]
+struct PlayTest {
+    camera_yaw: f32,
+    camera_pitch: f32,
+    camera_zoom: f32,
+    num_players: u32,
+    game_mode: GameMode,
+}
+
+#[derive(Component)]
+struct EditorText;
+
+/// Shows the edited level as it will be played.
+fn show_level(
+    editor: &Editor,
+    current_level: &mut CurrentLevel,
+    changes: &mut EventWriter<LaneChanged>,
+) {
+    current_level.0 = level_file::prepare(editor.level.clone());
+    changes.send(LaneChanged);
+}
+
+fn setup_editor(
+    mut commands: Commands,
+    mut editor: ResMut<Editor>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut changes: EventWriter<LaneChanged>,
+    mut q_camera: Query<&mut CameraController>,
+    play_test: Option<Res<PlayTest>>,
+) {
+    show_level(&editor, &mut current_level, &mut changes);
+    // So that the text is filled in again after a play test
+    editor.set_changed();
+
+    if let Some(play_test) = play_test {
+        for mut controller in q_camera.iter_mut() {
+            controller.yaw = play_test.camera_yaw;
+            controller.pitch = play_test.camera_pitch;
+            controller.zoom = play_test.camera_zoom;
+            controller.target_zoom = play_test.camera_zoom;
+        }
+        commands.insert_resource(GameState::new(play_test.num_players));
+        commands.insert_resource(play_test.game_mode);
+        commands.remove_resource::<PlayTest>();
+    }
+
+    commands.spawn((
+        EditorText,
+        TextBundle::from_sections([
+            TextSection::from_style(TextStyle {
+                font_size: 22.0,
+                color: Color::WHITE,
+                ..default()
+            }),
+            TextSection::from_style(TextStyle {
+                font_size: 22.0,
+                color: Color::ORANGE,
+                ..default()
+            }),
+            TextSection::from_style(TextStyle {
+                font_size: 22.0,
+                color: Color::WHITE,
+                ..default()
+            }),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+    ));
+}
+
+/// Moves the cursor with the arrow keys, relative to where the camera is looking.
+fn move_cursor(
+    keys: Res<Input<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    q_camera: Query<&CameraController>,
+) {
+    let Ok(controller) = q_camera.get_single() else {
+        return;
+    };
+
+    // Snap the direction the camera looks in to the grid
+    let look = -controller.ground_offset();
+    let forward = if look.x.abs() > look.z.abs() {
+        IVec2::new(look.x.signum() as i32, 0)
+    } else {
+        IVec2::new(0, look.z.signum() as i32)
+    };
+    let right = IVec2::new(-forward.y, forward.x);
+
+    let mut step = IVec2::ZERO;
+    if keys.just_pressed(KeyCode::Up) {
+        step += forward;
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        step -= forward;
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        step += right;
+    }
+    if keys.just_pressed(KeyCode::Left) {
+        step -= right;
+    }
+    let mut height = 0;
+    if keys.just_pressed(KeyCode::PageUp) {
+        height += 1;
+    }
+    if keys.just_pressed(KeyCode::PageDown) {
+        height -= 1;
+    }
+
+    if step != IVec2::ZERO || height != 0 {
+        let (x, y, z) = editor.cursor;
+        editor.cursor = (x + step.x, y + height, z + step.y);
+    }
+}
+
+fn edit_tiles(
+    keys: Res<Input<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut changes: EventWriter<LaneChanged>,
+) {
+    if keys.just_pressed(KeyCode::Tab) {
+        editor.brush = editor.brush.next();
+    }
+    if keys.just_pressed(KeyCode::R) {
+        editor.direction = next_direction(editor.direction);
+    }
+
+    let cursor = editor.cursor;
+    let before = Tile::read(&editor.level, cursor);
+    let mut after = before.clone();
+    if keys.any_just_pressed([KeyCode::Space, KeyCode::Return]) {
+        let part = editor.brush.part(editor.direction);
+        if !matches!(part, LanePart::Wall(_)) {
+            // Walls stand on other tiles as obstacles, everything else replaces the tile
+            after.parts.retain(|p| matches!(p, LanePart::Wall(_)));
+        }
+        if !after.parts.contains(&part) {
+            after.parts.push(part);
+        }
+    } else if keys.any_just_pressed([KeyCode::Delete, KeyCode::Back]) {
+        after = Tile::default();
+    }
+
+    let ctrl = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    let changed = if after != before {
+        editor.perform(EditCommand::Tile {
+            pos: cursor,
+            before,
+            after,
+        });
+        true
+    } else if keys.just_pressed(KeyCode::Equals) {
+        let par = editor.level.par;
+        editor.perform(EditCommand::Par {
+            before: par,
+            after: par + 1,
+        });
+        true
+    } else if keys.just_pressed(KeyCode::Minus) && editor.level.par > 1 {
+        let par = editor.level.par;
+        editor.perform(EditCommand::Par {
+            before: par,
+            after: par - 1,
+        });
+        true
+    } else if ctrl && (keys.just_pressed(KeyCode::Y) || shift && keys.just_pressed(KeyCode::Z)) {
+        editor.redo()
+    } else if ctrl && keys.just_pressed(KeyCode::Z) {
+        editor.undo()
+    } else {
+        false
+    };
+
+    if changed {
+        editor.unsaved = true;
+        editor.leaving = false;
+        editor.saving_anyway = false;
+        editor.problems = validation::validate(&editor.level);
+        show_level(&editor, &mut current_level, &mut changes);
+    }
+}
+
+fn save_and_leave(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut thumbnails: EventWriter<TakeThumbnail>,
+    locale: Res<Locale>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if ctrl && keys.just_pressed(KeyCode::S) {
+        if !editor.problems.is_empty() && !editor.saving_anyway {
+            editor.saving_anyway = true;
+            let message = locale.get("editor.save_anyway").to_string();
+            ui::spawn_toast(&mut commands, message, Color::ORANGE, 1);
+            return;
+        }
+        let message = match library::save_course(&editor.id, &editor.level) {
+            Ok(()) => {
+                editor.unsaved = false;
+                thumbnails.send(TakeThumbnail(editor.id.clone()));
+                locale.format("editor.saved", &[("name", &editor.id)])
+            }
+            Err(e) => {
+                warn!("Could not save the course: {}", e);
+                locale.get("editor.save_failed").to_string()
+            }
+        };
+        ui::spawn_toast(&mut commands, message, Color::WHITE, 1);
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        if editor.unsaved && !editor.leaving {
+            editor.leaving = true;
+            let message = locale.get("editor.leave_unsaved").to_string();
+            ui::spawn_toast(&mut commands, message, Color::ORANGE, 1);
+        } else {
+            next_state.set(AppState::Library);
+        }
+    }
+}
+
+/// Run condition for the editing that is done with keys that are typed into the metadata.
+fn not_editing_metadata(editor: Res<Editor>) -> bool {
+    editor.metadata_field.is_none()
+}
+
+fn edit_metadata(
+    keys: Res<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    mut editor: ResMut<Editor>,
+) {
+    let Some(field) = editor.metadata_field else {
+        chars.clear();
+        if keys.just_pressed(METADATA_KEY) {
+            editor.metadata_before = Metadata::read(&editor.level);
+            editor.metadata_field = Some(MetadataField::Name);
+        }
+        return;
+    };
+
+    if keys.any_just_pressed([METADATA_KEY, KeyCode::Escape, KeyCode::Return]) {
+        chars.clear();
+        editor.metadata_field = None;
+        let after = Metadata::read(&editor.level);
+        if after != editor.metadata_before {
+            let before = editor.metadata_before.clone();
+            editor.perform(EditCommand::Metadata { before, after });
+            editor.unsaved = true;
+            editor.saving_anyway = false;
+        }
+        return;
+    }
+
+    let index = MetadataField::ALL
+        .iter()
+        .position(|&f| f == field)
+        .unwrap_or(0);
+    let count = MetadataField::ALL.len();
+    if keys.just_pressed(KeyCode::Down) || keys.just_pressed(KeyCode::Tab) {
+        editor.metadata_field = Some(MetadataField::ALL[(index + 1) % count]);
+    } else if keys.just_pressed(KeyCode::Up) {
+        editor.metadata_field = Some(MetadataField::ALL[(index + count - 1) % count]);
+    }
+
+    let editor = &mut *editor;
+    match field.text(&mut editor.level) {
+        Some(text) => {
+            for c in chars.iter().filter(|c| !c.char.is_control()) {
+                text.push(c.char);
+            }
+            if keys.just_pressed(KeyCode::Back) {
+                text.pop();
+            }
+        }
+        None => {
+            chars.clear();
+            // Not rated, and then every difficulty from easiest to hardest
+            let ratings: Vec<Option<Difficulty>> = std::iter::once(None)
+                .chain(Difficulty::ALL.map(Some))
+                .collect();
+            let current = ratings
+                .iter()
+                .position(|&d| d == editor.level.difficulty)
+                .unwrap_or(0);
+            if keys.just_pressed(KeyCode::Right) {
+                editor.level.difficulty = ratings[(current + 1) % ratings.len()];
+            } else if keys.just_pressed(KeyCode::Left) {
+                editor.level.difficulty = ratings[(current + ratings.len() - 1) % ratings.len()];
+            }
+        }
+    }
+}
+
+/// Plays the course alone, with the ball dropped on the tile under the cursor.
+#[allow(clippy::too_many_arguments)]
+fn start_play_test(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    editor: Res<Editor>,
+    q_camera: Query<&CameraController>,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+    locale: Res<Locale>,
+) {
+    if !keys.just_pressed(PLAY_TEST_KEY) {
+        return;
+    }
+    let Ok(controller) = q_camera.get_single() else {
+        return;
+    };
+
+    let (x, y, z) = editor.cursor;
+    let mut level = level_file::prepare(editor.level.clone());
+    level.tee_zones = vec![TeeZone {
+        center: (tile_center(x, y, z) + Vec3::Y * TEE_DROP_HEIGHT).into(),
+        size: [0.2, 0.2],
+        player_id: None,
+    }];
+
+    commands.insert_resource(PlayTest {
+        camera_yaw: controller.yaw,
+        camera_pitch: controller.pitch,
+        camera_zoom: controller.zoom,
+        num_players: game_state.num_players,
+        game_mode: *game_mode,
+    });
+    commands.insert_resource(CurrentLevel(level));
+    commands.insert_resource(GameState::new(1));
+    commands.insert_resource(GameMode::Standard);
+    next_state.set(AppState::InGame);
+
+    let message = locale.get("editor.play_test").to_string();
+    ui::spawn_toast(&mut commands, message, Color::WHITE, 1);
+}
+
+fn end_play_test(keys: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
+    if keys.just_pressed(PLAY_TEST_KEY) {
+        next_state.set(AppState::Editor);
+    }
+}
+
+fn update_editor_text(
+    mut q_text: Query<&mut Text, With<EditorText>>,
+    editor: Res<Editor>,
+    locale: Res<Locale>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+    let (x, y, z) = editor.cursor;
+    let unsaved = if editor.unsaved {
+        locale.get("editor.unsaved")
+    } else {
+        ""
+    };
+    let status = locale.format(
+        "editor.status",
+        &[
+            ("name", &editor.id),
+            ("unsaved", &unsaved),
+            ("par", &editor.level.par),
+            ("brush", &editor.brush.name(&locale)),
+            ("direction", &direction_name(editor.direction, &locale)),
+            ("x", &x),
+            ("y", &y),
+            ("z", &z),
+        ],
+    );
+    let problems: String = editor
+        .problems
+        .iter()
+        .map(|problem| format!("\n{}", problem.describe(&locale)))
+        .collect();
+    let metadata = match editor.metadata_field {
+        Some(selected) => {
+            let difficulty = editor
+                .level
+                .difficulty
+                .map_or(locale.get("difficulty.none"), |d| locale.get(d.key()));
+            let mut lines = vec![String::new()];
+            for field in MetadataField::ALL {
+                let (key, value) = match field {
+                    MetadataField::Name => ("editor.field.name", editor.level.name.as_str()),
+                    MetadataField::Author => ("editor.field.author", editor.level.author.as_str()),
+                    MetadataField::Difficulty => ("editor.field.difficulty", difficulty),
+                    MetadataField::Description => (
+                        "editor.field.description",
+                        editor.level.description.as_str(),
+                    ),
+                };
+                let marker = if field == selected { "> " } else { "  " };
+                let cursor = if field == selected && field != MetadataField::Difficulty {
+                    "_"
+                } else {
+                    ""
+                };
+                lines.push(format!(
+                    "{}{}{}",
+                    marker,
+                    locale.format(key, &[("value", &value)]),
+                    cursor
+                ));
+            }
+            lines.push(locale.get("editor.metadata_help").to_string());
+            lines.join("\n")
+        }
+        None => String::new(),
+    };
+    for mut text in q_text.iter_mut() {
+        text.sections[0].value = format!("{}\n{}", status, locale.get("editor.help"));
+        text.sections[1].value = problems.clone();
+        text.sections[2].value = metadata.clone();
+    }
+}
+
+/// Keeps the camera looking at the cursor. It can still be turned and zoomed with the mouse.
+fn move_editor_camera(
+    mut q_camera: Query<(&CameraController, &mut Transform)>,
+    editor: Res<Editor>,
+) {
+    let (x, y, z) = editor.cursor;
+    let target = tile_center(x, y, z);
+    for (controller, mut transform) in q_camera.iter_mut() {
+        let mut look = controller.ground_offset();
+        look.y = 0.8;
+        look = look.normalize();
+        transform.translation = target + look * CAMERA_DISTANCE * (-controller.zoom).exp();
+        transform.look_at(target, Vec3::Y);
+    }
+}
+
+fn draw_cursor(mut gizmos: Gizmos, editor: Res<Editor>) {
+    let (x, y, z) = editor.cursor;
+    gizmos.cuboid(
+        Transform::from_translation(tile_center(x, y, z)).with_scale(Vec3::new(
+            0.4,
+            LEVEL_HEIGHT,
+            0.4,
+        )),
+        CURSOR_COLOR,
+    );
+}
+
+fn cleanup_editor(mut commands: Commands, q_text: Query<Entity, With<EditorText>>) {
+    for entity in q_text.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::Ball,
+    camera::CameraController,
+    chat,
+    controls::{CommonAction, KeyBindings},
+    level::LevelEntity,
+    locale::Locale,
+    online::{NetMessage, NetRole, PlayerOwners, ReceivedMessage, SendMessage},
+    scoring::GameState,
+    ui, AppState,
+};
+
+const EMOTE_SECONDS: f32 = 2.0;
+/// How far above the ball the emote is shown.
+const EMOTE_HEIGHT: f32 = 0.08;
+/// How many pixels the emote rises while it is shown.
+const EMOTE_RISE: f32 = 30.0;
+
+/// Quick reactions shown above a player's ball, on the emote keys, 7 to 9 by default.
+pub struct EmotesPlugin;
+
+impl Plugin for EmotesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EmoteEvent>().add_systems(
+            Update,
+            (
+                (
+                    emote_input.run_if(chat::not_typing),
+                    receive_emotes,
+                    show_emotes,
+                )
+                    .chain(),
+                move_emotes,
+            )
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Emote {
+    NiceShot,
+    Laughing,
+    Crying,
+}
+
+impl Emote {
+    const ALL: [Emote; 3] = [Emote::NiceShot, Emote::Laughing, Emote::Crying];
+
+    fn action(self) -> CommonAction {
+        match self {
+            Emote::NiceShot => CommonAction::EmoteNiceShot,
+            Emote::Laughing => CommonAction::EmoteLaughing,
+            Emote::Crying => CommonAction::EmoteCrying,
+        }
+    }
+
+    pub fn text(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            Emote::NiceShot => "emote.nice_shot",
+            Emote::Laughing => "emote.laughing",
+            Emote::Crying => "emote.crying",
+        })
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Emote::NiceShot => Color::GREEN,
+            Emote::Laughing => Color::YELLOW,
+            Emote::Crying => Color::rgb(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+/// Sent when a player reacts, whether here or in another online game.
+#[derive(Event)]
+pub struct EmoteEvent {
+    pub player_id: u32,
+    pub emote: Emote,
+}
+
+/// An emote floating above a ball.
+#[derive(Component)]
+struct EmoteBillboard {
+    ball: Entity,
+    timer: Timer,
+}
+
+fn emote_input(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut emotes: EventWriter<EmoteEvent>,
+    mut messages: EventWriter<SendMessage>,
+    game_state: Res<GameState>,
+    player_owners: Res<PlayerOwners>,
+    role: Res<NetRole>,
+) {
+    // Spectators have no ball to react with
+    if !role.can_shoot() {
+        return;
+    }
+
+    for emote in Emote::ALL {
+        if bindings.common.just_pressed(&keys, emote.action()) {
+            // Online it's always our own ball reacting, also on the others' turns
+            let player_id = player_owners
+                .local_player()
+                .unwrap_or(game_state.current_player);
+            emotes.send(EmoteEvent { player_id, emote });
+            if *role != NetRole::Offline {
+                messages.send(SendMessage(NetMessage::Emote { player_id, emote }));
+            }
+        }
+    }
+}
+
+fn receive_emotes(mut received: EventReader<ReceivedMessage>, mut emotes: EventWriter<EmoteEvent>) {
+    for ReceivedMessage(message) in received.iter() {
+        if let NetMessage::Emote { player_id, emote } = *message {
+            emotes.send(EmoteEvent { player_id, emote });
+        }
+    }
+}
+
+fn show_emotes(
+    mut commands: Commands,
+    mut emotes: EventReader<EmoteEvent>,
+    q_ball: Query<(Entity, &Ball)>,
+    q_billboard: Query<(Entity, &EmoteBillboard)>,
+    locale: Res<Locale>,
+) {
+    for event in emotes.iter() {
+        let Some((ball, _)) = q_ball
+            .iter()
+            .find(|(_, ball)| ball.player_id == event.player_id)
+        else {
+            continue;
+        };
+
+        // A new reaction replaces the old one
+        for (entity, billboard) in q_billboard.iter() {
+            if billboard.ball == ball {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+
+        commands.spawn((
+            LevelEntity,
+            EmoteBillboard {
+                ball,
+                timer: Timer::from_seconds(EMOTE_SECONDS, TimerMode::Once),
+            },
+            TextBundle::from_section(
+                event.emote.text(&locale),
+                TextStyle {
+                    font_size: 28.0,
+                    color: event.emote.color(),
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            }),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+/// Keeps emotes above their ball on screen and removes them when they run out.
+fn move_emotes(
+    mut commands: Commands,
+    mut q_billboard: Query<(
+        Entity,
+        &mut EmoteBillboard,
+        &mut Style,
+        &mut Visibility,
+        &Node,
+    )>,
+    q_ball: Query<&GlobalTransform, With<Ball>>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<CameraController>>,
+    ui_scale: Res<UiScale>,
+    time: Res<Time>,
+) {
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+
+    for (entity, mut billboard, mut style, mut visibility, node) in q_billboard.iter_mut() {
+        billboard.timer.tick(time.delta());
+        let Ok(ball_transform) = q_ball.get(billboard.ball) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        if billboard.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        ui::place_above_in_world(
+            camera,
+            camera_transform,
+            ball_transform.translation() + Vec3::Y * EMOTE_HEIGHT,
+            node,
+            &ui_scale,
+            &mut style,
+            &mut visibility,
+        );
+        if let Val::Px(top) = style.top {
+            style.top = Val::Px(top - billboard.timer.percent() * EMOTE_RISE);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{ball::Ball, AppState};
+
+/// How far in front of a fan balls are blown, two tiles.
+const FAN_REACH: f32 = 0.8;
+const FAN_HEIGHT: f32 = 0.16;
+/// Acceleration of a ball right in front of a fan, fading out towards the end of its reach.
+const FAN_ACCELERATION: f32 = 3.0;
+const FAN_RADIUS: f32 = 0.07;
+/// In radians per second.
+const BLADE_SPEED: f32 = 20.0;
+const NUM_STREAKS: usize = 6;
+const STREAK_SPEED: f32 = 1.5;
+
+/// Fans standing on floor tiles that blow balls in front of them along the lane.
+pub struct FanPlugin;
+
+impl Plugin for FanPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (cycle_fans, (blow_balls, spin_blades, move_streaks))
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Turns a fan on and off again and again, instead of it always being on.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FanCycle {
+    pub on_seconds: f32,
+    pub off_seconds: f32,
+    /// How far into the cycle the fan starts, so that neighbouring fans can take turns.
+    #[serde(default)]
+    pub offset: f32,
+}
+
+impl FanCycle {
+    fn is_on(&self, seconds: f32) -> bool {
+        (seconds + self.offset).rem_euclid(self.on_seconds + self.off_seconds) < self.on_seconds
+    }
+}
+
+/// A fan blowing along its local z-axis. Its sensor covers the area it blows over.
+#[derive(Component)]
+pub struct Fan {
+    cycle: Option<FanCycle>,
+    seconds: f32,
+    pub on: bool,
+}
+
+#[derive(Component)]
+struct FanBlades;
+
+/// A streak of air moving away from a fan, to show where it blows.
+#[derive(Component)]
+struct FanStreak;
+
+/// Spawns a fan at the back edge of the tile centered at `tile_center`, blowing towards `direction`.
+pub fn spawn_fan(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    tile_center: Vec3,
+    direction: Vec3,
+    cycle: Option<FanCycle>,
+    groups: Option<CollisionGroups>,
+) -> Entity {
+    let position = tile_center - direction * 0.18 + Vec3::Y * 0.05;
+    let hub_position = Vec3::Y * (FAN_RADIUS + 0.01);
+
+    let housing_mesh = meshes.add(
+        shape::Cylinder {
+            radius: FAN_RADIUS + 0.01,
+            height: 0.01,
+            resolution: 24,
+            segments: 1,
+        }
+        .into(),
+    );
+    let housing_material = materials.add(Color::rgb(0.3, 0.3, 0.35).into());
+    let blade_mesh = meshes.add(shape::Box::new(FAN_RADIUS * 2.0, 0.02, 0.004).into());
+    let blade_material = materials.add(Color::rgb(0.8, 0.8, 0.85).into());
+    let streak_mesh = meshes.add(shape::Box::new(0.004, 0.004, 0.06).into());
+    let streak_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 1.0, 1.0, 0.5),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+
+    let mut fan = commands.spawn((
+        Fan {
+            cycle,
+            seconds: 0.0,
+            on: true,
+        },
+        SpatialBundle::from_transform(
+            Transform::from_translation(position).looking_to(-direction, Vec3::Y),
+        ),
+        Collider::compound(vec![(
+            Vec3::new(0.0, FAN_HEIGHT / 2.0, FAN_REACH / 2.0),
+            Quat::IDENTITY,
+            Collider::cuboid(0.2, FAN_HEIGHT / 2.0, FAN_REACH / 2.0),
+        )]),
+        Sensor,
+    ));
+    if let Some(groups) = groups {
+        fan.insert(groups);
+    }
+
+    fan.with_children(|parent| {
+        let mut housing = parent.spawn((
+            PbrBundle {
+                mesh: housing_mesh,
+                material: housing_material,
+                transform: Transform::from_translation(hub_position - Vec3::Z * 0.01)
+                    .with_rotation(Quat::from_rotation_x(PI / 2.0)),
+                ..default()
+            },
+            Collider::cylinder(0.005, FAN_RADIUS + 0.01),
+        ));
+        if let Some(groups) = groups {
+            housing.insert(groups);
+        }
+
+        parent
+            .spawn((
+                FanBlades,
+                SpatialBundle::from_transform(Transform::from_translation(hub_position)),
+            ))
+            .with_children(|parent| {
+                for angle in [0.0, PI / 2.0] {
+                    parent.spawn(PbrBundle {
+                        mesh: blade_mesh.clone(),
+                        material: blade_material.clone(),
+                        transform: Transform::from_rotation(Quat::from_rotation_z(angle)),
+                        ..default()
+                    });
+                }
+            });
+
+        for i in 0..NUM_STREAKS {
+            let t = i as f32 / NUM_STREAKS as f32;
+            parent.spawn((
+                FanStreak,
+                PbrBundle {
+                    mesh: streak_mesh.clone(),
+                    material: streak_material.clone(),
+                    transform: Transform::from_xyz(
+                        (t * 7.0).sin() * FAN_RADIUS,
+                        hub_position.y + (t * 11.0).cos() * FAN_RADIUS * 0.8,
+                        t * FAN_REACH,
+                    ),
+                    ..default()
+                },
+            ));
+        }
+    });
+    fan.id()
+}
+
+fn cycle_fans(mut q_fan: Query<&mut Fan>, time: Res<Time>) {
+    for mut fan in q_fan.iter_mut() {
+        fan.seconds += time.delta_seconds();
+        let on = fan.cycle.map_or(true, |cycle| cycle.is_on(fan.seconds));
+        if fan.on != on {
+            fan.on = on;
+        }
+    }
+}
+
+fn blow_balls(
+    q_fan: Query<(Entity, &Fan, &GlobalTransform)>,
+    mut q_ball: Query<(&GlobalTransform, &mut ExternalImpulse, &ReadMassProperties), With<Ball>>,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+) {
+    for (fan_entity, fan, fan_transform) in q_fan.iter() {
+        if !fan.on {
+            continue;
+        }
+        let direction = fan_transform.back();
+
+        for (e1, e2, intersecting) in rapier_context.intersection_pairs_with(fan_entity) {
+            let other = if e1 == fan_entity { e2 } else { e1 };
+            if !intersecting {
+                continue;
+            }
+            let Ok((ball_transform, mut impulse, mass)) = q_ball.get_mut(other) else {
+                continue;
+            };
+
+            let distance = (ball_transform.translation() - fan_transform.translation())
+                .dot(direction)
+                .max(0.0);
+            let falloff = (1.0 - distance / FAN_REACH).clamp(0.0, 1.0);
+            impulse.impulse +=
+                direction * FAN_ACCELERATION * falloff * mass.0.mass * time.delta_seconds();
+        }
+    }
+}
+
+fn spin_blades(
+    mut q_blades: Query<(&Parent, &mut Transform), With<FanBlades>>,
+    q_fan: Query<&Fan>,
+    time: Res<Time>,
+) {
+    for (parent, mut transform) in q_blades.iter_mut() {
+        if q_fan.get(parent.get()).is_ok_and(|fan| fan.on) {
+            transform.rotate_local_z(BLADE_SPEED * time.delta_seconds());
+        }
+    }
+}
+
+fn move_streaks(
+    mut q_streak: Query<(&Parent, &mut Transform, &mut Visibility), With<FanStreak>>,
+    q_fan: Query<&Fan>,
+    time: Res<Time>,
+) {
+    for (parent, mut transform, mut visibility) in q_streak.iter_mut() {
+        let on = q_fan.get(parent.get()).is_ok_and(|fan| fan.on);
+        let new_visibility = if on {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+        if on {
+            transform.translation.z =
+                (transform.translation.z + STREAK_SPEED * time.delta_seconds()) % FAN_REACH;
+        }
+    }
+}
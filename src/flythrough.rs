@@ -0,0 +1,227 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::{
+    ball,
+    camera::CameraController,
+    level::{self, CurrentLevel, LanePart},
+    locale::LocalizedText,
+    online::NetRole,
+    replay::ReplayPlayback,
+    AppState,
+};
+
+/// How fast the camera flies along the lane.
+const TILES_PER_SECOND: f32 = 4.0;
+/// How far above the lane the camera flies.
+const HEIGHT: f32 = 0.5;
+/// How many tiles ahead of itself the camera looks.
+const LOOK_AHEAD: f32 = 2.0;
+
+/// Flies the camera along the lane from the tee to the hole before the first shot, so that
+/// everybody gets to see the layout. Any key skips it.
+pub struct FlythroughPlugin;
+
+impl Plugin for FlythroughPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            start_flythrough
+                .run_if(not(resource_exists::<ReplayPlayback>()))
+                // Nobody else would wait for the fly-through to end
+                .run_if(resource_equals(NetRole::Offline)),
+        )
+        .add_systems(OnExit(AppState::InGame), end_flythrough)
+        .add_systems(
+            Update,
+            (
+                fly_through,
+                // Late, so that the key skipping the fly-through isn't also taken as a shot
+                skip_flythrough.after(ball::apply_shot),
+            ),
+        );
+    }
+}
+
+/// Moves the camera along `path` in place of the usual controller.
+#[derive(Component)]
+pub struct Flythrough {
+    /// Centers of the tiles from the tee to the hole.
+    path: Vec<Vec3>,
+    /// How many tiles along the path the camera is.
+    progress: f32,
+}
+
+#[derive(Component)]
+struct SkipHint;
+
+/// Run condition for things that have to wait until the fly-through is over.
+pub fn not_flying(q_flythrough: Query<(), With<Flythrough>>) -> bool {
+    q_flythrough.is_empty()
+}
+
+/// The shortest walk over floor tiles from the tile closest to the tee to a hole.
+fn lane_path(level: &level::Level) -> Option<Vec<(i32, i32, i32)>> {
+    let floors: HashSet<(i32, i32, i32)> = level
+        .lanes
+        .0
+        .iter()
+        .filter(|(_, part)| !matches!(part, LanePart::Wall(_)))
+        .map(|&(position, _)| position)
+        .collect();
+    let start = *floors
+        .iter()
+        .min_by_key(|&&(x, y, z)| x * x + y * y + z * z)?;
+    let goal = level
+        .holes()
+        .into_iter()
+        .find(|(_, owner)| owner.is_none())?
+        .0
+        .position;
+
+    // Breadth-first search, allowing a step up or down between neighbouring tiles
+    let mut came_from = HashMap::from([(start, start)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(tile @ (x, y, z)) = queue.pop_front() {
+        if tile == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while current != start {
+                current = came_from[&current];
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            for dy in -1..=1 {
+                let next = (x + dx, y + dy, z + dz);
+                if floors.contains(&next) && !came_from.contains_key(&next) {
+                    came_from.insert(next, tile);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// A point on a smooth curve through the points of `path`, `t` tiles from the start.
+fn point_on_path(path: &[Vec3], t: f32) -> Vec3 {
+    let last = path.len() - 1;
+    let t = t.clamp(0.0, last as f32);
+    let i = (t as usize).min(last.saturating_sub(1));
+    let f = t - i as f32;
+    let p = |index: isize| path[index.clamp(0, last as isize) as usize];
+    let (p0, p1, p2, p3) = (
+        p(i as isize - 1),
+        p(i as isize),
+        p(i as isize + 1),
+        p(i as isize + 2),
+    );
+    // Catmull-Rom spline
+    0.5 * (2.0 * p1
+        + (p2 - p0) * f
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * f * f
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * f * f * f)
+}
+
+fn start_flythrough(
+    mut commands: Commands,
+    q_camera: Query<Entity, With<CameraController>>,
+    current_level: Res<CurrentLevel>,
+) {
+    let Some(tiles) = lane_path(&current_level.0) else {
+        return;
+    };
+    if tiles.len() < 2 {
+        return;
+    }
+    let path = tiles
+        .into_iter()
+        .map(|(x, y, z)| level::tile_center(x, y, z))
+        .collect();
+
+    let Some(camera) = q_camera.iter().next() else {
+        return;
+    };
+    commands.entity(camera).insert(Flythrough {
+        path,
+        progress: 0.0,
+    });
+    commands.spawn((
+        SkipHint,
+        LocalizedText("flythrough.skip"),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(20.0),
+            right: Val::Px(20.0),
+            ..default()
+        }),
+    ));
+}
+
+fn fly_through(
+    mut commands: Commands,
+    mut q_camera: Query<(Entity, &mut Flythrough, &mut Transform)>,
+    q_hint: Query<Entity, With<SkipHint>>,
+    time: Res<Time>,
+) {
+    for (entity, mut flythrough, mut transform) in q_camera.iter_mut() {
+        flythrough.progress += TILES_PER_SECOND * time.delta_seconds();
+        let end = (flythrough.path.len() - 1) as f32;
+        if flythrough.progress >= end {
+            commands.entity(entity).remove::<Flythrough>();
+            for hint in q_hint.iter() {
+                commands.entity(hint).despawn_recursive();
+            }
+            continue;
+        }
+
+        let position = point_on_path(&flythrough.path, flythrough.progress);
+        let target = point_on_path(&flythrough.path, flythrough.progress + LOOK_AHEAD);
+        transform.translation = position + Vec3::Y * HEIGHT;
+        transform.look_at(target, Vec3::Y);
+    }
+}
+
+fn skip_flythrough(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    commands: Commands,
+    q_camera: Query<Entity, With<Flythrough>>,
+    q_hint: Query<Entity, With<SkipHint>>,
+) {
+    if q_camera.is_empty() {
+        return;
+    }
+    let pressed = keys.get_just_pressed().next().is_some()
+        || buttons.get_just_pressed().next().is_some()
+        || gamepad_buttons.get_just_pressed().next().is_some();
+    if pressed {
+        end_flythrough(commands, q_camera, q_hint);
+    }
+}
+
+fn end_flythrough(
+    mut commands: Commands,
+    q_camera: Query<Entity, With<Flythrough>>,
+    q_hint: Query<Entity, With<SkipHint>>,
+) {
+    for entity in q_camera.iter() {
+        commands.entity(entity).remove::<Flythrough>();
+    }
+    for entity in q_hint.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
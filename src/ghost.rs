@@ -0,0 +1,182 @@
+use std::hash::{Hash, Hasher};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    ball::{Ball, BallState},
+    level::{CurrentLevel, Level, LevelEntity},
+    scoring::BallHoled,
+};
+
+/// Time between two recorded positions.
+const SAMPLE_INTERVAL: f32 = 1.0 / 30.0;
+
+/// Positions of a ball over time, measured from its first shot.
+#[derive(Default, Clone)]
+pub struct Trajectory {
+    samples: Vec<(f32, Vec3)>,
+}
+
+impl Trajectory {
+    pub fn points(&self) -> impl Iterator<Item = Vec3> + '_ {
+        self.samples.iter().map(|&(_, p)| p)
+    }
+
+    fn position_at(&self, t: f32) -> Option<Vec3> {
+        let after = self.samples.partition_point(|&(st, _)| st <= t);
+        if after == 0 {
+            return self.samples.first().map(|&(_, p)| p);
+        }
+        let (t0, p0) = self.samples[after - 1];
+        match self.samples.get(after) {
+            Some(&(t1, p1)) => Some(p0.lerp(p1, (t - t0) / (t1 - t0))),
+            None => Some(p0),
+        }
+    }
+}
+
+/// The trajectory recorded for each player on the current hole.
+#[derive(Resource, Default)]
+pub struct CurrentTrajectories(HashMap<u32, (f32, Trajectory)>);
+
+impl CurrentTrajectories {
+    pub fn get(&self, player_id: u32) -> Option<&Trajectory> {
+        self.0.get(&player_id).map(|(_, trajectory)| trajectory)
+    }
+}
+
+/// The fewest strokes and the matching trajectory per level and player.
+#[derive(Resource, Default)]
+pub struct PersonalBests(HashMap<(u64, u32), (u32, Trajectory)>);
+
+#[derive(Component)]
+pub struct Ghost {
+    player_id: u32,
+    trajectory: Trajectory,
+}
+
+/// Identifies a level by its layout, so that generated courses get their own records.
+pub fn level_key(level: &Level) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    level.lanes.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn record_trajectories(
+    q_ball: Query<(&Transform, &Ball, &BallState)>,
+    mut current: ResMut<CurrentTrajectories>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+
+    for (transform, ball, state) in q_ball.iter() {
+        if ball.hits == 0 {
+            continue;
+        }
+
+        let (start, trajectory) = current
+            .0
+            .entry(ball.player_id)
+            .or_insert_with(|| (now, Trajectory::default()));
+        let t = now - *start;
+
+        let due = trajectory
+            .samples
+            .last()
+            .map_or(true, |&(last, _)| t - last >= SAMPLE_INTERVAL);
+        if *state == BallState::Moving && due {
+            trajectory.samples.push((t, transform.translation));
+        }
+    }
+}
+
+pub fn save_personal_bests(
+    mut holed: EventReader<BallHoled>,
+    mut current: ResMut<CurrentTrajectories>,
+    mut bests: ResMut<PersonalBests>,
+    current_level: Res<CurrentLevel>,
+) {
+    let key = level_key(&current_level.0);
+
+    for event in holed.iter() {
+        // Kept in the current trajectories too, for showing everybody's path after the hole
+        let Some((_, trajectory)) = current.0.get_mut(&event.player_id) else {
+            continue;
+        };
+        trajectory.samples.extend(
+            trajectory
+                .samples
+                .last()
+                .map(|&(t, _)| (t + SAMPLE_INTERVAL, event.position)),
+        );
+
+        let best = bests.0.get(&(key, event.player_id));
+        if best.map_or(true, |(score, _)| event.score < *score) {
+            bests
+                .0
+                .insert((key, event.player_id), (event.score, trajectory.clone()));
+        }
+    }
+}
+
+pub fn spawn_ghosts(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut current: ResMut<CurrentTrajectories>,
+    bests: Res<PersonalBests>,
+    current_level: Res<CurrentLevel>,
+) {
+    current.0.clear();
+
+    let key = level_key(&current_level.0);
+    let mesh = meshes.add(
+        shape::UVSphere {
+            radius: 0.025,
+            ..default()
+        }
+        .into(),
+    );
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 1.0, 1.0, 0.35),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+
+    for (&(level, player_id), (_, trajectory)) in bests.0.iter() {
+        if level != key {
+            continue;
+        }
+        commands.spawn((
+            LevelEntity,
+            Ghost {
+                player_id,
+                trajectory: trajectory.clone(),
+            },
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Ghosts start racing along their recorded line as soon as their player takes the first shot.
+pub fn move_ghosts(
+    mut q_ghost: Query<(&Ghost, &mut Transform, &mut Visibility)>,
+    current: Res<CurrentTrajectories>,
+    time: Res<Time>,
+) {
+    for (ghost, mut transform, mut visibility) in q_ghost.iter_mut() {
+        let Some((start, _)) = current.0.get(&ghost.player_id) else {
+            continue;
+        };
+        if let Some(pos) = ghost.trajectory.position_at(time.elapsed_seconds() - start) {
+            transform.translation = pos;
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
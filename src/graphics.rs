@@ -0,0 +1,110 @@
+use bevy::{pbr::DirectionalLightShadowMap, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{locale::Locale, save};
+
+const SAVE_FILE: &str = "graphics.ron";
+
+/// Lets the player trade looks for speed, which matters most in the browser.
+pub struct GraphicsPlugin;
+
+impl Plugin for GraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<GraphicsQuality>(SAVE_FILE))
+            .init_resource::<DirectionalLightShadowMap>()
+            .add_systems(
+                Update,
+                apply_graphics_quality.run_if(resource_changed::<GraphicsQuality>()),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraphicsQuality {
+    Low,
+    Medium,
+    High,
+}
+
+impl Default for GraphicsQuality {
+    fn default() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            GraphicsQuality::Medium
+        } else {
+            GraphicsQuality::High
+        }
+    }
+}
+
+impl GraphicsQuality {
+    pub fn next(self) -> Self {
+        match self {
+            GraphicsQuality::Low => GraphicsQuality::Medium,
+            GraphicsQuality::Medium => GraphicsQuality::High,
+            GraphicsQuality::High => GraphicsQuality::Low,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            GraphicsQuality::Low => "graphics.low",
+            GraphicsQuality::Medium => "graphics.medium",
+            GraphicsQuality::High => "graphics.high",
+        })
+    }
+
+    /// Whether lights cast shadows from shadow maps. Balls get blob shadows when they don't.
+    pub fn shadows(self) -> bool {
+        self != GraphicsQuality::Low
+    }
+
+    fn shadow_map_size(self) -> usize {
+        match self {
+            GraphicsQuality::Low => 1024,
+            GraphicsQuality::Medium => 2048,
+            GraphicsQuality::High => 4096,
+        }
+    }
+
+    fn msaa(self) -> Msaa {
+        match self {
+            GraphicsQuality::Low => Msaa::Off,
+            GraphicsQuality::Medium | GraphicsQuality::High => Msaa::Sample4,
+        }
+    }
+
+    /// Blades of grass on each floor tile.
+    pub fn grass_blades(self) -> usize {
+        match self {
+            GraphicsQuality::Low => 0,
+            GraphicsQuality::Medium => 150,
+            GraphicsQuality::High => 400,
+        }
+    }
+
+    fn hdr(self) -> bool {
+        self == GraphicsQuality::High
+    }
+}
+
+fn apply_graphics_quality(
+    quality: Res<GraphicsQuality>,
+    mut shadow_map: ResMut<DirectionalLightShadowMap>,
+    mut msaa: ResMut<Msaa>,
+    mut q_camera: Query<&mut Camera>,
+    mut q_light: Query<&mut DirectionalLight>,
+) {
+    shadow_map.size = quality.shadow_map_size();
+    for mut light in q_light.iter_mut() {
+        light.shadows_enabled = quality.shadows();
+    }
+    *msaa = quality.msaa();
+    for mut camera in q_camera.iter_mut() {
+        camera.hdr = quality.hdr();
+    }
+
+    // Only remember settings the player picked
+    if !quality.is_added() {
+        save::store(SAVE_FILE, &*quality);
+    }
+}
@@ -0,0 +1,142 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    pbr::NotShadowCaster,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    graphics::GraphicsQuality,
+    level::{self, LaneTile, LevelEntity},
+    AppState,
+};
+
+/// Different patches of grass, so that neighbouring tiles don't look the same.
+const NUM_VARIANTS: u64 = 4;
+/// Kept well below the ball radius, so that the grass never hides a ball.
+const BLADE_MIN_HEIGHT: f32 = 0.005;
+const BLADE_MAX_HEIGHT: f32 = 0.011;
+const ROOT_COLOR: Color = Color::rgb(0.15, 0.4, 0.12);
+const TIP_COLOR: Color = Color::rgb(0.45, 0.72, 0.3);
+
+/// Blades of grass on plain floor tiles, as many as the graphics quality allows.
+pub struct GrassPlugin;
+
+impl Plugin for GrassPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_grass.run_if(in_state(AppState::InGame)));
+    }
+}
+
+/// A floor tile to grow grass on, with the center of the top of the tile.
+#[derive(Component)]
+pub struct GrassTile {
+    pub top: Vec3,
+}
+
+#[derive(Component)]
+struct GrassPatch;
+
+/// The patch meshes for the graphics quality they were made for.
+struct GrassAssets {
+    quality: GraphicsQuality,
+    meshes: Vec<Handle<Mesh>>,
+    material: Handle<StandardMaterial>,
+}
+
+/// Grows grass on new tiles, and on all tiles again when the graphics quality changes.
+#[allow(clippy::too_many_arguments)]
+fn spawn_grass(
+    mut commands: Commands,
+    q_new_tile: Query<&GrassTile, Added<GrassTile>>,
+    q_tile: Query<&GrassTile>,
+    q_patch: Query<Entity, With<GrassPatch>>,
+    quality: Res<GraphicsQuality>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut assets: Local<Option<GrassAssets>>,
+) {
+    let outdated = assets.as_ref().map_or(true, |a| a.quality != *quality);
+    if outdated {
+        for entity in q_patch.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        *assets = Some(GrassAssets {
+            quality: *quality,
+            meshes: (0..NUM_VARIANTS)
+                .map(|seed| meshes.add(patch_mesh(quality.grass_blades(), seed)))
+                .collect(),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                perceptual_roughness: 0.8,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            }),
+        });
+    }
+    let Some(assets) = assets.as_ref() else {
+        return;
+    };
+    if quality.grass_blades() == 0 {
+        return;
+    }
+
+    let tiles: Vec<&GrassTile> = if outdated {
+        q_tile.iter().collect()
+    } else {
+        q_new_tile.iter().collect()
+    };
+    for tile in tiles {
+        // Pick a variant and a turn of the patch from where the tile is
+        let (x, _, z) = level::nearest_tile(tile.top);
+        let variant = (x * 7 + z * 13).rem_euclid(NUM_VARIANTS as i32) as usize;
+        let turn = (x * 3 + z).rem_euclid(4) as f32 * TAU / 4.0;
+        commands.spawn((
+            GrassPatch,
+            LaneTile,
+            LevelEntity,
+            PbrBundle {
+                mesh: assets.meshes[variant].clone(),
+                material: assets.material.clone(),
+                transform: Transform::from_translation(tile.top)
+                    .with_rotation(Quat::from_rotation_y(turn)),
+                ..default()
+            },
+            NotShadowCaster,
+        ));
+    }
+}
+
+/// One triangle per blade, spread over a tile and colored from the root to the tip.
+fn patch_mesh(blades: usize, seed: u64) -> Mesh {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let root = ROOT_COLOR.as_linear_rgba_f32();
+    let tip = TIP_COLOR.as_linear_rgba_f32();
+
+    let mut positions = Vec::with_capacity(blades * 3);
+    let mut colors = Vec::with_capacity(blades * 3);
+    for _ in 0..blades {
+        let base = Vec3::new(rng.gen_range(-0.19..0.19), 0.0, rng.gen_range(-0.19..0.19));
+        let angle = rng.gen_range(0.0..TAU);
+        let side = Vec3::new(angle.cos(), 0.0, angle.sin()) * rng.gen_range(0.001..0.002);
+        let height = rng.gen_range(BLADE_MIN_HEIGHT..BLADE_MAX_HEIGHT);
+        let lean = Vec3::new(rng.gen_range(-0.3..0.3), 1.0, rng.gen_range(-0.3..0.3)) * height;
+
+        positions.extend([base - side, base + side, base + lean].map(|p| p.to_array()));
+        colors.extend([root, root, tip]);
+    }
+
+    // Lit like the floor below, instead of like thin standing triangles
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+    let indices = (0..positions.len() as u32).collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
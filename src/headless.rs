@@ -0,0 +1,216 @@
+use std::{path::Path, time::Duration};
+
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    prelude::*,
+    render::{settings::WgpuSettings, RenderPlugin},
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{self, Ball, BallSpin, BallState, ShootEvent},
+    cli::CliArgs,
+    scoring::GameState,
+};
+
+const TIMESTEP: f32 = 1.0 / 60.0;
+
+/// Gives up if the script has not finished after this many simulated seconds.
+const MAX_SIMULATED_SECONDS: f32 = 600.0;
+
+/// Adds everything except rendering and windowing, and runs the app loop as fast as possible.
+pub fn add_default_plugins(app: &mut App) {
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            })
+            .set(RenderPlugin {
+                wgpu_settings: WgpuSettings {
+                    backends: None,
+                    ..default()
+                },
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugins(ScheduleRunnerPlugin::run_loop(Duration::ZERO));
+}
+
+/// A shot in a headless script, one per line:
+/// `<power> <angle in degrees> [left|right [strength]]`, where the spin strength defaults to 1.
+#[derive(Debug, Clone)]
+pub struct ScriptedShot {
+    power: f32,
+    angle: f32,
+    spin: Option<BallSpin>,
+}
+
+#[derive(Resource, Default)]
+pub struct ShotScript {
+    shots: Vec<ScriptedShot>,
+    next: usize,
+    expected_strokes: Option<u32>,
+    simulated_seconds: f32,
+}
+
+impl ShotScript {
+    pub fn load(path: &Path) -> Result<Vec<ScriptedShot>, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| (i, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                parse_shot(line)
+                    .ok_or_else(|| format!("{}:{}: invalid shot", path.display(), i + 1))
+            })
+            .collect()
+    }
+}
+
+fn parse_shot(line: &str) -> Option<ScriptedShot> {
+    let mut parts = line.split_whitespace();
+    let power = parts.next()?.parse().ok()?;
+    let angle: f32 = parts.next()?.parse().ok()?;
+    let spin_direction = match parts.next() {
+        None => None,
+        Some("left") => Some(-1.0),
+        Some("right") => Some(1.0),
+        Some(_) => return None,
+    };
+    let spin_strength: f32 = match parts.next() {
+        Some(strength) if spin_direction.is_some() => strength.parse().ok()?,
+        Some(_) => return None,
+        None => 1.0,
+    };
+    let spin = spin_direction.and_then(|dir: f32| BallSpin::from_signed(dir * spin_strength));
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(ScriptedShot {
+        power,
+        angle: angle.to_radians(),
+        spin,
+    })
+}
+
+/// Plays a level with shots read from a script instead of the keyboard and reports the result.
+pub struct HeadlessPlugin {
+    pub shots: Vec<ScriptedShot>,
+    pub expected_strokes: Option<u32>,
+}
+
+impl HeadlessPlugin {
+    pub fn from_args(args: &CliArgs) -> Self {
+        let shots = match &args.shots {
+            Some(path) => ShotScript::load(path).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }),
+            None => Vec::new(),
+        };
+        Self {
+            shots,
+            expected_strokes: args.expect_strokes,
+        }
+    }
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ShotScript {
+            shots: self.shots.clone(),
+            expected_strokes: self.expected_strokes,
+            ..default()
+        })
+        .add_systems(Startup, use_fixed_timestep)
+        .add_systems(
+            Update,
+            (
+                play_scripted_shots.before(ball::apply_shot),
+                check_script_finished,
+            ),
+        );
+    }
+}
+
+/// Steps the physics by a fixed amount each frame, since the frames are not tied to real time.
+fn use_fixed_timestep(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: TIMESTEP,
+        substeps: 1,
+    };
+}
+
+fn play_scripted_shots(
+    q_ball: Query<(&Ball, &BallState)>,
+    mut script: ResMut<ShotScript>,
+    game_state: Res<GameState>,
+    mut shots: EventWriter<ShootEvent>,
+) {
+    if game_state.shot_in_progress || script.next >= script.shots.len() {
+        return;
+    }
+
+    let current_player = game_state.current_player;
+    let aiming = q_ball
+        .iter()
+        .any(|(ball, state)| ball.player_id == current_player && *state == BallState::Aiming);
+    if !aiming {
+        return;
+    }
+
+    let shot = script.shots[script.next].clone();
+    script.next += 1;
+
+    shots.send(ShootEvent {
+        player_id: current_player,
+        power: shot.power,
+        angle: shot.angle,
+        spin: shot.spin,
+    });
+}
+
+fn check_script_finished(
+    q_ball: Query<&BallState, With<Ball>>,
+    mut script: ResMut<ShotScript>,
+    game_state: Res<GameState>,
+) {
+    script.simulated_seconds += TIMESTEP;
+
+    let all_finished = (0..game_state.num_players).all(|p| game_state.is_finished(p));
+    let out_of_shots = script.next >= script.shots.len()
+        && !game_state.shot_in_progress
+        && q_ball.iter().all(|state| state.is_at_rest());
+    let timed_out = script.simulated_seconds > MAX_SIMULATED_SECONDS;
+
+    if !all_finished && !out_of_shots && !timed_out {
+        return;
+    }
+
+    let mut success = all_finished;
+    for (player_id, player) in game_state.players.iter().enumerate() {
+        match player.scores.first() {
+            Some(strokes) => {
+                println!("Player {}: {} strokes", player_id + 1, strokes);
+                if script.expected_strokes.is_some_and(|e| e != *strokes) {
+                    success = false;
+                }
+            }
+            None => println!("Player {}: did not finish", player_id + 1),
+        }
+    }
+    if timed_out {
+        println!(
+            "Timed out after {} simulated seconds",
+            MAX_SIMULATED_SECONDS
+        );
+    }
+
+    std::process::exit(if success { 0 } else { 1 });
+}
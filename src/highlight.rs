@@ -0,0 +1,273 @@
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey, NotShadowCaster},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_resource::{
+            AsBindGroup, Face, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+};
+
+use crate::{
+    ball::{Ball, BallState},
+    level::{Hole, LevelEntity, FLOOR_TOP},
+    scoring::GameState,
+    AppState, GameMode,
+};
+
+/// Size of the ring quad compared to the radius of the hole.
+const RING_SCALE: f32 = 4.0;
+/// Height of the ring above the hole sensor, just above the floor.
+const RING_HEIGHT: f32 = FLOOR_TOP - 0.004;
+const GLOW_RADIUS: f32 = 0.034;
+/// Size of the outline compared to the ball it is around.
+const OUTLINE_SCALE: f32 = 1.15;
+const OUTLINE_COLOR: Color = Color::rgb(1.0, 1.0, 0.9);
+
+/// A pulsing ring around the hole the current player is playing towards, and a glow and an
+/// outline around their ball, so that it is clear where to go and whose turn it is.
+pub struct HighlightPlugin;
+
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MaterialPlugin::<HoleRingMaterial>::default(),
+            MaterialPlugin::<BallGlowMaterial>::default(),
+        ))
+        .add_systems(OnEnter(AppState::InGame), spawn_highlights)
+        .add_systems(
+            Update,
+            (move_hole_ring, move_ball_glow, outline_current_ball)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+#[derive(AsBindGroup, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "0d6124da-09a8-4d0e-b829-0bae5b47ee5b"]
+pub struct HoleRingMaterial {
+    #[uniform(0)]
+    color: Color,
+    /// Waves per second.
+    #[uniform(0)]
+    pulse_speed: f32,
+}
+
+impl Material for HoleRingMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/hole_ring.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+#[derive(AsBindGroup, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "3a9bf3c1-8ea0-4f88-b9fe-839c6f72cd25"]
+pub struct BallGlowMaterial {
+    #[uniform(0)]
+    color: Color,
+    /// Pulses per second.
+    #[uniform(0)]
+    pulse_speed: f32,
+}
+
+impl Material for BallGlowMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/ball_glow.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Add
+    }
+
+    // The glow is seen from inside the shell too when the camera is close
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+struct HoleRing;
+
+#[derive(Component)]
+struct BallGlow;
+
+#[derive(Component)]
+struct BallOutline;
+
+fn spawn_highlights(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut ring_materials: ResMut<Assets<HoleRingMaterial>>,
+    mut glow_materials: ResMut<Assets<BallGlowMaterial>>,
+) {
+    commands.spawn((
+        LevelEntity,
+        HoleRing,
+        MaterialMeshBundle {
+            mesh: meshes.add(shape::Plane::from_size(1.0).into()),
+            material: ring_materials.add(HoleRingMaterial {
+                color: Color::rgba(1.0, 0.95, 0.6, 0.9),
+                pulse_speed: 0.8,
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NotShadowCaster,
+    ));
+
+    commands.spawn((
+        LevelEntity,
+        BallGlow,
+        MaterialMeshBundle {
+            mesh: meshes.add(
+                shape::UVSphere {
+                    radius: GLOW_RADIUS,
+                    sectors: 24,
+                    stacks: 16,
+                }
+                .into(),
+            ),
+            material: glow_materials.add(BallGlowMaterial {
+                color: Color::rgba(1.0, 1.0, 0.8, 0.6),
+                pulse_speed: 1.0,
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NotShadowCaster,
+    ));
+}
+
+/// Puts the ring on the hole closest to the ball of the current player.
+fn move_hole_ring(
+    mut q_ring: Query<(&mut Transform, &mut Visibility), With<HoleRing>>,
+    q_hole: Query<(&Hole, &GlobalTransform)>,
+    q_ball: Query<(&Ball, &Transform), Without<HoleRing>>,
+    game_state: Res<GameState>,
+) {
+    let Ok((mut transform, mut visibility)) = q_ring.get_single_mut() else {
+        return;
+    };
+
+    let ball_position = q_ball
+        .iter()
+        .find(|(ball, _)| ball.player_id == game_state.current_player)
+        .map(|(_, transform)| transform.translation);
+    let closest = q_hole.iter().min_by(|(_, a), (_, b)| {
+        let distance =
+            |hole: &GlobalTransform| ball_position.map_or(0.0, |p| hole.translation().distance(p));
+        distance(a).total_cmp(&distance(b))
+    });
+
+    let new_visibility = match closest {
+        Some((hole, hole_transform)) => {
+            transform.translation = hole_transform.translation() + Vec3::Y * RING_HEIGHT;
+            transform.scale = Vec3::splat(hole.radius * RING_SCALE);
+            Visibility::Inherited
+        }
+        None => Visibility::Hidden,
+    };
+    if *visibility != new_visibility {
+        *visibility = new_visibility;
+    }
+}
+
+/// Keeps the glow around the ball of the current player while they aim and while it rolls.
+fn move_ball_glow(
+    mut q_glow: Query<(&mut Transform, &mut Visibility), With<BallGlow>>,
+    q_ball: Query<(&Ball, &Transform, &BallState), Without<BallGlow>>,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+) {
+    let Ok((mut transform, mut visibility)) = q_glow.get_single_mut() else {
+        return;
+    };
+
+    // Everybody plays at once in party mode, so there is no turn to show
+    let ball = q_ball.iter().find(|(ball, _, state)| {
+        ball.player_id == game_state.current_player && **state != BallState::Holed
+    });
+    let new_visibility = match ball {
+        Some((_, ball_transform, _)) if *game_mode != GameMode::Party => {
+            transform.translation = ball_transform.translation;
+            Visibility::Inherited
+        }
+        _ => Visibility::Hidden,
+    };
+    if *visibility != new_visibility {
+        *visibility = new_visibility;
+    }
+}
+
+/// Outlines the ball of the current player with slightly larger copies of its meshes that only
+/// show their back faces. They are made again whenever the turn goes to another ball, and until
+/// the scene of the ball has been loaded.
+#[allow(clippy::too_many_arguments)]
+fn outline_current_ball(
+    mut commands: Commands,
+    q_ball: Query<(Entity, &Ball)>,
+    q_children: Query<&Children>,
+    q_mesh: Query<&Handle<Mesh>, Without<BallOutline>>,
+    q_outline: Query<Entity, With<BallOutline>>,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut outlined: Local<Option<Entity>>,
+    mut material: Local<Option<Handle<StandardMaterial>>>,
+) {
+    let current_ball = q_ball
+        .iter()
+        .find(|(_, ball)| ball.player_id == game_state.current_player)
+        .map(|(entity, _)| entity)
+        .filter(|_| *game_mode != GameMode::Party);
+    if *outlined == current_ball {
+        return;
+    }
+
+    for entity in q_outline.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *outlined = None;
+    let Some(ball) = current_ball else {
+        return;
+    };
+
+    let material = material.get_or_insert_with(|| {
+        materials.add(StandardMaterial {
+            base_color: OUTLINE_COLOR,
+            unlit: true,
+            cull_mode: Some(Face::Front),
+            ..default()
+        })
+    });
+    for mesh_entity in q_children.iter_descendants(ball) {
+        let Ok(mesh) = q_mesh.get(mesh_entity) else {
+            continue;
+        };
+        let outline = commands
+            .spawn((
+                BallOutline,
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_scale(Vec3::splat(OUTLINE_SCALE)),
+                    ..default()
+                },
+                NotShadowCaster,
+            ))
+            .id();
+        commands.entity(mesh_entity).add_child(outline);
+        *outlined = Some(ball);
+    }
+}
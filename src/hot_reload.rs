@@ -0,0 +1,70 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    level::{CurrentLevel, LaneChanged},
+    level_file, AppState,
+};
+
+/// How often the level file is checked for changes.
+const POLL_SECONDS: f32 = 0.5;
+
+/// Rebuilds the lane whenever the level file being played is saved, for course authors.
+pub struct HotReloadPlugin {
+    pub path: PathBuf,
+}
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WatchedLevel {
+            path: self.path.clone(),
+            modified: modified_time(&self.path),
+            timer: Timer::from_seconds(POLL_SECONDS, TimerMode::Repeating),
+        })
+        .add_systems(
+            Update,
+            reload_changed_level.run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct WatchedLevel {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn reload_changed_level(
+    mut watched: ResMut<WatchedLevel>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut changes: EventWriter<LaneChanged>,
+    time: Res<Time>,
+) {
+    if !watched.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let modified = modified_time(&watched.path);
+    if modified == watched.modified {
+        return;
+    }
+    watched.modified = modified;
+
+    // Editors often save in several steps, so a broken file is only reported
+    match level_file::load(&watched.path) {
+        Ok(level) => {
+            info!("Reloaded {}", watched.path.display());
+            current_level.0 = level;
+            changes.send(LaneChanged);
+        }
+        Err(e) => warn!("{}", e),
+    }
+}
@@ -0,0 +1,196 @@
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    accessibility::Accessibility,
+    ball::{self, Ball, BallSpin, BallState, Jump, JumpEvent, ShootEvent, ShootSettings},
+    chat,
+    controls::{BallControls, CommonAction, KeyBindings},
+    flythrough,
+    level::Hole,
+    online::{NetRole, PlayerOwners},
+    powerup::{self, ActivePowerUp, UsePowerUp},
+    putting::Putting,
+    replay::ReplayPlayback,
+    scoring::GameState,
+    tuning::ShotTuning,
+    GameMode,
+};
+
+/// Turns keyboard and gamepad input into aiming and shots.
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(KeyBindings::load()).add_systems(
+            Update,
+            keyboard_input
+                .before(ball::apply_shot)
+                .run_if(not(resource_exists::<ReplayPlayback>()))
+                .run_if(|role: Res<NetRole>| role.can_shoot())
+                .run_if(chat::not_typing)
+                .run_if(flythrough::not_flying),
+        );
+    }
+}
+
+/// Power added per frame while the power key is held, and when putting.
+const POWER_SPEED: f32 = 0.1;
+const PUTT_POWER_SPEED: f32 = 0.02;
+/// Aim turned per frame while an aim key is held, and with fine adjustment.
+const AIM_SPEED: f32 = 0.5 / 180.0 * PI;
+const FINE_AIM_SPEED: f32 = 0.1 / 180.0 * PI;
+/// Aim turned at once per press with coarse adjustment. Coarse steps land on multiples of it.
+const COARSE_AIM_STEP: f32 = 15.0 / 180.0 * PI;
+/// How close the aim has to be to a straight direction or the hole to snap to it.
+const AIM_SNAP_RANGE: f32 = 3.0 / 180.0 * PI;
+
+#[allow(clippy::too_many_arguments)]
+fn keyboard_input(
+    keys: Res<Input<KeyCode>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    bindings: Res<KeyBindings>,
+    game_mode: Res<GameMode>,
+    player_owners: Res<PlayerOwners>,
+    mut q_ball: Query<(
+        &Velocity,
+        &BallState,
+        &mut ShootSettings,
+        &mut Jump,
+        &Ball,
+        &Transform,
+        Option<&ActivePowerUp>,
+        Option<&Putting>,
+    )>,
+    q_hole: Query<&GlobalTransform, With<Hole>>,
+    accessibility: Res<Accessibility>,
+    tuning: Res<ShotTuning>,
+    mut game_state: ResMut<GameState>,
+    mut shots: EventWriter<ShootEvent>,
+    mut jumps: EventWriter<JumpEvent>,
+    mut use_power_ups: EventWriter<UsePowerUp>,
+    time: Res<Time>,
+    mut turning: Local<HashMap<u32, bool>>,
+) {
+    let party = *game_mode == GameMode::Party;
+
+    if !party && bindings.common.just_pressed(&keys, CommonAction::SkipTurn) {
+        game_state.advance_turn();
+    }
+
+    for (&ball_velocity, &ball_state, mut shoot, mut jump, ball, transform, power_up, putting) in
+        q_ball.iter_mut()
+    {
+        if !party && ball.player_id != game_state.current_player {
+            continue;
+        }
+        // Online, the other players' balls are shot from their own games
+        if !player_owners.is_local(ball.player_id) {
+            continue;
+        }
+
+        // In party mode every player has their own keys and gamepad
+        let input_index = if party { ball.player_id as usize } else { 0 };
+        let controls = BallControls::from_keys(&keys, bindings.get(input_index))
+            .with_aim_modifiers(&keys, bindings.get(input_index))
+            .merge(BallControls::from_gamepad(
+                Gamepad::new(input_index),
+                &gamepad_axes,
+                &gamepad_buttons,
+            ));
+        let was_turning = turning
+            .insert(ball.player_id, controls.aim != 0.0)
+            .unwrap_or(false);
+
+        if ball_state == BallState::Aiming {
+            let power_speed = if putting.is_some() {
+                PUTT_POWER_SPEED
+            } else {
+                POWER_SPEED
+            };
+            let angle_speed = if controls.fine {
+                FINE_AIM_SPEED
+            } else {
+                AIM_SPEED
+            };
+            let spin_speed = 0.02;
+
+            shoot.power += controls.power * power_speed;
+            shoot.angle += controls.aim * angle_speed;
+            if controls.aim_step != 0.0 {
+                shoot.angle =
+                    ((shoot.angle / COARSE_AIM_STEP).round() + controls.aim_step) * COARSE_AIM_STEP;
+            }
+            if controls.spin != 0.0 {
+                let spin = shoot.spin.map_or(0.0, BallSpin::signed);
+                shoot.spin = BallSpin::from_signed(spin + controls.spin * spin_speed);
+            }
+            if controls.reset {
+                *shoot = ShootSettings {
+                    angle: shoot.angle,
+                    ..ShootSettings::default()
+                };
+            }
+
+            shoot.power = shoot
+                .power
+                .max(0.0)
+                .min(powerup::max_power(power_up, &tuning));
+
+            shoot.angle %= 2.0 * PI;
+            if shoot.angle < 0.0 {
+                shoot.angle += 2.0 * PI;
+            }
+
+            // Snapped when the aim keys are let go, unless the aim was being fine-tuned
+            if accessibility.aim_snapping && was_turning && controls.aim == 0.0 && !controls.fine {
+                let hole = q_hole
+                    .iter()
+                    .map(|hole| hole.translation() - transform.translation)
+                    .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+                    .map(|to_hole| f32::atan2(-to_hole.z, to_hole.x));
+                if let Some(target) = aim_snap_target(shoot.angle, hole) {
+                    shoot.angle = target.rem_euclid(2.0 * PI);
+                }
+            }
+        }
+
+        if controls.use_power_up && ball_state == BallState::Aiming {
+            use_power_ups.send(UsePowerUp {
+                player_id: ball.player_id,
+            });
+        }
+
+        if controls.shoot && ball_state == BallState::Aiming && shoot.power > 0.0 {
+            shots.send(ShootEvent::new(ball.player_id, &shoot));
+        }
+
+        if !jump.can_jump(ball_state) {
+            jump.charge = 0.0;
+        } else if controls.jump {
+            jump.charge = (jump.charge + time.delta_seconds() / Jump::CHARGE_SECONDS).min(1.0);
+        } else if jump.charge > 0.0 {
+            // Only jump off the ground, otherwise the charge is lost
+            if ball_velocity.linvel.y.abs() <= 0.05 {
+                jumps.send(JumpEvent {
+                    player_id: ball.player_id,
+                    charge: jump.charge,
+                });
+            }
+            jump.charge = 0.0;
+        }
+    }
+}
+
+/// The straight direction or hole direction closest to `angle`, if one is close enough.
+fn aim_snap_target(angle: f32, hole: Option<f32>) -> Option<f32> {
+    let difference = |target: f32| ((angle - target + PI).rem_euclid(2.0 * PI) - PI).abs();
+    (0..4)
+        .map(|i| i as f32 * PI / 2.0)
+        .chain(hole)
+        .filter(|&target| difference(target) <= AIM_SNAP_RANGE)
+        .min_by(|&a, &b| difference(a).total_cmp(&difference(b)))
+}
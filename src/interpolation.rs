@@ -0,0 +1,338 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{Ball, BALL_REST_SPEED},
+    online::{ConnectionStatus, NetMessage, NetRole, ReceivedMessage, SendMessage},
+    AppState,
+};
+
+/// How far behind the host the balls are shown, so that there is usually a newer ball state to
+/// move towards. A bit more than the time between two ball states.
+const INTERPOLATION_DELAY: f64 = 0.15;
+/// How far past the newest ball state a ball keeps moving on its own when states are late.
+const MAX_EXTRAPOLATION: f64 = 0.25;
+/// Ball states older than this, relative to the shown time, are dropped.
+const BUFFER_SECONDS: f64 = 1.0;
+/// A ball further than this from where it should be is moved there at once instead of smoothly.
+const SNAP_DISTANCE: f32 = 0.3;
+/// How long a shot taken here is simulated locally while waiting for the host to apply it.
+const PREDICTION_TIMEOUT: f32 = 2.0;
+/// How quickly the estimate of the host's clock follows delays getting longer.
+const CLOCK_DRIFT_RATE: f64 = 0.01;
+
+/// Smooths out the ball states that clients get from the host a few times a second.
+///
+/// Balls are shown slightly in the past, between the two ball states around that time, and
+/// keep moving on their own for a moment when the next state is late. A ball shot on this
+/// client is simulated here right away, and only put back where the host has it if the two
+/// disagree too much.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HostClock>()
+            .add_systems(OnEnter(AppState::InGame), reset_host_clock)
+            .add_systems(
+                Update,
+                (
+                    track_remote_balls,
+                    forget_old_host,
+                    predict_local_shots,
+                    buffer_ball_states,
+                    interpolate_remote_balls,
+                )
+                    .chain()
+                    .run_if(|role: Res<NetRole>| role.is_client())
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// A ball whose position comes from the host.
+#[derive(Component, Default)]
+struct RemoteBall {
+    /// Ball states from the host, oldest first.
+    buffer: VecDeque<TimedState>,
+    /// Set while a shot taken here is simulated locally.
+    prediction: Option<Prediction>,
+}
+
+struct Prediction {
+    timer: Timer,
+    /// Whether the host has been seen moving the ball, i.e. it has applied the shot.
+    confirmed: bool,
+}
+
+#[derive(Clone, Copy)]
+struct TimedState {
+    /// On the host's clock.
+    time: f64,
+    translation: Vec3,
+    rotation: Quat,
+    linvel: Vec3,
+    angvel: Vec3,
+}
+
+impl TimedState {
+    /// Where the ball would be `seconds` later if it kept going the same way.
+    fn extrapolate(&self, seconds: f64) -> Vec3 {
+        self.translation + self.linvel * seconds as f32
+    }
+
+    fn lerp(&self, other: &TimedState, t: f32) -> TimedState {
+        TimedState {
+            time: self.time + (other.time - self.time) * t as f64,
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            linvel: self.linvel.lerp(other.linvel, t),
+            angvel: self.angvel.lerp(other.angvel, t),
+        }
+    }
+}
+
+/// The difference between our clock and the host's, estimated from the ball states.
+#[derive(Resource, Default)]
+struct HostClock {
+    /// Our time minus the host's time when a ball state arrives, at its shortest.
+    offset: Option<f64>,
+}
+
+impl HostClock {
+    /// Returns whether the host's clock went back, which happens when another game takes over
+    /// as host. The estimate starts over then.
+    fn update(&mut self, host_time: f64, now: f64) -> bool {
+        let went_back = self
+            .host_time(now)
+            .is_some_and(|expected| host_time < expected - BUFFER_SECONDS);
+        if went_back {
+            self.offset = None;
+        }
+
+        let sample = now - host_time;
+        self.offset = Some(match self.offset {
+            // States that took longer are jitter, but the delay may also have grown for real
+            Some(offset) if sample > offset => offset + (sample - offset) * CLOCK_DRIFT_RATE,
+            _ => sample,
+        });
+        went_back
+    }
+
+    fn host_time(&self, now: f64) -> Option<f64> {
+        self.offset.map(|offset| now - offset)
+    }
+}
+
+fn reset_host_clock(mut clock: ResMut<HostClock>) {
+    clock.offset = None;
+}
+
+fn track_remote_balls(
+    mut commands: Commands,
+    q_ball: Query<Entity, (With<Ball>, Without<RemoteBall>)>,
+) {
+    for entity in q_ball.iter() {
+        commands.entity(entity).insert(RemoteBall::default());
+    }
+}
+
+/// Drops everything heard from a host that went away, since the new host's clock started at
+/// another time.
+fn forget_old_host(
+    mut statuses: EventReader<ConnectionStatus>,
+    mut q_remote: Query<&mut RemoteBall>,
+    mut clock: ResMut<HostClock>,
+) {
+    let host_changed = statuses
+        .iter()
+        .any(|status| matches!(status, ConnectionStatus::HostChanged));
+    if !host_changed {
+        return;
+    }
+
+    clock.offset = None;
+    for mut remote in q_remote.iter_mut() {
+        remote.buffer.clear();
+    }
+}
+
+/// Lets the balls of shots taken here move right away instead of waiting for the host.
+fn predict_local_shots(
+    mut sent: EventReader<SendMessage>,
+    mut q_ball: Query<(&Ball, &mut RemoteBall)>,
+) {
+    for SendMessage(message) in sent.iter() {
+        let NetMessage::Shot(shot) = message else {
+            continue;
+        };
+        for (_, mut remote) in q_ball
+            .iter_mut()
+            .filter(|(ball, _)| ball.player_id == shot.player_id)
+        {
+            remote.prediction = Some(Prediction {
+                timer: Timer::from_seconds(PREDICTION_TIMEOUT, TimerMode::Once),
+                confirmed: false,
+            });
+        }
+    }
+}
+
+fn buffer_ball_states(
+    mut received: EventReader<ReceivedMessage>,
+    mut q_ball: Query<(&Ball, &mut RemoteBall, &mut Transform, &mut Velocity)>,
+    mut clock: ResMut<HostClock>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds_f64();
+
+    for ReceivedMessage(message) in received.iter() {
+        let NetMessage::BallStates {
+            time: host_time,
+            balls,
+        } = message
+        else {
+            continue;
+        };
+        if clock.update(*host_time, now) {
+            info!("The host's clock went back, starting over");
+            for (_, mut remote, ..) in q_ball.iter_mut() {
+                remote.buffer.clear();
+            }
+        }
+
+        for snapshot in balls {
+            let Some((_, mut remote, mut transform, mut velocity)) = q_ball
+                .iter_mut()
+                .find(|(ball, ..)| ball.player_id == snapshot.player_id)
+            else {
+                continue;
+            };
+            if remote
+                .buffer
+                .back()
+                .is_some_and(|last| last.time >= *host_time)
+            {
+                continue;
+            }
+            let state = TimedState {
+                time: *host_time,
+                translation: Vec3::from_array(snapshot.translation),
+                rotation: Quat::from_array(snapshot.rotation),
+                linvel: Vec3::from_array(snapshot.linvel),
+                angvel: Vec3::from_array(snapshot.angvel),
+            };
+            remote.buffer.push_back(state);
+            while remote
+                .buffer
+                .front()
+                .is_some_and(|oldest| oldest.time < host_time - BUFFER_SECONDS)
+            {
+                remote.buffer.pop_front();
+            }
+
+            check_prediction(&mut remote, &state, &mut transform, &mut velocity);
+        }
+    }
+
+    // Shots the host never applied are taken back
+    for (_, mut remote, ..) in q_ball.iter_mut() {
+        if let Some(prediction) = &mut remote.prediction {
+            if !prediction.confirmed && prediction.timer.tick(time.delta()).finished() {
+                info!("The host did not apply a shot, following it again");
+                remote.prediction = None;
+            }
+        }
+    }
+}
+
+/// Compares a locally simulated ball with the newest state from the host, ending the
+/// prediction once both have stopped or when they have drifted too far apart.
+fn check_prediction(
+    remote: &mut RemoteBall,
+    state: &TimedState,
+    transform: &mut Transform,
+    velocity: &mut Velocity,
+) {
+    let Some(prediction) = &mut remote.prediction else {
+        return;
+    };
+    let host_moving = state.linvel.length() > BALL_REST_SPEED;
+    if !prediction.confirmed {
+        // Until the shot reaches the host, its states still show the ball before the shot
+        prediction.confirmed = host_moving;
+        return;
+    }
+
+    let local_moving = velocity.linvel.length() > BALL_REST_SPEED;
+    // Ours is ahead by about the delay between us and the host
+    let expected = state.extrapolate(INTERPOLATION_DELAY);
+    if transform.translation.distance(expected) > SNAP_DISTANCE {
+        debug!(
+            "Prediction was off by {}",
+            transform.translation.distance(expected)
+        );
+        transform.translation = expected;
+        transform.rotation = state.rotation;
+        velocity.linvel = state.linvel;
+        velocity.angvel = state.angvel;
+        remote.prediction = None;
+    } else if !host_moving && !local_moving {
+        remote.prediction = None;
+    }
+}
+
+/// Shows every ball that isn't predicted where the host had it a moment ago.
+fn interpolate_remote_balls(
+    mut q_ball: Query<(&RemoteBall, &mut Transform, &mut Velocity)>,
+    clock: Res<HostClock>,
+    time: Res<Time>,
+) {
+    let Some(host_now) = clock.host_time(time.elapsed_seconds_f64()) else {
+        return;
+    };
+    let render_time = host_now - INTERPOLATION_DELAY;
+
+    for (remote, mut transform, mut velocity) in q_ball.iter_mut() {
+        if remote.prediction.is_some() {
+            continue;
+        }
+        let Some(state) = sample(&remote.buffer, render_time) else {
+            continue;
+        };
+        transform.translation = state.translation;
+        transform.rotation = state.rotation;
+        velocity.linvel = state.linvel;
+        velocity.angvel = state.angvel;
+    }
+}
+
+/// The state of a ball at `time`, between the two buffered states around it, or moved on from
+/// the newest one if there is none after it yet.
+fn sample(buffer: &VecDeque<TimedState>, time: f64) -> Option<TimedState> {
+    let newest = buffer.back()?;
+    if time >= newest.time {
+        let ahead = (time - newest.time).min(MAX_EXTRAPOLATION);
+        return Some(TimedState {
+            translation: newest.extrapolate(ahead),
+            ..*newest
+        });
+    }
+
+    let after = buffer.iter().position(|state| state.time > time)?;
+    if after == 0 {
+        return Some(buffer[0]);
+    }
+    let (from, to) = (&buffer[after - 1], &buffer[after]);
+
+    // A ball that was put somewhere else, e.g. back on the course, jumps there
+    let span = (to.time - from.time) as f32;
+    let reachable = from.linvel.length().max(to.linvel.length()) * span + SNAP_DISTANCE;
+    if from.translation.distance(to.translation) > reachable {
+        return Some(*to);
+    }
+
+    let t = ((time - from.time) / (to.time - from.time)) as f32;
+    Some(from.lerp(to, t))
+}
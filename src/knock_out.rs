@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{self, Ball, BallStopped, ShootEvent},
+    scoring::{self, StrokeAdded},
+    GameMode,
+};
+
+/// An optional rule where knocking another player's ball out of the course costs the shooter a
+/// penalty stroke, and the knocked ball is put back where it was before the shot.
+///
+/// Party play is left out, since there it is not clear whose shot a contact belongs to.
+pub struct KnockOutPlugin;
+
+impl Plugin for KnockOutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KnockOutPenalty>()
+            .init_resource::<LastShooter>()
+            .add_systems(
+                Update,
+                (
+                    remember_positions.after(ball::apply_shot),
+                    attribute_contacts,
+                    restore_knocked_balls
+                        .after(scoring::check_ball_in_hole)
+                        .before(scoring::check_ball_on_ground),
+                )
+                    .chain()
+                    .run_if(resource_equals(KnockOutPenalty(true)))
+                    .run_if(not(resource_equals(GameMode::Party))),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct KnockOutPenalty(pub bool);
+
+/// The player who took the shot that is being played out.
+#[derive(Resource, Default)]
+struct LastShooter(Option<u32>);
+
+/// Where a ball was when the last shot was taken.
+#[derive(Component)]
+struct PreShotPosition(Vec3);
+
+/// Set on a ball that was hit by the shooter's ball, or by another ball it hit, during the
+/// current shot.
+#[derive(Component)]
+struct KnockedBy(u32);
+
+fn remember_positions(
+    mut commands: Commands,
+    mut shots: EventReader<ShootEvent>,
+    q_ball: Query<(Entity, &Transform), With<Ball>>,
+    mut shooter: ResMut<LastShooter>,
+) {
+    let Some(shot) = shots.iter().last() else {
+        return;
+    };
+    shooter.0 = Some(shot.player_id);
+    for (entity, transform) in q_ball.iter() {
+        commands
+            .entity(entity)
+            .insert(PreShotPosition(transform.translation))
+            .remove::<KnockedBy>();
+    }
+}
+
+fn attribute_contacts(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_ball: Query<(&Ball, Option<&KnockedBy>)>,
+    shooter: Res<LastShooter>,
+) {
+    let Some(shooter) = shooter.0 else {
+        return;
+    };
+
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *event else {
+            continue;
+        };
+        let (Ok((ball1, knocked1)), Ok((ball2, knocked2))) = (q_ball.get(e1), q_ball.get(e2))
+        else {
+            continue;
+        };
+
+        // Contacts pass on along chains of balls hitting each other
+        let in_play = |ball: &Ball, knocked: Option<&KnockedBy>| {
+            ball.player_id == shooter || knocked.is_some()
+        };
+        for (victim, ball, pusher, pusher_knocked) in
+            [(e2, ball2, ball1, knocked1), (e1, ball1, ball2, knocked2)]
+        {
+            if ball.player_id != shooter && in_play(pusher, pusher_knocked) {
+                commands.entity(victim).insert(KnockedBy(shooter));
+            }
+        }
+    }
+}
+
+/// Handles knocked balls that stopped out of the course before the usual out-of-bounds rule
+/// gets to them.
+fn restore_knocked_balls(
+    mut commands: Commands,
+    mut stopped: EventReader<BallStopped>,
+    mut q_ball: Query<(
+        &mut Ball,
+        &mut Transform,
+        &mut Velocity,
+        Option<&KnockedBy>,
+        Option<&PreShotPosition>,
+    )>,
+    mut strokes: EventWriter<StrokeAdded>,
+) {
+    for event in stopped.iter() {
+        let Ok((_, mut transform, mut velocity, knocked, before)) = q_ball.get_mut(event.entity)
+        else {
+            continue;
+        };
+        let Some(&KnockedBy(shooter)) = knocked else {
+            continue;
+        };
+        commands.entity(event.entity).remove::<KnockedBy>();
+        let (Some(before), true) = (before, transform.translation.y < scoring::OUT_OF_BOUNDS_Y)
+        else {
+            continue;
+        };
+
+        transform.translation = before.0;
+        *velocity = Velocity::zero();
+        if let Some((mut shooter_ball, ..)) = q_ball
+            .iter_mut()
+            .find(|(ball, ..)| ball.player_id == shooter)
+        {
+            shooter_ball.hits += 1;
+        }
+        strokes.send(StrokeAdded {
+            player_id: shooter,
+            penalty: true,
+        });
+    }
+}
@@ -0,0 +1,1108 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    core_pipeline::{prepass::DepthPrepass, Skybox},
+    gltf::{GltfMesh, GltfNode},
+    pbr::EnvironmentMapLight,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    breakable::{self, Breakable},
+    collision,
+    depth_of_field::DepthOfField,
+    fan::{self, FanCycle},
+    grass::GrassTile,
+    lighting::{self, Atmosphere, TimeOfDay},
+    pickup::{self, Pickup},
+    sky::Sky,
+    surface::SurfaceType,
+    terrain::Terrain,
+    tube::{self, TubeShape},
+    AppState, NeedsColorChange,
+};
+
+/// Builds the course of the current level: lanes, holes, props, sky and lighting.
+///
+/// The level to build is read from the `CurrentLevel` resource when entering `AppState::InGame`.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AmbientLight {
+            color: Color::WHITE,
+            brightness: 1.0 / 4.0f32,
+        })
+        .init_resource::<Levels>()
+        .add_event::<LaneChanged>()
+        .add_systems(Startup, setup_sun)
+        .add_systems(
+            OnEnter(AppState::InGame),
+            (load_level, apply_level_sky, apply_time_of_day),
+        )
+        .add_systems(OnExit(AppState::InGame), despawn_level)
+        .add_systems(OnExit(AppState::Editor), despawn_level)
+        .add_systems(
+            Update,
+            rebuild_lane.run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor))),
+        );
+    }
+}
+
+/// Despawns everything of the level that was played, so that the next one starts from scratch.
+fn despawn_level(mut commands: Commands, q_level: Query<Entity, With<LevelEntity>>) {
+    for entity in q_level.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn setup_sun(mut commands: Commands) {
+    commands.spawn((
+        Sun,
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                shadows_enabled: true,
+                illuminance: 20000.0,
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 1.5, -1.0)
+                .looking_at(Vec3::new(0.0, 0.0, 0.0), Vec3::Y),
+            ..default()
+        },
+    ));
+}
+
+/// A cup that balls can be holed in. Also the component on the hole's sensor.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Hole {
+    /// Tile the hole is in, which should be a `HoleFloor` so that there is a cup to fall into.
+    pub position: (i32, i32, i32),
+    #[serde(default = "Hole::default_radius")]
+    pub radius: f32,
+    /// Par for finishing in this hole, if it differs from the level's par.
+    #[serde(default)]
+    pub par: Option<u32>,
+    /// Strokes added to the score of a player finishing in this hole. Negative for a bonus.
+    #[serde(default)]
+    pub modifier: i32,
+}
+
+impl Hole {
+    pub fn at(position: (i32, i32, i32)) -> Self {
+        Self {
+            position,
+            radius: Self::default_radius(),
+            par: None,
+            modifier: 0,
+        }
+    }
+
+    /// The score for finishing in this hole after the given number of strokes.
+    pub fn score(&self, strokes: u32) -> u32 {
+        (strokes as i32 + self.modifier).max(1) as u32
+    }
+
+    fn default_radius() -> f32 {
+        0.05
+    }
+}
+
+/// Everything spawned for the tiles of the lane and its holes, so that it can be rebuilt.
+#[derive(Component)]
+pub struct LaneTile;
+
+/// Everything that belongs to the level being played, from the course to the balls and their
+/// markers, so that it can all be despawned when the level is left.
+#[derive(Component)]
+pub struct LevelEntity;
+
+/// The kind of lane tile a floor collider belongs to.
+#[derive(Component, Clone, Copy)]
+pub struct FloorTile(pub LanePart);
+
+/// Sent when the tiles of the current level have changed and the lane should be rebuilt.
+#[derive(Event)]
+pub struct LaneChanged;
+
+/// Collider of a wall tile.
+#[derive(Component)]
+pub struct Wall;
+
+/// Sensor above a checkpoint tile.
+#[derive(Component)]
+pub struct Checkpoint {
+    pub position: Vec3,
+}
+
+/// A box of tiles where balls fall with a different gravity. Also the component on its sensor.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GravityZone {
+    /// Opposite corner tiles of the box, both included.
+    pub from: (i32, i32, i32),
+    pub to: (i32, i32, i32),
+    /// Multiplier of the normal gravity inside the zone. Negative values make balls float up.
+    pub gravity_scale: f32,
+}
+
+impl GravityZone {
+    /// Center and half size of the box in world space, from the floor up to the next height level.
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let a = tile_center(self.from.0, self.from.1, self.from.2);
+        let b = tile_center(self.to.0, self.to.1, self.to.2);
+        let min = a.min(b) - Vec3::new(0.2, 0.0, 0.2);
+        let max = a.max(b) + Vec3::new(0.2, LEVEL_HEIGHT, 0.2);
+        ((min + max) / 2.0, (max - min) / 2.0)
+    }
+}
+
+/// Where balls falling out of the course are put back, in place of where they were last hit.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DropZone {
+    /// Tile the ball is dropped onto.
+    pub position: (i32, i32, i32),
+    /// Opposite corner tiles of the area this zone is for, both included. Balls leaving the
+    /// course over it are dropped here. When `None`, the zone is for the whole course.
+    #[serde(default)]
+    pub hazard: Option<((i32, i32, i32), (i32, i32, i32))>,
+}
+
+impl DropZone {
+    /// True when `position` is over the hazard area of the zone, at any height.
+    fn covers(&self, position: Vec3) -> bool {
+        let Some((from, to)) = self.hazard else {
+            return true;
+        };
+        let a = tile_center(from.0, from.1, from.2);
+        let b = tile_center(to.0, to.1, to.2);
+        let min = a.min(b) - Vec3::new(0.2, 0.0, 0.2);
+        let max = a.max(b) + Vec3::new(0.2, 0.0, 0.2);
+        (min.x..=max.x).contains(&position.x) && (min.z..=max.z).contains(&position.z)
+    }
+}
+
+/// Tiles keyed by grid position `(x, y, z)`, where `y` is the height level.
+#[derive(Default, Clone, Hash, Serialize, Deserialize)]
+pub struct LaneConfig(pub Vec<((i32, i32, i32), LanePart)>);
+
+impl LaneConfig {
+    pub fn with_3x3(
+        mut self,
+        cx: i32,
+        cy: i32,
+        cz: i32,
+        around: LanePart,
+        center: LanePart,
+    ) -> Self {
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let x = cx + dx;
+                let z = cz + dz;
+                self.0
+                    .push(((x, cy, z), if dx == 0 && dz == 0 { center } else { around }));
+            }
+        }
+        self
+    }
+
+    /// Adds walls on every floor edge that does not lead to another floor tile.
+    ///
+    /// An edge next to a floor one level below is left open so the ball can drop down onto it.
+    /// Lanes two or more levels apart do not affect each other, which is how bridges are made.
+    pub fn with_walls_around(mut self) -> Self {
+        let grass: HashSet<_> = self
+            .0
+            .iter()
+            .filter(|(_, part)| part.is_floor())
+            .map(|(pos, _)| *pos)
+            .collect();
+        // Tubes have their own sides, but the floor leading into them must stay open
+        let passable: HashSet<_> = self
+            .0
+            .iter()
+            .filter(|(_, part)| part.is_floor() || part.tube().is_some())
+            .map(|(pos, _)| *pos)
+            .collect();
+        let open = |x: i32, y: i32, z: i32| {
+            passable.contains(&(x, y, z)) || passable.contains(&(x, y - 1, z))
+        };
+
+        let mut walls: Vec<(i32, i32, i32, Direction)> = Vec::new();
+        for &(x, y, z) in grass.iter() {
+            if !open(x + 1, y, z) {
+                walls.push((x, y, z, Direction::Right));
+            }
+            if !open(x - 1, y, z) {
+                walls.push((x, y, z, Direction::Left));
+            }
+            if !open(x, y, z + 1) {
+                walls.push((x, y, z, Direction::Up));
+            }
+            if !open(x, y, z - 1) {
+                walls.push((x, y, z, Direction::Down));
+            }
+        }
+
+        for &(x, y, z, dir) in walls.iter() {
+            self.0.push(((x, y, z), LanePart::Wall(dir)));
+        }
+
+        self
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Level {
+    /// Shown on scorecards. Empty for courses without a name.
+    #[serde(default)]
+    pub name: String,
+    /// Who made the course. Empty when unknown.
+    #[serde(default)]
+    pub author: String,
+    /// How hard the author rates the course. Unrated when `None`.
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    /// A few words about the course, shown when picking it.
+    #[serde(default)]
+    pub description: String,
+    pub lanes: LaneConfig,
+    pub sky: Sky,
+    pub time_of_day: TimeOfDay,
+    pub props: Vec<Prop>,
+    pub par: u32,
+    /// Floor tiles that are not the default green.
+    #[serde(default)]
+    pub surfaces: Vec<((i32, i32, i32), SurfaceType)>,
+    /// Extra lanes that only collide with one player's ball, for split-lane party courses.
+    #[serde(default)]
+    pub player_lanes: Vec<PlayerLane>,
+    /// Where balls can be holed. When empty, every `HoleFloor` tile has a hole.
+    #[serde(default)]
+    pub holes: Vec<Hole>,
+    /// Multiplier of the normal gravity for the whole level, e.g. for holes on the moon.
+    #[serde(default = "Level::default_gravity_scale")]
+    pub gravity_scale: f32,
+    /// Multiplier of how quickly balls slow down when rolling on any surface, e.g. below 1 for
+    /// an ice world.
+    #[serde(default = "Level::default_physics_scale")]
+    pub damping_scale: f32,
+    /// Multiplier of how bouncy balls are against everything.
+    #[serde(default = "Level::default_physics_scale")]
+    pub restitution_scale: f32,
+    #[serde(default)]
+    pub gravity_zones: Vec<GravityZone>,
+    /// On and off cycles of fans. Fans without one are always on.
+    #[serde(default)]
+    pub fan_cycles: Vec<((i32, i32, i32), FanCycle)>,
+    /// Obstacles that can be smashed, e.g. to open up shortcuts.
+    #[serde(default)]
+    pub breakables: Vec<Breakable>,
+    /// Coins and stars to collect along the way.
+    #[serde(default)]
+    pub pickups: Vec<Pickup>,
+    /// A whole course modeled in a glTF scene, e.g. `courses/canyon.gltf#Scene0`, played in
+    /// addition to the tiles. See `CourseImportPlugin` for how its nodes are named.
+    #[serde(default)]
+    pub course_scene: Option<String>,
+    /// Rolling ground in place of floor tiles.
+    #[serde(default)]
+    pub terrain: Vec<Terrain>,
+    #[serde(default)]
+    pub atmosphere: Atmosphere,
+    /// Where balls start. When empty, they start in the default zone by the origin.
+    #[serde(default)]
+    pub tee_zones: Vec<TeeZone>,
+    #[serde(default)]
+    pub drop_zones: Vec<DropZone>,
+}
+
+impl Level {
+    fn default_gravity_scale() -> f32 {
+        1.0
+    }
+
+    fn default_physics_scale() -> f32 {
+        1.0
+    }
+
+    /// All tiles in the level, along with the player whose lane they belong to, if any.
+    pub fn tiles(&self) -> impl Iterator<Item = (((i32, i32, i32), LanePart), Option<u32>)> + '_ {
+        let shared_tiles = self.lanes.0.iter().map(|&tile| (tile, None));
+        let owned_tiles = self.player_lanes.iter().flat_map(|player_lane| {
+            player_lane
+                .lanes
+                .0
+                .iter()
+                .map(|&tile| (tile, Some(player_lane.player_id)))
+        });
+        shared_tiles.chain(owned_tiles)
+    }
+
+    /// The zone that the ball of `player_id` starts in.
+    pub fn tee_zone(&self, player_id: u32) -> TeeZone {
+        let own = self
+            .tee_zones
+            .iter()
+            .find(|z| z.player_id == Some(player_id));
+        let shared = self.tee_zones.iter().find(|z| z.player_id.is_none());
+        own.or(shared).copied().unwrap_or_default()
+    }
+
+    /// The tile center of the closest drop zone for a ball that fell out of the course at
+    /// `position`, if any zone is for that part of the course.
+    pub fn drop_zone_for(&self, position: Vec3) -> Option<Vec3> {
+        let distance = |point: Vec3| Vec2::new(point.x - position.x, point.z - position.z).length();
+        self.drop_zones
+            .iter()
+            .filter(|zone| zone.covers(position))
+            .map(|zone| tile_center(zone.position.0, zone.position.1, zone.position.2))
+            .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+    }
+
+    /// The par of the level when finishing in the given hole.
+    pub fn par_for(&self, hole: &Hole) -> u32 {
+        hole.par.unwrap_or(self.par)
+    }
+
+    /// Every hole in the level, along with the player whose lane it is in, if any.
+    pub fn holes(&self) -> Vec<(Hole, Option<u32>)> {
+        if !self.holes.is_empty() {
+            return self.holes.iter().map(|&hole| (hole, None)).collect();
+        }
+
+        self.tiles()
+            .filter(|((_, part), _)| *part == LanePart::HoleFloor)
+            .map(|((position, _), owner)| (Hole::at(position), owner))
+            .collect()
+    }
+}
+
+/// How hard a course is, as rated by its author.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    /// Key of its name in the language files.
+    pub fn key(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "difficulty.easy",
+            Difficulty::Medium => "difficulty.medium",
+            Difficulty::Hard => "difficulty.hard",
+            Difficulty::Expert => "difficulty.expert",
+        }
+    }
+}
+
+/// An area that balls start in, where each player can pick their spot before their first shot.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TeeZone {
+    /// Middle of the area, at the height balls are dropped from.
+    pub center: [f32; 3],
+    /// Extent of the area along x and z.
+    pub size: [f32; 2],
+    /// The player the zone is for, or `None` for everybody without a zone of their own.
+    #[serde(default)]
+    pub player_id: Option<u32>,
+}
+
+impl Default for TeeZone {
+    fn default() -> Self {
+        Self {
+            center: [-0.2, 1.0, 0.0],
+            size: [0.4, 0.8],
+            player_id: None,
+        }
+    }
+}
+
+impl TeeZone {
+    /// The point of the zone at `u` and `v` from 0 to 1 along x and z.
+    pub fn point(&self, u: f32, v: f32) -> Vec3 {
+        Vec3::from(self.center) + Vec3::new((u - 0.5) * self.size[0], 0.0, (v - 0.5) * self.size[1])
+    }
+
+    /// `position` moved sideways into the zone, keeping its height.
+    pub fn clamp(&self, position: Vec3) -> Vec3 {
+        let half = Vec3::new(self.size[0], 0.0, self.size[1]) * 0.5;
+        let center = Vec3::from(self.center);
+        let clamped = position.clamp(center - half, center + half);
+        Vec3::new(clamped.x, position.y, clamped.z)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlayerLane {
+    pub player_id: u32,
+    pub lanes: LaneConfig,
+}
+
+/// A decorative glTF scene placed around the lane. Props have no colliders.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Prop {
+    pub scene: String,
+    pub position: [f32; 3],
+    /// Euler angles (XYZ) in radians.
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "Prop::default_scale")]
+    pub scale: [f32; 3],
+    #[serde(default)]
+    pub color: Option<Color>,
+}
+
+impl Prop {
+    pub fn new(scene: &str, position: [f32; 3]) -> Self {
+        Self {
+            scene: scene.to_string(),
+            position,
+            rotation: [0.0; 3],
+            scale: Self::default_scale(),
+            color: None,
+        }
+    }
+
+    pub fn default_scale() -> [f32; 3] {
+        [1.0; 3]
+    }
+
+    pub fn with_rotation(mut self, rotation: [f32; 3]) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: [f32; 3]) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn transform(&self) -> Transform {
+        let [rx, ry, rz] = self.rotation;
+        Transform::from_translation(self.position.into())
+            .with_rotation(Quat::from_euler(EulerRot::XYZ, rx, ry, rz))
+            .with_scale(self.scale.into())
+    }
+
+    pub fn tree(x: f32, z: f32, height: f32) -> Self {
+        Prop::new("models/cone.gltf#Scene0", [x, 0.1 + height * 0.5, z])
+            .with_scale([height * 10.0, height * 20.0, height * 10.0])
+            .with_color(Color::rgb(0.1, 0.4, 0.15))
+    }
+
+    pub fn rock(x: f32, z: f32, size: f32) -> Self {
+        Prop::new("models/cube.gltf#Scene0", [x, 0.1, z])
+            .with_rotation([0.3, x + z, 0.2])
+            .with_scale([size * 20.0; 3])
+            .with_color(Color::rgb(0.45, 0.43, 0.4))
+    }
+}
+
+#[derive(Resource)]
+pub struct Levels {
+    pub level1: Level,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self {
+            level1: Level {
+                name: "Meadow".to_string(),
+                author: String::new(),
+                difficulty: Some(Difficulty::Easy),
+                description: String::new(),
+                lanes: LaneConfig::default()
+                    .with_3x3(0, 0, 0, LanePart::BasicFloor, LanePart::BasicFloor)
+                    .with_3x3(0, 0, 3, LanePart::BasicFloor, LanePart::BasicFloor)
+                    .with_3x3(0, 0, 6, LanePart::BasicFloor, LanePart::BasicFloor)
+                    .with_3x3(0, 0, 9, LanePart::BasicFloor, LanePart::BasicFloor)
+                    .with_3x3(3, 0, 9, LanePart::BasicFloor, LanePart::BasicFloor)
+                    .with_3x3(6, 0, 9, LanePart::BasicFloor, LanePart::BasicFloor)
+                    .with_3x3(6, 0, 12, LanePart::BasicFloor, LanePart::HoleFloor)
+                    .with_walls_around(),
+                sky: Sky::ClearDay,
+                time_of_day: TimeOfDay::Noon,
+                props: vec![
+                    Prop::tree(-1.0, 0.0, 0.6),
+                    Prop::tree(-1.1, 1.6, 0.8),
+                    Prop::tree(-0.9, 3.4, 0.5),
+                    Prop::tree(1.2, 1.2, 0.7),
+                    Prop::tree(3.6, 3.0, 0.9),
+                    Prop::rock(1.0, 0.2, 0.1),
+                    Prop::rock(3.5, 4.6, 0.15),
+                    Prop::rock(-0.9, 5.0, 0.08),
+                ],
+                par: 3,
+                surfaces: Vec::new(),
+                player_lanes: Vec::new(),
+                holes: Vec::new(),
+                gravity_scale: 1.0,
+                damping_scale: 1.0,
+                restitution_scale: 1.0,
+                gravity_zones: Vec::new(),
+                fan_cycles: Vec::new(),
+                breakables: Vec::new(),
+                pickups: Vec::new(),
+                course_scene: None,
+                terrain: Vec::new(),
+                atmosphere: Atmosphere::default(),
+                tee_zones: Vec::new(),
+                drop_zones: Vec::new(),
+            },
+        }
+    }
+}
+
+/// The level that is played when entering `AppState::InGame`.
+#[derive(Resource)]
+pub struct CurrentLevel(pub Level);
+
+#[derive(Component)]
+struct Sun;
+
+fn apply_time_of_day(
+    mut commands: Commands,
+    mut q_sun: Query<(&mut DirectionalLight, &mut Transform), With<Sun>>,
+    q_camera: Query<Entity, With<Camera3d>>,
+    mut ambient: ResMut<AmbientLight>,
+    current_level: Res<CurrentLevel>,
+) {
+    let level = &current_level.0;
+    let preset = level.time_of_day.preset();
+
+    for (mut light, mut transform) in q_sun.iter_mut() {
+        light.color = preset.sun_color;
+        light.illuminance = preset.sun_illuminance;
+        *transform = preset.sun_transform();
+    }
+
+    ambient.color = preset.ambient_color;
+    ambient.brightness = preset.ambient_brightness;
+
+    for camera in q_camera.iter() {
+        let mut camera = commands.entity(camera);
+        camera.insert(level.atmosphere.fog_settings(&preset));
+        match level.atmosphere.depth_of_field {
+            Some(strength) => camera.insert((DepthOfField::new(strength), DepthPrepass)),
+            None => camera.remove::<(DepthOfField, DepthPrepass)>(),
+        };
+    }
+
+    if preset.lane_lights {
+        for &((x, y, z), part) in level.lanes.0.iter() {
+            if part == LanePart::BasicFloor && x.rem_euclid(3) == 0 && z.rem_euclid(3) == 0 {
+                let mut light = lighting::lane_light();
+                light.transform = Transform::from_translation(tile_center(x, y, z) + Vec3::Y * 0.5);
+                commands.spawn((light, LevelEntity));
+            }
+        }
+    }
+}
+
+/// Vertical distance between two height levels of the tile grid.
+pub const LEVEL_HEIGHT: f32 = 0.2;
+
+/// Height of the top of a floor tile above the tile center.
+pub const FLOOR_TOP: f32 = 0.05;
+/// Thickness of the lip around a hole. Thin enough to roll over, but fast balls get thrown off it.
+const RIM_RADIUS: f32 = 0.003;
+const RIM_SEGMENTS: usize = 16;
+/// A bit bouncier than the ball, so balls hitting the far side of the cup too fast pop out.
+const RIM_RESTITUTION: f32 = 0.6;
+
+/// The tile whose center is closest to `position`.
+pub fn nearest_tile(position: Vec3) -> (i32, i32, i32) {
+    (
+        (position.x / 0.4).round() as i32,
+        ((position.y - 0.3) / LEVEL_HEIGHT).round() as i32,
+        (position.z / 0.4).round() as i32,
+    )
+}
+
+/// World position of the center of the tile at the given grid coordinates.
+pub fn tile_center(x: i32, y: i32, z: i32) -> Vec3 {
+    Vec3::new(
+        x as f32 * 0.4,
+        0.3 + y as f32 * LEVEL_HEIGHT,
+        z as f32 * 0.4,
+    )
+}
+
+fn apply_level_sky(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    q_camera: Query<Entity, With<Camera3d>>,
+    current_level: Res<CurrentLevel>,
+) {
+    let cubemap = images.add(current_level.0.sky.create_cubemap());
+
+    for camera in q_camera.iter() {
+        commands.entity(camera).insert((
+            Skybox(cubemap.clone()),
+            EnvironmentMapLight {
+                diffuse_map: cubemap.clone(),
+                specular_map: cubemap.clone(),
+            },
+        ));
+    }
+}
+
+struct LaneModels<'a> {
+    basic_floor: &'a GltfNode,
+    hole_floor: &'a GltfNode,
+    wall: &'a GltfNode,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum LanePart {
+    BasicFloor,
+    HoleFloor,
+    /// A floor tile that moves the respawn point of balls passing over it.
+    Checkpoint,
+    Wall(Direction),
+    /// A closed tube running along the given direction.
+    Tunnel(Direction),
+    /// An open half-pipe running along the given direction.
+    HalfPipe(Direction),
+    /// A floor tile with a fan at its back edge, blowing towards the given direction.
+    Fan(Direction),
+}
+
+impl LanePart {
+    pub fn is_floor(self) -> bool {
+        matches!(
+            self,
+            LanePart::BasicFloor | LanePart::HoleFloor | LanePart::Checkpoint | LanePart::Fan(_)
+        )
+    }
+
+    pub fn tube(self) -> Option<(TubeShape, Direction)> {
+        match self {
+            LanePart::Tunnel(dir) => Some((TubeShape::Tunnel, dir)),
+            LanePart::HalfPipe(dir) => Some((TubeShape::HalfPipe, dir)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Left,
+    Down,
+    Right,
+}
+
+impl Direction {
+    /// Unit vector pointing towards the neighbouring tile in this direction.
+    pub fn vector(self) -> Vec3 {
+        match self {
+            Direction::Up => Vec3::Z,
+            Direction::Left => Vec3::NEG_X,
+            Direction::Down => Vec3::NEG_Z,
+            Direction::Right => Vec3::X,
+        }
+    }
+}
+
+fn load_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    current_level: Res<CurrentLevel>,
+) {
+    commands.spawn((
+        LevelEntity,
+        Collider::cuboid(100.0, 0.1, 100.0),
+        Friction::new(1.0),
+        TransformBundle::from(Transform::from_xyz(0.0, 0.0, 0.0)),
+    ));
+
+    for prop in current_level.0.props.iter() {
+        let mut entity = commands.spawn((
+            LevelEntity,
+            SceneBundle {
+                scene: asset_server.load(prop.scene.as_str()),
+                transform: prop.transform(),
+                ..default()
+            },
+        ));
+        if let Some(color) = prop.color {
+            entity.insert(NeedsColorChange(color));
+        }
+    }
+
+    let level = &current_level.0;
+    spawn_lane(
+        &mut commands,
+        &asset_server,
+        &nodes,
+        &gltf_meshes,
+        &mut meshes,
+        &mut materials,
+        level,
+    );
+
+    for &breakable in level.breakables.iter() {
+        breakable::spawn_breakable(&mut commands, &mut meshes, &mut materials, breakable);
+    }
+
+    for &pickup in level.pickups.iter() {
+        pickup::spawn_pickup(&mut commands, &mut meshes, &mut materials, pickup);
+    }
+
+    if !level.gravity_zones.is_empty() {
+        let zone_mesh = meshes.add(shape::Cube::new(2.0).into());
+        let zone_material = materials.add(StandardMaterial {
+            base_color: Color::rgba(0.6, 0.3, 1.0, 0.12),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        for &zone in level.gravity_zones.iter() {
+            let (center, half_size) = zone.bounds();
+            commands.spawn((
+                LevelEntity,
+                PbrBundle {
+                    mesh: zone_mesh.clone(),
+                    material: zone_material.clone(),
+                    transform: Transform::from_translation(center).with_scale(half_size),
+                    ..default()
+                },
+                // Scaled to the zone by the transform, like the mesh
+                Collider::cuboid(1.0, 1.0, 1.0),
+                Sensor,
+                ActiveEvents::COLLISION_EVENTS,
+                zone,
+            ));
+        }
+    }
+}
+
+/// Spawns the tiles of the lane and the holes in them, all marked with `LaneTile`.
+fn spawn_lane(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    nodes: &Assets<GltfNode>,
+    gltf_meshes: &Assets<GltfMesh>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    level: &Level,
+) {
+    let lane_models = LaneModels {
+        basic_floor: nodes
+            .get(&asset_server.load("models/lane.gltf#Node0"))
+            .unwrap(),
+        hole_floor: nodes
+            .get(&asset_server.load("models/lane.gltf#Node2"))
+            .unwrap(),
+        wall: nodes
+            .get(&asset_server.load("models/lane.gltf#Node1"))
+            .unwrap(),
+    };
+
+    let surfaces: HashMap<(i32, i32, i32), SurfaceType> = level.surfaces.iter().copied().collect();
+    let fan_cycles: HashMap<(i32, i32, i32), FanCycle> = level.fan_cycles.iter().copied().collect();
+    let mut surface_materials: HashMap<SurfaceType, Handle<StandardMaterial>> = HashMap::new();
+    let checkpoint_mesh = meshes.add(shape::Plane::from_size(0.3).into());
+    let checkpoint_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 0.85, 0.2, 0.6),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    let mut tube_assets: HashMap<TubeShape, (Handle<Mesh>, Handle<StandardMaterial>, Collider)> =
+        HashMap::new();
+
+    for (((sx, sy, sz), part), owner) in level.tiles() {
+        // Tiles owned by a player only collide with that player's ball
+        let groups = owner.map(|player_id| {
+            let group = collision::player_group(player_id);
+            CollisionGroups::new(group, group)
+        });
+
+        if let Some((shape, dir)) = part.tube() {
+            let (mesh, material, collider) = tube_assets
+                .entry(shape)
+                .or_insert_with(|| {
+                    (
+                        meshes.add(shape.mesh()),
+                        materials.add(shape.material()),
+                        shape.collider(),
+                    )
+                })
+                .clone();
+
+            commands
+                .spawn((
+                    LaneTile,
+                    LevelEntity,
+                    RigidBody::Fixed,
+                    MaterialMeshBundle {
+                        mesh,
+                        material,
+                        transform: tube::tube_transform(tile_center(sx, sy, sz), dir),
+                        ..default()
+                    },
+                    Friction::new(1.0),
+                ))
+                .with_children(|parent| {
+                    let mut collider = parent.spawn((collider, TransformBundle::IDENTITY));
+                    if let Some(groups) = groups {
+                        collider.insert(groups);
+                    }
+                    if shape == TubeShape::Tunnel {
+                        parent.spawn(tube::interior_light());
+                    }
+                });
+            continue;
+        }
+
+        let node = match part {
+            LanePart::BasicFloor | LanePart::Checkpoint | LanePart::Fan(_) => {
+                lane_models.basic_floor
+            }
+            LanePart::HoleFloor => lane_models.hole_floor,
+            LanePart::Wall(_) => lane_models.wall,
+            LanePart::Tunnel(_) | LanePart::HalfPipe(_) => unreachable!(),
+        };
+        let mesh = node.mesh.as_ref().unwrap();
+        let gltf_mesh = gltf_meshes.get(mesh).unwrap();
+
+        let is_floor = part.is_floor();
+        let surface = surfaces.get(&(sx, sy, sz)).copied().unwrap_or_default();
+        let mut material = gltf_mesh.primitives[0].material.as_ref().unwrap().clone();
+        if is_floor {
+            if let Some(handle) = surface_materials.get(&surface) {
+                material = handle.clone();
+            } else if let Some(mut custom) = materials.get(&material).cloned() {
+                if surface.customize_material(&mut custom) {
+                    material = materials.add(custom);
+                }
+                surface_materials.insert(surface, material.clone());
+            }
+        }
+
+        let collider = collision::create_collider_from_gltf_node(node, &gltf_meshes, &meshes, true);
+        let extra_transform = match part {
+            LanePart::BasicFloor => Transform::IDENTITY * Transform::from_xyz(0.0, 0.025, 0.0),
+            LanePart::HoleFloor => Transform::IDENTITY * Transform::from_xyz(0.0, 0.025, 0.0),
+            LanePart::Checkpoint => Transform::IDENTITY * Transform::from_xyz(0.0, 0.025, 0.0),
+            LanePart::Fan(_) => Transform::IDENTITY * Transform::from_xyz(0.0, 0.025, 0.0),
+            LanePart::Tunnel(_) | LanePart::HalfPipe(_) => unreachable!(),
+            LanePart::Wall(dir) => {
+                let rot_transform = match dir {
+                    Direction::Up => Transform::IDENTITY,
+                    Direction::Left => Transform::from_rotation(Quat::from_rotation_y(-PI / 2.0)),
+                    Direction::Down => Transform::from_rotation(Quat::from_rotation_y(PI)),
+                    Direction::Right => Transform::from_rotation(Quat::from_rotation_y(PI / 2.0)),
+                };
+                rot_transform * Transform::from_xyz(0.2, 0.05, 0.0)
+            }
+        };
+
+        commands
+            .spawn((
+                LaneTile,
+                LevelEntity,
+                RigidBody::Fixed,
+                MaterialMeshBundle {
+                    mesh: gltf_mesh.primitives[0].mesh.clone(),
+                    material,
+                    transform: Transform::from_translation(tile_center(sx, sy, sz))
+                        .with_rotation(Quat::from_rotation_y(-PI / 2.0))
+                        * extra_transform
+                        * node.transform.with_translation(Vec3::ZERO),
+                    ..default()
+                },
+                Friction::new(1.0),
+            ))
+            .with_children(|parent| {
+                let mut collider = parent.spawn((collider, TransformBundle::IDENTITY));
+                if is_floor {
+                    if let Some(groups) = groups {
+                        collider.insert(groups);
+                    }
+                    collider.insert((surface, FloorTile(part), ActiveEvents::COLLISION_EVENTS));
+                } else {
+                    // Only in the wall group, so that ghost balls pass through owned walls too
+                    let filters = groups.map_or(Group::ALL, |groups| groups.filters);
+                    collider.insert((
+                        Wall,
+                        CollisionGroups::new(collision::WALL_GROUP, filters),
+                        ActiveEvents::COLLISION_EVENTS,
+                    ));
+                }
+            });
+
+        if part == LanePart::BasicFloor && surface == SurfaceType::Green {
+            commands.spawn((
+                LaneTile,
+                LevelEntity,
+                GrassTile {
+                    top: tile_center(sx, sy, sz) + Vec3::Y * FLOOR_TOP,
+                },
+            ));
+        }
+
+        if let LanePart::Fan(dir) = part {
+            let fan = fan::spawn_fan(
+                commands,
+                meshes,
+                materials,
+                tile_center(sx, sy, sz),
+                dir.vector(),
+                fan_cycles.get(&(sx, sy, sz)).copied(),
+                groups,
+            );
+            commands.entity(fan).insert((LaneTile, LevelEntity));
+        }
+
+        if part == LanePart::Checkpoint {
+            let position = tile_center(sx, sy, sz) + Vec3::Y * 0.05;
+            commands.spawn((
+                LaneTile,
+                LevelEntity,
+                PbrBundle {
+                    mesh: checkpoint_mesh.clone(),
+                    material: checkpoint_material.clone(),
+                    transform: Transform::from_translation(position + Vec3::Y * 0.002),
+                    ..default()
+                },
+            ));
+            let mut checkpoint = commands.spawn((
+                LaneTile,
+                LevelEntity,
+                Collider::cuboid(0.2, 0.1, 0.2),
+                TransformBundle::from_transform(Transform::from_translation(
+                    position + Vec3::Y * 0.1,
+                )),
+                Sensor,
+                ActiveEvents::COLLISION_EVENTS,
+                Checkpoint { position },
+            ));
+            if let Some(groups) = groups {
+                checkpoint.insert(groups);
+            }
+        }
+    }
+
+    for (hole, owner) in level.holes() {
+        let groups = owner.map(|player_id| {
+            let group = collision::player_group(player_id);
+            CollisionGroups::new(group, group)
+        });
+        let (x, y, z) = hole.position;
+        let entities = spawn_hole(commands, hole, tile_center(x, y, z), groups);
+        for entity in entities {
+            commands.entity(entity).insert((LaneTile, LevelEntity));
+        }
+    }
+}
+
+/// Despawns the lane and spawns it again from the current level, after its file has changed.
+#[allow(clippy::too_many_arguments)]
+fn rebuild_lane(
+    mut commands: Commands,
+    mut changes: EventReader<LaneChanged>,
+    q_tile: Query<Entity, With<LaneTile>>,
+    asset_server: Res<AssetServer>,
+    nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    current_level: Res<CurrentLevel>,
+) {
+    if changes.is_empty() {
+        return;
+    }
+    changes.clear();
+
+    for entity in q_tile.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_lane(
+        &mut commands,
+        &asset_server,
+        &nodes,
+        &gltf_meshes,
+        &mut meshes,
+        &mut materials,
+        &current_level.0,
+    );
+}
+
+/// Spawns the sensor that detects balls in a hole and the rim around it, returning both. The cup
+/// itself is part of the floor, whose top is `FLOOR_TOP` above `center`.
+pub fn spawn_hole(
+    commands: &mut Commands,
+    hole: Hole,
+    center: Vec3,
+    groups: Option<CollisionGroups>,
+) -> [Entity; 2] {
+    let mut entity = commands.spawn((
+        Collider::cylinder(0.02, hole.radius),
+        TransformBundle::from_transform(Transform::from_translation(
+            center + Vec3::Y * (0.03 - 0.025),
+        )),
+        Sensor,
+        ActiveEvents::COLLISION_EVENTS,
+        hole,
+    ));
+    if let Some(groups) = groups {
+        entity.insert(groups);
+    }
+    let sensor = entity.id();
+
+    let mut rim = commands.spawn((
+        rim_collider(hole.radius),
+        TransformBundle::from_transform(Transform::from_translation(center + Vec3::Y * FLOOR_TOP)),
+        Restitution {
+            coefficient: RIM_RESTITUTION,
+            combine_rule: CoefficientCombineRule::Max,
+        },
+    ));
+    if let Some(groups) = groups {
+        rim.insert(groups);
+    }
+
+    [sensor, rim.id()]
+}
+
+/// A ring of capsules lying along the edge of a cup with the given radius.
+fn rim_collider(radius: f32) -> Collider {
+    let point = |i: usize| {
+        let angle = i as f32 / RIM_SEGMENTS as f32 * 2.0 * PI;
+        Vec3::new(angle.cos(), 0.0, angle.sin()) * radius
+    };
+    let segments = (0..RIM_SEGMENTS)
+        .map(|i| {
+            (
+                Vec3::ZERO,
+                Quat::IDENTITY,
+                Collider::capsule(point(i), point(i + 1), RIM_RADIUS),
+            )
+        })
+        .collect();
+    Collider::compound(segments)
+}
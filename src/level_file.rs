@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use bevy::utils::HashSet;
+
+use crate::level::{LanePart, Level};
+
+/// Reads a level from a RON file.
+///
+/// Walls around the lane are generated when loading, so level files only need to list the
+/// floor tiles and any obstacles. Lanes lead onto terrain without a wall in between.
+pub fn load(path: &Path) -> Result<Level, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let level: Level =
+        ron::from_str(&text).map_err(|e| format!("Could not parse {}: {}", path.display(), e))?;
+    Ok(prepare(level))
+}
+
+/// Turns a level as authored into one that can be played, with walls around the lane.
+pub fn prepare(mut level: Level) -> Level {
+    level.lanes = level.lanes.with_walls_around();
+
+    let terrain_tiles: HashSet<_> = level.terrain.iter().flat_map(|t| t.tiles()).collect();
+    level.lanes.0.retain(|&((x, y, z), part)| match part {
+        LanePart::Wall(dir) => {
+            let step = dir.vector();
+            !terrain_tiles.contains(&(x + step.x as i32, y, z + step.z as i32))
+        }
+        _ => true,
+    });
+    level
+}
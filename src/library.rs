@@ -0,0 +1,408 @@
+use bevy::{
+    prelude::*,
+    render::texture::{CompressedImageFormats, ImageType},
+};
+
+#[cfg(target_arch = "wasm32")]
+use crate::course_code;
+use crate::{
+    editor::Editor,
+    level::{CurrentLevel, LaneConfig, LanePart, Level, Levels},
+    level_file,
+    locale::Locale,
+    menu::{self, BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    scoring::GameState,
+    AppState, GameMode,
+};
+
+/// Directory that courses made in the editor are kept in, one RON file each.
+#[cfg(not(target_arch = "wasm32"))]
+const COURSES_DIR: &str = "courses";
+/// In the browser, courses are kept in local storage under keys starting with this.
+#[cfg(target_arch = "wasm32")]
+const STORAGE_PREFIX: &str = "golf.course.";
+/// Thumbnails are kept in local storage as base64 under keys starting with this.
+#[cfg(target_arch = "wasm32")]
+const THUMBNAIL_PREFIX: &str = "golf.thumbnail.";
+
+const THUMBNAIL_WIDTH: f32 = 96.0;
+const THUMBNAIL_HEIGHT: f32 = 64.0;
+
+/// A screen listing the courses made in the editor, to play or edit them.
+pub struct LibraryPlugin;
+
+impl Plugin for LibraryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Library), setup_library_screen)
+            .add_systems(OnExit(AppState::Library), cleanup_library_screen)
+            .add_systems(Update, library_buttons.run_if(in_state(AppState::Library)));
+    }
+}
+
+/// A course in the library, as it was authored, i.e. without the walls added when playing.
+pub struct LibraryCourse {
+    /// Name of its file, or its key in local storage, without the extension or prefix.
+    pub id: String,
+    pub level: Level,
+}
+
+impl LibraryCourse {
+    /// The name to show, falling back to the file name for courses without one.
+    pub fn title(&self) -> &str {
+        if self.level.name.is_empty() {
+            &self.id
+        } else {
+            &self.level.name
+        }
+    }
+}
+
+/// The author, difficulty and description of a course, on as many lines as there is to say.
+pub fn details(level: &Level, locale: &Locale) -> String {
+    let mut credits = Vec::new();
+    if !level.author.is_empty() {
+        credits.push(locale.format("library.author", &[("author", &level.author)]));
+    }
+    if let Some(difficulty) = level.difficulty {
+        credits.push(locale.get(difficulty.key()).to_string());
+    }
+    let mut lines = Vec::new();
+    if !credits.is_empty() {
+        lines.push(credits.join(", "));
+    }
+    if !level.description.is_empty() {
+        lines.push(level.description.clone());
+    }
+    lines.join("\n")
+}
+
+/// Every course in the library, sorted by id.
+pub fn list_courses() -> Vec<LibraryCourse> {
+    let mut courses: Vec<LibraryCourse> = stored_courses()
+        .into_iter()
+        .filter_map(|(id, text)| match ron::from_str(&text) {
+            Ok(level) => Some(LibraryCourse { id, level }),
+            Err(e) => {
+                warn!("Could not parse the course {}: {}", id, e);
+                None
+            }
+        })
+        .collect();
+    courses.sort_by(|a, b| a.id.cmp(&b.id));
+    courses
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn stored_courses() -> Vec<(String, String)> {
+    let Ok(entries) = std::fs::read_dir(COURSES_DIR) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "ron"))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_string_lossy().into_owned();
+            let text = std::fs::read_to_string(&path).ok()?;
+            Some((id, text))
+        })
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn stored_courses() -> Vec<(String, String)> {
+    (0..)
+        .map_while(storage::key)
+        .filter_map(|key| {
+            let id = key.strip_prefix(STORAGE_PREFIX)?.to_string();
+            let text = storage::get_item(&key)?;
+            Some((id, text))
+        })
+        .collect()
+}
+
+/// Writes a course to the library, replacing the one with the same id.
+pub fn save_course(id: &str, level: &Level) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(level, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::create_dir_all(COURSES_DIR).map_err(|e| e.to_string())?;
+        let path = std::path::Path::new(COURSES_DIR).join(format!("{}.ron", id));
+        std::fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        storage::set_item(&format!("{}{}", STORAGE_PREFIX, id), &text);
+        Ok(())
+    }
+}
+
+/// The picture of the course, if one was saved along with it.
+pub fn thumbnail(id: &str) -> Option<Image> {
+    let bytes = thumbnail_bytes(id)?;
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+    )
+    .ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn thumbnail_bytes(id: &str) -> Option<Vec<u8>> {
+    std::fs::read(std::path::Path::new(COURSES_DIR).join(format!("{}.png", id))).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn thumbnail_bytes(id: &str) -> Option<Vec<u8>> {
+    course_code::from_base64(&storage::get_item(&format!("{}{}", THUMBNAIL_PREFIX, id))?)
+}
+
+/// Stores a PNG picture of the course next to it.
+pub fn save_thumbnail(id: &str, png: &[u8]) -> Result<(), String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::create_dir_all(COURSES_DIR).map_err(|e| e.to_string())?;
+        let path = std::path::Path::new(COURSES_DIR).join(format!("{}.png", id));
+        std::fs::write(&path, png).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let text = course_code::to_base64(png);
+        storage::set_item(&format!("{}{}", THUMBNAIL_PREFIX, id), &text);
+        Ok(())
+    }
+}
+
+/// An id that no course in the library has yet.
+fn unused_id(courses: &[LibraryCourse]) -> String {
+    (1..)
+        .map(|n| format!("course-{}", n))
+        .find(|id| courses.iter().all(|c| &c.id != id))
+        .expect("there are endless ids to try")
+}
+
+/// A short straight lane with a hole, to start a new course from.
+fn starter_course() -> Level {
+    let mut lanes = LaneConfig::default();
+    for z in -1..=6 {
+        for x in -1..=1 {
+            let part = if (x, z) == (0, 5) {
+                LanePart::HoleFloor
+            } else {
+                LanePart::BasicFloor
+            };
+            lanes.0.push(((x, 0, z), part));
+        }
+    }
+    Level {
+        name: String::new(),
+        lanes,
+        par: 3,
+        difficulty: None,
+        ..Levels::default().level1
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = localStorage, js_name = key)]
+        pub fn key(index: u32) -> Option<String>;
+
+        #[wasm_bindgen(js_namespace = localStorage, js_name = getItem)]
+        pub fn get_item(key: &str) -> Option<String>;
+
+        #[wasm_bindgen(js_namespace = localStorage, js_name = setItem)]
+        pub fn set_item(key: &str, value: &str);
+    }
+}
+
+#[derive(Component)]
+struct LibraryRoot;
+
+#[derive(Component, Clone)]
+enum LibraryButton {
+    Play(String),
+    Edit(String),
+    New,
+    Back,
+}
+
+fn setup_library_screen(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    locale: Res<Locale>,
+) {
+    let courses = list_courses();
+
+    commands
+        .spawn((
+            LibraryRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("library.title"),
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            if courses.is_empty() {
+                parent.spawn(TextBundle::from_section(
+                    locale.get("library.empty"),
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ));
+            }
+
+            for course in courses.iter() {
+                let thumbnail = thumbnail(&course.id).map(|image| images.add(image));
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(12.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        let size = Style {
+                            width: Val::Px(THUMBNAIL_WIDTH),
+                            height: Val::Px(THUMBNAIL_HEIGHT),
+                            ..default()
+                        };
+                        match thumbnail {
+                            Some(texture) => {
+                                row.spawn(ImageBundle {
+                                    style: size,
+                                    image: UiImage::new(texture),
+                                    ..default()
+                                });
+                            }
+                            None => {
+                                row.spawn(NodeBundle {
+                                    style: size,
+                                    background_color: Color::rgb(0.1, 0.2, 0.1).into(),
+                                    ..default()
+                                });
+                            }
+                        }
+                        row.spawn(TextBundle::from_sections([
+                            TextSection::new(
+                                locale.format(
+                                    "library.course",
+                                    &[("name", &course.title()), ("par", &course.level.par)],
+                                ),
+                                TextStyle {
+                                    font_size: 26.0,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ),
+                            TextSection::new(
+                                details(&course.level, &locale)
+                                    .lines()
+                                    .map(|line| format!("\n{}", line))
+                                    .collect::<String>(),
+                                TextStyle {
+                                    font_size: 18.0,
+                                    color: Color::GRAY,
+                                    ..default()
+                                },
+                            ),
+                        ]));
+                        menu::spawn_button(
+                            row,
+                            LibraryButton::Play(course.id.clone()),
+                            locale.get("library.play"),
+                        );
+                        menu::spawn_button(
+                            row,
+                            LibraryButton::Edit(course.id.clone()),
+                            locale.get("library.edit"),
+                        );
+                    });
+            }
+
+            menu::spawn_button(parent, LibraryButton::New, locale.get("library.new"));
+            menu::spawn_button(parent, LibraryButton::Back, locale.get("common.back"));
+        });
+}
+
+fn library_buttons(
+    mut commands: Commands,
+    mut q_button: Query<(&Interaction, &LibraryButton, &mut BackgroundColor), Changed<Interaction>>,
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                // Read again, since the courses may have changed on disk
+                let courses = list_courses();
+                let find = |id: &str| courses.iter().find(|c| c.id == id).map(|c| c.level.clone());
+                match button {
+                    LibraryButton::Play(id) => {
+                        let Some(level) = find(id) else {
+                            continue;
+                        };
+                        commands.insert_resource(CurrentLevel(level_file::prepare(level)));
+                        commands.insert_resource(GameState::new(game_state.num_players));
+                        commands.insert_resource(GameMode::Standard);
+                        next_state.set(AppState::InGame);
+                    }
+                    LibraryButton::Edit(id) => {
+                        let Some(level) = find(id) else {
+                            continue;
+                        };
+                        commands.insert_resource(Editor::new(id.clone(), level));
+                        next_state.set(AppState::Editor);
+                    }
+                    LibraryButton::New => {
+                        commands
+                            .insert_resource(Editor::new(unused_id(&courses), starter_course()));
+                        next_state.set(AppState::Editor);
+                    }
+                    LibraryButton::Back => next_state.set(AppState::Menu),
+                }
+            }
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+fn cleanup_library_screen(mut commands: Commands, q_root: Query<Entity, With<LibraryRoot>>) {
+    for entity in q_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
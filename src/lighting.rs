@@ -0,0 +1,130 @@
+use bevy::{pbr::FogFalloff, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Lighting preset that a level can pick to set the mood of the course.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimeOfDay {
+    Morning,
+    #[default]
+    Noon,
+    Sunset,
+    Night,
+}
+
+pub struct LightingPreset {
+    pub sun_color: Color,
+    pub sun_illuminance: f32,
+    /// Height of the sun above the horizon in radians.
+    pub sun_elevation: f32,
+    pub ambient_color: Color,
+    pub ambient_brightness: f32,
+    pub fog_color: Color,
+    pub fog_distance: f32,
+    /// Whether lamps should be placed along the lane.
+    pub lane_lights: bool,
+}
+
+impl TimeOfDay {
+    pub fn preset(self) -> LightingPreset {
+        match self {
+            TimeOfDay::Morning => LightingPreset {
+                sun_color: Color::rgb(1.0, 0.9, 0.75),
+                sun_illuminance: 12000.0,
+                sun_elevation: 0.35,
+                ambient_color: Color::rgb(0.85, 0.9, 1.0),
+                ambient_brightness: 0.2,
+                fog_color: Color::rgba(0.85, 0.88, 0.95, 1.0),
+                fog_distance: 12.0,
+                lane_lights: false,
+            },
+            TimeOfDay::Noon => LightingPreset {
+                sun_color: Color::WHITE,
+                sun_illuminance: 20000.0,
+                sun_elevation: 0.98,
+                ambient_color: Color::WHITE,
+                ambient_brightness: 0.25,
+                fog_color: Color::rgba(0.75, 0.88, 1.0, 1.0),
+                fog_distance: 30.0,
+                lane_lights: false,
+            },
+            TimeOfDay::Sunset => LightingPreset {
+                sun_color: Color::rgb(1.0, 0.6, 0.35),
+                sun_illuminance: 8000.0,
+                sun_elevation: 0.15,
+                ambient_color: Color::rgb(1.0, 0.75, 0.6),
+                ambient_brightness: 0.15,
+                fog_color: Color::rgba(1.0, 0.6, 0.4, 1.0),
+                fog_distance: 15.0,
+                lane_lights: true,
+            },
+            TimeOfDay::Night => LightingPreset {
+                sun_color: Color::rgb(0.6, 0.7, 1.0),
+                sun_illuminance: 500.0,
+                sun_elevation: 0.8,
+                ambient_color: Color::rgb(0.4, 0.45, 0.7),
+                ambient_brightness: 0.05,
+                fog_color: Color::rgba(0.02, 0.03, 0.08, 1.0),
+                fog_distance: 10.0,
+                lane_lights: true,
+            },
+        }
+    }
+}
+
+impl LightingPreset {
+    pub fn sun_transform(&self) -> Transform {
+        let dir = Vec3::new(0.0, self.sun_elevation.sin(), -self.sun_elevation.cos());
+        Transform::from_translation(dir).looking_at(Vec3::ZERO, Vec3::Y)
+    }
+
+    pub fn fog_settings(&self) -> FogSettings {
+        FogSettings {
+            color: self.fog_color,
+            directional_light_color: self.sun_color,
+            directional_light_exponent: 30.0,
+            falloff: FogFalloff::Linear {
+                start: self.fog_distance * 0.5,
+                end: self.fog_distance,
+            },
+        }
+    }
+}
+
+/// Fog and focus a level can set to get a feel of its own, on top of its time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Atmosphere {
+    /// Replaces the fog color of the time of day.
+    #[serde(default)]
+    pub fog_color: Option<Color>,
+    /// Fog thickening exponentially with this density, instead of the time of day's linear fog.
+    #[serde(default)]
+    pub fog_density: Option<f32>,
+    /// Blurs what is nearer or further away than the ball, with a strength from 0 to 1.
+    #[serde(default)]
+    pub depth_of_field: Option<f32>,
+}
+
+impl Atmosphere {
+    pub fn fog_settings(&self, preset: &LightingPreset) -> FogSettings {
+        let mut fog = preset.fog_settings();
+        if let Some(color) = self.fog_color {
+            fog.color = color;
+        }
+        if let Some(density) = self.fog_density {
+            fog.falloff = FogFalloff::Exponential { density };
+        }
+        fog
+    }
+}
+
+pub fn lane_light() -> PointLightBundle {
+    PointLightBundle {
+        point_light: PointLight {
+            color: Color::rgb(1.0, 0.85, 0.6),
+            intensity: 40.0,
+            range: 2.0,
+            ..default()
+        },
+        ..default()
+    }
+}
@@ -0,0 +1,455 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddrV4, UdpSocket};
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::BallStyle,
+    chat,
+    cli::CliArgs,
+    customize::{self, BallStyles},
+    level::CurrentLevel,
+    locale::Locale,
+    menu::{self, ButtonLabel, BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    online::{
+        self, HostPort, NetMessage, NetRole, OnlineStyles, PeerJoined, PeerLeft, PlayerOwners,
+        ReceivedMessage, SendMessage, Welcomed,
+    },
+    procgen,
+    scoring::GameState,
+    AppState, GameMode, GameSeed,
+};
+
+/// The token the host is known by in the lobby. Clients get random ones from the host.
+const HOST_TOKEN: u64 = 0;
+
+/// Letters used in join codes, without the ones that are easily mixed up.
+const CODE_ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+/// Enough letters for an IPv4 address and a port, at five bits each.
+const CODE_LENGTH: usize = 10;
+
+/// A screen before an online game where everybody who joined is listed with their ball and
+/// marks themselves as ready. The host starts the game once all are.
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lobby>()
+            .add_systems(OnEnter(AppState::Lobby), setup_lobby_screen)
+            .add_systems(OnExit(AppState::Lobby), cleanup_lobby_screen)
+            .add_systems(
+                Update,
+                (
+                    lobby_menu_button.run_if(in_state(AppState::Menu)),
+                    handle_lobby_messages,
+                    (lobby_buttons, share_own_entry, update_lobby_text)
+                        .chain()
+                        .after(handle_lobby_messages)
+                        .run_if(in_state(AppState::Lobby)),
+                ),
+            );
+    }
+}
+
+/// Somebody waiting in the lobby.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LobbyMember {
+    pub token: u64,
+    pub name: String,
+    pub ready: bool,
+    pub style: BallStyle,
+}
+
+#[derive(Resource, Default)]
+struct Lobby {
+    /// Everybody in the lobby as the host sees it, in the order they become players.
+    members: Vec<LobbyMember>,
+    /// What the host knows us by, once it has welcomed us.
+    token: Option<u64>,
+    ready: bool,
+    /// Set when our own entry has to be sent out again.
+    changed: bool,
+}
+
+impl Lobby {
+    /// Adds or replaces the entry of a member.
+    fn update(&mut self, member: LobbyMember) {
+        match self.members.iter_mut().find(|m| m.token == member.token) {
+            Some(existing) => *existing = member,
+            None => self.members.push(member),
+        }
+    }
+
+    fn all_ready(&self) -> bool {
+        !self.members.is_empty() && self.members.iter().all(|m| m.ready)
+    }
+}
+
+/// Opens the lobby from the menu, hosting a game on the default port if not online yet.
+#[derive(Component)]
+pub struct LobbyButton;
+
+#[derive(Component, Clone, Copy)]
+enum LobbyScreenButton {
+    Ready,
+    Start,
+    Back,
+}
+
+#[derive(Component)]
+struct LobbyRoot;
+
+#[derive(Component)]
+struct MemberList;
+
+/// A short code standing for the address of a game, e.g. `KQ4RZ-A8M2C`.
+pub fn join_code(address: SocketAddrV4) -> String {
+    let mut value = (u64::from(u32::from(*address.ip())) << 16) | u64::from(address.port());
+    let mut letters = Vec::with_capacity(CODE_LENGTH);
+    for _ in 0..CODE_LENGTH {
+        letters.push(CODE_ALPHABET[(value & 31) as usize] as char);
+        value >>= 5;
+    }
+    letters.reverse();
+
+    let (first, second) = letters.split_at(CODE_LENGTH / 2);
+    format!(
+        "{}-{}",
+        first.iter().collect::<String>(),
+        second.iter().collect::<String>()
+    )
+}
+
+/// The address a join code stands for, or `None` if `code` is not one.
+pub fn parse_join_code(code: &str) -> Option<SocketAddrV4> {
+    let letters: Vec<u8> = code
+        .bytes()
+        .filter(|&b| b != b'-')
+        .map(|b| b.to_ascii_uppercase())
+        .collect();
+    if letters.len() != CODE_LENGTH {
+        return None;
+    }
+
+    let mut value = 0u64;
+    for letter in letters {
+        let digit = CODE_ALPHABET.iter().position(|&c| c == letter)?;
+        value = (value << 5) | digit as u64;
+    }
+    if value >> 48 != 0 {
+        return None;
+    }
+    Some(SocketAddrV4::new(
+        Ipv4Addr::from((value >> 16) as u32),
+        value as u16,
+    ))
+}
+
+/// The address others on the network would reach this computer at.
+fn local_ip() -> Ipv4Addr {
+    // Connecting a UDP socket sends nothing, but picks the network interface to use
+    let address = UdpSocket::bind("0.0.0.0:0").and_then(|socket| {
+        socket.connect("8.8.8.8:80")?;
+        socket.local_addr()
+    });
+    match address.map(|a| a.ip()) {
+        Ok(IpAddr::V4(ip)) => ip,
+        _ => Ipv4Addr::LOCALHOST,
+    }
+}
+
+fn lobby_menu_button(
+    mut commands: Commands,
+    mut q_button: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<LobbyButton>),
+    >,
+    mut role: ResMut<NetRole>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                if *role != NetRole::Offline || online::host_game(&mut commands, &mut role) {
+                    next_state.set(AppState::Lobby);
+                }
+            }
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+fn setup_lobby_screen(
+    mut commands: Commands,
+    mut lobby: ResMut<Lobby>,
+    role: Res<NetRole>,
+    host_port: Option<Res<HostPort>>,
+    locale: Res<Locale>,
+) {
+    lobby.changed = true;
+
+    let info = match host_port {
+        Some(port) => locale.format(
+            "lobby.code",
+            &[("code", &join_code(SocketAddrV4::new(local_ip(), port.0)))],
+        ),
+        None => locale.get("lobby.waiting").to_string(),
+    };
+
+    commands
+        .spawn((
+            LobbyRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("lobby.title"),
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                info,
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn((MemberList, TextBundle::default()));
+            if role.can_shoot() {
+                menu::spawn_button(
+                    parent,
+                    LobbyScreenButton::Ready,
+                    &ready_label(lobby.ready, &locale),
+                );
+            }
+            if *role == NetRole::Host {
+                menu::spawn_button(parent, LobbyScreenButton::Start, locale.get("lobby.start"));
+            }
+            menu::spawn_button(parent, LobbyScreenButton::Back, locale.get("common.back"));
+        });
+}
+
+fn ready_label(ready: bool, locale: &Locale) -> String {
+    locale.format("lobby.ready", &[("value", &locale.on_off(ready))])
+}
+
+fn handle_lobby_messages(
+    mut received: EventReader<ReceivedMessage>,
+    mut welcomed: EventReader<Welcomed>,
+    mut joined: EventReader<PeerJoined>,
+    mut left: EventReader<PeerLeft>,
+    mut messages: EventWriter<SendMessage>,
+    mut lobby: ResMut<Lobby>,
+    mut player_owners: ResMut<PlayerOwners>,
+    role: Res<NetRole>,
+) {
+    for Welcomed(token) in welcomed.iter() {
+        lobby.token = Some(*token);
+        lobby.changed = true;
+        player_owners.local = Some(*token);
+    }
+
+    // Somebody new needs to see who is there
+    let mut changed = joined.iter().count() > 0;
+    for PeerLeft(token) in left.iter() {
+        lobby.members.retain(|m| m.token != *token);
+        changed = true;
+    }
+    for ReceivedMessage(message) in received.iter() {
+        match message {
+            NetMessage::LobbyMember(member) if *role == NetRole::Host => {
+                lobby.update(member.clone());
+                changed = true;
+            }
+            NetMessage::Lobby(members) if role.is_client() => {
+                lobby.members = members.clone();
+            }
+            _ => {}
+        }
+    }
+
+    if changed && *role == NetRole::Host {
+        messages.send(SendMessage(NetMessage::Lobby(lobby.members.clone())));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn lobby_buttons(
+    mut q_button: Query<
+        (
+            &Interaction,
+            &LobbyScreenButton,
+            &mut BackgroundColor,
+            &Children,
+        ),
+        Changed<Interaction>,
+    >,
+    mut q_label: Query<&mut Text, With<ButtonLabel>>,
+    keys: Res<Input<KeyCode>>,
+    mut lobby: ResMut<Lobby>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut game_state: ResMut<GameState>,
+    mut game_mode: ResMut<GameMode>,
+    mut online_styles: ResMut<OnlineStyles>,
+    mut player_owners: ResMut<PlayerOwners>,
+    seed: Res<GameSeed>,
+    mut next_state: ResMut<NextState<AppState>>,
+    locale: Res<Locale>,
+) {
+    for (interaction, button, mut background, children) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match button {
+                LobbyScreenButton::Ready => {
+                    lobby.ready = !lobby.ready;
+                    lobby.changed = true;
+                    let mut labels = q_label.iter_many_mut(children.iter());
+                    while let Some(mut text) = labels.fetch_next() {
+                        text.sections[0].value = ready_label(lobby.ready, &locale);
+                    }
+                }
+                LobbyScreenButton::Start if lobby.all_ready() => {
+                    // A random course from the seed, with one player per member. The course
+                    // and rules are sent to everybody else when the game starts.
+                    let mut rng = seed.rng(GameSeed::COURSE_STREAM);
+                    current_level.0 =
+                        procgen::generate_level(rng.gen(), &procgen::CourseSettings::default());
+                    *game_state = GameState::new(lobby.members.len() as u32);
+                    *game_mode = GameMode::Standard;
+                    online_styles.0 = lobby.members.iter().map(|m| m.style.clone()).collect();
+                    player_owners.owners = lobby.members.iter().map(|m| m.token).collect();
+                    player_owners.local = Some(HOST_TOKEN);
+                    next_state.set(AppState::InGame);
+                }
+                LobbyScreenButton::Start => info!("Waiting for everybody to be ready"),
+                LobbyScreenButton::Back => next_state.set(AppState::Menu),
+            },
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+/// Sends our own entry to the host, or updates it directly on the host.
+fn share_own_entry(
+    mut lobby: ResMut<Lobby>,
+    mut messages: EventWriter<SendMessage>,
+    styles: Res<BallStyles>,
+    role: Res<NetRole>,
+    args: Res<CliArgs>,
+) {
+    if !lobby.changed || !role.can_shoot() {
+        return;
+    }
+    let token = match *role {
+        NetRole::Host => HOST_TOKEN,
+        _ => match lobby.token {
+            Some(token) => token,
+            // Not welcomed yet
+            None => return,
+        },
+    };
+    lobby.changed = false;
+
+    let member = LobbyMember {
+        token,
+        name: chat::sender_name(&args, *role),
+        ready: lobby.ready,
+        style: styles.get(0).unwrap_or_else(|| customize::initial_style(0)),
+    };
+    if *role == NetRole::Host {
+        lobby.update(member);
+        messages.send(SendMessage(NetMessage::Lobby(lobby.members.clone())));
+    } else {
+        messages.send(SendMessage(NetMessage::LobbyMember(member)));
+    }
+}
+
+fn update_lobby_text(
+    mut q_text: Query<&mut Text, With<MemberList>>,
+    lobby: Res<Lobby>,
+    seed: Res<GameSeed>,
+    role: Res<NetRole>,
+    locale: Res<Locale>,
+) {
+    if !lobby.is_changed() {
+        return;
+    }
+
+    let mut sections = Vec::new();
+    for (player_id, member) in lobby.members.iter().enumerate() {
+        sections.push(TextSection::new(
+            format!("{}. {}", player_id + 1, member.name),
+            TextStyle {
+                font_size: 28.0,
+                color: member.style.color(),
+                ..default()
+            },
+        ));
+        let status = if member.ready {
+            locale.get("lobby.is_ready")
+        } else {
+            locale.get("lobby.not_ready")
+        };
+        sections.push(TextSection::new(
+            format!(" - {} - {}\n", member.style.shape.name(&locale), status),
+            TextStyle {
+                font_size: 28.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+    }
+    // The course is made from the host's seed
+    if *role == NetRole::Host {
+        sections.push(TextSection::new(
+            locale.format("lobby.seed", &[("seed", &seed.0)]),
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+    }
+
+    for mut text in q_text.iter_mut() {
+        text.sections = sections.clone();
+    }
+}
+
+fn cleanup_lobby_screen(
+    mut commands: Commands,
+    q_root: Query<Entity, With<LobbyRoot>>,
+    mut lobby: ResMut<Lobby>,
+) {
+    for entity in q_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // Nobody is ready in the next lobby. The token stays, since the host only welcomes us once
+    // per connection.
+    *lobby = Lobby {
+        token: lobby.token,
+        ..default()
+    };
+}
@@ -0,0 +1,135 @@
+use std::{collections::HashMap, fmt::Display};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::save;
+
+const SAVE_FILE: &str = "language.ron";
+
+/// Translations of all text shown in the UI, picked by a language in the settings.
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Locale::new(save::load(SAVE_FILE)))
+            .add_systems(
+                Update,
+                (
+                    save_language.run_if(resource_changed::<Locale>()),
+                    update_localized_texts,
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Swedish,
+}
+
+impl Language {
+    pub fn next(self) -> Self {
+        match self {
+            Language::English => Language::Swedish,
+            Language::Swedish => Language::English,
+        }
+    }
+
+    /// The name of the language in itself, so that it can be found without understanding the
+    /// current one.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Swedish => "Svenska",
+        }
+    }
+
+    /// The strings of the language as a RON map from keys to text. Built into the game, so that
+    /// they are there from the first frame on every platform.
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../assets/lang/en.ron"),
+            Language::Swedish => include_str!("../assets/lang/sv.ron"),
+        }
+    }
+
+    fn strings(self) -> HashMap<String, String> {
+        ron::from_str(self.source()).unwrap_or_else(|e| {
+            warn!("Could not parse the strings for {:?}: {}", self, e);
+            HashMap::new()
+        })
+    }
+}
+
+/// The strings of the current language.
+///
+/// Strings are looked up by keys like `menu.play`. Strings missing from the current language
+/// are taken from English, and strings missing from both show their key.
+#[derive(Resource)]
+pub struct Locale {
+    language: Language,
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            strings: language.strings(),
+            fallback: Language::English.strings(),
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+        self.strings = language.strings();
+    }
+
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, |text| text.as_str())
+    }
+
+    /// The string for `key` with each `{name}` in it replaced by the matching argument.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+
+    /// "on" or "off" in the current language.
+    pub fn on_off(&self, value: bool) -> &str {
+        self.get(if value { "common.on" } else { "common.off" })
+    }
+}
+
+/// A text that always shows the string with the given key, also after the language changes.
+#[derive(Component)]
+pub struct LocalizedText(pub &'static str);
+
+fn update_localized_texts(mut q_text: Query<(Ref<LocalizedText>, &mut Text)>, locale: Res<Locale>) {
+    for (localized, mut text) in q_text.iter_mut() {
+        if localized.is_added() || locale.is_changed() {
+            text.sections[0].value = locale.get(localized.0).to_string();
+        }
+    }
+}
+
+fn save_language(locale: Res<Locale>) {
+    // Only remember settings the player picked
+    if !locale.is_added() {
+        save::store(SAVE_FILE, &locale.language);
+    }
+}
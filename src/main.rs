@@ -1,17 +1,141 @@
-use std::f32::consts::PI;
-
-use bevy::{
-    gltf::{GltfMesh, GltfNode},
-    input::mouse::{MouseMotion, MouseWheel},
-    pbr::DirectionalLightShadowMap,
+use bevy::{prelude::*, scene::SceneInstance};
+use bevy_rapier3d::{
     prelude::*,
-    scene::SceneInstance,
-    utils::HashSet,
+    render::{DebugRenderContext, RapierDebugRenderPlugin},
 };
-use bevy_rapier3d::{prelude::*, render::RapierDebugRenderPlugin};
-use rand::Rng;
-
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use accessibility::AccessibilityPlugin;
+use achievements::AchievementsPlugin;
+use aim_decal::AimDecalPlugin;
+use ball::BallPlugin;
+use blob_shadow::BlobShadowPlugin;
+use breakable::BreakablePlugin;
+use camera::CameraPlugin;
+use chat::ChatPlugin;
+use cli::CliArgs;
+use course_code::CourseCodePlugin;
+use course_import::CourseImportPlugin;
+use customize::CustomizePlugin;
+use daily::DailyPlugin;
+use depth_of_field::DepthOfFieldPlugin;
+use director::DirectorPlugin;
+use display::{DisplayPlugin, DisplaySettings};
+use editor::EditorPlugin;
+use emotes::EmotesPlugin;
+use fan::FanPlugin;
+use flythrough::FlythroughPlugin;
+use graphics::GraphicsPlugin;
+use grass::GrassPlugin;
+use highlight::HighlightPlugin;
+use input::InputPlugin;
+use interpolation::InterpolationPlugin;
+use knock_out::KnockOutPlugin;
+use level::{CurrentLevel, LevelPlugin, Levels};
+use library::LibraryPlugin;
+use lobby::LobbyPlugin;
+use locale::LocalePlugin;
+use music::MusicPlugin;
+use online::{NetRole, OnlinePlugin};
+use perf_overlay::PerfOverlayPlugin;
+use pickup::PickupPlugin;
+use powerup::PowerUpPlugin;
+use profile::ProfilePlugin;
+use putting::PuttingPlugin;
+use rebind::RebindPlugin;
+use replay::ReplayPlugin;
+use results::ResultsPlugin;
+use scorecard::ScorecardPlugin;
+use scoring::{GameState, ScoringPlugin};
+use settings::SettingsPlugin;
+use shot_clock::ShotClockPlugin;
+use shot_history::ShotHistoryPlugin;
+use sound::SoundPlugin;
+use spectator::SpectatorPlugin;
+use stats::StatsPlugin;
+use stuck::StuckPlugin;
+use tee::TeePlugin;
+use terrain::TerrainPlugin;
+use thumbnail::ThumbnailPlugin;
+use tournament::TournamentPlugin;
+use tuning::TuningPlugin;
+use ui::UiPlugin;
+
+mod accessibility;
+mod achievements;
+mod aim_decal;
+mod ball;
+mod benchmark;
+mod blob_shadow;
+mod breakable;
+mod camera;
+mod celebration;
+mod chat;
+mod cli;
+mod clipboard;
 mod collision;
+mod controls;
+mod course_code;
+mod course_import;
+mod customize;
+mod daily;
+mod depth_of_field;
+mod director;
+mod display;
+mod editor;
+mod emotes;
+mod fan;
+mod flythrough;
+mod ghost;
+mod graphics;
+mod grass;
+mod headless;
+mod highlight;
+mod hot_reload;
+mod input;
+mod interpolation;
+mod knock_out;
+mod level;
+mod level_file;
+mod library;
+mod lighting;
+mod lobby;
+mod locale;
+mod menu;
+mod music;
+mod online;
+mod perf_overlay;
+mod pickup;
+mod powerup;
+mod practice;
+mod procgen;
+mod profile;
+mod putting;
+mod rebind;
+mod replay;
+mod results;
+mod save;
+mod scorecard;
+mod scoring;
+mod settings;
+mod shot_clock;
+mod shot_history;
+mod sky;
+mod sound;
+mod spectator;
+mod stats;
+mod stuck;
+mod surface;
+mod tee;
+mod terrain;
+mod thumbnail;
+mod time_control;
+mod tournament;
+mod tube;
+mod tuning;
+mod ui;
+mod validation;
 
 // These constants are defined in `Transform` units.
 // Using the default 2D camera they correspond 1:1 with screen pixels.
@@ -24,7 +148,23 @@ const NUM_PLAYERS: u32 = 4;
 enum AppState {
     #[default]
     Loading,
+    Menu,
+    /// Picking the look of each player's ball.
+    Customize,
     InGame,
+    /// The standings of a tournament between two of its courses.
+    Standings,
+    Settings,
+    /// Binding keys to actions, reached from the settings.
+    Controls,
+    /// The lifetime stats of every player.
+    Profiles,
+    /// Waiting for everybody to be ready before an online game.
+    Lobby,
+    /// The courses saved on this device.
+    Library,
+    /// Building or changing a course from the library.
+    Editor,
 }
 
 fn main() {
@@ -32,167 +172,240 @@ fn main() {
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
 
-    let mut app = App::new();
+    let args = CliArgs::from_env();
 
-    app.add_plugins(DefaultPlugins.set(WindowPlugin {
-        primary_window: Some(Window {
-            title: "Golf".to_string(),
-            canvas: Some("#app".to_string()),
-            ..default()
-        }),
-        ..default()
-    }))
-    .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
-    .add_state::<AppState>()
-    .insert_resource(ClearColor(BACKGROUND_COLOR))
-    .insert_resource(AmbientLight {
-        color: Color::WHITE,
-        brightness: 1.0 / 4.0f32,
-    })
-    .insert_resource(DirectionalLightShadowMap { size: 4096 })
-    .insert_resource(FixedTime::new_from_secs(1.0 / 60.0))
-    .insert_resource(AssetsLoading::default())
-    .insert_resource(GameState::new(NUM_PLAYERS))
-    .insert_resource(Lanes::default())
-    .add_systems(Startup, setup_graphics)
-    .add_systems(OnEnter(AppState::Loading), load_assets)
-    .add_systems(OnEnter(AppState::InGame), (load_level, spawn_balls))
-    .add_systems(
-        Update,
-        (
-            check_assets_ready,
-            camera_input,
-            move_camera_to_ball,
-            keyboard_input,
-            update_shoot_power_indicator,
-            check_ball_in_hole,
-            customize_scene_materials,
-            stop_ball_from_spinning_forever,
-            check_ball_on_ground,
-        ),
+    // The benchmark always uses the same seed, so that its reports can be compared
+    let seed = GameSeed(
+        args.seed
+            .or(args.benchmark.map(|_| benchmark::SEED))
+            .unwrap_or_else(rand::random),
     );
 
-    if cfg!(debug_assertions) {
-        app.add_plugins(RapierDebugRenderPlugin::default());
+    let mut current_level = Levels::default().level1;
+    if args.benchmark.is_some() {
+        current_level = benchmark::stress_course(seed.0);
+    } else if let Some(path) = &args.level {
+        current_level = level_file::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
     }
 
-    app.run();
-}
-
-#[derive(Component)]
-struct Ball {
-    player_id: u32,
-    hits: u32,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-enum BallSpin {
-    Left,
-    Right,
-}
-
-#[derive(Component, Debug, Clone, PartialEq, Default)]
-struct ShootSettings {
-    power: f32,
-    angle: f32,
-    spin: Option<BallSpin>,
-}
-
-#[derive(Component)]
-struct ShootPowerIndicator;
+    let display_settings = DisplaySettings::load();
 
-#[derive(Component)]
-struct Hole;
-
-#[derive(Default)]
-struct LaneConfig(Vec<((i32, i32), LanePart)>);
+    let mut app = App::new();
 
-impl LaneConfig {
-    fn with_3x3(mut self, cx: i32, cy: i32, around: LanePart, center: LanePart) -> Self {
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                let x = cx + dx;
-                let y = cy + dy;
-                self.0
-                    .push(((x, y), if dx == 0 && dy == 0 { center } else { around }));
-            }
-        }
-        self
+    if args.headless {
+        headless::add_default_plugins(&mut app);
+    } else {
+        let mut window = Window {
+            title: "Golf".to_string(),
+            canvas: Some("#app".to_string()),
+            ..default()
+        };
+        display_settings.apply(&mut window);
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(window),
+            ..default()
+        }));
     }
 
-    fn with_walls_around(mut self) -> Self {
-        let grass: HashSet<_> = self
-            .0
-            .iter()
-            .filter(|(_, part)| *part == LanePart::BasicFloor || *part == LanePart::HoleFloor)
-            .map(|(pos, _)| *pos)
-            .collect();
+    app.add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_plugins((
+            LevelPlugin,
+            BallPlugin,
+            ScoringPlugin,
+            CameraPlugin,
+            InputPlugin,
+            UiPlugin,
+            StatsPlugin,
+            AchievementsPlugin,
+            CustomizePlugin,
+            GraphicsPlugin,
+            PerfOverlayPlugin,
+            ReplayPlugin,
+            ShotClockPlugin,
+            StuckPlugin,
+            TuningPlugin {
+                hot_reload: args.dev,
+            },
+        ))
+        .add_plugins((
+            FanPlugin,
+            BreakablePlugin,
+            PickupPlugin,
+            PowerUpPlugin,
+            CourseImportPlugin,
+            TerrainPlugin,
+            GrassPlugin,
+            HighlightPlugin,
+            AimDecalPlugin,
+            PuttingPlugin,
+            DepthOfFieldPlugin,
+            BlobShadowPlugin,
+        ))
+        .add_plugins((
+            ResultsPlugin,
+            ScorecardPlugin,
+            ShotHistoryPlugin,
+            ProfilePlugin,
+            TournamentPlugin,
+            DailyPlugin,
+            SettingsPlugin,
+            DisplayPlugin,
+            RebindPlugin,
+            AccessibilityPlugin,
+            LocalePlugin,
+            MusicPlugin,
+            SoundPlugin,
+        ))
+        .add_plugins((
+            OnlinePlugin::from_args(&args),
+            LobbyPlugin,
+            InterpolationPlugin,
+            CourseCodePlugin,
+            LibraryPlugin,
+            EditorPlugin,
+            ThumbnailPlugin,
+            SpectatorPlugin,
+            ChatPlugin,
+            EmotesPlugin,
+            DirectorPlugin,
+            FlythroughPlugin,
+            TeePlugin,
+            KnockOutPlugin,
+        ))
+        .add_state::<AppState>()
+        .insert_resource(ClearColor(BACKGROUND_COLOR))
+        .insert_resource(FixedTime::new_from_secs(1.0 / 60.0))
+        .insert_resource(AssetsLoading::default())
+        .insert_resource(GameState::new(args.players.unwrap_or(
+            if args.benchmark.is_some() {
+                benchmark::NUM_PLAYERS
+            } else {
+                NUM_PLAYERS
+            },
+        )))
+        .insert_resource(CurrentLevel(current_level))
+        .insert_resource(display_settings)
+        .insert_resource(seed)
+        .init_resource::<GameMode>()
+        .init_resource::<time_control::TimeControl>()
+        .init_resource::<practice::PracticeStats>()
+        .init_resource::<ghost::CurrentTrajectories>()
+        .init_resource::<ghost::PersonalBests>()
+        .add_systems(OnEnter(AppState::Loading), load_assets)
+        .add_systems(OnEnter(AppState::Menu), menu::setup_menu)
+        .add_systems(OnExit(AppState::Menu), menu::cleanup_menu)
+        .add_systems(
+            OnEnter(AppState::InGame),
+            (
+                ghost::spawn_ghosts,
+                practice::setup_driving_range.run_if(resource_equals(GameMode::Practice)),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                check_assets_ready.run_if(in_state(AppState::Loading)),
+                (
+                    menu::menu_buttons,
+                    menu::edit_seed.run_if(course_code::not_importing),
+                )
+                    .run_if(in_state(AppState::Menu)),
+                customize_scene_materials,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                ghost::record_trajectories,
+                ghost::save_personal_bests,
+                ghost::move_ghosts,
+            )
+                .chain()
+                .after(scoring::check_ball_in_hole),
+        )
+        .add_systems(
+            Update,
+            (
+                practice::replace_stopped_balls.after(scoring::advance_turn_when_ball_stopped),
+                practice::update_practice_text,
+            )
+                .run_if(resource_equals(GameMode::Practice)),
+        )
+        .add_systems(
+            Update,
+            (
+                celebration::start_hole_in_one_celebration.after(scoring::check_ball_in_hole),
+                (
+                    time_control::fast_forward_input,
+                    time_control::detect_dramatic_shots,
+                    time_control::sync_time_scale,
+                )
+                    .chain()
+                    .after(celebration::start_hole_in_one_celebration),
+                celebration::animate_confetti,
+                (
+                    celebration::orbit_camera_around_hole.after(camera::move_camera_to_ball),
+                    celebration::end_celebration,
+                )
+                    .run_if(resource_exists::<celebration::Celebration>()),
+            ),
+        );
 
-        let mut walls: Vec<(i32, i32, Direction)> = Vec::new();
-        for (x, y) in grass.iter() {
-            if !grass.contains(&(*x + 1, *y)) {
-                walls.push((*x, *y, Direction::Right));
-            }
-            if !grass.contains(&(*x - 1, *y)) {
-                walls.push((*x, *y, Direction::Left));
-            }
-            if !grass.contains(&(*x, *y + 1)) {
-                walls.push((*x, *y, Direction::Up));
-            }
-            if !grass.contains(&(*x, *y - 1)) {
-                walls.push((*x, *y, Direction::Down));
-            }
-        }
+    if !args.headless {
+        // Always available so collider problems can be looked at in release builds too, but off
+        // unless asked for
+        app.add_plugins(RapierDebugRenderPlugin {
+            enabled: args.debug_render,
+            ..default()
+        })
+        .add_systems(Update, toggle_debug_render);
+    }
 
-        for &(x, y, dir) in walls.iter() {
-            self.0.push(((x, y), LanePart::Wall(dir)));
-        }
+    if args.benchmark.is_some() {
+        // Every ball is shot at once, like in party play
+        app.add_plugins(benchmark::BenchmarkPlugin::from_args(&args))
+            .insert_resource(GameMode::Party);
+    } else if args.headless {
+        app.add_plugins(headless::HeadlessPlugin::from_args(&args));
+    }
 
-        self
+    // The shot tuning is reloaded by `TuningPlugin` in dev mode even without a level
+    if let (true, Some(path)) = (args.dev, &args.level) {
+        app.add_plugins(hot_reload::HotReloadPlugin { path: path.clone() });
     }
-}
 
-#[derive(Resource)]
-struct Lanes {
-    level1: LaneConfig,
+    app.insert_resource(args);
+
+    app.run();
 }
 
-impl Default for Lanes {
-    fn default() -> Self {
-        Self {
-            level1: LaneConfig::default()
-                .with_3x3(0, 0, LanePart::BasicFloor, LanePart::BasicFloor)
-                .with_3x3(0, 3, LanePart::BasicFloor, LanePart::BasicFloor)
-                .with_3x3(0, 6, LanePart::BasicFloor, LanePart::BasicFloor)
-                .with_3x3(0, 9, LanePart::BasicFloor, LanePart::BasicFloor)
-                .with_3x3(3, 9, LanePart::BasicFloor, LanePart::BasicFloor)
-                .with_3x3(6, 9, LanePart::BasicFloor, LanePart::BasicFloor)
-                .with_3x3(6, 12, LanePart::BasicFloor, LanePart::HoleFloor)
-                .with_walls_around(),
-        }
-    }
+/// Which rules the current game is played with.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum GameMode {
+    #[default]
+    Standard,
+    /// An open driving range without holes, turns or a ball limit.
+    Practice,
+    /// Everybody aims and shoots at the same time with their own controls.
+    Party,
 }
 
+/// Seed that all gameplay randomness is derived from, so that a game can be reproduced.
 #[derive(Resource)]
-struct GameState {
-    num_players: u32,
-    current_player: u32,
-    players: Vec<PlayerData>,
-}
+struct GameSeed(u64);
 
-#[derive(Default)]
-struct PlayerData {
-    last_pos: Vec3,
-    scores: Vec<u32>,
-}
+impl GameSeed {
+    const COURSE_STREAM: u64 = 1;
+    const BALLS_STREAM: u64 = 2;
+    const BENCHMARK_STREAM: u64 = 3;
 
-impl GameState {
-    fn new(num_players: u32) -> Self {
-        GameState {
-            num_players,
-            current_player: 0,
-            players: (0..num_players).map(|_| PlayerData::default()).collect(),
-        }
+    /// Creates an independent random generator for one part of the game, so that e.g. the
+    /// balls don't change when the course generator consumes a different amount of numbers.
+    fn rng(&self, stream: u64) -> StdRng {
+        StdRng::seed_from_u64(self.0 ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15))
     }
 }
 
@@ -217,198 +430,46 @@ fn load_assets(server: Res<AssetServer>, mut loading: ResMut<AssetsLoading>) {
 fn check_assets_ready(
     server: Res<AssetServer>,
     loading: Res<AssetsLoading>,
+    args: Res<CliArgs>,
+    role: Res<NetRole>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     use bevy::asset::LoadState;
 
     if server.get_group_load_state(loading.0.iter().map(|a| a.id())) == LoadState::Loaded {
-        next_state.set(AppState::InGame);
-    }
-}
-
-fn setup_graphics(mut commands: Commands) {
-    commands.spawn((
-        CameraController {
-            rotation: Quat::from_rotation_y(PI),
-            zoom: 0.0,
-        },
-        Camera3dBundle {
-            camera: Camera {
-                hdr: true,
-                ..default()
-            },
-            transform: Transform::from_xyz(0.0, 1.5, 1.0)
-                .looking_at(Vec3::new(0.0, 0.0, 0.0), Vec3::Y),
-            ..default()
-        },
-    ));
-
-    commands.spawn(DirectionalLightBundle {
-        directional_light: DirectionalLight {
-            shadows_enabled: true,
-            illuminance: 20000.0,
-            ..default()
-        },
-        transform: Transform::from_xyz(0.0, 1.5, -1.0)
-            .looking_at(Vec3::new(0.0, 0.0, 0.0), Vec3::Y),
-        ..default()
-    });
-}
-
-struct LaneModels<'a> {
-    basic_floor: &'a GltfNode,
-    hole_floor: &'a GltfNode,
-    wall: &'a GltfNode,
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum LanePart {
-    BasicFloor,
-    HoleFloor,
-    Wall(Direction),
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Direction {
-    Up,
-    Left,
-    Down,
-    Right,
-}
-
-fn load_level(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    nodes: Res<Assets<GltfNode>>,
-    gltf_meshes: Res<Assets<GltfMesh>>,
-    meshes: Res<Assets<Mesh>>,
-    lanes: Res<Lanes>,
-) {
-    commands.spawn((
-        Collider::cuboid(100.0, 0.1, 100.0),
-        Friction::new(1.0),
-        TransformBundle::from(Transform::from_xyz(0.0, 0.0, 0.0)),
-    ));
-
-    let lane_models = LaneModels {
-        basic_floor: nodes
-            .get(&asset_server.load("models/lane.gltf#Node0"))
-            .unwrap(),
-        hole_floor: nodes
-            .get(&asset_server.load("models/lane.gltf#Node2"))
-            .unwrap(),
-        wall: nodes
-            .get(&asset_server.load("models/lane.gltf#Node1"))
-            .unwrap(),
-    };
-
-    for ((sx, sz), part) in lanes.level1.0.clone() {
-        let node = match part {
-            LanePart::BasicFloor => lane_models.basic_floor,
-            LanePart::HoleFloor => lane_models.hole_floor,
-            LanePart::Wall(_) => lane_models.wall,
-        };
-        let mesh = node.mesh.as_ref().unwrap();
-        let gltf_mesh = gltf_meshes.get(mesh).unwrap();
-
-        let collider = collision::create_collider_from_gltf_node(node, &gltf_meshes, &meshes, true);
-        let extra_transform = match part {
-            LanePart::BasicFloor => Transform::IDENTITY * Transform::from_xyz(0.0, 0.025, 0.0),
-            LanePart::HoleFloor => Transform::IDENTITY * Transform::from_xyz(0.0, 0.025, 0.0),
-            LanePart::Wall(dir) => {
-                let rot_transform = match dir {
-                    Direction::Up => Transform::IDENTITY,
-                    Direction::Left => Transform::from_rotation(Quat::from_rotation_y(-PI / 2.0)),
-                    Direction::Down => Transform::from_rotation(Quat::from_rotation_y(PI)),
-                    Direction::Right => Transform::from_rotation(Quat::from_rotation_y(PI / 2.0)),
-                };
-                rot_transform * Transform::from_xyz(0.2, 0.05, 0.0)
-            }
-        };
-
-        commands
-            .spawn((
-                RigidBody::Fixed,
-                MaterialMeshBundle {
-                    mesh: gltf_mesh.primitives[0].mesh.clone(),
-                    material: gltf_mesh.primitives[0].material.as_ref().unwrap().clone(),
-                    transform: Transform::from_xyz(sx as f32 * 0.4, 0.3, sz as f32 * 0.4)
-                        .with_rotation(Quat::from_rotation_y(-PI / 2.0))
-                        * extra_transform
-                        * node.transform.with_translation(Vec3::ZERO),
-                    ..default()
-                },
-                Friction::new(1.0),
-            ))
-            .with_children(|parent| {
-                parent.spawn((collider, TransformBundle::IDENTITY));
-            });
-
-        if part == LanePart::HoleFloor {
-            commands.spawn((
-                Collider::cylinder(0.02, 0.05),
-                TransformBundle::from_transform(Transform::from_xyz(
-                    sx as f32 * 0.4,
-                    0.3 - 0.025 + 0.03,
-                    sz as f32 * 0.4,
-                )),
-                Sensor,
-                Hole,
-            ));
+        // A level given on the command line is played right away
+        if args.level.is_some() || args.headless || args.benchmark.is_some() {
+            next_state.set(AppState::InGame);
+        } else if *role != NetRole::Offline {
+            next_state.set(AppState::Lobby);
+        } else {
+            next_state.set(AppState::Menu);
         }
     }
 }
 
-fn spawn_balls(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    game_state: Res<GameState>,
-) {
-    let mut rng = rand::thread_rng();
-    for player_id in 0..game_state.num_players {
-        let shape = match rng.gen_range(0..=2) {
-            0 => BallShape::Sphere,
-            1 => BallShape::Cube,
-            _ => BallShape::Cone,
-        };
-
-        spawn_ball(
-            &mut commands,
-            &asset_server,
-            player_id,
-            rng.gen_range(-0.4..0.4),
-            rng.gen_range(-0.4..0.0),
-            Color::hsl(rng.gen_range(0.0..360.0), 1.0, 0.5),
-            shape,
-        );
+/// Shows or hides the collider outlines when F4 is pressed.
+fn toggle_debug_render(keys: Res<Input<KeyCode>>, mut context: ResMut<DebugRenderContext>) {
+    if keys.just_pressed(KeyCode::F4) {
+        context.enabled = !context.enabled;
     }
-
-    commands.spawn((
-        ShootPowerIndicator,
-        PbrBundle {
-            mesh: meshes.add(shape::Cube::new(1.0).into()),
-            transform: Transform::from_xyz(0.0, 0.0, 0.0)
-                .with_rotation(Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, 0.0))
-                .with_scale(Vec3::new(0.0, 0.0, 0.0)),
-            material: materials.add(StandardMaterial {
-                base_color: Color::CYAN,
-                ..Default::default()
-            }),
-            ..Default::default()
-        },
-    ));
 }
 
 fn customize_scene_materials(
     mut commands: Commands,
-    unloaded_instances: Query<(Entity, &SceneInstance, &NeedsColorChange)>,
+    unloaded_instances: Query<(
+        Entity,
+        &SceneInstance,
+        &NeedsColorChange,
+        Option<&ball::BallFinish>,
+        Option<&ball::BallSkin>,
+        Option<&ball::BallMarkings>,
+    )>,
     mut handles: Query<(Entity, &mut Handle<StandardMaterial>)>,
     mut pbr_materials: ResMut<Assets<StandardMaterial>>,
     scene_manager: Res<SceneSpawner>,
 ) {
-    for (entity, instance, requesed_change) in unloaded_instances.iter() {
+    for (entity, instance, requesed_change, finish, skin, markings) in unloaded_instances.iter() {
         if scene_manager.instance_is_ready(**instance) {
             commands.entity(entity).remove::<NeedsColorChange>();
         }
@@ -420,319 +481,18 @@ fn customize_scene_materials(
             };
             let mut new_material = material.clone();
             new_material.base_color = requesed_change.0;
-
-            *material_handle = pbr_materials.add(new_material);
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum BallShape {
-    Sphere,
-    Cube,
-    Cone,
-}
-
-fn spawn_ball(
-    commands: &mut Commands,
-    asset_server: &AssetServer,
-    player_id: u32,
-    offset_sideways: f32,
-    offset_along: f32,
-    color: Color,
-    shape: BallShape,
-) {
-    let model_file = match shape {
-        BallShape::Sphere => "sphere",
-        BallShape::Cube => "cube",
-        BallShape::Cone => "cone",
-    };
-    let scene_handle = asset_server.load(format!("models/{}.gltf#Scene0", model_file));
-
-    let rr = 0.01; // rounding radius
-
-    let collider = match shape {
-        BallShape::Sphere => Collider::ball(0.025),
-        BallShape::Cube => Collider::round_cuboid(0.025 - rr, 0.025 - rr, 0.025 - rr, rr),
-        BallShape::Cone => Collider::round_cone(0.025 - rr, 0.025 - rr, rr),
-    };
-
-    let model_oversize = 1.0;
-
-    let r = 0.025;
-    let density = 4.0;
-    let mass = r * r * r * 8.0 * density;
-
-    let principal_inertia = Vec3::new(1.0, 1.0, 1.0) * 3.0 / 10.0 * r * r * mass;
-
-    commands
-        .spawn((
-            RigidBody::Dynamic,
-            collider,
-            ExternalImpulse::default(),
-            ExternalForce::default(),
-            Restitution {
-                coefficient: 0.5,
-                combine_rule: CoefficientCombineRule::Max,
-            },
-            Friction {
-                coefficient: 1.0,
-                combine_rule: CoefficientCombineRule::Max,
-            },
-            ColliderMassProperties::MassProperties(MassProperties {
-                local_center_of_mass: Vec3::ZERO,
-                mass,
-                principal_inertia_local_frame: Quat::IDENTITY,
-                principal_inertia,
-            }),
-            ReadMassProperties::default(),
-            Damping {
-                linear_damping: 0.6,
-                angular_damping: 0.9,
-            },
-            Ccd::enabled(),
-        ))
-        .insert(Velocity {
-            linvel: Vec3::new(0.0, 0.0, 0.0),
-            angvel: Vec3::new(0.0, 0.0, 0.0),
-        })
-        .insert(SceneBundle {
-            scene: scene_handle,
-            transform: Transform::from_xyz(offset_along, 1.0, offset_sideways)
-                .with_scale(Vec3::ONE / model_oversize),
-            ..default()
-        })
-        .insert(NeedsColorChange(color))
-        .insert(Ball { player_id, hits: 0 })
-        .insert(ShootSettings::default());
-}
-
-fn stop_ball_from_spinning_forever(
-    mut q_ball: Query<(&mut ExternalImpulse, &Velocity, &ReadMassProperties), With<Ball>>,
-) {
-    for (mut f, vel, mass) in q_ball.iter_mut() {
-        if vel.linvel.length() < 0.05 {
-            f.impulse -= vel.linvel * mass.0.mass * 0.9;
-            f.torque_impulse = -vel.angvel * mass.0.principal_inertia * 0.9;
-        }
-    }
-}
-
-fn check_ball_in_hole(
-    mut commands: Commands,
-    rapier_context: Res<RapierContext>,
-    q_hole: Query<Entity, With<Hole>>,
-    q_ball: Query<(Entity, &Velocity, &Ball), Without<Hole>>,
-    mut game_state: ResMut<GameState>,
-) {
-    for hole_entity in q_hole.iter() {
-        for (ball_entity, ball_velocity, ball) in q_ball.iter() {
-            if ball_velocity.linvel.length() < 0.01
-                && rapier_context.intersection_pair(hole_entity, ball_entity) == Some(true)
-            {
-                game_state.players[ball.player_id as usize]
-                    .scores
-                    .push(ball.hits);
-                println!("Player {} finished in {} moves", ball.player_id, ball.hits);
-
-                commands.entity(ball_entity).despawn_recursive();
-
-                game_state.current_player =
-                    (game_state.current_player + 1) % game_state.num_players;
-
-                if game_state.players.iter().all(|p| p.scores.len() == 1) {
-                    println!("Level 1 completed!");
-                }
+            if let Some(finish) = finish {
+                finish.apply(&mut new_material);
             }
-        }
-    }
-}
-
-fn check_ball_on_ground(
-    mut q_ball: Query<(&mut Transform, &Velocity, &Ball)>,
-    game_state: Res<GameState>,
-) {
-    for (mut ball_transform, ball_velocity, ball) in q_ball.iter_mut() {
-        if ball_velocity.linvel.length() < 0.01 && ball_transform.translation.y < 0.34 {
-            ball_transform.translation =
-                game_state.players[ball.player_id as usize].last_pos + Vec3::Y;
-        }
-    }
-}
-
-fn update_shoot_power_indicator(
-    mut q_indicator: Query<&mut Transform, (With<ShootPowerIndicator>, Without<Ball>)>,
-    q_ball: Query<(&Transform, &ShootSettings, &Ball)>,
-    game_state: Res<GameState>,
-) {
-    if let Some((ball_transform, shoot_settings, _)) = q_ball
-        .iter()
-        .find(|(_, _, ball)| ball.player_id == game_state.current_player)
-    {
-        let length = shoot_settings.power * 0.1;
-        let pos = ball_transform.translation;
-        let angle = shoot_settings.angle;
-        let scale = Vec3::new(length, if length == 0.0 { 0.0 } else { 0.005 }, 0.02);
-
-        let t1 = Transform::from_xyz(length * 0.5, 0.0, 0.0).with_scale(scale);
-        let t2 = Transform::from_translation(pos).with_rotation(Quat::from_rotation_y(angle));
-
-        if let Ok(mut indicator_transform) = q_indicator.get_single_mut() {
-            *indicator_transform = t2 * t1;
-        }
-    }
-}
-
-#[derive(Component)]
-struct CameraController {
-    rotation: Quat,
-    zoom: f32,
-}
-
-fn camera_input(
-    mut mouse_motion: EventReader<MouseMotion>,
-    mut mouse_wheel: EventReader<MouseWheel>,
-    buttons: Res<Input<MouseButton>>,
-    mut query: Query<&mut CameraController>,
-    time: Res<Time>,
-) {
-    for mut controller in query.iter_mut() {
-        for wheel in mouse_wheel.iter() {
-            controller.zoom += wheel.y * 0.001;
-        }
-        if buttons.pressed(MouseButton::Left) {
-            for mouse in mouse_motion.iter() {
-                let delta = mouse.delta * time.delta_seconds() * 0.3;
-                controller.rotation *= Quat::from_euler(EulerRot::XYZ, -delta.y, -delta.x, 0.0);
-            }
-        }
-    }
-}
-
-fn move_camera_to_ball(
-    mut query: Query<(&CameraController, &mut Transform), Without<Ball>>,
-    q_ball: Query<(&Transform, &Ball)>,
-    game_state: Res<GameState>,
-) {
-    if let Ok((controller, mut transform)) = query.get_single_mut() {
-        if let Some((ball_transform, _)) = q_ball
-            .iter()
-            .find(|(_, ball)| ball.player_id == game_state.current_player)
-        {
-            let ball_pos = ball_transform.translation;
-            let mut look = controller.rotation * Vec3::Z;
-            look.y = 0.3;
-            look = look.normalize();
-            transform.translation = ball_pos + look * (-controller.zoom).exp();
-            transform.look_at(ball_pos, Vec3::Y);
-        }
-    }
-}
-
-fn keyboard_input(
-    keys: Res<Input<KeyCode>>,
-    mut q_ball: Query<(
-        &mut ExternalImpulse,
-        &ReadMassProperties,
-        &Transform,
-        &Velocity,
-        &mut ShootSettings,
-        &mut Ball,
-    )>,
-    mut game_state: ResMut<GameState>,
-) {
-    if keys.just_pressed(KeyCode::C) || keys.just_pressed(KeyCode::N) {
-        game_state.current_player = (game_state.current_player + 1) % game_state.num_players;
-    }
-
-    if let Some((
-        mut ball_impulse,
-        &ball_mass,
-        ball_transform,
-        &ball_velocity,
-        mut shoot,
-        mut ball,
-    )) = q_ball
-        .iter_mut()
-        .find(|(_, _, _, _, _, ball)| ball.player_id == game_state.current_player)
-    {
-        if ball_velocity.linvel.length() < 0.01 {
-            let max_power = 10.0;
-            let power_speed = 0.1;
-            let angle_speed = 0.5 / 180.0 * PI;
-
-            if keys.pressed(KeyCode::W) {
-                shoot.power += power_speed;
-            }
-            if keys.pressed(KeyCode::S) {
-                shoot.power -= power_speed;
-            }
-            if keys.pressed(KeyCode::A) {
-                shoot.angle += angle_speed;
-            }
-            if keys.pressed(KeyCode::D) {
-                shoot.angle -= angle_speed;
-            }
-            if keys.just_pressed(KeyCode::Q) {
-                if let Some(BallSpin::Left) = shoot.spin {
-                    shoot.spin = None;
-                } else {
-                    shoot.spin = Some(BallSpin::Left);
-                }
-            }
-            if keys.just_pressed(KeyCode::E) {
-                if let Some(BallSpin::Right) = shoot.spin {
-                    shoot.spin = None;
-                } else {
-                    shoot.spin = Some(BallSpin::Right);
-                }
+            if let Some(skin) = skin {
+                new_material.base_color_texture = Some(skin.0.clone());
             }
-            if keys.just_pressed(KeyCode::Escape) {
-                *shoot = ShootSettings {
-                    angle: shoot.angle,
-                    ..ShootSettings::default()
-                };
+            // Being able to tell the balls apart matters more than the skin
+            if let Some(markings) = markings {
+                new_material.base_color_texture = Some(markings.0.clone());
             }
 
-            shoot.power = shoot.power.max(0.0).min(max_power);
-
-            shoot.angle %= 2.0 * PI;
-            if shoot.angle < 0.0 {
-                shoot.angle += 2.0 * PI;
-            }
-        }
-
-        if keys.just_pressed(KeyCode::Space) {
-            if ball_velocity.linvel.length() < 0.01 && shoot.power > 0.0 {
-                let rot = Quat::from_euler(EulerRot::XYZ, 0.0, shoot.angle, 0.0);
-                let transform = Transform::from_rotation(rot);
-                let dir = transform * Vec3::X;
-
-                let power_multiplier = 1.0 * ball_mass.0.mass;
-                let shot = dir * shoot.power * power_multiplier;
-                ball_impulse.impulse.x += shot.x;
-                ball_impulse.impulse.y += shot.y;
-                ball_impulse.impulse.z += shot.z;
-
-                let torqe_magnitude = 1.0 * ball_mass.0.mass;
-                let torque_amount = match shoot.spin {
-                    Some(BallSpin::Left) => -torqe_magnitude,
-                    Some(BallSpin::Right) => torqe_magnitude,
-                    None => 0.0,
-                };
-                ball_impulse.torque_impulse.y += torque_amount;
-                ball_impulse.torque_impulse.x += torque_amount;
-
-                ball.hits += 1;
-                game_state.players[ball.player_id as usize].last_pos = ball_transform.translation;
-
-                *shoot = ShootSettings {
-                    angle: shoot.angle,
-                    ..ShootSettings::default()
-                };
-            } else if ball_velocity.linvel.y.abs() <= 0.05 {
-                ball_impulse.impulse.y += 7.0 * ball_mass.0.mass;
-            }
+            *material_handle = pbr_materials.add(new_material);
         }
     }
 }
@@ -0,0 +1,355 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    collision::BallCollisionRule,
+    course_code::ImportCourseButton,
+    daily::{self, DailyButton},
+    knock_out::KnockOutPenalty,
+    level::{CurrentLevel, Levels},
+    lobby::LobbyButton,
+    locale::Locale,
+    practice, procgen, replay,
+    scoring::{GameState, TeamMode, TurnOrder},
+    shot_clock::ShotClock,
+    tournament::TournamentButton,
+    AppState, GameMode, GameSeed,
+};
+
+pub const BUTTON_COLOR: Color = Color::rgb(0.15, 0.35, 0.15);
+pub const BUTTON_HOVER_COLOR: Color = Color::rgb(0.25, 0.5, 0.25);
+
+#[derive(Component)]
+pub struct MenuRoot;
+
+#[derive(Component, Clone, Copy)]
+pub enum MenuButton {
+    Play,
+    RandomCourse,
+    Party,
+    Practice,
+    WatchReplay,
+    BallCollisions,
+    KnockOut,
+    TurnOrder,
+    Teams,
+    ShotClock,
+    Settings,
+    NewSeed,
+    Customize,
+    Profiles,
+    Library,
+}
+
+#[derive(Component)]
+pub struct SeedText;
+
+#[derive(Component)]
+pub struct ButtonLabel;
+
+#[allow(clippy::too_many_arguments)]
+pub fn setup_menu(
+    mut commands: Commands,
+    seed: Res<GameSeed>,
+    collision_rule: Res<BallCollisionRule>,
+    knock_out: Res<KnockOutPenalty>,
+    turn_order: Res<TurnOrder>,
+    team_mode: Res<TeamMode>,
+    shot_clock: Res<ShotClock>,
+    locale: Res<Locale>,
+) {
+    commands
+        .spawn((
+            MenuRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("menu.title"),
+                TextStyle {
+                    font_size: 56.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            spawn_button(parent, MenuButton::Play, locale.get("menu.play"));
+            spawn_button(
+                parent,
+                MenuButton::RandomCourse,
+                locale.get("menu.random_course"),
+            );
+            spawn_button(parent, MenuButton::Party, locale.get("menu.party"));
+            spawn_button(
+                parent,
+                DailyButton,
+                &locale.format(
+                    "menu.daily",
+                    &[("date", &daily::date_label(daily::today()))],
+                ),
+            );
+            spawn_button(parent, TournamentButton::New, locale.get("menu.tournament"));
+            spawn_button(
+                parent,
+                TournamentButton::Resume,
+                locale.get("menu.resume_tournament"),
+            );
+            spawn_button(parent, LobbyButton, locale.get("menu.online"));
+            spawn_button(parent, ImportCourseButton, locale.get("menu.import_course"));
+            spawn_button(parent, MenuButton::Library, locale.get("menu.library"));
+            spawn_button(parent, MenuButton::Practice, locale.get("menu.practice"));
+            spawn_button(
+                parent,
+                MenuButton::WatchReplay,
+                locale.get("menu.watch_replay"),
+            );
+            spawn_button(
+                parent,
+                MenuButton::BallCollisions,
+                &ball_collisions_label(*collision_rule, &locale),
+            );
+            spawn_button(
+                parent,
+                MenuButton::KnockOut,
+                &knock_out_label(*knock_out, &locale),
+            );
+            spawn_button(
+                parent,
+                MenuButton::TurnOrder,
+                &turn_order_label(*turn_order, &locale),
+            );
+            spawn_button(parent, MenuButton::Teams, &teams_label(*team_mode, &locale));
+            spawn_button(
+                parent,
+                MenuButton::ShotClock,
+                &shot_clock_label(&shot_clock, &locale),
+            );
+            spawn_button(parent, MenuButton::Customize, locale.get("menu.customize"));
+            spawn_button(parent, MenuButton::Profiles, locale.get("menu.profiles"));
+            spawn_button(parent, MenuButton::Settings, locale.get("menu.settings"));
+            spawn_button(parent, MenuButton::NewSeed, locale.get("menu.new_seed"));
+            parent.spawn((
+                SeedText,
+                TextBundle::from_section(
+                    seed_label(seed.0, &locale),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+pub fn spawn_button(parent: &mut ChildBuilder, button: impl Component, label: &str) {
+    parent
+        .spawn((
+            button,
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(260.0),
+                    height: Val::Px(50.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ButtonLabel,
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 28.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn menu_buttons(
+    mut commands: Commands,
+    mut q_button: Query<
+        (&Interaction, &MenuButton, &mut BackgroundColor, &Children),
+        Changed<Interaction>,
+    >,
+    mut q_label: Query<&mut Text, (With<ButtonLabel>, Without<SeedText>)>,
+    mut q_seed_text: Query<&mut Text, With<SeedText>>,
+    mut seed: ResMut<GameSeed>,
+    mut collision_rule: ResMut<BallCollisionRule>,
+    mut knock_out: ResMut<KnockOutPenalty>,
+    mut turn_order: ResMut<TurnOrder>,
+    mut team_mode: ResMut<TeamMode>,
+    mut shot_clock: ResMut<ShotClock>,
+    mut current_level: ResMut<CurrentLevel>,
+    levels: Res<Levels>,
+    mut game_mode: ResMut<GameMode>,
+    mut game_state: ResMut<GameState>,
+    mut next_state: ResMut<NextState<AppState>>,
+    locale: Res<Locale>,
+) {
+    for (interaction, button, mut background, children) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match button {
+                MenuButton::Play => {
+                    current_level.0 = levels.level1.clone();
+                    *game_mode = GameMode::Standard;
+                    next_state.set(AppState::InGame);
+                }
+                MenuButton::RandomCourse => {
+                    let mut rng = seed.rng(GameSeed::COURSE_STREAM);
+                    current_level.0 =
+                        procgen::generate_level(rng.gen(), &procgen::CourseSettings::default());
+                    *game_mode = GameMode::Standard;
+                    next_state.set(AppState::InGame);
+                }
+                MenuButton::Party => {
+                    current_level.0 = levels.level1.clone();
+                    *game_mode = GameMode::Party;
+                    next_state.set(AppState::InGame);
+                }
+                MenuButton::Practice => {
+                    current_level.0 = practice::driving_range(&locale);
+                    *game_mode = GameMode::Practice;
+                    *game_state = GameState::new(1);
+                    next_state.set(AppState::InGame);
+                }
+                MenuButton::WatchReplay => match replay::load_last_round() {
+                    Some(replay) => {
+                        replay::start_playback(&mut commands, replay);
+                        next_state.set(AppState::InGame);
+                    }
+                    None => info!("There is no round to watch yet"),
+                },
+                MenuButton::BallCollisions => {
+                    *collision_rule = collision_rule.next();
+                    let mut labels = q_label.iter_many_mut(children.iter());
+                    while let Some(mut text) = labels.fetch_next() {
+                        text.sections[0].value = ball_collisions_label(*collision_rule, &locale);
+                    }
+                }
+                MenuButton::KnockOut => {
+                    knock_out.0 = !knock_out.0;
+                    let mut labels = q_label.iter_many_mut(children.iter());
+                    while let Some(mut text) = labels.fetch_next() {
+                        text.sections[0].value = knock_out_label(*knock_out, &locale);
+                    }
+                }
+                MenuButton::TurnOrder => {
+                    *turn_order = turn_order.next();
+                    let mut labels = q_label.iter_many_mut(children.iter());
+                    while let Some(mut text) = labels.fetch_next() {
+                        text.sections[0].value = turn_order_label(*turn_order, &locale);
+                    }
+                }
+                MenuButton::Teams => {
+                    *team_mode = team_mode.next();
+                    let mut labels = q_label.iter_many_mut(children.iter());
+                    while let Some(mut text) = labels.fetch_next() {
+                        text.sections[0].value = teams_label(*team_mode, &locale);
+                    }
+                }
+                MenuButton::ShotClock => {
+                    shot_clock.limit = shot_clock.next_limit();
+                    let mut labels = q_label.iter_many_mut(children.iter());
+                    while let Some(mut text) = labels.fetch_next() {
+                        text.sections[0].value = shot_clock_label(&shot_clock, &locale);
+                    }
+                }
+                MenuButton::Customize => next_state.set(AppState::Customize),
+                MenuButton::Profiles => next_state.set(AppState::Profiles),
+                MenuButton::Library => next_state.set(AppState::Library),
+                MenuButton::Settings => next_state.set(AppState::Settings),
+                MenuButton::NewSeed => {
+                    seed.0 = rand::random();
+                    if let Ok(mut text) = q_seed_text.get_single_mut() {
+                        text.sections[0].value = seed_label(seed.0, &locale);
+                    }
+                }
+            },
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+fn ball_collisions_label(rule: BallCollisionRule, locale: &Locale) -> String {
+    locale.format("menu.ball_collisions", &[("value", &rule.name(locale))])
+}
+
+fn knock_out_label(penalty: KnockOutPenalty, locale: &Locale) -> String {
+    locale.format("menu.knock_out", &[("value", &locale.on_off(penalty.0))])
+}
+
+fn turn_order_label(order: TurnOrder, locale: &Locale) -> String {
+    locale.format("menu.turn_order", &[("value", &order.name(locale))])
+}
+
+fn teams_label(team_mode: TeamMode, locale: &Locale) -> String {
+    locale.format("menu.teams", &[("value", &team_mode.name(locale))])
+}
+
+fn shot_clock_label(shot_clock: &ShotClock, locale: &Locale) -> String {
+    locale.format(
+        "menu.shot_clock",
+        &[("value", &shot_clock.limit_name(locale))],
+    )
+}
+
+fn seed_label(seed: u64, locale: &Locale) -> String {
+    locale.format("menu.seed", &[("seed", &seed)])
+}
+
+/// Lets the player type in a seed while in the menu.
+pub fn edit_seed(
+    mut chars: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut q_seed_text: Query<&mut Text, With<SeedText>>,
+    mut seed: ResMut<GameSeed>,
+    locale: Res<Locale>,
+) {
+    let old_seed = seed.0;
+
+    for c in chars.iter() {
+        if let Some(digit) = c.char.to_digit(10) {
+            // Random seeds use nearly all digits, so typing over one starts a new seed instead
+            seed.0 = seed
+                .0
+                .checked_mul(10)
+                .and_then(|s| s.checked_add(digit as u64))
+                .unwrap_or(digit as u64);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        seed.0 /= 10;
+    }
+
+    if seed.0 != old_seed {
+        if let Ok(mut text) = q_seed_text.get_single_mut() {
+            text.sections[0].value = seed_label(seed.0, &locale);
+        }
+    }
+}
+
+pub fn cleanup_menu(mut commands: Commands, q_root: Query<Entity, With<MenuRoot>>) {
+    for entity in q_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
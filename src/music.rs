@@ -0,0 +1,206 @@
+use bevy::{audio::Volume, prelude::*, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::{save, scoring::GameState, AppState};
+
+const SAVE_FILE: &str = "audio.ron";
+
+/// How long one track takes to fade into the next.
+const CROSSFADE_SECONDS: f32 = 3.0;
+
+/// Background music from a playlist for each part of the game, fading from one track into the
+/// next.
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<AudioSettings>(SAVE_FILE))
+            .insert_resource(MusicPlayer::default())
+            .add_systems(
+                Update,
+                (
+                    play_music,
+                    save_audio_settings.run_if(resource_changed::<AudioSettings>()),
+                ),
+            );
+    }
+}
+
+/// Volumes from 0 to 1.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioSettings {
+    pub music_volume: f32,
+    #[serde(default = "default_effects_volume")]
+    pub effects_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            music_volume: 0.7,
+            effects_volume: default_effects_volume(),
+        }
+    }
+}
+
+fn default_effects_volume() -> f32 {
+    1.0
+}
+
+/// The tracks played in each part of the game, in order.
+#[derive(Deserialize, Default)]
+struct Playlist {
+    menu: Vec<Track>,
+    in_game: Vec<Track>,
+    results: Vec<Track>,
+}
+
+#[derive(Deserialize, Clone)]
+struct Track {
+    /// Relative to the assets directory.
+    path: String,
+    /// Length of the track, so that the next one can start fading in before it ends.
+    seconds: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MusicKind {
+    Menu,
+    InGame,
+    Results,
+}
+
+#[derive(Resource)]
+struct MusicPlayer {
+    playlist: Playlist,
+    /// The track to play next of each kind.
+    next: [usize; 3],
+}
+
+impl Default for MusicPlayer {
+    fn default() -> Self {
+        let playlist =
+            ron::from_str(include_str!("../assets/music/playlist.ron")).unwrap_or_else(|e| {
+                warn!("Could not parse the playlist: {}", e);
+                Playlist::default()
+            });
+        Self {
+            playlist,
+            next: [0; 3],
+        }
+    }
+}
+
+impl MusicPlayer {
+    fn next_track(&mut self, kind: MusicKind) -> Option<Track> {
+        let tracks = match kind {
+            MusicKind::Menu => &self.playlist.menu,
+            MusicKind::InGame => &self.playlist.in_game,
+            MusicKind::Results => &self.playlist.results,
+        };
+        if tracks.is_empty() {
+            return None;
+        }
+        let next = &mut self.next[kind as usize];
+        let track = tracks[*next % tracks.len()].clone();
+        *next = (*next + 1) % tracks.len();
+        Some(track)
+    }
+}
+
+/// A playing track.
+#[derive(Component)]
+struct Music {
+    kind: MusicKind,
+    /// How far the track has faded in, from 0 to 1.
+    fade: f32,
+    fading_out: bool,
+    /// Runs out when the next track should start fading in.
+    timer: Timer,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_music(
+    mut commands: Commands,
+    mut q_music: Query<(Entity, &mut Music, Option<&AudioSink>)>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut player: ResMut<MusicPlayer>,
+    asset_server: Res<AssetServer>,
+    settings: Res<AudioSettings>,
+    state: Res<State<AppState>>,
+    game_state: Res<GameState>,
+    time: Res<Time>,
+) {
+    let finished = game_state.num_players > 0
+        && (0..game_state.num_players).all(|p| game_state.is_finished(p));
+    let kind = match state.get() {
+        AppState::Loading => None,
+        AppState::InGame if finished => Some(MusicKind::Results),
+        AppState::InGame => Some(MusicKind::InGame),
+        AppState::Standings => Some(MusicKind::Results),
+        _ => Some(MusicKind::Menu),
+    };
+    // Browsers keep playing the audio of tabs in the background
+    let focused = q_window.get_single().map_or(true, |window| window.focused);
+    let muted = cfg!(target_arch = "wasm32") && !focused;
+
+    let step = time.delta_seconds() / CROSSFADE_SECONDS;
+    let mut playing = false;
+    for (entity, mut music, sink) in q_music.iter_mut() {
+        if music.timer.tick(time.delta()).finished() || Some(music.kind) != kind {
+            music.fading_out = true;
+        }
+        if music.fading_out {
+            music.fade -= step;
+            if music.fade <= 0.0 {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        } else {
+            music.fade = (music.fade + step).min(1.0);
+            playing = true;
+        }
+
+        if let Some(sink) = sink {
+            let volume = if muted {
+                0.0
+            } else {
+                music.fade * settings.music_volume
+            };
+            sink.set_volume(volume);
+        }
+    }
+
+    let Some(kind) = kind else {
+        return;
+    };
+    if playing {
+        return;
+    }
+    let Some(track) = player.next_track(kind) else {
+        return;
+    };
+    commands.spawn((
+        Music {
+            kind,
+            fade: 0.0,
+            fading_out: false,
+            timer: Timer::from_seconds(
+                (track.seconds - CROSSFADE_SECONDS).max(CROSSFADE_SECONDS),
+                TimerMode::Once,
+            ),
+        },
+        AudioBundle {
+            source: asset_server.load(track.path),
+            // Faded in from silence once playing
+            settings: PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(0.0)),
+        },
+    ));
+}
+
+fn save_audio_settings(settings: Res<AudioSettings>) {
+    // Only remember settings the player picked
+    if !settings.is_added() {
+        save::store(SAVE_FILE, &*settings);
+    }
+}
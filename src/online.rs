@@ -0,0 +1,1210 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{self, Ball, BallSpin, BallStyle, ShootEvent},
+    cli::CliArgs,
+    collision::BallCollisionRule,
+    emotes::Emote,
+    level::{CurrentLevel, Hole, Level},
+    lobby::{self, LobbyMember},
+    locale::Locale,
+    replay::RecordedShot,
+    scoring::{GameState, StrokeAdded, TeamMode, TurnOrder},
+    ui, AppState, GameMode, GameSeed,
+};
+
+/// How often the host sends the position of every ball.
+const BALL_STATE_INTERVAL: f32 = 0.1;
+
+/// Bumped whenever the messages change, so that mismatched games refuse each other instead of
+/// failing to parse everything.
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// The port a game hosted from the menu listens on.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Lets a game be played over the network. One game hosts and is the authority on where the
+/// balls are, while others join it to play along or to watch.
+pub struct OnlinePlugin {
+    role: NetRole,
+    address: Option<String>,
+    host_port: Option<u16>,
+}
+
+impl OnlinePlugin {
+    pub fn from_args(args: &CliArgs) -> Self {
+        // Join codes are turned back into the address they stand for
+        let resolve = |address: &String| {
+            lobby::parse_join_code(address).map_or_else(|| address.clone(), |a| a.to_string())
+        };
+        let (role, address) = if let Some(port) = args.host {
+            (NetRole::Host, Some(format!("0.0.0.0:{}", port)))
+        } else if let Some(address) = &args.join {
+            (NetRole::Player, Some(resolve(address)))
+        } else if let Some(address) = &args.spectate {
+            (NetRole::Spectator, Some(resolve(address)))
+        } else {
+            (NetRole::Offline, None)
+        };
+        Self {
+            role,
+            address,
+            host_port: args.host,
+        }
+    }
+}
+
+impl Plugin for OnlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.role)
+            .add_event::<SendMessage>()
+            .add_event::<ReceivedMessage>()
+            .add_event::<PeerJoined>()
+            .add_event::<ConnectionStatus>()
+            .add_event::<Welcomed>()
+            .add_event::<PeerLeft>()
+            .init_resource::<PendingStart>()
+            .init_resource::<OnlineStyles>()
+            .init_resource::<PlayerOwners>()
+            .init_resource::<PendingResync>()
+            .init_resource::<ReceivedShots>()
+            .insert_resource(BallStateTimer(Timer::from_seconds(
+                BALL_STATE_INTERVAL,
+                TimerMode::Repeating,
+            )));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(
+            Update,
+            (
+                transport::receive_messages.before(handle_messages),
+                transport::send_messages.after(send_ball_states),
+            )
+                .run_if(resource_exists::<transport::Connection>()),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(address) = &self.address {
+            match transport::Connection::open(self.role, address) {
+                Ok(connection) => {
+                    app.insert_resource(connection);
+                    if let Some(port) = self.host_port {
+                        app.insert_resource(HostPort(port));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Could not connect to {}: {}", address, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        if self.address.is_some() {
+            warn!("Online games are not supported in the browser");
+        }
+
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            announce_game.run_if(resource_equals(NetRole::Host)),
+        )
+        .add_systems(
+            OnEnter(AppState::Menu),
+            (clear_online_styles, clear_player_owners),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_messages.before(ball::apply_shot),
+                send_local_shots.after(ball::apply_shot),
+                (
+                    announce_game.run_if(on_event::<PeerJoined>()),
+                    send_ball_states,
+                )
+                    .chain()
+                    .run_if(resource_equals(NetRole::Host))
+                    .run_if(in_state(AppState::InGame)),
+                send_resync
+                    .after(announce_game)
+                    .run_if(on_event::<PeerJoined>())
+                    .run_if(resource_equals(NetRole::Host))
+                    .run_if(in_state(AppState::InGame)),
+                apply_resync
+                    .after(handle_messages)
+                    .run_if(in_state(AppState::InGame)),
+                start_online_game
+                    .run_if(in_state(AppState::Menu).or_else(in_state(AppState::Lobby))),
+                show_connection_status,
+            ),
+        );
+    }
+}
+
+/// What this game does in an online match.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetRole {
+    #[default]
+    Offline,
+    /// Runs the authoritative simulation that everybody else follows.
+    Host,
+    /// Plays along in somebody else's game.
+    Player,
+    /// Follows somebody else's game without taking any shots.
+    Spectator,
+}
+
+impl NetRole {
+    pub fn is_client(self) -> bool {
+        matches!(self, NetRole::Player | NetRole::Spectator)
+    }
+
+    pub fn can_shoot(self) -> bool {
+        self != NetRole::Spectator
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum NetMessage {
+    /// The course and rules, sent by the host whenever a game starts or somebody joins.
+    Start {
+        seed: u64,
+        level: Level,
+        num_players: u32,
+        mode: GameMode,
+        collision_rule: BallCollisionRule,
+        #[serde(default)]
+        turn_order: TurnOrder,
+        #[serde(default)]
+        team_mode: TeamMode,
+        /// The ball of every player, as chosen in the lobby.
+        #[serde(default)]
+        styles: Vec<BallStyle>,
+        /// The lobby token of whoever shoots for each player.
+        #[serde(default)]
+        owners: Vec<u64>,
+    },
+    Shot(RecordedShot),
+    /// Where the host sees every ball right now. `time` is the host's clock, in seconds.
+    BallStates {
+        time: f64,
+        balls: Vec<BallSnapshot>,
+    },
+    Chat {
+        from: String,
+        text: String,
+    },
+    Emote {
+        player_id: u32,
+        emote: Emote,
+    },
+    /// The first thing a client sends. `token` is the one it was welcomed with before, if it is
+    /// coming back after losing the connection.
+    Hello {
+        version: u32,
+        token: Option<u64>,
+        /// Where the client listens for the others in case it has to take over as host.
+        #[serde(default)]
+        listen_port: Option<u16>,
+        /// Whether the client only watches, so the host turns away any shots it sends.
+        #[serde(default)]
+        spectator: bool,
+    },
+    /// The host's answer to a `Hello`, with the token to rejoin with.
+    Welcome {
+        token: u64,
+    },
+    /// The host's answer to a `Hello` from a game speaking another version of the protocol.
+    Rejected {
+        version: u32,
+    },
+    /// Who takes over if the host goes away, in order. Sent by the host whenever somebody
+    /// joins or leaves.
+    Succession(Vec<Successor>),
+    /// Turn and scores as the host sees them, sent whenever somebody joins or rejoins so that
+    /// they can pick up in the middle of a hole.
+    Resync {
+        current_player: u32,
+        players: Vec<PlayerSnapshot>,
+    },
+    /// A client's own entry in the lobby, sent to the host whenever it changes.
+    LobbyMember(LobbyMember),
+    /// Everybody in the lobby, sent by the host whenever somebody joins, leaves or changes.
+    Lobby(Vec<LobbyMember>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Successor {
+    pub token: u64,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    /// Strokes on the current hole, or `None` if the ball is not on the course.
+    pub hits: Option<u32>,
+    pub scores: Vec<u32>,
+    pub holes: Vec<Hole>,
+    pub pickups: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BallSnapshot {
+    pub player_id: u32,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub linvel: [f32; 3],
+    pub angvel: [f32; 3],
+}
+
+/// A message to send to everybody else in the game.
+#[derive(Event)]
+pub struct SendMessage(pub NetMessage);
+
+/// A message that arrived from another game.
+#[derive(Event)]
+pub struct ReceivedMessage(pub NetMessage);
+
+/// Sent on the host when somebody connects, or reconnects, and has been welcomed.
+#[derive(Event)]
+pub struct PeerJoined;
+
+/// Sent on a client when the host welcomed it, with the token it is known by.
+#[derive(Event)]
+pub struct Welcomed(pub u64);
+
+/// Sent on the host when somebody who was welcomed goes away, with their token.
+#[derive(Event)]
+pub struct PeerLeft(pub u64);
+
+/// The port this game hosts on, if it does.
+#[derive(Resource)]
+pub struct HostPort(pub u16);
+
+/// The balls chosen in the lobby for the online game being played, by player.
+#[derive(Resource, Default)]
+pub struct OnlineStyles(pub Vec<BallStyle>);
+
+impl OnlineStyles {
+    pub fn get(&self, player_id: u32) -> Option<BallStyle> {
+        self.0.get(player_id as usize).cloned()
+    }
+}
+
+/// Who shoots for which player in an online game started from the lobby.
+#[derive(Resource, Default)]
+pub struct PlayerOwners {
+    /// The lobby token of the owner of each player. Empty if anybody may shoot for anyone.
+    pub owners: Vec<u64>,
+    /// Our own lobby token, once we have one.
+    pub local: Option<u64>,
+}
+
+impl PlayerOwners {
+    pub fn owns(&self, token: u64, player_id: u32) -> bool {
+        match self.owners.get(player_id as usize) {
+            Some(&owner) => owner == token,
+            None => self.owners.is_empty(),
+        }
+    }
+
+    /// Whether this game shoots for `player_id`.
+    pub fn is_local(&self, player_id: u32) -> bool {
+        match self.local {
+            Some(local) => self.owns(local, player_id),
+            None => self.owners.is_empty(),
+        }
+    }
+
+    /// The player this game shoots for, if it owns one.
+    pub fn local_player(&self) -> Option<u32> {
+        let local = self.local?;
+        let player_id = self.owners.iter().position(|&owner| owner == local)?;
+        Some(player_id as u32)
+    }
+}
+
+/// Starts hosting a game on `DEFAULT_PORT` from within the game. Returns whether it worked.
+pub fn host_game(commands: &mut Commands, role: &mut NetRole) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let address = format!("0.0.0.0:{}", DEFAULT_PORT);
+        match transport::Connection::open(NetRole::Host, &address) {
+            Ok(connection) => {
+                commands.insert_resource(connection);
+                commands.insert_resource(HostPort(DEFAULT_PORT));
+                *role = NetRole::Host;
+                true
+            }
+            Err(e) => {
+                warn!("Could not host on {}: {}", address, e);
+                false
+            }
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (commands, role);
+        warn!("Online games are not supported in the browser");
+        false
+    }
+}
+
+/// Changes to a client's connection to the host.
+#[derive(Event, Debug, Clone, Copy)]
+pub enum ConnectionStatus {
+    /// The connection dropped and reconnecting has started.
+    Lost,
+    /// The host welcomed us back.
+    Restored,
+    /// The host went away for good and another game took over.
+    HostChanged,
+    /// The host went away for good and this game took over.
+    NowHosting,
+    /// The host could not be reached again.
+    GaveUp,
+    /// The host speaks another version of the protocol.
+    Rejected { host_version: u32 },
+}
+
+/// The game to start once the menu is shown, if the host sent it before that.
+#[derive(Resource, Default)]
+struct PendingStart(Option<NetMessage>);
+
+/// Scores to catch up on once the balls of the current game exist.
+#[derive(Resource, Default)]
+struct PendingResync(Option<NetMessage>);
+
+/// Players whose shot this frame came over the network, so it isn't sent back out.
+#[derive(Resource, Default)]
+struct ReceivedShots(Vec<u32>);
+
+#[derive(Resource)]
+struct BallStateTimer(Timer);
+
+#[allow(clippy::too_many_arguments)]
+fn announce_game(
+    mut messages: EventWriter<SendMessage>,
+    current_level: Res<CurrentLevel>,
+    seed: Res<GameSeed>,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    collision_rule: Res<BallCollisionRule>,
+    turn_order: Res<TurnOrder>,
+    team_mode: Res<TeamMode>,
+    online_styles: Res<OnlineStyles>,
+    player_owners: Res<PlayerOwners>,
+) {
+    messages.send(SendMessage(NetMessage::Start {
+        seed: seed.0,
+        level: current_level.0.clone(),
+        num_players: game_state.num_players,
+        mode: *game_mode,
+        collision_rule: *collision_rule,
+        turn_order: *turn_order,
+        team_mode: *team_mode,
+        styles: online_styles.0.clone(),
+        owners: player_owners.owners.clone(),
+    }));
+}
+
+fn handle_messages(
+    mut received: EventReader<ReceivedMessage>,
+    mut shots: EventWriter<ShootEvent>,
+    mut received_shots: ResMut<ReceivedShots>,
+    mut pending_start: ResMut<PendingStart>,
+    mut pending_resync: ResMut<PendingResync>,
+    role: Res<NetRole>,
+) {
+    received_shots.0.clear();
+
+    for ReceivedMessage(message) in received.iter() {
+        match message {
+            NetMessage::Start { .. } if role.is_client() => {
+                pending_start.0 = Some(message.clone());
+            }
+            NetMessage::Start { .. } => {}
+            NetMessage::Resync { .. } if role.is_client() => {
+                pending_resync.0 = Some(message.clone());
+            }
+            NetMessage::Shot(shot) => {
+                received_shots.0.push(shot.player_id);
+                shots.send(ShootEvent {
+                    player_id: shot.player_id,
+                    power: shot.power,
+                    angle: shot.angle,
+                    spin: BallSpin::from_signed(shot.spin),
+                });
+            }
+            // Ball states are smoothed out by the interpolation plugin
+            NetMessage::BallStates { .. }
+            | NetMessage::Chat { .. }
+            | NetMessage::Emote { .. }
+            | NetMessage::Resync { .. }
+            | NetMessage::LobbyMember(_)
+            | NetMessage::Lobby(_) => {}
+            // The handshake is handled by the transport
+            NetMessage::Hello { .. }
+            | NetMessage::Welcome { .. }
+            | NetMessage::Rejected { .. }
+            | NetMessage::Succession(_) => {}
+        }
+    }
+}
+
+fn send_resync(
+    mut messages: EventWriter<SendMessage>,
+    game_state: Res<GameState>,
+    q_ball: Query<&Ball>,
+) {
+    let players = game_state
+        .players
+        .iter()
+        .enumerate()
+        .map(|(player_id, player)| PlayerSnapshot {
+            hits: q_ball
+                .iter()
+                .find(|ball| ball.player_id == player_id as u32)
+                .map(|ball| ball.hits),
+            scores: player.scores.clone(),
+            holes: player.holes.clone(),
+            pickups: player.pickups,
+        })
+        .collect();
+    messages.send(SendMessage(NetMessage::Resync {
+        current_player: game_state.current_player,
+        players,
+    }));
+}
+
+/// Catches up with the host's scores, e.g. after reconnecting in the middle of a hole. Balls
+/// the host no longer has are removed; their positions come with the next ball states.
+fn apply_resync(
+    mut commands: Commands,
+    mut pending_resync: ResMut<PendingResync>,
+    mut game_state: ResMut<GameState>,
+    mut q_ball: Query<(Entity, &mut Ball)>,
+) {
+    // Wait until the level has been set up
+    if q_ball.is_empty() {
+        return;
+    }
+    let Some(NetMessage::Resync {
+        current_player,
+        players,
+    }) = pending_resync.0.take()
+    else {
+        return;
+    };
+
+    if players.len() != game_state.players.len() {
+        warn!("Ignoring resync for {} players", players.len());
+        return;
+    }
+
+    game_state.current_player = current_player;
+    game_state.shot_in_progress = false;
+    for (player, snapshot) in game_state.players.iter_mut().zip(&players) {
+        player.scores = snapshot.scores.clone();
+        player.holes = snapshot.holes.clone();
+        player.pickups = snapshot.pickups;
+    }
+    for (entity, mut ball) in q_ball.iter_mut() {
+        match players.get(ball.player_id as usize).and_then(|p| p.hits) {
+            Some(hits) => ball.hits = hits,
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+}
+
+fn show_connection_status(
+    mut commands: Commands,
+    mut statuses: EventReader<ConnectionStatus>,
+    locale: Res<Locale>,
+) {
+    for status in statuses.iter() {
+        let (message, color) = match status {
+            ConnectionStatus::Lost => (locale.get("online.lost").to_string(), Color::ORANGE),
+            ConnectionStatus::Restored => (locale.get("online.restored").to_string(), Color::GREEN),
+            ConnectionStatus::HostChanged => {
+                (locale.get("online.host_changed").to_string(), Color::GREEN)
+            }
+            ConnectionStatus::NowHosting => {
+                (locale.get("online.now_hosting").to_string(), Color::GREEN)
+            }
+            ConnectionStatus::GaveUp => (locale.get("online.gave_up").to_string(), Color::RED),
+            ConnectionStatus::Rejected { host_version } => (
+                locale.format(
+                    "online.rejected",
+                    &[("ours", &PROTOCOL_VERSION), ("theirs", host_version)],
+                ),
+                Color::RED,
+            ),
+        };
+        ui::spawn_toast(&mut commands, message, color, 2);
+    }
+}
+
+/// Joins the game the host started, from the menu.
+#[allow(clippy::too_many_arguments)]
+fn start_online_game(
+    mut pending_start: ResMut<PendingStart>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut seed: ResMut<GameSeed>,
+    mut game_state: ResMut<GameState>,
+    mut game_mode: ResMut<GameMode>,
+    mut collision_rule: ResMut<BallCollisionRule>,
+    mut turn_order: ResMut<TurnOrder>,
+    mut team_mode: ResMut<TeamMode>,
+    mut online_styles: ResMut<OnlineStyles>,
+    mut player_owners: ResMut<PlayerOwners>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(NetMessage::Start {
+        seed: host_seed,
+        level,
+        num_players,
+        mode,
+        collision_rule: host_collision_rule,
+        turn_order: host_turn_order,
+        team_mode: host_team_mode,
+        styles,
+        owners,
+    }) = pending_start.0.take()
+    else {
+        return;
+    };
+
+    current_level.0 = level;
+    seed.0 = host_seed;
+    *game_state = GameState::new(num_players);
+    *game_mode = mode;
+    *collision_rule = host_collision_rule;
+    *turn_order = host_turn_order;
+    *team_mode = host_team_mode;
+    online_styles.0 = styles;
+    player_owners.owners = owners;
+    next_state.set(AppState::InGame);
+}
+
+/// Games started from the menu use everybody's own ball styles again.
+fn clear_online_styles(mut online_styles: ResMut<OnlineStyles>) {
+    online_styles.0.clear();
+}
+
+/// Anybody may shoot for anyone in games not started from the lobby.
+fn clear_player_owners(mut player_owners: ResMut<PlayerOwners>) {
+    player_owners.owners.clear();
+}
+
+fn send_local_shots(
+    mut shots: EventReader<ShootEvent>,
+    mut strokes: EventReader<StrokeAdded>,
+    mut messages: EventWriter<SendMessage>,
+    received_shots: Res<ReceivedShots>,
+    role: Res<NetRole>,
+) {
+    if *role == NetRole::Offline {
+        return;
+    }
+
+    // Shots that were not applied don't add a stroke
+    let taken: Vec<u32> = strokes
+        .iter()
+        .filter(|stroke| !stroke.penalty)
+        .map(|stroke| stroke.player_id)
+        .collect();
+
+    for shot in shots.iter() {
+        if taken.contains(&shot.player_id) && !received_shots.0.contains(&shot.player_id) {
+            messages.send(SendMessage(NetMessage::Shot(RecordedShot {
+                player_id: shot.player_id,
+                power: shot.power,
+                angle: shot.angle,
+                spin: shot.spin.map_or(0.0, BallSpin::signed),
+                // Ball positions are sent separately
+                tee: None,
+                power_up: false,
+                jump: None,
+            })));
+        }
+    }
+}
+
+fn send_ball_states(
+    mut messages: EventWriter<SendMessage>,
+    mut timer: ResMut<BallStateTimer>,
+    q_ball: Query<(&Ball, &Transform, &Velocity)>,
+    time: Res<Time>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let balls = q_ball
+        .iter()
+        .map(|(ball, transform, velocity)| BallSnapshot {
+            player_id: ball.player_id,
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            linvel: velocity.linvel.to_array(),
+            angvel: velocity.angvel.to_array(),
+        })
+        .collect();
+    messages.send(SendMessage(NetMessage::BallStates {
+        time: time.elapsed_seconds_f64(),
+        balls,
+    }));
+}
+
+/// Messages are sent over TCP as one line of RON each. The host passes every message it gets
+/// on to everybody else.
+///
+/// A client starts with a `Hello` and gets a token back, which it uses to rejoin if the
+/// connection drops. Until then, the host doesn't send it anything else.
+///
+/// Players also listen on a port of their own and the host tells everybody who is next in line.
+/// If the host can't be reached again, the clients try each successor in turn, and the one whose
+/// turn it is starts hosting with the same tokens so the others can rejoin there.
+#[cfg(not(target_arch = "wasm32"))]
+mod transport {
+    use std::{
+        io::{ErrorKind, Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+        time::Duration,
+    };
+
+    use bevy::prelude::*;
+
+    use super::{
+        ConnectionStatus, NetMessage, NetRole, PeerJoined, PeerLeft, PlayerOwners, ReceivedMessage,
+        SendMessage, Successor, Welcomed, PROTOCOL_VERSION,
+    };
+
+    /// Time between attempts to reach the host again.
+    const RECONNECT_INTERVAL: f32 = 2.0;
+    const MAX_RECONNECT_ATTEMPTS: u32 = 15;
+    /// Attempts to make before moving on to the next in line to host.
+    const ATTEMPTS_PER_HOST: u32 = 2;
+    /// How long to wait for the host on each attempt. The game stalls while waiting.
+    const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+    /// Longest message a peer may send. Courses are sent whole, so they set the limit.
+    const MAX_LINE_LENGTH: usize = 1 << 20;
+    /// Most bytes waiting to go out to a peer before it is given up on for not reading them.
+    const MAX_OUTGOING: usize = 4 << 20;
+
+    #[derive(Resource)]
+    pub struct Connection {
+        listener: Option<TcpListener>,
+        peers: Vec<Peer>,
+        /// The host to reconnect to, for clients.
+        address: Option<String>,
+        /// What the host welcomed us with, for clients.
+        token: Option<u64>,
+        /// Every token handed out, for the host.
+        tokens: Vec<u64>,
+        reconnect: Option<Reconnect>,
+        /// Where a player listens in case it has to take over as host.
+        fallback: Option<TcpListener>,
+        /// Who takes over if the host goes away, as told by the host.
+        succession: Vec<Successor>,
+        /// Whether the host has to send out a new succession.
+        succession_changed: bool,
+    }
+
+    struct Reconnect {
+        timer: Timer,
+        attempts: u32,
+        host_changed: bool,
+    }
+
+    struct Peer {
+        stream: TcpStream,
+        buffer: Vec<u8>,
+        /// What could not be sent yet without blocking.
+        outgoing: Vec<u8>,
+        /// Whether the handshake is done and game messages may be exchanged.
+        greeted: bool,
+        token: Option<u64>,
+        /// Where the peer can be reached if it takes over as host.
+        fallback: Option<String>,
+        /// Whether the peer joined to watch, and so has no business shooting.
+        spectator: bool,
+    }
+
+    impl Peer {
+        fn new(stream: TcpStream, greeted: bool) -> std::io::Result<Self> {
+            stream.set_nonblocking(true)?;
+            stream.set_nodelay(true)?;
+            Ok(Self {
+                stream,
+                buffer: Vec::new(),
+                outgoing: Vec::new(),
+                greeted,
+                token: None,
+                fallback: None,
+                spectator: false,
+            })
+        }
+
+        /// Reads whatever has arrived and returns the complete lines, or an error if the
+        /// connection was closed or the peer sends a line that is too long.
+        fn read_lines(&mut self) -> std::io::Result<Vec<String>> {
+            let mut chunk = [0; 4096];
+            loop {
+                match self.stream.read(&mut chunk) {
+                    Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                    Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let mut lines = Vec::new();
+            while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                lines.push(String::from_utf8_lossy(&line[..end]).into_owned());
+            }
+            if self.buffer.len() > MAX_LINE_LENGTH {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    "message too long",
+                ));
+            }
+            Ok(lines)
+        }
+
+        /// Queues a line and sends as much as the connection takes right away.
+        fn send_line(&mut self, line: &str) -> std::io::Result<()> {
+            self.queue_line(line);
+            self.flush()
+        }
+
+        fn queue_line(&mut self, line: &str) {
+            self.outgoing.extend_from_slice(line.as_bytes());
+            self.outgoing.push(b'\n');
+        }
+
+        /// Sends what has been queued until the connection would block. The rest is sent on
+        /// later updates.
+        fn flush(&mut self) -> std::io::Result<()> {
+            while !self.outgoing.is_empty() {
+                match self.stream.write(&self.outgoing) {
+                    Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                    Ok(n) => {
+                        self.outgoing.drain(..n);
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e),
+                }
+            }
+            if self.outgoing.len() > MAX_OUTGOING {
+                return Err(std::io::Error::new(
+                    ErrorKind::TimedOut,
+                    "peer is not keeping up",
+                ));
+            }
+            Ok(())
+        }
+
+        fn send(&mut self, message: &NetMessage) -> std::io::Result<()> {
+            let line = ron::to_string(message)
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+            self.send_line(&line)
+        }
+    }
+
+    /// Connects to the host and says hello, without blocking for long if it is unreachable.
+    fn connect(
+        address: &str,
+        role: NetRole,
+        token: Option<u64>,
+        fallback: Option<&TcpListener>,
+    ) -> std::io::Result<Peer> {
+        let listen_port = fallback
+            .and_then(|listener| listener.local_addr().ok())
+            .map(|address| address.port());
+
+        let mut last_error = std::io::Error::from(ErrorKind::AddrNotAvailable);
+        for socket_address in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    let mut peer = Peer::new(stream, true)?;
+                    peer.send(&NetMessage::Hello {
+                        version: PROTOCOL_VERSION,
+                        token,
+                        listen_port,
+                        spectator: role == NetRole::Spectator,
+                    })?;
+                    return Ok(peer);
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    impl Connection {
+        pub fn open(role: NetRole, address: &str) -> std::io::Result<Self> {
+            if role == NetRole::Host {
+                let listener = TcpListener::bind(address)?;
+                listener.set_nonblocking(true)?;
+                info!("Hosting an online game on {}", address);
+                Ok(Self {
+                    listener: Some(listener),
+                    peers: Vec::new(),
+                    address: None,
+                    token: None,
+                    tokens: Vec::new(),
+                    reconnect: None,
+                    fallback: None,
+                    succession: Vec::new(),
+                    succession_changed: false,
+                })
+            } else {
+                // Spectators don't take over, since nobody would be left to shoot
+                let fallback = if role == NetRole::Player {
+                    let listener = TcpListener::bind("0.0.0.0:0")?;
+                    listener.set_nonblocking(true)?;
+                    Some(listener)
+                } else {
+                    None
+                };
+                let peer = connect(address, role, None, fallback.as_ref())?;
+                info!("Joined the online game at {}", address);
+                Ok(Self {
+                    listener: None,
+                    peers: vec![peer],
+                    address: Some(address.to_string()),
+                    token: None,
+                    tokens: Vec::new(),
+                    reconnect: None,
+                    fallback,
+                    succession: Vec::new(),
+                    succession_changed: false,
+                })
+            }
+        }
+
+        /// Starts trying to reach the host again once a client has lost it.
+        fn check_connection(&mut self, status: &mut EventWriter<ConnectionStatus>) {
+            if self.listener.is_none()
+                && self.peers.is_empty()
+                && self.address.is_some()
+                && self.reconnect.is_none()
+            {
+                self.reconnect = Some(Reconnect {
+                    timer: Timer::from_seconds(RECONNECT_INTERVAL, TimerMode::Repeating),
+                    attempts: 0,
+                    host_changed: false,
+                });
+                status.send(ConnectionStatus::Lost);
+            }
+        }
+
+        /// Moves on to the next in line to host, which may be us. Returns false if there is
+        /// nobody left.
+        fn next_host(
+            &mut self,
+            role: &mut NetRole,
+            status: &mut EventWriter<ConnectionStatus>,
+        ) -> bool {
+            if self.succession.is_empty() {
+                return false;
+            }
+            let successor = self.succession.remove(0);
+
+            if Some(successor.token) == self.token {
+                info!("Taking over as host");
+                self.listener = self.fallback.take();
+                self.tokens = self.succession.iter().map(|s| s.token).collect();
+                self.address = None;
+                self.reconnect = None;
+                self.succession.clear();
+                *role = NetRole::Host;
+                status.send(ConnectionStatus::NowHosting);
+            } else {
+                info!("Moving on to {} as host", successor.address);
+                self.address = Some(successor.address);
+                if let Some(reconnect) = &mut self.reconnect {
+                    reconnect.attempts = 0;
+                    reconnect.host_changed = true;
+                }
+            }
+            true
+        }
+
+        fn try_reconnect(
+            &mut self,
+            time: &Time,
+            role: &mut NetRole,
+            status: &mut EventWriter<ConnectionStatus>,
+        ) {
+            let (Some(reconnect), Some(address)) = (&mut self.reconnect, &self.address) else {
+                return;
+            };
+            if !reconnect.timer.tick(time.delta()).just_finished() {
+                return;
+            }
+
+            reconnect.attempts += 1;
+            let attempts = reconnect.attempts;
+            match connect(address, *role, self.token, self.fallback.as_ref()) {
+                Ok(peer) => {
+                    info!("Reconnected to {}", address);
+                    self.peers.push(peer);
+                }
+                Err(e) => {
+                    info!("Could not reconnect to {}: {}", address, e);
+                    if attempts >= ATTEMPTS_PER_HOST && self.next_host(role, status) {
+                        return;
+                    }
+                    if attempts >= MAX_RECONNECT_ATTEMPTS {
+                        warn!("Giving up on reconnecting");
+                        self.address = None;
+                        self.reconnect = None;
+                        status.send(ConnectionStatus::GaveUp);
+                    }
+                }
+            }
+        }
+
+        /// Tells everybody who is next in line to host.
+        fn send_succession(&mut self) {
+            let message = NetMessage::Succession(
+                self.peers
+                    .iter()
+                    .filter(|peer| peer.greeted)
+                    .filter_map(|peer| {
+                        Some(Successor {
+                            token: peer.token?,
+                            address: peer.fallback.clone()?,
+                        })
+                    })
+                    .collect(),
+            );
+            for peer in self.peers.iter_mut().filter(|peer| peer.greeted) {
+                let _ = peer.send(&message);
+            }
+            self.succession_changed = false;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn receive_messages(
+        mut connection: ResMut<Connection>,
+        mut received: EventWriter<ReceivedMessage>,
+        mut joined: EventWriter<PeerJoined>,
+        mut left: EventWriter<PeerLeft>,
+        mut welcomed: EventWriter<Welcomed>,
+        mut status: EventWriter<ConnectionStatus>,
+        mut role: ResMut<NetRole>,
+        player_owners: Res<PlayerOwners>,
+        time: Res<Time>,
+    ) {
+        let connection = &mut *connection;
+
+        if let Some(listener) = &connection.listener {
+            while let Ok((stream, address)) = listener.accept() {
+                match Peer::new(stream, false) {
+                    Ok(peer) => {
+                        info!("{} connected", address);
+                        connection.peers.push(peer);
+                    }
+                    Err(e) => warn!("Could not accept {}: {}", address, e),
+                }
+            }
+        }
+
+        connection.try_reconnect(&time, &mut role, &mut status);
+
+        let Connection {
+            listener,
+            peers,
+            address,
+            token: our_token,
+            tokens,
+            reconnect,
+            succession,
+            succession_changed,
+            ..
+        } = &mut *connection;
+        let is_host = listener.is_some();
+
+        let mut relayed = Vec::new();
+        peers.retain_mut(|peer| {
+            let lines = match peer.read_lines() {
+                Ok(lines) => lines,
+                Err(e) => {
+                    info!("Lost connection: {}", e);
+                    *succession_changed |= peer.greeted;
+                    if let Some(token) = peer.token {
+                        left.send(PeerLeft(token));
+                    }
+                    return false;
+                }
+            };
+            for line in lines {
+                let message = match ron::from_str::<NetMessage>(&line) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        warn!("Could not parse message: {}", e);
+                        continue;
+                    }
+                };
+                match message {
+                    NetMessage::Hello {
+                        version,
+                        token,
+                        listen_port,
+                        spectator,
+                    } if is_host && !peer.greeted => {
+                        if version != PROTOCOL_VERSION {
+                            info!("Turning away a game with protocol version {}", version);
+                            let _ = peer.send(&NetMessage::Rejected {
+                                version: PROTOCOL_VERSION,
+                            });
+                            return false;
+                        }
+                        let token = match token.filter(|token| tokens.contains(token)) {
+                            Some(token) => {
+                                info!("A player rejoined");
+                                token
+                            }
+                            None => {
+                                let token = rand::random();
+                                tokens.push(token);
+                                token
+                            }
+                        };
+                        if peer.send(&NetMessage::Welcome { token }).is_err() {
+                            return false;
+                        }
+                        peer.greeted = true;
+                        peer.token = Some(token);
+                        peer.spectator = spectator;
+                        peer.fallback = listen_port.and_then(|port| {
+                            let ip = peer.stream.peer_addr().ok()?.ip();
+                            Some(SocketAddr::new(ip, port).to_string())
+                        });
+                        *succession_changed = true;
+                        joined.send(PeerJoined);
+                    }
+                    _ if is_host && !peer.greeted => {
+                        warn!("Dropping a peer that skipped the handshake");
+                        return false;
+                    }
+                    NetMessage::Welcome { token } if !is_host => {
+                        if let Some(reconnect) = reconnect.take() {
+                            status.send(if reconnect.host_changed {
+                                ConnectionStatus::HostChanged
+                            } else {
+                                ConnectionStatus::Restored
+                            });
+                        }
+                        *our_token = Some(token);
+                        welcomed.send(Welcomed(token));
+                    }
+                    NetMessage::Rejected { version } if !is_host => {
+                        warn!(
+                            "The host uses protocol version {}, but we use {}",
+                            version, PROTOCOL_VERSION
+                        );
+                        *address = None;
+                        *reconnect = None;
+                        status.send(ConnectionStatus::Rejected {
+                            host_version: version,
+                        });
+                        return false;
+                    }
+                    NetMessage::Succession(successors) if !is_host => {
+                        *succession = successors;
+                    }
+                    // Spectators don't shoot at all, even before anybody owns a player
+                    NetMessage::Shot(shot) if is_host && peer.spectator => {
+                        warn!(
+                            "Dropping a shot for player {} from a spectator",
+                            shot.player_id + 1
+                        );
+                    }
+                    // Nobody gets to shoot for somebody else's player
+                    NetMessage::Shot(shot)
+                        if is_host
+                            && !peer
+                                .token
+                                .is_some_and(|token| player_owners.owns(token, shot.player_id)) =>
+                    {
+                        warn!(
+                            "Dropping a shot for player {} from a peer that doesn't own it",
+                            shot.player_id + 1
+                        );
+                    }
+                    NetMessage::Hello { .. }
+                    | NetMessage::Welcome { .. }
+                    | NetMessage::Rejected { .. }
+                    | NetMessage::Succession(_) => {}
+                    message => {
+                        received.send(ReceivedMessage(message));
+                        relayed.push((peer.stream.peer_addr().ok(), line));
+                    }
+                }
+            }
+            true
+        });
+
+        if is_host {
+            for (from, line) in relayed {
+                for peer in peers.iter_mut().filter(|peer| peer.greeted) {
+                    if peer.stream.peer_addr().ok() != from {
+                        let _ = peer.send_line(&line);
+                    }
+                }
+            }
+            if *succession_changed {
+                connection.send_succession();
+            }
+        }
+
+        connection.check_connection(&mut status);
+    }
+
+    pub fn send_messages(
+        mut connection: ResMut<Connection>,
+        mut messages: EventReader<SendMessage>,
+        mut left: EventWriter<PeerLeft>,
+        mut status: EventWriter<ConnectionStatus>,
+    ) {
+        let connection = &mut *connection;
+
+        let lines: Vec<String> = messages
+            .iter()
+            .filter_map(|SendMessage(message)| match ron::to_string(message) {
+                Ok(line) => Some(line),
+                Err(e) => {
+                    warn!("Could not serialize message: {}", e);
+                    None
+                }
+            })
+            .collect();
+        connection.peers.retain_mut(|peer| {
+            if peer.greeted {
+                for line in &lines {
+                    peer.queue_line(line);
+                }
+            }
+            // Also sends what was left over from earlier updates
+            match peer.flush() {
+                Ok(()) => true,
+                Err(e) => {
+                    info!("Lost connection: {}", e);
+                    connection.succession_changed |= peer.greeted;
+                    if let Some(token) = peer.token {
+                        left.send(PeerLeft(token));
+                    }
+                    false
+                }
+            }
+        });
+
+        connection.check_connection(&mut status);
+    }
+}
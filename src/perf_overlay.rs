@@ -0,0 +1,99 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+/// An overlay with performance numbers that testers can include in bug reports, toggled with F3.
+pub struct PerfOverlayPlugin;
+
+impl Plugin for PerfOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((FrameTimeDiagnosticsPlugin, EntityCountDiagnosticsPlugin))
+            .add_systems(Startup, setup_perf_overlay)
+            .add_systems(Update, (toggle_perf_overlay, update_perf_overlay));
+    }
+}
+
+#[derive(Component)]
+struct PerfOverlay;
+
+fn setup_perf_overlay(mut commands: Commands) {
+    commands.spawn((
+        PerfOverlay,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // Above the power-up slot
+            bottom: Val::Px(60.0),
+            right: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        Visibility::Hidden,
+    ));
+}
+
+fn toggle_perf_overlay(
+    keys: Res<Input<KeyCode>>,
+    mut q_overlay: Query<&mut Visibility, With<PerfOverlay>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    for mut visibility in q_overlay.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn update_perf_overlay(
+    mut q_overlay: Query<(&mut Text, &Visibility), With<PerfOverlay>>,
+    diagnostics: Res<DiagnosticsStore>,
+    q_bodies: Query<(&RigidBody, Option<&Sleeping>)>,
+    q_meshes: Query<&ComputedVisibility, With<Handle<Mesh>>>,
+) {
+    let Ok((mut text, visibility)) = q_overlay.get_single_mut() else {
+        return;
+    };
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    let smoothed = |id| {
+        diagnostics
+            .get(id)
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .unwrap_or(0.0)
+    };
+
+    let active_bodies = q_bodies
+        .iter()
+        .filter(|(body, sleeping)| {
+            **body == RigidBody::Dynamic && !sleeping.map_or(false, |s| s.sleeping)
+        })
+        .count();
+    // Bevy doesn't count draw calls, but each visible mesh is roughly one
+    let visible_meshes = q_meshes.iter().filter(|v| v.is_visible()).count();
+
+    text.sections[0].value = format!(
+        "FPS {:.0}\nFrame {:.1} ms\nEntities {:.0}\nActive bodies {}\nVisible meshes {}",
+        smoothed(FrameTimeDiagnosticsPlugin::FPS),
+        smoothed(FrameTimeDiagnosticsPlugin::FRAME_TIME),
+        smoothed(EntityCountDiagnosticsPlugin::ENTITY_COUNT),
+        active_bodies,
+        visible_meshes,
+    );
+}
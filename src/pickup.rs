@@ -0,0 +1,218 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::Ball,
+    level::{tile_center, LevelEntity},
+    locale::Locale,
+    powerup::PowerUp,
+    scoring::GameState,
+    ui, AppState,
+};
+
+/// Height above the floor that pickups float at, about where the ball passes through.
+const FLOAT_HEIGHT: f32 = 0.08;
+const BOB_HEIGHT: f32 = 0.01;
+/// In radians per second.
+const SPIN_SPEED: f32 = 2.0;
+
+/// Coins and stars on the course that balls collect by rolling through them.
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PickupCollected>().add_systems(
+            Update,
+            (collect_pickups, show_pickup_toasts, animate_pickups)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Something to collect above a floor tile. Also the component on its sensor.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pickup {
+    pub position: (i32, i32, i32),
+    pub kind: PickupKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PickupKind {
+    Coin,
+    /// Worth more than a coin, and usually harder to get to.
+    Star,
+    /// Goes into the player's power-up slot instead of adding to their tally.
+    PowerUp(PowerUp),
+}
+
+impl PickupKind {
+    /// How much the pickup adds to the tally of the player collecting it.
+    pub fn value(self) -> u32 {
+        match self {
+            PickupKind::Coin => 1,
+            PickupKind::Star => 3,
+            PickupKind::PowerUp(_) => 0,
+        }
+    }
+
+    fn name(self, locale: &Locale) -> &str {
+        match self {
+            PickupKind::Coin => locale.get("pickup.coin"),
+            PickupKind::Star => locale.get("pickup.star"),
+            PickupKind::PowerUp(power_up) => power_up.name(locale),
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct PickupCollected {
+    pub player_id: u32,
+    pub kind: PickupKind,
+}
+
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    pickup: Pickup,
+) {
+    let (x, y, z) = pickup.position;
+    let position = tile_center(x, y, z) + Vec3::Y * (0.05 + FLOAT_HEIGHT);
+
+    let (mesh, color, emissive) = match pickup.kind {
+        PickupKind::Coin => (
+            meshes.add(
+                shape::Cylinder {
+                    radius: 0.03,
+                    height: 0.006,
+                    resolution: 24,
+                    segments: 1,
+                }
+                .into(),
+            ),
+            Color::GOLD,
+            Color::BLACK,
+        ),
+        PickupKind::Star => (
+            meshes.add(
+                shape::Icosphere {
+                    radius: 0.025,
+                    subdivisions: 0,
+                }
+                .try_into()
+                .unwrap(),
+            ),
+            Color::YELLOW,
+            Color::rgb(0.6, 0.5, 0.0),
+        ),
+        PickupKind::PowerUp(power_up) => (
+            meshes.add(shape::Cube::new(0.035).into()),
+            power_up.color(),
+            power_up.color() * 0.5,
+        ),
+    };
+
+    commands
+        .spawn((
+            LevelEntity,
+            pickup,
+            SpatialBundle::from_transform(Transform::from_translation(position)),
+            Collider::ball(0.04),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+        ))
+        .with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh,
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    emissive,
+                    metallic: 0.8,
+                    perceptual_roughness: 0.3,
+                    ..default()
+                }),
+                // Coins stand on their edge
+                transform: Transform::from_rotation(Quat::from_rotation_x(PI / 2.0)),
+                ..default()
+            });
+        });
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_pickup: Query<&Pickup>,
+    q_ball: Query<&Ball>,
+    mut collected: EventWriter<PickupCollected>,
+    mut game_state: ResMut<GameState>,
+) {
+    let mut taken = Vec::new();
+
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *event else {
+            continue;
+        };
+        let (ball, pickup_entity, pickup) = match (q_ball.get(e1), q_pickup.get(e2)) {
+            (Ok(ball), Ok(pickup)) => (ball, e2, pickup),
+            _ => match (q_ball.get(e2), q_pickup.get(e1)) {
+                (Ok(ball), Ok(pickup)) => (ball, e1, pickup),
+                _ => continue,
+            },
+        };
+        // Two balls may reach the same pickup at once
+        if taken.contains(&pickup_entity) {
+            continue;
+        }
+        taken.push(pickup_entity);
+
+        let player = &mut game_state.players[ball.player_id as usize];
+        match pickup.kind {
+            PickupKind::PowerUp(power_up) => player.power_up = Some(power_up),
+            kind => player.pickups += kind.value(),
+        }
+        collected.send(PickupCollected {
+            player_id: ball.player_id,
+            kind: pickup.kind,
+        });
+        commands.entity(pickup_entity).despawn_recursive();
+    }
+}
+
+fn show_pickup_toasts(
+    mut commands: Commands,
+    mut collected: EventReader<PickupCollected>,
+    locale: Res<Locale>,
+) {
+    for event in collected.iter() {
+        let message = match event.kind {
+            PickupKind::PowerUp(power_up) => locale.format(
+                "pickup.got_power_up",
+                &[
+                    ("player", &(event.player_id + 1)),
+                    ("power_up", &power_up.name(&locale)),
+                ],
+            ),
+            kind => locale.format(
+                "pickup.got",
+                &[
+                    ("player", &(event.player_id + 1)),
+                    ("value", &kind.value()),
+                    ("pickup", &kind.name(&locale)),
+                ],
+            ),
+        };
+        ui::spawn_toast(&mut commands, message, Color::GOLD, 2);
+    }
+}
+
+fn animate_pickups(mut q_pickup: Query<(&Pickup, &mut Transform)>, time: Res<Time>) {
+    let bob = (time.elapsed_seconds() * 2.0).sin() * BOB_HEIGHT;
+    for (pickup, mut transform) in q_pickup.iter_mut() {
+        let (x, y, z) = pickup.position;
+        transform.translation.y = tile_center(x, y, z).y + 0.05 + FLOAT_HEIGHT + bob;
+        transform.rotate_y(SPIN_SPEED * time.delta_seconds());
+    }
+}
@@ -0,0 +1,269 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{self, Ball, BallState, BallStopped, ShootEvent},
+    level::Wall,
+    locale::Locale,
+    scoring::GameState,
+    surface::SurfaceContacts,
+    tuning::ShotTuning,
+    AppState,
+};
+
+/// How much further a super shot can go than a normal one.
+const SUPER_SHOT_POWER_FACTOR: f32 = 1.5;
+/// Part of its speed a sticky ball loses every frame after landing.
+const STICKY_BRAKING: f32 = 0.3;
+
+/// One-shot effects that players pick up on the course and use before a shot.
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UsePowerUp>()
+            .add_systems(Startup, setup_power_up_slot)
+            .add_systems(
+                Update,
+                (
+                    activate_power_ups.before(ball::apply_shot),
+                    mark_power_up_shots.after(ball::apply_shot),
+                    stick_on_landing,
+                    brake_sticky_balls,
+                    end_power_ups,
+                    update_power_up_slot,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUp {
+    /// Allows shooting with more power than usual.
+    SuperShot,
+    /// The ball stops almost right where it lands after a jump or drop, or where it hits a wall.
+    Sticky,
+    /// The ball passes through walls.
+    Ghost,
+}
+
+impl PowerUp {
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            PowerUp::SuperShot => "power_up.super_shot",
+            PowerUp::Sticky => "power_up.sticky",
+            PowerUp::Ghost => "power_up.ghost",
+        })
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUp::SuperShot => Color::ORANGE_RED,
+            PowerUp::Sticky => Color::LIME_GREEN,
+            PowerUp::Ghost => Color::rgb(0.7, 0.7, 1.0),
+        }
+    }
+}
+
+/// Sent when a player wants to use the power-up in their slot on their next shot.
+#[derive(Event)]
+pub struct UsePowerUp {
+    pub player_id: u32,
+}
+
+/// A power-up in use on a ball. It lasts until the ball comes to rest after its next shot.
+#[derive(Component)]
+pub struct ActivePowerUp {
+    pub kind: PowerUp,
+    shot: bool,
+    airborne: bool,
+    landed: bool,
+}
+
+/// The most power a ball can be shot with.
+pub fn max_power(active: Option<&ActivePowerUp>, tuning: &ShotTuning) -> f32 {
+    match active {
+        Some(active) if active.kind == PowerUp::SuperShot => {
+            tuning.max_power * SUPER_SHOT_POWER_FACTOR
+        }
+        _ => tuning.max_power,
+    }
+}
+
+/// Whether the ball currently passes through walls.
+pub fn is_ghost(active: Option<&ActivePowerUp>) -> bool {
+    active.is_some_and(|active| active.kind == PowerUp::Ghost)
+}
+
+#[derive(Component)]
+struct PowerUpSlot;
+
+fn setup_power_up_slot(mut commands: Commands) {
+    commands.spawn((
+        PowerUpSlot,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 22.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        Visibility::Hidden,
+    ));
+}
+
+fn activate_power_ups(
+    mut commands: Commands,
+    mut events: EventReader<UsePowerUp>,
+    q_ball: Query<(Entity, &Ball, &BallState), Without<ActivePowerUp>>,
+    mut game_state: ResMut<GameState>,
+) {
+    for event in events.iter() {
+        let Some((entity, _, _)) = q_ball.iter().find(|(_, ball, state)| {
+            ball.player_id == event.player_id && **state == BallState::Aiming
+        }) else {
+            continue;
+        };
+        let Some(kind) = game_state.players[event.player_id as usize].power_up.take() else {
+            continue;
+        };
+        info!("Player {} used {:?}", event.player_id + 1, kind);
+        commands.entity(entity).insert(ActivePowerUp {
+            kind,
+            shot: false,
+            airborne: false,
+            landed: false,
+        });
+    }
+}
+
+fn mark_power_up_shots(
+    mut shots: EventReader<ShootEvent>,
+    mut q_ball: Query<(&Ball, &mut ActivePowerUp)>,
+) {
+    for shot in shots.iter() {
+        for (ball, mut active) in q_ball.iter_mut() {
+            if ball.player_id == shot.player_id {
+                active.shot = true;
+            }
+        }
+    }
+}
+
+fn stick_on_landing(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut q_ball: Query<(&mut ActivePowerUp, &SurfaceContacts)>,
+    q_wall: Query<(), With<Wall>>,
+) {
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *event else {
+            continue;
+        };
+        for (ball, wall) in [(e1, e2), (e2, e1)] {
+            if !q_wall.contains(wall) {
+                continue;
+            }
+            if let Ok((mut active, _)) = q_ball.get_mut(ball) {
+                if active.kind == PowerUp::Sticky && active.shot {
+                    active.landed = true;
+                }
+            }
+        }
+    }
+
+    for (mut active, contacts) in q_ball.iter_mut() {
+        if active.kind != PowerUp::Sticky || !active.shot || active.landed {
+            continue;
+        }
+        if contacts.is_airborne() {
+            active.airborne = true;
+        } else if active.airborne {
+            active.landed = true;
+        }
+    }
+}
+
+fn brake_sticky_balls(
+    mut q_ball: Query<(
+        &mut ExternalImpulse,
+        &Velocity,
+        &ReadMassProperties,
+        &ActivePowerUp,
+    )>,
+) {
+    for (mut impulse, velocity, mass, active) in q_ball.iter_mut() {
+        if active.landed {
+            impulse.impulse -= velocity.linvel * mass.0.mass * STICKY_BRAKING;
+            impulse.torque_impulse -= velocity.angvel * mass.0.principal_inertia * STICKY_BRAKING;
+        }
+    }
+}
+
+fn end_power_ups(
+    mut commands: Commands,
+    mut stopped: EventReader<BallStopped>,
+    q_active: Query<&ActivePowerUp>,
+) {
+    for event in stopped.iter() {
+        if q_active.get(event.entity).is_ok_and(|active| active.shot) {
+            commands.entity(event.entity).remove::<ActivePowerUp>();
+        }
+    }
+}
+
+fn update_power_up_slot(
+    mut q_slot: Query<(&mut Text, &mut Visibility), With<PowerUpSlot>>,
+    q_active: Query<(&Ball, &ActivePowerUp)>,
+    game_state: Res<GameState>,
+    locale: Res<Locale>,
+) {
+    let Ok((mut text, mut visibility)) = q_slot.get_single_mut() else {
+        return;
+    };
+
+    let mut lines = Vec::new();
+    for (ball, active) in q_active.iter() {
+        lines.push(locale.format(
+            "power_up.active",
+            &[
+                ("player", &(ball.player_id + 1)),
+                ("power_up", &active.kind.name(&locale)),
+            ],
+        ));
+    }
+    for (player_id, player) in game_state.players.iter().enumerate() {
+        if let Some(power_up) = player.power_up {
+            lines.push(locale.format(
+                "power_up.ready",
+                &[
+                    ("player", &(player_id + 1)),
+                    ("power_up", &power_up.name(&locale)),
+                ],
+            ));
+        }
+    }
+
+    let new_visibility = if lines.is_empty() {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    if *visibility != new_visibility {
+        *visibility = new_visibility;
+    }
+    let value = lines.join("\n");
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+
+use crate::{
+    ball::{spawn_ball, Ball, BallState, BallStopped, BallStyle, ShootSettings},
+    level::{tile_center, LaneConfig, LanePart, Level, LevelEntity},
+    lighting::{Atmosphere, TimeOfDay},
+    locale::Locale,
+    scoring::GameState,
+    sky::Sky,
+};
+
+const RANGE_HALF_WIDTH: i32 = 4;
+const RANGE_LENGTH: i32 = 40;
+const MAX_RETIRED_BALLS: usize = 20;
+
+#[derive(Resource, Default)]
+pub struct PracticeStats {
+    shots: u32,
+    last_distance: Option<f32>,
+    longest_distance: f32,
+}
+
+/// A ball that has already been shot on the driving range and is left lying around.
+#[derive(Component)]
+pub struct RetiredBall {
+    index: u32,
+}
+
+#[derive(Component)]
+pub struct PracticeText;
+
+/// An open, flat lane without holes, built from scratch so that nothing of the other courses
+/// ends up on it.
+pub fn driving_range(locale: &Locale) -> Level {
+    let mut lanes = LaneConfig::default();
+    for x in -RANGE_HALF_WIDTH..=RANGE_HALF_WIDTH {
+        for z in -1..RANGE_LENGTH {
+            lanes.0.push(((x, 0, z), LanePart::BasicFloor));
+        }
+    }
+
+    Level {
+        name: locale.get("menu.practice").to_string(),
+        author: String::new(),
+        difficulty: None,
+        description: String::new(),
+        lanes: lanes.with_walls_around(),
+        sky: Sky::ClearDay,
+        time_of_day: TimeOfDay::Noon,
+        props: Vec::new(),
+        par: 0,
+        surfaces: Vec::new(),
+        player_lanes: Vec::new(),
+        holes: Vec::new(),
+        gravity_scale: 1.0,
+        damping_scale: 1.0,
+        restitution_scale: 1.0,
+        gravity_zones: Vec::new(),
+        fan_cycles: Vec::new(),
+        breakables: Vec::new(),
+        pickups: Vec::new(),
+        course_scene: None,
+        terrain: Vec::new(),
+        atmosphere: Atmosphere::default(),
+        tee_zones: Vec::new(),
+        drop_zones: Vec::new(),
+    }
+}
+
+pub fn setup_driving_range(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut stats: ResMut<PracticeStats>,
+) {
+    *stats = PracticeStats::default();
+
+    let mesh = meshes.add(shape::Cube::new(1.0).into());
+    let material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        unlit: true,
+        ..default()
+    });
+
+    // One marker per meter along the range
+    let floor_top = tile_center(0, 0, 0).y + 0.051;
+    let width = (RANGE_HALF_WIDTH * 2 + 1) as f32 * 0.4;
+    let length = RANGE_LENGTH as f32 * 0.4;
+    for meters in 1..=length as u32 {
+        commands.spawn((
+            LevelEntity,
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(0.0, floor_top, meters as f32)
+                    .with_scale(Vec3::new(width - 0.1, 0.002, 0.02)),
+                ..default()
+            },
+        ));
+    }
+
+    commands.spawn((
+        LevelEntity,
+        PracticeText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+/// Measures each shot and hands out a fresh ball at the tee once it has stopped.
+pub fn replace_stopped_balls(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    q_ball: Query<(&Ball, &Transform)>,
+    q_retired: Query<(Entity, &RetiredBall)>,
+    mut stopped: EventReader<BallStopped>,
+    mut stats: ResMut<PracticeStats>,
+    game_state: Res<GameState>,
+) {
+    for event in stopped.iter() {
+        let Ok((ball, transform)) = q_ball.get(event.entity) else {
+            continue;
+        };
+        if ball.hits == 0 {
+            continue;
+        }
+
+        let start = game_state.players[ball.player_id as usize].last_pos;
+        let distance = (transform.translation - start).xz().length();
+        stats.shots += 1;
+        stats.last_distance = Some(distance);
+        stats.longest_distance = stats.longest_distance.max(distance);
+
+        commands
+            .entity(event.entity)
+            .remove::<(Ball, BallState, ShootSettings)>()
+            .insert(RetiredBall { index: stats.shots });
+
+        if q_retired.iter().len() >= MAX_RETIRED_BALLS {
+            if let Some((oldest, _)) = q_retired.iter().min_by_key(|(_, r)| r.index) {
+                commands.entity(oldest).despawn_recursive();
+            }
+        }
+
+        let hue = (stats.shots * 47 % 360) as f32;
+        spawn_ball(
+            &mut commands,
+            &asset_server,
+            ball.player_id,
+            Vec3::new(0.0, 1.0, 0.0),
+            BallStyle { hue, ..default() },
+        );
+    }
+}
+
+pub fn update_practice_text(
+    mut q_text: Query<&mut Text, With<PracticeText>>,
+    stats: Res<PracticeStats>,
+    locale: Res<Locale>,
+) {
+    let Ok(mut text) = q_text.get_single_mut() else {
+        return;
+    };
+    let last = match stats.last_distance {
+        Some(d) => locale.format("practice.distance", &[("distance", &format!("{:.2}", d))]),
+        None => locale.get("practice.no_shot").to_string(),
+    };
+    text.sections[0].value = locale.format(
+        "practice.stats",
+        &[
+            ("shots", &stats.shots),
+            ("last", &last),
+            ("longest", &format!("{:.2}", stats.longest_distance)),
+        ],
+    );
+}
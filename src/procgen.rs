@@ -0,0 +1,156 @@
+use bevy::utils::HashSet;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    level::{Direction, LaneConfig, LanePart, Level},
+    lighting::{Atmosphere, TimeOfDay},
+    sky::Sky,
+    surface::SurfaceType,
+};
+
+/// Distance in tiles between the centers of two neighbouring 3x3 blocks.
+const BLOCK_SIZE: i32 = 3;
+
+pub struct CourseSettings {
+    pub min_blocks: u32,
+    pub max_blocks: u32,
+    /// Probability of turning at each block, if turning is possible.
+    pub turn_chance: f64,
+    /// Probability of placing an obstacle wall in a block.
+    pub hazard_chance: f64,
+    /// Probability of a block having a special surface like ice or rubber.
+    pub surface_chance: f64,
+}
+
+impl Default for CourseSettings {
+    fn default() -> Self {
+        Self {
+            min_blocks: 5,
+            max_blocks: 10,
+            turn_chance: 0.35,
+            hazard_chance: 0.2,
+            surface_chance: 0.15,
+        }
+    }
+}
+
+/// Generates a random course by walking 3x3 blocks of floor from the tee at (0, 0).
+///
+/// The walk never goes backwards and never revisits a block, so the lane never overlaps itself.
+pub fn generate_level(seed: u64, settings: &CourseSettings) -> Level {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let num_blocks = rng.gen_range(settings.min_blocks..=settings.max_blocks);
+    let mut blocks = vec![(0, 0)];
+    let mut visited: HashSet<(i32, i32)> = blocks.iter().copied().collect();
+    let mut dir = Direction::Up;
+    let mut turns = 0;
+
+    while blocks.len() < num_blocks as usize {
+        let (x, y) = *blocks.last().unwrap();
+
+        let free = |d: Direction| !visited.contains(&step(x, y, d));
+        let options: Vec<Direction> = [Direction::Up, Direction::Left, Direction::Right]
+            .into_iter()
+            .filter(|&d| free(d))
+            .collect();
+        if options.is_empty() {
+            break;
+        }
+
+        let keep_going = options.contains(&dir) && !rng.gen_bool(settings.turn_chance);
+        if !keep_going {
+            let new_dir = options[rng.gen_range(0..options.len())];
+            if new_dir != dir {
+                turns += 1;
+            }
+            dir = new_dir;
+        }
+
+        let next = step(x, y, dir);
+        visited.insert(next);
+        blocks.push(next);
+    }
+
+    let mut lanes = LaneConfig::default();
+    let last = blocks.len() - 1;
+    for (i, &(x, y)) in blocks.iter().enumerate() {
+        let center = if i == last {
+            LanePart::HoleFloor
+        } else {
+            LanePart::BasicFloor
+        };
+        lanes = lanes.with_3x3(x, 0, y, LanePart::BasicFloor, center);
+    }
+    lanes = lanes.with_walls_around();
+
+    // Obstacles are added after the outer walls so they don't affect the enclosure.
+    for &(x, y) in blocks.iter().take(last).skip(1) {
+        if rng.gen_bool(settings.hazard_chance) {
+            let dir = match rng.gen_range(0..4) {
+                0 => Direction::Up,
+                1 => Direction::Left,
+                2 => Direction::Down,
+                _ => Direction::Right,
+            };
+            lanes.0.push(((x, 0, y), LanePart::Wall(dir)));
+        }
+    }
+
+    let mut surfaces = Vec::new();
+    for &(x, y) in blocks.iter().take(last).skip(1) {
+        if rng.gen_bool(settings.surface_chance) {
+            let surface = match rng.gen_range(0..3) {
+                0 => SurfaceType::Ice,
+                1 => SurfaceType::Rubber,
+                _ => SurfaceType::Carpet,
+            };
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    surfaces.push(((x + dx, 0, y + dy), surface));
+                }
+            }
+        }
+    }
+
+    Level {
+        name: format!("Random {:04X}", seed & 0xFFFF),
+        author: String::new(),
+        difficulty: None,
+        description: String::new(),
+        lanes,
+        sky: Sky::ClearDay,
+        time_of_day: TimeOfDay::Noon,
+        props: Vec::new(),
+        par: estimate_par(blocks.len() as u32, turns),
+        surfaces,
+        player_lanes: Vec::new(),
+        holes: Vec::new(),
+        gravity_scale: 1.0,
+        damping_scale: 1.0,
+        restitution_scale: 1.0,
+        gravity_zones: Vec::new(),
+        fan_cycles: Vec::new(),
+        breakables: Vec::new(),
+        pickups: Vec::new(),
+        course_scene: None,
+        terrain: Vec::new(),
+        atmosphere: Atmosphere::default(),
+        tee_zones: Vec::new(),
+        drop_zones: Vec::new(),
+    }
+}
+
+/// A rough guess of how many strokes a decent player needs for a lane.
+pub fn estimate_par(num_blocks: u32, turns: u32) -> u32 {
+    2 + num_blocks / 4 + turns / 2
+}
+
+fn step(x: i32, y: i32, dir: Direction) -> (i32, i32) {
+    match dir {
+        Direction::Up => (x, y + BLOCK_SIZE),
+        Direction::Down => (x, y - BLOCK_SIZE),
+        Direction::Left => (x - BLOCK_SIZE, y),
+        Direction::Right => (x + BLOCK_SIZE, y),
+    }
+}
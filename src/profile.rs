@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{Ball, BallShape},
+    cli::CliArgs,
+    locale::Locale,
+    menu::{self, BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    replay::ReplayPlayback,
+    results, save,
+    scoring::{self, BallHoled, GameState},
+    AppState, GameMode,
+};
+
+const SAVE_FILE: &str = "profiles.ron";
+
+/// Lifetime stats of every player, added to at the end of each round and shown on a screen of
+/// their own.
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<Profiles>(SAVE_FILE))
+            .add_systems(OnEnter(AppState::Profiles), setup_profile_screen)
+            .add_systems(OnExit(AppState::Profiles), cleanup_profile_screen)
+            .add_systems(
+                Update,
+                (
+                    record_rounds
+                        .after(scoring::check_ball_in_hole)
+                        .run_if(in_state(AppState::InGame)),
+                    profile_buttons.run_if(in_state(AppState::Profiles)),
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    pub rounds_played: u32,
+    /// Strokes of all rounds together, for the average.
+    pub total_strokes: u32,
+    pub holes_in_one: u32,
+    /// How many rounds were played with each ball shape.
+    pub shape_rounds: Vec<(BallShape, u32)>,
+}
+
+impl Profile {
+    pub fn average_strokes(&self) -> Option<f32> {
+        (self.rounds_played > 0).then(|| self.total_strokes as f32 / self.rounds_played as f32)
+    }
+
+    /// The shape played with the most, the earliest one on ties.
+    pub fn favorite_shape(&self) -> Option<BallShape> {
+        self.shape_rounds
+            .iter()
+            .rev()
+            .max_by_key(|&&(_, rounds)| rounds)
+            .map(|&(shape, _)| shape)
+    }
+
+    fn add_round(&mut self, strokes: u32, holes_in_one: u32, shape: Option<BallShape>) {
+        self.rounds_played += 1;
+        self.total_strokes += strokes;
+        self.holes_in_one += holes_in_one;
+        if let Some(shape) = shape {
+            match self.shape_rounds.iter_mut().find(|(s, _)| *s == shape) {
+                Some((_, rounds)) => *rounds += 1,
+                None => self.shape_rounds.push((shape, 1)),
+            }
+        }
+    }
+}
+
+/// The profile of each player, by player id.
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct Profiles(Vec<Profile>);
+
+impl Profiles {
+    pub fn get(&self, player_id: u32) -> Profile {
+        self.0.get(player_id as usize).cloned().unwrap_or_default()
+    }
+
+    fn get_mut(&mut self, player_id: u32) -> &mut Profile {
+        let index = player_id as usize;
+        if self.0.len() <= index {
+            self.0.resize(index + 1, Profile::default());
+        }
+        &mut self.0[index]
+    }
+}
+
+/// What has happened so far in the round being played.
+#[derive(Default)]
+struct Round {
+    shapes: Vec<(u32, BallShape)>,
+    holes_in_one: Vec<u32>,
+    recorded: bool,
+}
+
+#[derive(Component)]
+struct ProfileRoot;
+
+#[derive(Component, Clone, Copy)]
+enum ProfileButton {
+    Back,
+}
+
+/// Adds each finished round to the profiles. Practice, replays and headless or benchmark runs
+/// are not rounds of the players.
+#[allow(clippy::too_many_arguments)]
+fn record_rounds(
+    mut holed: EventReader<BallHoled>,
+    q_new_ball: Query<(&Ball, &BallShape), Added<Ball>>,
+    mut round: Local<Round>,
+    mut profiles: ResMut<Profiles>,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    playback: Option<Res<ReplayPlayback>>,
+    args: Res<CliArgs>,
+) {
+    for (ball, &shape) in q_new_ball.iter() {
+        // The first ball of a new round
+        if round.recorded {
+            *round = Round::default();
+        }
+        round
+            .shapes
+            .retain(|&(player_id, _)| player_id != ball.player_id);
+        round.shapes.push((ball.player_id, shape));
+    }
+    for event in holed.iter() {
+        if event.strokes == 1 {
+            round.holes_in_one.push(event.player_id);
+        }
+    }
+
+    if round.recorded
+        || !results::all_finished(&game_state)
+        || *game_mode == GameMode::Practice
+        || playback.is_some()
+        || args.headless
+        || args.benchmark.is_some()
+    {
+        return;
+    }
+    round.recorded = true;
+
+    for player_id in 0..game_state.num_players {
+        let strokes = game_state.players[player_id as usize].scores.iter().sum();
+        let holes_in_one = round
+            .holes_in_one
+            .iter()
+            .filter(|&&p| p == player_id)
+            .count() as u32;
+        let shape = round
+            .shapes
+            .iter()
+            .find(|&&(p, _)| p == player_id)
+            .map(|&(_, shape)| shape);
+        profiles
+            .get_mut(player_id)
+            .add_round(strokes, holes_in_one, shape);
+    }
+    save::store(SAVE_FILE, &*profiles);
+}
+
+fn profile_line(player_id: u32, profile: &Profile, locale: &Locale) -> String {
+    let average = profile
+        .average_strokes()
+        .map_or("-".to_string(), |average| format!("{:.1}", average));
+    let shape = profile
+        .favorite_shape()
+        .map_or("-", |shape| shape.name(locale));
+    locale.format(
+        "profile.line",
+        &[
+            ("player", &(player_id + 1)),
+            ("rounds", &profile.rounds_played),
+            ("average", &average),
+            ("holes_in_one", &profile.holes_in_one),
+            ("shape", &shape),
+        ],
+    )
+}
+
+fn setup_profile_screen(
+    mut commands: Commands,
+    profiles: Res<Profiles>,
+    game_state: Res<GameState>,
+    locale: Res<Locale>,
+) {
+    let lines: Vec<String> = (0..game_state.num_players)
+        .map(|player_id| profile_line(player_id, &profiles.get(player_id), &locale))
+        .collect();
+
+    commands
+        .spawn((
+            ProfileRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("profile.title"),
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                lines.join("\n"),
+                TextStyle {
+                    font_size: 26.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            menu::spawn_button(parent, ProfileButton::Back, locale.get("common.back"));
+        });
+}
+
+fn profile_buttons(
+    mut q_button: Query<(&Interaction, &ProfileButton, &mut BackgroundColor), Changed<Interaction>>,
+    keys: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match *button {
+                ProfileButton::Back => next_state.set(AppState::Menu),
+            },
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+fn cleanup_profile_screen(mut commands: Commands, q_root: Query<Entity, With<ProfileRoot>>) {
+    for entity in q_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{Ball, BallState, ShootSettings},
+    level::{FloorTile, Hole, LanePart},
+    AppState,
+};
+
+/// A ball closer than this to a hole, on the tile of the hole, is putting.
+const PUTT_RANGE: f32 = 0.8;
+/// How far below the center of a ball to look for the tile it rests on.
+const GROUND_PROBE: f32 = 0.1;
+/// Length of each dash of the aim line, and of the gaps between them.
+const DASH_LENGTH: f32 = 0.015;
+/// How far the aim line reaches past the hole.
+const LINE_OVERSHOOT: f32 = 0.2;
+const LINE_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+
+/// Makes short putts less fiddly: when a ball rests on the tile of a hole close to it, the power
+/// changes in finer steps and a dotted line shows exactly where it is aimed.
+pub struct PuttingPlugin;
+
+impl Plugin for PuttingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (detect_putts, draw_putt_lines)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// On a ball that is aiming at a hole close by.
+#[derive(Component)]
+pub struct Putting {
+    /// Center of the hole.
+    hole: Vec3,
+}
+
+/// Looks straight down from every aiming ball for the tile it rests on, rather than going by its
+/// contacts, since those include walls and neighbouring tiles.
+fn detect_putts(
+    mut commands: Commands,
+    q_ball: Query<(Entity, &Transform, &BallState, Option<&Putting>), With<Ball>>,
+    q_floor: Query<&FloorTile>,
+    q_hole: Query<&GlobalTransform, With<Hole>>,
+    rapier_context: Res<RapierContext>,
+) {
+    for (entity, transform, state, putting) in q_ball.iter() {
+        let position = transform.translation;
+        let on_hole_tile = *state == BallState::Aiming
+            && rapier_context
+                .cast_ray(
+                    position,
+                    Vec3::NEG_Y,
+                    GROUND_PROBE,
+                    true,
+                    QueryFilter::new()
+                        .exclude_sensors()
+                        .exclude_rigid_body(entity),
+                )
+                .and_then(|(collider, _)| q_floor.get(collider).ok())
+                .is_some_and(|tile| tile.0 == LanePart::HoleFloor);
+
+        let hole = q_hole
+            .iter()
+            .map(|hole| hole.translation())
+            .filter(|_| on_hole_tile)
+            .min_by(|a, b| {
+                a.xz()
+                    .distance_squared(position.xz())
+                    .total_cmp(&b.xz().distance_squared(position.xz()))
+            })
+            .filter(|hole| hole.xz().distance(position.xz()) < PUTT_RANGE);
+
+        match (hole, putting) {
+            (Some(hole), putting) if putting.map_or(true, |p| p.hole != hole) => {
+                commands.entity(entity).insert(Putting { hole });
+            }
+            (None, Some(_)) => {
+                commands.entity(entity).remove::<Putting>();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw_putt_lines(mut gizmos: Gizmos, q_ball: Query<(&Transform, &ShootSettings, &Putting)>) {
+    for (transform, shoot, putting) in q_ball.iter() {
+        // At the height of the ball, since the hole is below the floor
+        let start = transform.translation;
+        let hole = Vec3::new(putting.hole.x, start.y, putting.hole.z);
+        let forward = Quat::from_rotation_y(shoot.angle) * Vec3::X;
+        let length = start.distance(hole) + LINE_OVERSHOOT;
+
+        let mut along = 0.0;
+        while along < length {
+            let end = (along + DASH_LENGTH).min(length);
+            gizmos.line(start + forward * along, start + forward * end, LINE_COLOR);
+            along += DASH_LENGTH * 2.0;
+        }
+
+        // Marks how far the aim passes the hole to either side
+        let closest = start + forward * (hole - start).dot(forward).max(0.0);
+        gizmos.line(closest, hole, LINE_COLOR);
+    }
+}
@@ -0,0 +1,349 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::{BallAction, CommonAction, KeyBindings},
+    locale::Locale,
+    menu::{self, ButtonLabel, BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    ui, AppState,
+};
+
+/// A screen reached from the settings where every action can be bound to another key.
+pub struct RebindPlugin;
+
+impl Plugin for RebindPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Controls), setup_controls_screen)
+            .add_systems(OnExit(AppState::Controls), cleanup_controls_screen)
+            .add_systems(
+                Update,
+                (controls_buttons, capture_key, update_binding_labels)
+                    .chain()
+                    .run_if(in_state(AppState::Controls)),
+            );
+    }
+}
+
+/// Whose keys are shown, and the action waiting for a key press to be bound to, if any.
+#[derive(Resource, Default)]
+struct Rebinding {
+    player: usize,
+    waiting: Option<Binding>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Binding {
+    Ball(BallAction),
+    Common(CommonAction),
+}
+
+impl Binding {
+    fn name(self, locale: &Locale) -> &str {
+        match self {
+            Binding::Ball(action) => action.name(locale),
+            Binding::Common(action) => action.name(locale),
+        }
+    }
+}
+
+#[derive(Component)]
+struct ControlsRoot;
+
+#[derive(Component)]
+struct PlayerLabel;
+
+#[derive(Component, Clone, Copy)]
+enum ControlsButton {
+    Bind(Binding),
+    PreviousPlayer,
+    NextPlayer,
+    Defaults,
+    Back,
+}
+
+fn setup_controls_screen(mut commands: Commands, locale: Res<Locale>) {
+    commands.init_resource::<Rebinding>();
+
+    commands
+        .spawn((
+            ControlsRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("controls.title"),
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            spawn_row(parent, |row| {
+                spawn_binding_button(row, ControlsButton::PreviousPlayer, "<", 40.0);
+                row.spawn((
+                    PlayerLabel,
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 28.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ),
+                ));
+                spawn_binding_button(row, ControlsButton::NextPlayer, ">", 40.0);
+            });
+            // The player's keys on the left and the shared ones on the right, split over as many
+            // columns as it takes to keep them as short as the player's
+            spawn_row(parent, |row| {
+                spawn_column(row, |column| {
+                    for action in BallAction::ALL {
+                        let button = ControlsButton::Bind(Binding::Ball(action));
+                        spawn_binding_button(column, button, "", 320.0);
+                    }
+                });
+                for chunk in CommonAction::ALL.chunks(BallAction::ALL.len()) {
+                    spawn_column(row, |column| {
+                        for &action in chunk {
+                            let button = ControlsButton::Bind(Binding::Common(action));
+                            spawn_binding_button(column, button, "", 320.0);
+                        }
+                    });
+                }
+            });
+            spawn_row(parent, |row| {
+                menu::spawn_button(
+                    row,
+                    ControlsButton::Defaults,
+                    locale.get("controls.defaults"),
+                );
+                menu::spawn_button(row, ControlsButton::Back, locale.get("common.back"));
+            });
+        });
+}
+
+fn spawn_row(parent: &mut ChildBuilder, children: impl FnOnce(&mut ChildBuilder)) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(24.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(children);
+}
+
+fn spawn_column(parent: &mut ChildBuilder, children: impl FnOnce(&mut ChildBuilder)) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_self: AlignSelf::FlexStart,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(children);
+}
+
+/// Smaller than the menu buttons, so that all actions fit on the screen.
+fn spawn_binding_button(
+    parent: &mut ChildBuilder,
+    button: ControlsButton,
+    label: &str,
+    width: f32,
+) {
+    parent
+        .spawn((
+            button,
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(width),
+                    height: Val::Px(34.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ButtonLabel,
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn controls_buttons(
+    mut q_button: Query<
+        (&Interaction, &ControlsButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<KeyBindings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let num_players = bindings.players.len();
+
+    for (interaction, button, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match *button {
+                ControlsButton::Bind(binding) => rebinding.waiting = Some(binding),
+                ControlsButton::PreviousPlayer => {
+                    rebinding.player = (rebinding.player + num_players - 1) % num_players;
+                    rebinding.waiting = None;
+                }
+                ControlsButton::NextPlayer => {
+                    rebinding.player = (rebinding.player + 1) % num_players;
+                    rebinding.waiting = None;
+                }
+                ControlsButton::Defaults => {
+                    *bindings = KeyBindings::default();
+                    bindings.store();
+                    rebinding.waiting = None;
+                }
+                ControlsButton::Back => next_state.set(AppState::Settings),
+            },
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+/// Binds the next key pressed to the waiting action. A key that was already bound to another
+/// action of the same player is swapped with the old key, so that no action is left unbound.
+/// Keys that would clash between the players' keys and the shared ones are refused.
+fn capture_key(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<KeyBindings>,
+    mut next_state: ResMut<NextState<AppState>>,
+    locale: Res<Locale>,
+) {
+    let Some(key) = keys.get_just_pressed().next().copied() else {
+        return;
+    };
+    let Some(binding) = rebinding.waiting.take() else {
+        if key == KeyCode::Escape {
+            next_state.set(AppState::Settings);
+        }
+        return;
+    };
+    if key == KeyCode::Escape {
+        return;
+    }
+
+    let conflict = match binding {
+        Binding::Ball(_) => bindings
+            .ball_conflict(rebinding.player, key)
+            .map(|action| action.name(&locale).to_string()),
+        Binding::Common(action) => bindings
+            .common_conflict(action, key)
+            .map(|(player, action)| {
+                locale.format(
+                    "controls.player_action",
+                    &[("player", &(player + 1)), ("action", &action.name(&locale))],
+                )
+            }),
+    };
+    if let Some(other) = conflict {
+        let message = locale.format(
+            "controls.conflict",
+            &[("key", &format!("{:?}", key)), ("action", &other)],
+        );
+        ui::spawn_toast(&mut commands, message, Color::ORANGE, 0);
+        return;
+    }
+
+    match binding {
+        Binding::Ball(action) => {
+            let player_keys = &mut bindings.players[rebinding.player];
+            let old_key = player_keys.key(action);
+            if let Some(other) = BallAction::ALL
+                .into_iter()
+                .find(|&a| player_keys.key(a) == key)
+            {
+                *player_keys.key_mut(other) = old_key;
+            }
+            *player_keys.key_mut(action) = key;
+        }
+        Binding::Common(action) => {
+            let common_keys = &mut bindings.common;
+            let old_key = common_keys.key(action);
+            if let Some(other) = CommonAction::ALL
+                .into_iter()
+                .find(|&a| common_keys.key(a) == key)
+            {
+                *common_keys.key_mut(other) = old_key;
+            }
+            *common_keys.key_mut(action) = key;
+        }
+    }
+    bindings.store();
+}
+
+fn update_binding_labels(
+    q_button: Query<(&ControlsButton, &Children)>,
+    mut q_label: Query<&mut Text, (With<ButtonLabel>, Without<PlayerLabel>)>,
+    mut q_player_label: Query<&mut Text, With<PlayerLabel>>,
+    rebinding: Res<Rebinding>,
+    bindings: Res<KeyBindings>,
+    locale: Res<Locale>,
+) {
+    if !rebinding.is_changed() && !bindings.is_changed() {
+        return;
+    }
+
+    if let Ok(mut text) = q_player_label.get_single_mut() {
+        text.sections[0].value =
+            locale.format("hud.player", &[("player", &(rebinding.player + 1))]);
+    }
+    for (button, children) in q_button.iter() {
+        let ControlsButton::Bind(binding) = *button else {
+            continue;
+        };
+        let key = match binding {
+            _ if rebinding.waiting == Some(binding) => locale.get("controls.press_key").to_string(),
+            Binding::Ball(action) => format!("{:?}", bindings.get(rebinding.player).key(action)),
+            Binding::Common(action) => format!("{:?}", bindings.common.key(action)),
+        };
+        let mut labels = q_label.iter_many_mut(children.iter());
+        while let Some(mut text) = labels.fetch_next() {
+            text.sections[0].value = locale.format(
+                "controls.binding",
+                &[("action", &binding.name(&locale)), ("key", &key)],
+            );
+        }
+    }
+}
+
+fn cleanup_controls_screen(mut commands: Commands, q_root: Query<Entity, With<ControlsRoot>>) {
+    for entity in q_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Rebinding>();
+}
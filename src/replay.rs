@@ -0,0 +1,292 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{self, Ball, BallSpin, BallState, JumpEvent, ShootEvent},
+    collision::BallCollisionRule,
+    customize::BallStyles,
+    knock_out::KnockOutPenalty,
+    level::{CurrentLevel, Level},
+    powerup::{ActivePowerUp, UsePowerUp},
+    save,
+    scoring::{self, BallHoled, GameState, StrokeAdded, TeamMode, TurnOrder},
+    AppState, GameMode, GameSeed,
+};
+
+const REPLAY_FILE: &str = "last_round.replay.ron";
+
+/// Records every shot of a round so that it can be watched again from the menu.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            start_recording.run_if(not(resource_exists::<ReplayPlayback>())),
+        )
+        .add_systems(OnExit(AppState::InGame), stop_playback)
+        .add_systems(
+            Update,
+            (
+                (
+                    record_shots.after(ball::apply_shot),
+                    record_jumps.after(ball::apply_jumps),
+                    save_recording.after(scoring::check_ball_in_hole),
+                )
+                    .run_if(resource_exists::<Recording>()),
+                play_replay
+                    .before(ball::apply_shot)
+                    .run_if(resource_exists::<ReplayPlayback>()),
+            ),
+        );
+    }
+}
+
+/// Everything needed to play a round again exactly like it went.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub collision_rule: BallCollisionRule,
+    #[serde(default)]
+    pub turn_order: TurnOrder,
+    #[serde(default)]
+    pub team_mode: TeamMode,
+    #[serde(default)]
+    pub knock_out: KnockOutPenalty,
+    pub num_players: u32,
+    pub styles: BallStyles,
+    pub level: Level,
+    pub shots: Vec<RecordedShot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedShot {
+    pub player_id: u32,
+    pub power: f32,
+    pub angle: f32,
+    /// From -1 (full left) to 1 (full right).
+    #[serde(default)]
+    pub spin: f32,
+    /// Where the player put their ball in the tee zone, on their first shot.
+    #[serde(default)]
+    pub tee: Option<[f32; 3]>,
+    /// Whether the player used their power-up on the shot.
+    #[serde(default)]
+    pub power_up: bool,
+    /// The jump made while the ball was rolling after the shot.
+    #[serde(default)]
+    pub jump: Option<RecordedJump>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RecordedJump {
+    /// Seconds from the shot to the jump.
+    pub after: f32,
+    pub charge: f32,
+}
+
+#[derive(Resource)]
+struct Recording {
+    replay: Replay,
+    /// The index of the last shot of each player and when it was taken, to add jumps to.
+    last_shots: HashMap<u32, (usize, f32)>,
+}
+
+/// Takes the shots from a replay instead of from the players.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    shots: Vec<RecordedShot>,
+    next: usize,
+    /// Jumps of shots that have been played, with the time to make them at.
+    pending_jumps: Vec<(u32, f32, f32)>,
+}
+
+/// Reads the replay of the most recently played round, if there is one.
+pub fn load_last_round() -> Option<Replay> {
+    save::try_load(REPLAY_FILE)
+}
+
+/// Sets up the game to watch `replay` once the game starts.
+pub fn start_playback(commands: &mut Commands, replay: Replay) {
+    commands.insert_resource(CurrentLevel(replay.level));
+    commands.insert_resource(GameSeed(replay.seed));
+    commands.insert_resource(replay.mode);
+    commands.insert_resource(replay.collision_rule);
+    commands.insert_resource(replay.turn_order);
+    commands.insert_resource(replay.team_mode);
+    commands.insert_resource(replay.knock_out);
+    commands.insert_resource(GameState::new(replay.num_players));
+    commands.insert_resource(replay.styles);
+    commands.insert_resource(ReplayPlayback {
+        shots: replay.shots,
+        next: 0,
+        pending_jumps: Vec::new(),
+    });
+}
+
+/// Gives the controls back to the players once the replay has been left.
+fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_recording(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    seed: Res<GameSeed>,
+    game_mode: Res<GameMode>,
+    collision_rule: Res<BallCollisionRule>,
+    turn_order: Res<TurnOrder>,
+    team_mode: Res<TeamMode>,
+    knock_out: Res<KnockOutPenalty>,
+    game_state: Res<GameState>,
+    styles: Res<BallStyles>,
+) {
+    commands.insert_resource(Recording {
+        replay: Replay {
+            seed: seed.0,
+            mode: *game_mode,
+            collision_rule: *collision_rule,
+            turn_order: *turn_order,
+            team_mode: *team_mode,
+            knock_out: *knock_out,
+            num_players: game_state.num_players,
+            styles: styles.clone(),
+            level: current_level.0.clone(),
+            shots: Vec::new(),
+        },
+        last_shots: HashMap::default(),
+    });
+}
+
+fn record_shots(
+    mut shots: EventReader<ShootEvent>,
+    mut strokes: EventReader<StrokeAdded>,
+    q_ball: Query<(&Ball, &Transform, Option<&ActivePowerUp>)>,
+    mut recording: ResMut<Recording>,
+    time: Res<Time>,
+) {
+    // Shots that were not applied don't add a stroke
+    let taken: Vec<u32> = strokes
+        .iter()
+        .filter(|stroke| !stroke.penalty)
+        .map(|stroke| stroke.player_id)
+        .collect();
+
+    for shot in shots.iter() {
+        if taken.contains(&shot.player_id) {
+            let first = !recording
+                .replay
+                .shots
+                .iter()
+                .any(|recorded| recorded.player_id == shot.player_id);
+            let ball = q_ball
+                .iter()
+                .find(|(ball, _, _)| ball.player_id == shot.player_id);
+            let tee = ball
+                .filter(|_| first)
+                .map(|(_, transform, _)| transform.translation.into());
+            let power_up = ball.is_some_and(|(_, _, active)| active.is_some());
+
+            let index = recording.replay.shots.len();
+            recording
+                .last_shots
+                .insert(shot.player_id, (index, time.elapsed_seconds()));
+            recording.replay.shots.push(RecordedShot {
+                player_id: shot.player_id,
+                power: shot.power,
+                angle: shot.angle,
+                spin: shot.spin.map_or(0.0, BallSpin::signed),
+                tee,
+                power_up,
+                jump: None,
+            });
+        }
+    }
+}
+
+fn record_jumps(
+    mut jumps: EventReader<JumpEvent>,
+    mut recording: ResMut<Recording>,
+    time: Res<Time>,
+) {
+    for jump in jumps.iter() {
+        let Some(&(index, shot_time)) = recording.last_shots.get(&jump.player_id) else {
+            continue;
+        };
+        recording.replay.shots[index].jump = Some(RecordedJump {
+            after: time.elapsed_seconds() - shot_time,
+            charge: jump.charge,
+        });
+    }
+}
+
+fn save_recording(mut holed: EventReader<BallHoled>, recording: Res<Recording>) {
+    if holed.iter().count() > 0 {
+        save::store(REPLAY_FILE, &recording.replay);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_replay(
+    mut q_ball: Query<(&Ball, &BallState, &mut Transform, Option<&ActivePowerUp>)>,
+    mut playback: ResMut<ReplayPlayback>,
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    time: Res<Time>,
+    mut shots: EventWriter<ShootEvent>,
+    mut jumps: EventWriter<JumpEvent>,
+    mut use_power_ups: EventWriter<UsePowerUp>,
+) {
+    let now = time.elapsed_seconds();
+    playback.pending_jumps.retain(|&(player_id, at, charge)| {
+        if at > now {
+            return true;
+        }
+        jumps.send(JumpEvent { player_id, charge });
+        false
+    });
+
+    let Some(shot) = playback.shots.get(playback.next).cloned() else {
+        return;
+    };
+
+    // Everybody shoots whenever they like in party mode, otherwise it's one shot at a time
+    if *game_mode != GameMode::Party
+        && (game_state.shot_in_progress || shot.player_id != game_state.current_player)
+    {
+        return;
+    }
+    let Some((_, _, mut transform, active)) = q_ball.iter_mut().find(|(ball, state, _, _)| {
+        ball.player_id == shot.player_id && **state == BallState::Aiming
+    }) else {
+        return;
+    };
+    if let Some(tee) = shot.tee {
+        transform.translation = tee.into();
+    }
+    // The power-up is taken into use first and the shot waits until it is active
+    let has_power_up = game_state.players[shot.player_id as usize]
+        .power_up
+        .is_some();
+    if shot.power_up && active.is_none() && has_power_up {
+        use_power_ups.send(UsePowerUp {
+            player_id: shot.player_id,
+        });
+        return;
+    }
+
+    playback.next += 1;
+    if let Some(jump) = shot.jump {
+        playback
+            .pending_jumps
+            .push((shot.player_id, now + jump.after, jump.charge));
+    }
+    shots.send(ShootEvent {
+        player_id: shot.player_id,
+        power: shot.power,
+        angle: shot.angle,
+        spin: BallSpin::from_signed(shot.spin),
+    });
+}
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::{CommonAction, KeyBindings},
+    daily,
+    level::{CurrentLevel, Level},
+    locale::Locale,
+    scoring::{GameState, Handicaps, TEAMS},
+    tournament::Tournament,
+    AppState,
+};
+
+/// The standings shown once every player has finished the course.
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, show_results.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), cleanup_results);
+    }
+}
+
+#[derive(Component)]
+struct ResultsRoot;
+
+/// True once every player has finished the course.
+pub fn all_finished(game_state: &GameState) -> bool {
+    game_state.num_players > 0 && (0..game_state.num_players).all(|p| game_state.is_finished(p))
+}
+
+/// The name of the course and today's date.
+pub fn course_line(level: &Level, locale: &Locale) -> String {
+    let course = if level.name.is_empty() {
+        locale.get("results.unnamed_course")
+    } else {
+        level.name.as_str()
+    };
+    locale.format(
+        "results.course",
+        &[
+            ("course", &course),
+            ("date", &daily::date_label(daily::today())),
+        ],
+    )
+}
+
+/// A line for each player from best to worst, followed by the teams if there are any.
+pub fn result_lines(game_state: &GameState, handicaps: &Handicaps, locale: &Locale) -> Vec<String> {
+    // Players are ranked by net score, keeping the usual order between equal ones
+    let mut ranking = game_state.ranking();
+    ranking.sort_by_key(|&player_id| game_state.net_score(player_id, handicaps));
+
+    let mut lines = Vec::new();
+    for (place, player_id) in ranking.into_iter().enumerate() {
+        let player = &game_state.players[player_id as usize];
+        let team = match player.team {
+            Some(team) => {
+                let name = locale.get(TEAMS[team as usize].0);
+                locale.format("results.team", &[("team", &name)])
+            }
+            None => String::new(),
+        };
+        let handicap = match handicaps.get(player_id) {
+            0 => String::new(),
+            handicap => locale.format(
+                "results.handicap",
+                &[
+                    ("handicap", &handicap),
+                    ("net", &game_state.net_score(player_id, handicaps)),
+                ],
+            ),
+        };
+        lines.push(locale.format(
+            "results.line",
+            &[
+                ("place", &(place + 1)),
+                ("player", &(player_id + 1)),
+                ("team", &team),
+                ("strokes", &player.scores.iter().sum::<u32>()),
+                ("handicap", &handicap),
+                ("coins", &player.pickups),
+            ],
+        ));
+    }
+
+    let teams = game_state.team_standings();
+    if !teams.is_empty() {
+        lines.push(String::new());
+        lines.push(locale.get("results.teams").to_string());
+        for (place, (team, score)) in teams.into_iter().enumerate() {
+            let score = score.map_or("-".to_string(), |score| score.to_string());
+            lines.push(locale.format(
+                "results.team_line",
+                &[
+                    ("place", &(place + 1)),
+                    ("team", &locale.get(TEAMS[team as usize].0)),
+                    ("score", &score),
+                ],
+            ));
+        }
+    }
+    lines
+}
+
+fn show_results(
+    mut commands: Commands,
+    q_root: Query<(), With<ResultsRoot>>,
+    game_state: Res<GameState>,
+    handicaps: Res<Handicaps>,
+    current_level: Res<CurrentLevel>,
+    tournament: Option<Res<Tournament>>,
+    bindings: Res<KeyBindings>,
+    locale: Res<Locale>,
+) {
+    if !q_root.is_empty() || !all_finished(&game_state) {
+        return;
+    }
+
+    let mut lines = result_lines(&game_state, &handicaps, &locale);
+    lines.push(String::new());
+    lines.push(locale.format(
+        "results.paths",
+        &[(
+            "key",
+            &format!("{:?}", bindings.common.key(CommonAction::TogglePaths)),
+        )],
+    ));
+    #[cfg(not(target_arch = "wasm32"))]
+    lines.push(locale.get("results.export").to_string());
+    #[cfg(target_arch = "wasm32")]
+    lines.push(locale.get("results.export_web").to_string());
+    if tournament.is_some() {
+        lines.push(locale.get("results.to_standings").to_string());
+    }
+
+    commands
+        .spawn((
+            ResultsRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locale.get("results.title"),
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                course_line(&current_level.0, &locale),
+                TextStyle {
+                    font_size: 22.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                lines.join("\n"),
+                TextStyle {
+                    font_size: 26.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn cleanup_results(mut commands: Commands, q_root: Query<Entity, With<ResultsRoot>>) {
+    for entity in q_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
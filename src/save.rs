@@ -0,0 +1,64 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Directory that persistent data such as unlocked achievements is stored in.
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIR: &str = "saves";
+
+/// Reads `name` from the save directory, falling back to the default if it is missing or broken.
+///
+/// Nothing is persisted in the browser, so there the default is always returned.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    try_load(name).unwrap_or_default()
+}
+
+/// Reads `name` from the save directory, or returns `None` if it is missing or broken.
+pub fn try_load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = std::path::Path::new(SAVE_DIR).join(name);
+        if let Ok(text) = std::fs::read_to_string(&path) {
+            match ron::from_str(&text) {
+                Ok(value) => return Some(value),
+                Err(e) => bevy::log::warn!("Could not parse {}: {}", path.display(), e),
+            }
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = name;
+
+    None
+}
+
+/// Deletes `name` from the save directory, if it is there.
+pub fn remove(name: &str) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = std::path::Path::new(SAVE_DIR).join(name);
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                bevy::log::warn!("Could not remove {}: {}", path.display(), e);
+            }
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = name;
+}
+
+/// Writes `value` to `name` in the save directory. Errors are logged and otherwise ignored.
+pub fn store<T: Serialize>(name: &str, value: &T) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let dir = std::path::Path::new(SAVE_DIR);
+        let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                std::fs::write(dir.join(name), text).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            bevy::log::warn!("Could not save {}: {}", name, e);
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = (name, value);
+}
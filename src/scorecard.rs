@@ -0,0 +1,149 @@
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+
+#[cfg(target_arch = "wasm32")]
+use crate::clipboard;
+use crate::{
+    daily,
+    level::{CurrentLevel, Level},
+    locale::Locale,
+    results,
+    scoring::{GameState, Handicaps},
+    ui, AppState,
+};
+
+/// Directory that exported scorecards are written to.
+#[cfg(not(target_arch = "wasm32"))]
+const EXPORT_DIR: &str = "scorecards";
+
+const TEXT_KEY: KeyCode = KeyCode::C;
+const PICTURE_KEY: KeyCode = KeyCode::P;
+
+/// Exports the scorecard of a finished round, as text or as a picture of the results screen.
+///
+/// On native the scorecard is written to a file. In the browser the text is copied to the
+/// clipboard and the picture is downloaded.
+pub struct ScorecardPlugin;
+
+impl Plugin for ScorecardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (export_text, export_picture).run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// The whole scorecard as plain text, with the score of every player in every hole.
+fn scorecard_text(
+    game_state: &GameState,
+    handicaps: &Handicaps,
+    level: &Level,
+    locale: &Locale,
+) -> String {
+    let mut lines = vec![
+        locale.get("scorecard.title").to_string(),
+        results::course_line(level, locale),
+        String::new(),
+    ];
+    lines.extend(results::result_lines(game_state, handicaps, locale));
+
+    lines.push(String::new());
+    lines.push(locale.get("scorecard.holes").to_string());
+    for (player_id, player) in game_state.players.iter().enumerate() {
+        for (&score, hole) in player.scores.iter().zip(player.holes.iter()) {
+            let number = level
+                .holes
+                .iter()
+                .position(|h| h.position == hole.position)
+                .unwrap_or(0);
+            let par = level.par_for(hole);
+            lines.push(locale.format(
+                "scorecard.hole",
+                &[
+                    ("player", &(player_id + 1)),
+                    ("hole", &(number + 1)),
+                    ("par", &par),
+                    ("strokes", &score),
+                    ("to_par", &format!("{:+}", score as i32 - par as i32)),
+                ],
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// A path in the export directory that is not taken yet, named after today's date.
+#[cfg(not(target_arch = "wasm32"))]
+fn export_path(extension: &str) -> std::path::PathBuf {
+    let date = daily::date_label(daily::today());
+    let dir = std::path::Path::new(EXPORT_DIR);
+    (1..)
+        .map(|n| dir.join(format!("scorecard-{}-{}.{}", date, n, extension)))
+        .find(|path| !path.exists())
+        .expect("there are endless names to try")
+}
+
+fn export_text(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    handicaps: Res<Handicaps>,
+    current_level: Res<CurrentLevel>,
+    locale: Res<Locale>,
+) {
+    if !keys.just_pressed(TEXT_KEY) || !results::all_finished(&game_state) {
+        return;
+    }
+    let text = scorecard_text(&game_state, &handicaps, &current_level.0, &locale);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let message = {
+        let path = export_path("txt");
+        let result = std::fs::create_dir_all(EXPORT_DIR).and_then(|_| std::fs::write(&path, text));
+        match result {
+            Ok(()) => locale.format("scorecard.saved", &[("path", &path.display())]),
+            Err(e) => {
+                warn!("Could not save the scorecard to {}: {}", path.display(), e);
+                locale.get("scorecard.failed").to_string()
+            }
+        }
+    };
+    #[cfg(target_arch = "wasm32")]
+    let message = {
+        clipboard::copy(&text);
+        locale.get("scorecard.copied").to_string()
+    };
+
+    ui::spawn_toast(&mut commands, message, Color::WHITE, 1);
+}
+
+fn export_picture(
+    keys: Res<Input<KeyCode>>,
+    q_window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    game_state: Res<GameState>,
+) {
+    if !keys.just_pressed(PICTURE_KEY) || !results::all_finished(&game_state) {
+        return;
+    }
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    // The results screen is showing, so a screenshot of the window is a picture of the scorecard.
+    // In the browser the picture is downloaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    let path = {
+        if let Err(e) = std::fs::create_dir_all(EXPORT_DIR) {
+            warn!("Could not create {}: {}", EXPORT_DIR, e);
+            return;
+        }
+        export_path("png")
+    };
+    #[cfg(target_arch = "wasm32")]
+    let path = format!("scorecard-{}.png", daily::date_label(daily::today()));
+
+    if let Err(e) = screenshots.save_screenshot_to_disk(window, path) {
+        warn!("Could not take a picture of the scorecard: {}", e);
+    }
+}
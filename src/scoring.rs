@@ -0,0 +1,354 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{self, Ball, BallState, BallStopped, InHole, RespawnPoint},
+    level::{CurrentLevel, Hole},
+    locale::Locale,
+    powerup::PowerUp,
+    stats::ShotStats,
+    AppState, GameMode,
+};
+
+/// Counts strokes, detects when balls are holed or out of bounds, and passes the turn on.
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TurnOrder>()
+            .init_resource::<TeamMode>()
+            .init_resource::<Handicaps>()
+            .add_event::<StrokeAdded>()
+            .add_systems(OnEnter(AppState::InGame), assign_teams)
+            .add_event::<BallHoled>()
+            .add_systems(
+                Update,
+                (
+                    // Holed balls and respawn points are marked through commands just before
+                    apply_deferred,
+                    check_ball_in_hole,
+                    check_ball_on_ground,
+                    advance_turn_when_ball_stopped.run_if(not(resource_equals(GameMode::Party))),
+                )
+                    .chain()
+                    .after(ball::track_checkpoints),
+            );
+    }
+}
+
+/// Balls resting below this height have fallen out of the course.
+pub const OUT_OF_BOUNDS_Y: f32 = 0.34;
+
+/// Sent when a ball comes to rest inside a hole.
+#[derive(Event)]
+pub struct BallHoled {
+    pub player_id: u32,
+    pub strokes: u32,
+    /// The strokes with the modifier of the hole applied.
+    pub score: u32,
+    pub hole: Hole,
+    pub position: Vec3,
+}
+
+/// Sent whenever a stroke is added to a player's score, including penalties.
+#[derive(Event)]
+pub struct StrokeAdded {
+    pub player_id: u32,
+    pub penalty: bool,
+}
+
+/// Who plays next once a shot has settled.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TurnOrder {
+    /// Everybody takes turns in player order.
+    #[default]
+    Rotation,
+    /// The ball farthest from the hole plays first, like in real golf.
+    FarthestFirst,
+}
+
+impl TurnOrder {
+    pub fn next(self) -> Self {
+        match self {
+            TurnOrder::Rotation => TurnOrder::FarthestFirst,
+            TurnOrder::FarthestFirst => TurnOrder::Rotation,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            TurnOrder::Rotation => "turn_order.rotation",
+            TurnOrder::FarthestFirst => "turn_order.farthest_first",
+        })
+    }
+}
+
+/// Whether everybody plays for themselves or in teams.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TeamMode {
+    #[default]
+    Solo,
+    /// Two teams with alternating players, where the best score of each team counts.
+    Pairs,
+}
+
+impl TeamMode {
+    pub fn next(self) -> Self {
+        match self {
+            TeamMode::Solo => TeamMode::Pairs,
+            TeamMode::Pairs => TeamMode::Solo,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            TeamMode::Solo => "teams.solo",
+            TeamMode::Pairs => "teams.pairs",
+        })
+    }
+
+    pub fn team_of(self, player_id: u32) -> Option<u32> {
+        match self {
+            TeamMode::Solo => None,
+            TeamMode::Pairs => Some(player_id % 2),
+        }
+    }
+}
+
+/// Locale keys of the names of the teams, and their ball hues in degrees.
+pub const TEAMS: [(&str, f32); 2] = [("team.red", 0.0), ("team.blue", 220.0)];
+
+/// The most strokes a player's handicap can take off.
+pub const MAX_HANDICAP: u32 = 36;
+
+/// Strokes taken off each player's total for the round, so that players of different skill can
+/// play against each other.
+#[derive(Resource, Clone, Default)]
+pub struct Handicaps(Vec<u32>);
+
+impl Handicaps {
+    pub fn get(&self, player_id: u32) -> u32 {
+        self.0.get(player_id as usize).copied().unwrap_or(0)
+    }
+
+    pub fn adjust(&mut self, player_id: u32, delta: i32) {
+        let index = player_id as usize;
+        if self.0.len() <= index {
+            self.0.resize(index + 1, 0);
+        }
+        self.0[index] = self.0[index].saturating_add_signed(delta).min(MAX_HANDICAP);
+    }
+}
+
+#[derive(Resource)]
+pub struct GameState {
+    pub num_players: u32,
+    pub current_player: u32,
+    /// Set when the current player shoots and cleared when the turn passes on.
+    pub shot_in_progress: bool,
+    pub players: Vec<PlayerData>,
+}
+
+#[derive(Default)]
+pub struct PlayerData {
+    pub last_pos: Vec3,
+    pub scores: Vec<u32>,
+    /// The hole each score was made in, for levels with more than one.
+    pub holes: Vec<Hole>,
+    /// Value of the coins and stars collected, which breaks ties.
+    pub pickups: u32,
+    /// A power-up that was picked up and is waiting to be used.
+    pub power_up: Option<PowerUp>,
+    pub team: Option<u32>,
+    /// Every shot of the current hole, in order.
+    pub shots: Vec<ShotStats>,
+}
+
+impl GameState {
+    pub fn new(num_players: u32) -> Self {
+        GameState {
+            num_players,
+            current_player: 0,
+            shot_in_progress: false,
+            players: (0..num_players).map(|_| PlayerData::default()).collect(),
+        }
+    }
+
+    pub fn is_finished(&self, player_id: u32) -> bool {
+        !self.players[player_id as usize].scores.is_empty()
+    }
+
+    /// Player ids from best to worst: fewest strokes first, then most pickups. Players that have
+    /// not finished yet come last.
+    pub fn ranking(&self) -> Vec<u32> {
+        let mut ranking: Vec<u32> = (0..self.num_players).collect();
+        ranking.sort_by_key(|&player_id| {
+            let player = &self.players[player_id as usize];
+            (
+                player.scores.is_empty(),
+                player.scores.iter().sum::<u32>(),
+                std::cmp::Reverse(player.pickups),
+            )
+        });
+        ranking
+    }
+
+    /// The total strokes of a player with their handicap taken off, never below zero.
+    pub fn net_score(&self, player_id: u32, handicaps: &Handicaps) -> u32 {
+        let gross: u32 = self.players[player_id as usize].scores.iter().sum();
+        gross.saturating_sub(handicaps.get(player_id))
+    }
+
+    /// Team ids from best to worst, along with the team score. A team scores the best score of
+    /// its players on each hole, and has no score until one of them has finished.
+    pub fn team_standings(&self) -> Vec<(u32, Option<u32>)> {
+        let mut teams: Vec<u32> = self.players.iter().filter_map(|p| p.team).collect();
+        teams.sort();
+        teams.dedup();
+
+        let mut standings: Vec<(u32, Option<u32>)> = teams
+            .into_iter()
+            .map(|team| {
+                let members = || self.players.iter().filter(move |p| p.team == Some(team));
+                let holes = members().map(|p| p.scores.len()).max().unwrap_or(0);
+                let score = (0..holes)
+                    .map(|hole| {
+                        members()
+                            .filter_map(|p| p.scores.get(hole).copied())
+                            .min()
+                            .unwrap_or(0)
+                    })
+                    .sum();
+                (team, (holes > 0).then_some(score))
+            })
+            .collect();
+        standings.sort_by_key(|&(_, score)| (score.is_none(), score));
+        standings
+    }
+
+    /// Passes the turn to the given player.
+    pub fn pass_turn_to(&mut self, player_id: u32) {
+        self.shot_in_progress = false;
+        self.current_player = player_id;
+    }
+
+    /// Passes the turn to the next player that has not finished yet.
+    pub fn advance_turn(&mut self) {
+        self.shot_in_progress = false;
+        for _ in 0..self.num_players {
+            self.current_player = (self.current_player + 1) % self.num_players;
+            if !self.is_finished(self.current_player) {
+                break;
+            }
+        }
+    }
+}
+
+fn assign_teams(mut game_state: ResMut<GameState>, team_mode: Res<TeamMode>) {
+    for (player_id, player) in game_state.players.iter_mut().enumerate() {
+        player.team = team_mode.team_of(player_id as u32);
+    }
+}
+
+pub fn check_ball_in_hole(
+    mut commands: Commands,
+    mut q_ball: Query<(&Ball, &Transform, &mut BallState, &InHole)>,
+    q_hole: Query<&Hole>,
+    mut stopped: EventReader<BallStopped>,
+    mut holed: EventWriter<BallHoled>,
+    mut game_state: ResMut<GameState>,
+) {
+    for event in stopped.iter() {
+        let Ok((ball, transform, mut state, in_hole)) = q_ball.get_mut(event.entity) else {
+            continue;
+        };
+        let Ok(&hole) = q_hole.get(in_hole.0) else {
+            continue;
+        };
+        let score = hole.score(ball.hits);
+        holed.send(BallHoled {
+            player_id: ball.player_id,
+            strokes: ball.hits,
+            score,
+            hole,
+            position: transform.translation,
+        });
+
+        let player = &mut game_state.players[ball.player_id as usize];
+        player.scores.push(score);
+        player.holes.push(hole);
+        debug!(
+            "Player {} finished in {} moves in the hole at {:?}, scoring {}",
+            ball.player_id, ball.hits, hole.position, score
+        );
+
+        *state = BallState::Holed;
+        commands.entity(event.entity).despawn_recursive();
+
+        if game_state.players.iter().all(|p| p.scores.len() == 1) {
+            info!("Level 1 completed!");
+        }
+    }
+}
+
+pub fn check_ball_on_ground(
+    mut q_ball: Query<(&mut Transform, &Ball, &BallState, Option<&RespawnPoint>)>,
+    mut stopped: EventReader<BallStopped>,
+    game_state: Res<GameState>,
+    current_level: Res<CurrentLevel>,
+) {
+    for event in stopped.iter() {
+        let Ok((mut ball_transform, ball, state, respawn)) = q_ball.get_mut(event.entity) else {
+            continue;
+        };
+        if *state != BallState::Holed && ball_transform.translation.y < OUT_OF_BOUNDS_Y {
+            // Drop zones are placed to keep balls from falling out at the same spot again
+            let drop_zone = current_level.0.drop_zone_for(ball_transform.translation);
+            let respawn_pos = match (drop_zone, respawn) {
+                (Some(pos), _) => pos,
+                (None, Some(RespawnPoint(pos))) => *pos,
+                (None, None) => game_state.players[ball.player_id as usize].last_pos,
+            };
+            ball_transform.translation = respawn_pos + Vec3::Y;
+        }
+    }
+}
+
+pub fn advance_turn_when_ball_stopped(
+    mut stopped: EventReader<BallStopped>,
+    mut game_state: ResMut<GameState>,
+    turn_order: Res<TurnOrder>,
+    q_ball: Query<(&Ball, &Transform)>,
+    q_hole: Query<&GlobalTransform, With<Hole>>,
+) {
+    let current_player = game_state.current_player;
+    if !stopped.iter().any(|e| e.player_id == current_player) || !game_state.shot_in_progress {
+        return;
+    }
+
+    let farthest_player = match *turn_order {
+        TurnOrder::Rotation => None,
+        TurnOrder::FarthestFirst => {
+            // Measured to the closest hole, since levels may have several
+            let distance_to_hole = |position: Vec3| {
+                q_hole
+                    .iter()
+                    .map(|hole| hole.translation().distance(position))
+                    .min_by(f32::total_cmp)
+            };
+            q_ball
+                .iter()
+                .filter(|(ball, _)| !game_state.is_finished(ball.player_id))
+                .filter_map(|(ball, transform)| {
+                    Some((ball.player_id, distance_to_hole(transform.translation)?))
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(player_id, _)| player_id)
+        }
+    };
+
+    match farthest_player {
+        Some(player_id) => game_state.pass_turn_to(player_id),
+        None => game_state.advance_turn(),
+    }
+}
@@ -0,0 +1,448 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    accessibility::Accessibility,
+    camera::CameraSettings,
+    display::DisplaySettings,
+    graphics::GraphicsQuality,
+    locale::{Locale, LocalizedText},
+    menu::{self, ButtonLabel, BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    music::AudioSettings,
+    AppState,
+};
+
+/// A screen reached from the menu with the settings that are kept between games.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Settings), setup_settings_screen)
+            .add_systems(OnExit(AppState::Settings), cleanup_settings_screen)
+            .add_systems(
+                Update,
+                (
+                    settings_buttons,
+                    update_settings_labels,
+                    drag_sliders,
+                    update_slider_fills,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Settings)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct SettingsRoot;
+
+#[derive(Component, Clone, Copy)]
+enum SettingsButton {
+    Language,
+    Graphics,
+    DisplayMode,
+    Resolution,
+    Vsync,
+    CameraDistance,
+    Controls,
+    Palette,
+    BallMarkings,
+    UiScale,
+    HighContrast,
+    ReducedMotion,
+    AimSnapping,
+    Back,
+}
+
+/// A bar that sets a value from 0 to 1 where it is clicked or dragged.
+#[derive(Component, Clone, Copy)]
+enum SettingsSlider {
+    MusicVolume,
+    EffectsVolume,
+}
+
+impl SettingsSlider {
+    fn value(self, audio: &AudioSettings) -> f32 {
+        match self {
+            SettingsSlider::MusicVolume => audio.music_volume,
+            SettingsSlider::EffectsVolume => audio.effects_volume,
+        }
+    }
+
+    fn set_value(self, audio: &mut AudioSettings, value: f32) {
+        match self {
+            SettingsSlider::MusicVolume => audio.music_volume = value,
+            SettingsSlider::EffectsVolume => audio.effects_volume = value,
+        }
+    }
+}
+
+#[derive(Component)]
+struct SliderFill;
+
+fn setup_settings_screen(
+    mut commands: Commands,
+    graphics: Res<GraphicsQuality>,
+    display: Res<DisplaySettings>,
+    accessibility: Res<Accessibility>,
+    camera: Res<CameraSettings>,
+    audio: Res<AudioSettings>,
+    locale: Res<Locale>,
+) {
+    let label = |button| {
+        button_label(
+            button,
+            *graphics,
+            &display,
+            &accessibility,
+            &camera,
+            &locale,
+        )
+    };
+
+    commands
+        .spawn((
+            SettingsRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                LocalizedText("settings.title"),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 48.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(32.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    spawn_section(row, "settings.game", |column| {
+                        let mut buttons = vec![SettingsButton::Language, SettingsButton::Graphics];
+                        // The browser decides about the window
+                        if cfg!(not(target_arch = "wasm32")) {
+                            buttons.extend([
+                                SettingsButton::DisplayMode,
+                                SettingsButton::Resolution,
+                                SettingsButton::Vsync,
+                            ]);
+                        }
+                        buttons.extend([SettingsButton::CameraDistance, SettingsButton::Controls]);
+                        for button in buttons {
+                            menu::spawn_button(column, button, &label(button));
+                        }
+                        spawn_slider(
+                            column,
+                            SettingsSlider::MusicVolume,
+                            "settings.music_volume",
+                            &audio,
+                        );
+                        spawn_slider(
+                            column,
+                            SettingsSlider::EffectsVolume,
+                            "settings.effects_volume",
+                            &audio,
+                        );
+                    });
+                    spawn_section(row, "settings.accessibility", |column| {
+                        for button in [
+                            SettingsButton::Palette,
+                            SettingsButton::BallMarkings,
+                            SettingsButton::UiScale,
+                            SettingsButton::HighContrast,
+                            SettingsButton::ReducedMotion,
+                            SettingsButton::AimSnapping,
+                        ] {
+                            menu::spawn_button(column, button, &label(button));
+                        }
+                    });
+                });
+            let back = SettingsButton::Back;
+            menu::spawn_button(parent, back, &label(back));
+        });
+}
+
+/// A column of buttons under a heading.
+fn spawn_section(
+    parent: &mut ChildBuilder,
+    title_key: &'static str,
+    buttons: impl FnOnce(&mut ChildBuilder),
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|column| {
+            column.spawn((
+                LocalizedText(title_key),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 28.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+            buttons(column);
+        });
+}
+
+fn spawn_slider(
+    parent: &mut ChildBuilder,
+    slider: SettingsSlider,
+    label_key: &'static str,
+    audio: &AudioSettings,
+) {
+    parent.spawn((
+        LocalizedText(label_key),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ),
+    ));
+    parent
+        .spawn((
+            slider,
+            // A button, so that it is told when it is pressed
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(260.0),
+                    height: Val::Px(24.0),
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SliderFill,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(slider.value(audio) * 100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: BUTTON_HOVER_COLOR.into(),
+                    ..default()
+                },
+            ));
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn settings_buttons(
+    mut q_button: Query<
+        (&Interaction, &SettingsButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    keys: Res<Input<KeyCode>>,
+    mut graphics: ResMut<GraphicsQuality>,
+    mut display: ResMut<DisplaySettings>,
+    mut accessibility: ResMut<Accessibility>,
+    mut camera: ResMut<CameraSettings>,
+    mut locale: ResMut<Locale>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match *button {
+                SettingsButton::Language => {
+                    let language = locale.language().next();
+                    locale.set_language(language);
+                }
+                SettingsButton::Graphics => *graphics = graphics.next(),
+                SettingsButton::DisplayMode => display.mode = display.mode.next(),
+                SettingsButton::Resolution => display.size = display.next_size(),
+                SettingsButton::Vsync => display.vsync = !display.vsync,
+                SettingsButton::CameraDistance => *camera = camera.next_range(),
+                SettingsButton::Palette => accessibility.palette = accessibility.palette.next(),
+                SettingsButton::BallMarkings => {
+                    accessibility.ball_markings = !accessibility.ball_markings;
+                }
+                SettingsButton::UiScale => accessibility.ui_scale = accessibility.next_ui_scale(),
+                SettingsButton::HighContrast => {
+                    accessibility.high_contrast = !accessibility.high_contrast;
+                }
+                SettingsButton::ReducedMotion => {
+                    accessibility.reduced_motion = !accessibility.reduced_motion;
+                }
+                SettingsButton::AimSnapping => {
+                    accessibility.aim_snapping = !accessibility.aim_snapping;
+                }
+                SettingsButton::Controls => next_state.set(AppState::Controls),
+                SettingsButton::Back => next_state.set(AppState::Menu),
+            },
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+/// Relabels all buttons when a setting or the language changes.
+fn update_settings_labels(
+    q_button: Query<(&SettingsButton, &Children)>,
+    mut q_label: Query<&mut Text, With<ButtonLabel>>,
+    graphics: Res<GraphicsQuality>,
+    display: Res<DisplaySettings>,
+    accessibility: Res<Accessibility>,
+    camera: Res<CameraSettings>,
+    locale: Res<Locale>,
+) {
+    if !graphics.is_changed()
+        && !display.is_changed()
+        && !accessibility.is_changed()
+        && !camera.is_changed()
+        && !locale.is_changed()
+    {
+        return;
+    }
+
+    for (&button, children) in q_button.iter() {
+        let label = button_label(
+            button,
+            *graphics,
+            &display,
+            &accessibility,
+            &camera,
+            &locale,
+        );
+        let mut labels = q_label.iter_many_mut(children.iter());
+        while let Some(mut text) = labels.fetch_next() {
+            if text.sections[0].value != label {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+/// Sets the value of a slider to where the cursor is for as long as it is held down on it.
+fn drag_sliders(
+    q_slider: Query<(&Interaction, &SettingsSlider, &Node, &GlobalTransform)>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut audio: ResMut<AudioSettings>,
+) {
+    let Some(cursor) = q_window
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+
+    for (interaction, &slider, node, transform) in q_slider.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let width = node.size().x;
+        let left = transform.translation().x - width / 2.0;
+        let value = ((cursor.x - left) / width).clamp(0.0, 1.0);
+        if slider.value(&audio) != value {
+            slider.set_value(&mut audio, value);
+        }
+    }
+}
+
+fn update_slider_fills(
+    q_slider: Query<(&SettingsSlider, &Children)>,
+    mut q_fill: Query<&mut Style, With<SliderFill>>,
+    audio: Res<AudioSettings>,
+) {
+    if !audio.is_changed() {
+        return;
+    }
+
+    for (&slider, children) in q_slider.iter() {
+        let mut fills = q_fill.iter_many_mut(children.iter());
+        while let Some(mut style) = fills.fetch_next() {
+            style.width = Val::Percent(slider.value(&audio) * 100.0);
+        }
+    }
+}
+
+fn button_label(
+    button: SettingsButton,
+    graphics: GraphicsQuality,
+    display: &DisplaySettings,
+    accessibility: &Accessibility,
+    camera: &CameraSettings,
+    locale: &Locale,
+) -> String {
+    let (key, value) = match button {
+        // Always in its own language, see `Language::name`
+        SettingsButton::Language => ("settings.language", locale.language().name().to_string()),
+        SettingsButton::Graphics => ("settings.graphics", graphics.name(locale).to_string()),
+        SettingsButton::DisplayMode => ("settings.window", display.mode.name(locale).to_string()),
+        SettingsButton::Resolution => ("settings.resolution", display.size_name()),
+        SettingsButton::Vsync => ("settings.vsync", locale.on_off(display.vsync).to_string()),
+        SettingsButton::CameraDistance => ("settings.camera_distance", camera.range_name()),
+        SettingsButton::Controls => ("settings.controls", String::new()),
+        SettingsButton::Palette => (
+            "settings.palette",
+            accessibility.palette.name(locale).to_string(),
+        ),
+        SettingsButton::BallMarkings => (
+            "settings.ball_markings",
+            locale.on_off(accessibility.ball_markings).to_string(),
+        ),
+        SettingsButton::UiScale => (
+            "settings.ui_scale",
+            format!("{:.0}", accessibility.ui_scale * 100.0),
+        ),
+        SettingsButton::HighContrast => (
+            "settings.high_contrast",
+            locale.on_off(accessibility.high_contrast).to_string(),
+        ),
+        SettingsButton::ReducedMotion => (
+            "settings.reduced_motion",
+            locale.on_off(accessibility.reduced_motion).to_string(),
+        ),
+        SettingsButton::AimSnapping => (
+            "settings.aim_snapping",
+            locale.on_off(accessibility.aim_snapping).to_string(),
+        ),
+        SettingsButton::Back => ("common.back", String::new()),
+    };
+    locale.format(key, &[("value", &value)])
+}
+
+fn cleanup_settings_screen(mut commands: Commands, q_root: Query<Entity, With<SettingsRoot>>) {
+    for entity in q_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::{
+    ball::{self, Ball, BallState, ShootEvent, ShootSettings},
+    flythrough,
+    locale::Locale,
+    online::NetRole,
+    replay::ReplayPlayback,
+    scoring::GameState,
+    tuning::ShotTuning,
+    AppState, GameMode,
+};
+
+/// Limits the players can pick for the shot clock, in seconds.
+const LIMITS: [Option<f32>; 3] = [None, Some(30.0), Some(60.0)];
+
+/// An optional time limit for aiming. When it runs out, the ball is hit straight ahead with
+/// half power, so that nobody can stall the game.
+pub struct ShotClockPlugin;
+
+impl Plugin for ShotClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShotClock>()
+            .add_systems(Startup, setup_shot_clock_text)
+            .add_systems(
+                Update,
+                (
+                    run_shot_clock
+                        .before(ball::apply_shot)
+                        .run_if(in_state(AppState::InGame))
+                        .run_if(not(resource_equals(GameMode::Party)))
+                        .run_if(not(resource_exists::<ReplayPlayback>()))
+                        .run_if(flythrough::not_flying)
+                        // Only one game may take the shot in online games
+                        .run_if(|role: Res<NetRole>| !role.is_client()),
+                    update_shot_clock_text,
+                ),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ShotClock {
+    /// Seconds a player has to shoot, or `None` for no limit.
+    pub limit: Option<f32>,
+    /// Seconds left for the player whose ball is aiming.
+    remaining: Option<f32>,
+}
+
+impl ShotClock {
+    pub fn next_limit(&self) -> Option<f32> {
+        let index = LIMITS.iter().position(|&l| l == self.limit).unwrap_or(0);
+        LIMITS[(index + 1) % LIMITS.len()]
+    }
+
+    pub fn limit_name(&self, locale: &Locale) -> String {
+        match self.limit {
+            Some(seconds) => locale.format("shot_clock.seconds", &[("seconds", &seconds)]),
+            None => locale.on_off(false).to_string(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct ShotClockText;
+
+fn setup_shot_clock_text(mut commands: Commands) {
+    commands.spawn((
+        ShotClockText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 36.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Percent(48.0),
+            ..default()
+        }),
+    ));
+}
+
+fn run_shot_clock(
+    mut clock: ResMut<ShotClock>,
+    q_ball: Query<(&Ball, &BallState, &ShootSettings)>,
+    game_state: Res<GameState>,
+    mut shots: EventWriter<ShootEvent>,
+    tuning: Res<ShotTuning>,
+    time: Res<Time>,
+) {
+    let Some(limit) = clock.limit else {
+        clock.remaining = None;
+        return;
+    };
+
+    let aiming_ball = q_ball.iter().find(|(ball, state, _)| {
+        ball.player_id == game_state.current_player && **state == BallState::Aiming
+    });
+    let Some((ball, _, shoot)) = aiming_ball else {
+        clock.remaining = None;
+        return;
+    };
+
+    // The clock starts over whenever a ball starts aiming
+    let remaining = clock.remaining.unwrap_or(limit) - time.delta_seconds();
+    clock.remaining = Some(remaining);
+
+    if remaining <= 0.0 {
+        info!("Shot clock ran out for player {}", ball.player_id + 1);
+        shots.send(ShootEvent {
+            player_id: ball.player_id,
+            power: tuning.max_power / 2.0,
+            angle: shoot.angle,
+            spin: None,
+        });
+        clock.remaining = None;
+    }
+}
+
+fn update_shot_clock_text(
+    mut q_text: Query<(&mut Text, &mut Visibility), With<ShotClockText>>,
+    clock: Res<ShotClock>,
+) {
+    let Ok((mut text, mut visibility)) = q_text.get_single_mut() else {
+        return;
+    };
+
+    match clock.remaining {
+        Some(remaining) => {
+            text.sections[0].value = format!("{:.0}", remaining.ceil());
+            text.sections[0].style.color = if remaining < 5.0 {
+                Color::RED
+            } else {
+                Color::WHITE
+            };
+            *visibility = Visibility::Inherited;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    ball::{Ball, BallColor},
+    controls::{CommonAction, KeyBindings},
+    ghost::CurrentTrajectories,
+    results,
+    scoring::GameState,
+    AppState,
+};
+
+/// Lifts the lines off the floor, so that they are not hidden in it.
+const LINE_LIFT: f32 = 0.01;
+
+/// Draws the path of every player's ball over the course once the hole is over, so that the
+/// routes can be compared.
+pub struct ShotHistoryPlugin;
+
+impl Plugin for ShotHistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ShowPaths(true))
+            .add_systems(OnEnter(AppState::InGame), forget_ball_colors)
+            .add_systems(
+                Update,
+                (remember_ball_colors, toggle_paths, draw_paths).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// Whether the paths are drawn on the results screen.
+#[derive(Resource)]
+struct ShowPaths(bool);
+
+/// Ball colors by player, kept for after the balls are holed and gone.
+#[derive(Resource, Default)]
+struct BallColors(HashMap<u32, Color>);
+
+fn forget_ball_colors(mut commands: Commands) {
+    commands.insert_resource(BallColors::default());
+}
+
+fn remember_ball_colors(
+    q_ball: Query<(&Ball, &BallColor), Changed<BallColor>>,
+    mut colors: ResMut<BallColors>,
+) {
+    for (ball, color) in q_ball.iter() {
+        colors.0.insert(ball.player_id, color.0);
+    }
+}
+
+fn toggle_paths(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    game_state: Res<GameState>,
+    mut show: ResMut<ShowPaths>,
+) {
+    if bindings
+        .common
+        .just_pressed(&keys, CommonAction::TogglePaths)
+        && results::all_finished(&game_state)
+    {
+        show.0 = !show.0;
+    }
+}
+
+fn draw_paths(
+    mut gizmos: Gizmos,
+    trajectories: Res<CurrentTrajectories>,
+    colors: Res<BallColors>,
+    game_state: Res<GameState>,
+    show: Res<ShowPaths>,
+) {
+    if !show.0 || !results::all_finished(&game_state) {
+        return;
+    }
+
+    for player_id in 0..game_state.num_players {
+        let Some(trajectory) = trajectories.get(player_id) else {
+            continue;
+        };
+        let color = colors.0.get(&player_id).copied().unwrap_or(Color::WHITE);
+        gizmos.linestrip(trajectory.points().map(|p| p + Vec3::Y * LINE_LIFT), color);
+    }
+}
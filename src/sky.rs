@@ -0,0 +1,95 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{
+        Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+const CUBEMAP_SIZE: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Sky {
+    #[default]
+    ClearDay,
+    Overcast,
+    Sunset,
+}
+
+impl Sky {
+    /// Colors at the zenith, at the horizon and below the horizon.
+    fn gradient(self) -> (Color, Color, Color) {
+        match self {
+            Sky::ClearDay => (
+                Color::rgb(0.25, 0.5, 0.95),
+                Color::rgb(0.75, 0.88, 1.0),
+                Color::rgb(0.35, 0.4, 0.35),
+            ),
+            Sky::Overcast => (
+                Color::rgb(0.55, 0.58, 0.62),
+                Color::rgb(0.8, 0.8, 0.82),
+                Color::rgb(0.35, 0.36, 0.36),
+            ),
+            Sky::Sunset => (
+                Color::rgb(0.2, 0.2, 0.5),
+                Color::rgb(1.0, 0.55, 0.3),
+                Color::rgb(0.25, 0.15, 0.15),
+            ),
+        }
+    }
+
+    /// Builds a gradient cubemap usable both as a skybox and as an environment map.
+    pub fn create_cubemap(self) -> Image {
+        let (zenith, horizon, ground) = self.gradient();
+
+        let mut data = Vec::with_capacity((CUBEMAP_SIZE * CUBEMAP_SIZE * 6 * 4) as usize);
+        for face in 0..6 {
+            for py in 0..CUBEMAP_SIZE {
+                for px in 0..CUBEMAP_SIZE {
+                    let u = (px as f32 + 0.5) / CUBEMAP_SIZE as f32 * 2.0 - 1.0;
+                    let v = (py as f32 + 0.5) / CUBEMAP_SIZE as f32 * 2.0 - 1.0;
+
+                    // Face order: +X, -X, +Y, -Y, +Z, -Z
+                    let dir = match face {
+                        0 => Vec3::new(1.0, -v, -u),
+                        1 => Vec3::new(-1.0, -v, u),
+                        2 => Vec3::new(u, 1.0, v),
+                        3 => Vec3::new(u, -1.0, -v),
+                        4 => Vec3::new(u, -v, 1.0),
+                        _ => Vec3::new(-u, -v, -1.0),
+                    }
+                    .normalize();
+
+                    let color = if dir.y >= 0.0 {
+                        lerp_color(horizon, zenith, dir.y.sqrt())
+                    } else {
+                        lerp_color(horizon, ground, (-dir.y * 4.0).min(1.0))
+                    };
+                    data.extend_from_slice(&color.as_rgba_u8());
+                }
+            }
+        }
+
+        let mut image = Image::new(
+            Extent3d {
+                width: CUBEMAP_SIZE,
+                height: CUBEMAP_SIZE,
+                depth_or_array_layers: 6,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
+            ..default()
+        });
+        image
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let a = Vec4::from(a.as_rgba_f32());
+    let b = Vec4::from(b.as_rgba_f32());
+    Color::from(a.lerp(b, t))
+}
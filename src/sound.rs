@@ -0,0 +1,351 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source, Volume},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::Ball,
+    fan::Fan,
+    level::Wall,
+    music::AudioSettings,
+    surface::{SurfaceContacts, SurfaceType},
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Distance between the ears of the listener, for telling left from right.
+const EAR_GAP: f32 = 0.2;
+/// Balls hitting something slower than this are not heard.
+const IMPACT_MIN_SPEED: f32 = 0.3;
+/// Balls hitting something this fast or faster make the loudest sound.
+const IMPACT_MAX_SPEED: f32 = 4.0;
+const FAN_VOLUME: f32 = 0.3;
+/// Balls rolling slower than this are not heard.
+const ROLL_MIN_SPEED: f32 = 0.05;
+/// Balls rolling this fast or faster make the loudest and highest sound.
+const ROLL_MAX_SPEED: f32 = 5.0;
+const ROLL_VOLUME: f32 = 0.5;
+
+/// Sound effects that come from where they happen in the world, heard from the camera.
+///
+/// The sounds are made from noise and tones while playing, so that no sound files are needed.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Synth>().add_systems(
+            Update,
+            (
+                play_impact_sounds,
+                add_fan_hums,
+                set_fan_hum_volumes,
+                add_rolling_sounds,
+                set_rolling_sounds,
+                move_spatial_sounds,
+            ),
+        );
+    }
+}
+
+/// Where sounds are heard from, put on the camera.
+#[derive(Component)]
+pub struct Listener;
+
+#[derive(Clone, Copy, TypeUuid, TypePath)]
+#[uuid = "4f3b7c1e-2a9d-4e6b-8c5f-1d7e9a3b6c20"]
+enum Synth {
+    /// A short knock, higher and longer the harder the hit, with `strength` from 0 to 1.
+    Impact { strength: f32 },
+    /// The endless whir of a fan.
+    FanHum,
+    /// The endless sound of a ball rolling on a surface.
+    Rolling { surface: SurfaceType },
+}
+
+impl Decodable for Synth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthDecoder {
+            synth: *self,
+            sample: 0,
+            noise_state: 0x9E37_79B9,
+            filtered: 0.0,
+        }
+    }
+}
+
+struct SynthDecoder {
+    synth: Synth,
+    sample: u32,
+    noise_state: u32,
+    /// Low-passed noise, for softer sounds than plain noise.
+    filtered: f32,
+}
+
+impl SynthDecoder {
+    fn noise(&mut self) -> f32 {
+        // Xorshift, good enough for sound
+        self.noise_state ^= self.noise_state << 13;
+        self.noise_state ^= self.noise_state >> 17;
+        self.noise_state ^= self.noise_state << 5;
+        self.noise_state as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let t = self.sample as f32 / SAMPLE_RATE as f32;
+        self.sample = self.sample.wrapping_add(1);
+        let noise = self.noise();
+
+        match self.synth {
+            Synth::Impact { strength } => {
+                let length = 0.05 + 0.1 * strength;
+                if t > length {
+                    return None;
+                }
+                let envelope = (-t / length * 6.0).exp();
+                let thump = (t * (120.0 + 80.0 * strength) * TAU).sin();
+                self.filtered += (noise - self.filtered) * 0.3;
+                Some((thump * 0.6 + self.filtered * 0.4) * envelope * strength)
+            }
+            Synth::FanHum => {
+                let hum = (t * 95.0 * TAU).sin() * (1.0 + 0.2 * (t * 3.0 * TAU).sin());
+                self.filtered += (noise - self.filtered) * 0.05;
+                Some(hum * 0.3 + self.filtered * 0.7)
+            }
+            Synth::Rolling { surface } => Some(match surface {
+                SurfaceType::Green => {
+                    self.filtered += (noise - self.filtered) * 0.04;
+                    self.filtered * 2.0
+                }
+                SurfaceType::Ice => {
+                    // A thin hiss, the high part of the noise
+                    self.filtered += (noise - self.filtered) * 0.5;
+                    (noise - self.filtered) * 0.3
+                }
+                SurfaceType::Rubber => {
+                    self.filtered += (noise - self.filtered) * 0.02;
+                    (t * 70.0 * TAU).sin() * 0.3 + self.filtered * 2.0
+                }
+                SurfaceType::Carpet => {
+                    self.filtered += (noise - self.filtered) * 0.01;
+                    self.filtered * 3.0
+                }
+            }),
+        }
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// A looping sound following a fan.
+#[derive(Component)]
+struct FanHum;
+
+/// A looping sound following a ball, heard while the ball rolls on the given surface.
+#[derive(Component)]
+struct RollingSound(SurfaceType);
+
+fn spatial_settings(listener: Transform, position: Vec3) -> SpatialSettings {
+    SpatialSettings::new(listener, EAR_GAP, position)
+}
+
+/// Knocks where balls hit walls or each other.
+fn play_impact_sounds(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_ball: Query<(&Velocity, &GlobalTransform), With<Ball>>,
+    q_solid: Query<(), Or<(With<Wall>, With<Ball>)>>,
+    q_listener: Query<&GlobalTransform, With<Listener>>,
+    mut synths: ResMut<Assets<Synth>>,
+    settings: Res<AudioSettings>,
+) {
+    let Ok(listener) = q_listener.get_single() else {
+        return;
+    };
+
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *event else {
+            continue;
+        };
+        let Some((velocity, transform)) = [(e1, e2), (e2, e1)]
+            .into_iter()
+            .filter(|&(_, other)| q_solid.contains(other))
+            .find_map(|(ball, _)| q_ball.get(ball).ok())
+        else {
+            continue;
+        };
+
+        let speed = velocity.linvel.length();
+        if speed < IMPACT_MIN_SPEED {
+            continue;
+        }
+        let strength =
+            ((speed - IMPACT_MIN_SPEED) / (IMPACT_MAX_SPEED - IMPACT_MIN_SPEED)).clamp(0.1, 1.0);
+        let position = transform.translation();
+        commands.spawn((
+            SpatialBundle::from_transform(Transform::from_translation(position)),
+            SpatialAudioSourceBundle {
+                source: synths.add(Synth::Impact { strength }),
+                settings: PlaybackSettings::DESPAWN
+                    .with_volume(Volume::new_relative(settings.effects_volume)),
+                spatial: spatial_settings(listener.compute_transform(), position),
+            },
+        ));
+    }
+}
+
+fn add_fan_hums(
+    mut commands: Commands,
+    q_fan: Query<(Entity, &GlobalTransform), Added<Fan>>,
+    q_listener: Query<&GlobalTransform, With<Listener>>,
+    mut synths: ResMut<Assets<Synth>>,
+    mut hum: Local<Option<Handle<Synth>>>,
+) {
+    let Ok(listener) = q_listener.get_single() else {
+        return;
+    };
+
+    for (entity, transform) in q_fan.iter() {
+        let hum = hum.get_or_insert_with(|| synths.add(Synth::FanHum));
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                FanHum,
+                SpatialBundle::default(),
+                SpatialAudioSourceBundle {
+                    source: hum.clone(),
+                    // Turned up by `set_fan_hum_volumes` while the fan is on
+                    settings: PlaybackSettings::ONCE.with_volume(Volume::new_relative(0.0)),
+                    spatial: spatial_settings(
+                        listener.compute_transform(),
+                        transform.translation(),
+                    ),
+                },
+            ));
+        });
+    }
+}
+
+fn set_fan_hum_volumes(
+    q_hum: Query<(&Parent, &SpatialAudioSink), With<FanHum>>,
+    q_fan: Query<&Fan>,
+    settings: Res<AudioSettings>,
+) {
+    for (parent, sink) in q_hum.iter() {
+        let on = q_fan.get(parent.get()).map_or(false, |fan| fan.on);
+        let volume = if on {
+            FAN_VOLUME * settings.effects_volume
+        } else {
+            0.0
+        };
+        if sink.volume() != volume {
+            sink.set_volume(volume);
+        }
+    }
+}
+
+fn add_rolling_sounds(
+    mut commands: Commands,
+    q_ball: Query<(Entity, &GlobalTransform), Added<Ball>>,
+    q_listener: Query<&GlobalTransform, With<Listener>>,
+    mut synths: ResMut<Assets<Synth>>,
+    mut sounds: Local<Vec<Handle<Synth>>>,
+) {
+    let Ok(listener) = q_listener.get_single() else {
+        return;
+    };
+    if sounds.is_empty() {
+        for surface in SurfaceType::ALL {
+            sounds.push(synths.add(Synth::Rolling { surface }));
+        }
+    }
+
+    for (entity, transform) in q_ball.iter() {
+        commands.entity(entity).with_children(|parent| {
+            for (surface, sound) in SurfaceType::ALL.into_iter().zip(sounds.iter()) {
+                parent.spawn((
+                    RollingSound(surface),
+                    SpatialBundle::default(),
+                    SpatialAudioSourceBundle {
+                        source: sound.clone(),
+                        // Turned up by `set_rolling_sounds` while the ball rolls on the surface
+                        settings: PlaybackSettings::ONCE.with_volume(Volume::new_relative(0.0)),
+                        spatial: spatial_settings(
+                            listener.compute_transform(),
+                            transform.translation(),
+                        ),
+                    },
+                ));
+            }
+        });
+    }
+}
+
+/// Louder and higher the faster the ball rolls, and quiet while it is in the air or at rest.
+fn set_rolling_sounds(
+    q_sound: Query<(&Parent, &RollingSound, &SpatialAudioSink)>,
+    q_ball: Query<(&Velocity, &SurfaceContacts), With<Ball>>,
+    settings: Res<AudioSettings>,
+) {
+    for (parent, sound, sink) in q_sound.iter() {
+        let Ok((velocity, contacts)) = q_ball.get(parent.get()) else {
+            continue;
+        };
+        let speed = velocity.linvel.length();
+        let rolling =
+            !contacts.is_airborne() && contacts.current() == sound.0 && speed >= ROLL_MIN_SPEED;
+        let amount = (speed / ROLL_MAX_SPEED).min(1.0);
+
+        let volume = if rolling {
+            ROLL_VOLUME * amount * settings.effects_volume
+        } else {
+            0.0
+        };
+        if sink.volume() != volume {
+            sink.set_volume(volume);
+        }
+        if rolling {
+            sink.set_speed(0.7 + 0.8 * amount);
+        }
+    }
+}
+
+/// Keeps every sound where its entity is, as heard from wherever the listener is now.
+fn move_spatial_sounds(
+    q_sound: Query<(&SpatialAudioSink, &GlobalTransform)>,
+    q_listener: Query<&GlobalTransform, With<Listener>>,
+) {
+    let Ok(listener) = q_listener.get_single() else {
+        return;
+    };
+    let listener = listener.compute_transform();
+
+    for (sink, transform) in q_sound.iter() {
+        sink.set_emitter_position(transform.translation());
+        sink.set_listener_position(listener, EAR_GAP);
+    }
+}
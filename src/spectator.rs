@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+
+use crate::{
+    ball::Ball,
+    camera::CameraController,
+    chat,
+    controls::{CommonAction, KeyBindings},
+    director::Director,
+    level::LevelEntity,
+    locale::Locale,
+    online::NetRole,
+    scoring::GameState,
+    AppState,
+};
+
+/// In meters per second.
+const FREE_CAMERA_SPEED: f32 = 1.5;
+
+/// Extras for watching an online game: a scoreboard and a camera that can fly around freely.
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            setup_scoreboard.run_if(resource_equals(NetRole::Spectator)),
+        )
+        .add_systems(
+            Update,
+            (
+                (toggle_free_camera, fly_free_camera).run_if(chat::not_typing),
+                update_scoreboard,
+            )
+                .run_if(resource_equals(NetRole::Spectator)),
+        );
+    }
+}
+
+/// Keeps the camera from following the balls, so it can be flown around with WASD.
+#[derive(Component)]
+pub struct FreeCamera;
+
+#[derive(Component)]
+struct Scoreboard;
+
+fn setup_scoreboard(mut commands: Commands) {
+    commands.spawn((
+        LevelEntity,
+        Scoreboard,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+    ));
+}
+
+fn update_scoreboard(
+    mut q_text: Query<&mut Text, With<Scoreboard>>,
+    q_ball: Query<&Ball>,
+    game_state: Res<GameState>,
+    bindings: Res<KeyBindings>,
+    locale: Res<Locale>,
+) {
+    for mut text in q_text.iter_mut() {
+        let free_camera_key = format!("{:?}", bindings.common.key(CommonAction::FreeCamera));
+        let mut value = locale.format("spectator.title", &[("key", &free_camera_key)]);
+        value.push('\n');
+        for player_id in game_state.ranking() {
+            let player = &game_state.players[player_id as usize];
+            let strokes = match player.scores.last() {
+                Some(score) => locale.format("spectator.holed", &[("score", score)]),
+                None => {
+                    let hits = q_ball
+                        .iter()
+                        .find(|ball| ball.player_id == player_id)
+                        .map_or(0, |ball| ball.hits);
+                    locale.format("spectator.strokes", &[("strokes", &hits)])
+                }
+            };
+            let marker = if player_id == game_state.current_player {
+                ">"
+            } else {
+                " "
+            };
+            value += &locale.format(
+                "spectator.line",
+                &[
+                    ("marker", &marker),
+                    ("player", &(player_id + 1)),
+                    ("strokes", &strokes),
+                    ("coins", &player.pickups),
+                ],
+            );
+            value.push('\n');
+        }
+        text.sections[0].value = value;
+    }
+}
+
+fn toggle_free_camera(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    q_camera: Query<(Entity, Option<&FreeCamera>), With<CameraController>>,
+) {
+    if !bindings
+        .common
+        .just_pressed(&keys, CommonAction::FreeCamera)
+    {
+        return;
+    }
+    for (entity, free) in q_camera.iter() {
+        if free.is_some() {
+            commands.entity(entity).remove::<FreeCamera>();
+        } else {
+            commands
+                .entity(entity)
+                .remove::<Director>()
+                .insert(FreeCamera);
+        }
+    }
+}
+
+fn fly_free_camera(
+    mut q_camera: Query<(&CameraController, &mut Transform), With<FreeCamera>>,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    time: Res<Time>,
+) {
+    for (controller, mut transform) in q_camera.iter_mut() {
+        transform.rotation = controller.rotation();
+
+        let mut direction = Vec3::ZERO;
+        for (action, dir) in [
+            (CommonAction::CameraForward, transform.forward()),
+            (CommonAction::CameraBack, transform.back()),
+            (CommonAction::CameraLeft, transform.left()),
+            (CommonAction::CameraRight, transform.right()),
+            (CommonAction::CameraUp, Vec3::Y),
+            (CommonAction::CameraDown, Vec3::NEG_Y),
+        ] {
+            if bindings.common.pressed(&keys, action) {
+                direction += dir;
+            }
+        }
+        transform.translation +=
+            direction.normalize_or_zero() * FREE_CAMERA_SPEED * time.delta_seconds();
+    }
+}
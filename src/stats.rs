@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{self, Ball, BallSpin, BallStopped, Jump, ShootEvent},
+    level::{LevelEntity, Wall},
+    locale::Locale,
+    scoring::{self, BallHoled, GameState, StrokeAdded},
+    surface::SurfaceContacts,
+    time_control::TimeControl,
+};
+
+/// How long the stats panel stays up after a ball is holed.
+const PANEL_SECONDS: f32 = 8.0;
+
+/// Records what happens during every shot and shows a summary when a ball is holed.
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                start_shot_tracking.after(ball::apply_shot),
+                (track_shots, count_wall_bounces),
+                // Must happen before a holed ball is despawned
+                finish_shot_tracking
+                    .after(ball::track_checkpoints)
+                    .before(scoring::check_ball_in_hole),
+                show_stats_panel.after(scoring::check_ball_in_hole),
+                close_stats_panel,
+            ),
+        );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, export_stats_csv.after(scoring::check_ball_in_hole));
+    }
+}
+
+/// Measurements of a single shot, from hitting the ball until it stops.
+#[derive(Debug, Clone, Default)]
+pub struct ShotStats {
+    pub power: f32,
+    /// In radians.
+    pub angle: f32,
+    /// Length of the path the ball traveled.
+    pub distance: f32,
+    /// Seconds without touching the floor.
+    pub airtime: f32,
+    pub wall_bounces: u32,
+    pub time_to_stop: f32,
+    /// From -1 (full left) to 1 (full right).
+    pub spin: f32,
+    pub jumped: bool,
+}
+
+/// Collects the stats of a ball's shot while it is moving.
+#[derive(Component)]
+struct ShotTracker {
+    stats: ShotStats,
+    last_position: Vec3,
+}
+
+#[derive(Component)]
+struct StatsPanel {
+    timer: Timer,
+}
+
+fn start_shot_tracking(
+    mut commands: Commands,
+    mut shots: EventReader<ShootEvent>,
+    mut strokes: EventReader<StrokeAdded>,
+    q_ball: Query<(Entity, &Ball, &Transform)>,
+) {
+    // Shots that were not applied don't add a stroke
+    let taken: Vec<u32> = strokes
+        .iter()
+        .filter(|stroke| !stroke.penalty)
+        .map(|stroke| stroke.player_id)
+        .collect();
+
+    for shot in shots.iter() {
+        if !taken.contains(&shot.player_id) {
+            continue;
+        }
+        let Some((entity, _, transform)) = q_ball
+            .iter()
+            .find(|(_, ball, _)| ball.player_id == shot.player_id)
+        else {
+            continue;
+        };
+
+        commands.entity(entity).insert(ShotTracker {
+            stats: ShotStats {
+                power: shot.power,
+                angle: shot.angle,
+                spin: shot.spin.map_or(0.0, BallSpin::signed),
+                ..default()
+            },
+            last_position: transform.translation,
+        });
+    }
+}
+
+fn track_shots(
+    mut q_ball: Query<(&Transform, &SurfaceContacts, &Jump, &mut ShotTracker)>,
+    time: Res<Time>,
+    time_control: Res<TimeControl>,
+) {
+    // Measure in simulated time, so slow motion doesn't make shots look longer
+    let dt = time.delta_seconds() * time_control.scale();
+
+    for (transform, contacts, jump, mut tracker) in q_ball.iter_mut() {
+        let position = transform.translation;
+        tracker.stats.distance += position.distance(tracker.last_position);
+        tracker.last_position = position;
+
+        if contacts.is_airborne() {
+            tracker.stats.airtime += dt;
+        }
+        tracker.stats.time_to_stop += dt;
+        tracker.stats.jumped |= jump.used;
+    }
+}
+
+fn count_wall_bounces(
+    mut collision_events: EventReader<CollisionEvent>,
+    q_wall: Query<(), With<Wall>>,
+    mut q_tracker: Query<&mut ShotTracker>,
+) {
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *event else {
+            continue;
+        };
+
+        for (ball, wall) in [(e1, e2), (e2, e1)] {
+            if !q_wall.contains(wall) {
+                continue;
+            }
+            if let Ok(mut tracker) = q_tracker.get_mut(ball) {
+                tracker.stats.wall_bounces += 1;
+            }
+        }
+    }
+}
+
+fn finish_shot_tracking(
+    mut commands: Commands,
+    mut stopped: EventReader<BallStopped>,
+    q_tracker: Query<&ShotTracker>,
+    mut game_state: ResMut<GameState>,
+) {
+    for event in stopped.iter() {
+        let Ok(tracker) = q_tracker.get(event.entity) else {
+            continue;
+        };
+
+        game_state.players[event.player_id as usize]
+            .shots
+            .push(tracker.stats.clone());
+        commands.entity(event.entity).remove::<ShotTracker>();
+    }
+}
+
+fn show_stats_panel(
+    mut commands: Commands,
+    mut holed: EventReader<BallHoled>,
+    q_panel: Query<Entity, With<StatsPanel>>,
+    game_state: Res<GameState>,
+    locale: Res<Locale>,
+) {
+    for event in holed.iter() {
+        for panel in q_panel.iter() {
+            commands.entity(panel).despawn_recursive();
+        }
+
+        let mut text = locale.format("stats.title", &[("player", &(event.player_id + 1))]);
+        text.push('\n');
+        let shots = &game_state.players[event.player_id as usize].shots;
+        for (i, shot) in shots.iter().enumerate() {
+            text += &locale.format(
+                "stats.shot",
+                &[
+                    ("shot", &(i + 1)),
+                    ("power", &format!("{:.1}", shot.power)),
+                    ("angle", &format!("{:.0}", shot.angle.to_degrees())),
+                    ("distance", &format!("{:.2}", shot.distance)),
+                    ("airtime", &format!("{:.1}", shot.airtime)),
+                    ("walls", &shot.wall_bounces),
+                    ("time", &format!("{:.1}", shot.time_to_stop)),
+                ],
+            );
+            text.push('\n');
+        }
+
+        commands.spawn((
+            LevelEntity,
+            StatsPanel {
+                timer: Timer::from_seconds(PANEL_SECONDS, TimerMode::Once),
+            },
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        ));
+    }
+}
+
+fn close_stats_panel(
+    mut commands: Commands,
+    mut q_panel: Query<(Entity, &mut StatsPanel)>,
+    time: Res<Time>,
+) {
+    for (entity, mut panel) in q_panel.iter_mut() {
+        if panel.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Rewrites the CSV file given with `--stats-csv` with every shot so far whenever a ball is holed.
+#[cfg(not(target_arch = "wasm32"))]
+fn export_stats_csv(
+    mut holed: EventReader<BallHoled>,
+    game_state: Res<GameState>,
+    args: Res<crate::cli::CliArgs>,
+) {
+    if holed.iter().count() == 0 {
+        return;
+    }
+    let Some(path) = &args.stats_csv else {
+        return;
+    };
+
+    let mut csv = String::from(
+        "player,shot,power,angle,distance,airtime,wall_bounces,time_to_stop,spin,jumped\n",
+    );
+    for (player_id, player) in game_state.players.iter().enumerate() {
+        for (i, shot) in player.shots.iter().enumerate() {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                player_id + 1,
+                i + 1,
+                shot.power,
+                shot.angle.to_degrees(),
+                shot.distance,
+                shot.airtime,
+                shot.wall_bounces,
+                shot.time_to_stop,
+                shot.spin,
+                shot.jumped,
+            );
+        }
+    }
+
+    if let Err(e) = std::fs::write(path, csv) {
+        warn!("Could not write {}: {}", path.display(), e);
+    }
+}
@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{self, Ball, BallState, RespawnPoint},
+    chat,
+    controls::{CommonAction, KeyBindings},
+    locale::Locale,
+    scoring::{GameState, StrokeAdded},
+    AppState,
+};
+
+/// A ball that moves less than this...
+const STUCK_DISTANCE: f32 = 0.01;
+/// ...in this many seconds while not coming to rest is stuck.
+const STUCK_SECONDS: f32 = 3.0;
+/// Speed of the upwards nudge given to a stuck ball.
+const NUDGE_SPEED: f32 = 1.0;
+
+/// Frees balls that get wedged somewhere or balance on an edge without ever coming to rest.
+/// A stuck ball is first nudged, and if that doesn't help its player may reset it for a
+/// penalty stroke.
+pub struct StuckPlugin;
+
+impl Plugin for StuckPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_stuck_prompt).add_systems(
+            Update,
+            (
+                (
+                    detect_stuck_balls,
+                    reset_stuck_balls.run_if(chat::not_typing),
+                )
+                    .chain()
+                    .after(ball::apply_shot)
+                    .run_if(in_state(AppState::InGame)),
+                update_stuck_prompt,
+            ),
+        );
+    }
+}
+
+/// Keeps track of how long a moving ball has stayed in the same spot.
+#[derive(Component)]
+pub struct StuckDetector {
+    anchor: Vec3,
+    seconds: f32,
+    nudged: bool,
+    /// Set once nudging didn't help.
+    pub stuck: bool,
+}
+
+#[derive(Component)]
+struct StuckPrompt;
+
+fn setup_stuck_prompt(mut commands: Commands) {
+    commands.spawn((
+        StuckPrompt,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 28.0,
+                color: Color::ORANGE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(60.0),
+            left: Val::Percent(35.0),
+            ..default()
+        }),
+        Visibility::Hidden,
+    ));
+}
+
+fn detect_stuck_balls(
+    mut commands: Commands,
+    mut q_ball: Query<
+        (
+            Entity,
+            &Transform,
+            &BallState,
+            &ReadMassProperties,
+            &mut ExternalImpulse,
+            Option<&mut StuckDetector>,
+        ),
+        With<Ball>,
+    >,
+    time: Res<Time>,
+) {
+    for (entity, transform, state, mass, mut impulse, detector) in q_ball.iter_mut() {
+        let position = transform.translation;
+        let Some(mut detector) = detector else {
+            commands.entity(entity).insert(StuckDetector {
+                anchor: position,
+                seconds: 0.0,
+                nudged: false,
+                stuck: false,
+            });
+            continue;
+        };
+
+        if *state != BallState::Moving || position.distance(detector.anchor) > STUCK_DISTANCE {
+            *detector = StuckDetector {
+                anchor: position,
+                seconds: 0.0,
+                nudged: false,
+                stuck: false,
+            };
+            continue;
+        }
+
+        detector.seconds += time.delta_seconds();
+        if detector.seconds < STUCK_SECONDS || detector.stuck {
+            continue;
+        }
+
+        if detector.nudged {
+            detector.stuck = true;
+        } else {
+            info!("Nudging a stuck ball");
+            impulse.impulse += Vec3::Y * NUDGE_SPEED * mass.0.mass;
+            detector.nudged = true;
+            detector.seconds = 0.0;
+        }
+    }
+}
+
+fn reset_stuck_balls(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut q_ball: Query<(
+        &mut Transform,
+        &mut Velocity,
+        &mut Ball,
+        &mut StuckDetector,
+        Option<&RespawnPoint>,
+    )>,
+    game_state: Res<GameState>,
+    mut strokes: EventWriter<StrokeAdded>,
+) {
+    if !bindings
+        .common
+        .just_pressed(&keys, CommonAction::ResetStuck)
+    {
+        return;
+    }
+
+    for (mut transform, mut velocity, mut ball, mut detector, respawn) in q_ball.iter_mut() {
+        if !detector.stuck {
+            continue;
+        }
+
+        let respawn_pos = match respawn {
+            Some(RespawnPoint(pos)) => *pos,
+            None => game_state.players[ball.player_id as usize].last_pos,
+        };
+        transform.translation = respawn_pos + Vec3::Y;
+        *velocity = Velocity::zero();
+        detector.stuck = false;
+        detector.nudged = false;
+        detector.seconds = 0.0;
+
+        ball.hits += 1;
+        strokes.send(StrokeAdded {
+            player_id: ball.player_id,
+            penalty: true,
+        });
+    }
+}
+
+fn update_stuck_prompt(
+    mut q_prompt: Query<(&mut Text, &mut Visibility), With<StuckPrompt>>,
+    q_ball: Query<(&Ball, &StuckDetector)>,
+    bindings: Res<KeyBindings>,
+    locale: Res<Locale>,
+) {
+    let Ok((mut text, mut visibility)) = q_prompt.get_single_mut() else {
+        return;
+    };
+
+    match q_ball.iter().find(|(_, detector)| detector.stuck) {
+        Some((ball, _)) => {
+            text.sections[0].value = locale.format(
+                "stuck.prompt",
+                &[
+                    ("player", &(ball.player_id + 1)),
+                    (
+                        "key",
+                        &format!("{:?}", bindings.common.key(CommonAction::ResetStuck)),
+                    ),
+                ],
+            );
+            *visibility = Visibility::Inherited;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::level::CurrentLevel;
+
+/// The material of a floor tile, which changes how balls roll and bounce on it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SurfaceType {
+    #[default]
+    Green,
+    Ice,
+    Rubber,
+    Carpet,
+}
+
+impl SurfaceType {
+    pub const ALL: [SurfaceType; 4] = [
+        SurfaceType::Green,
+        SurfaceType::Ice,
+        SurfaceType::Rubber,
+        SurfaceType::Carpet,
+    ];
+
+    pub fn friction(self) -> f32 {
+        match self {
+            SurfaceType::Green => 1.0,
+            SurfaceType::Ice => 0.05,
+            SurfaceType::Rubber => 1.0,
+            SurfaceType::Carpet => 1.5,
+        }
+    }
+
+    pub fn restitution(self) -> f32 {
+        match self {
+            SurfaceType::Green => 0.5,
+            SurfaceType::Ice => 0.3,
+            SurfaceType::Rubber => 0.95,
+            SurfaceType::Carpet => 0.2,
+        }
+    }
+
+    /// Linear damping of a ball rolling on the surface.
+    pub fn rolling_damping(self) -> f32 {
+        match self {
+            SurfaceType::Green => 0.6,
+            SurfaceType::Ice => 0.1,
+            SurfaceType::Rubber => 0.6,
+            SurfaceType::Carpet => 1.5,
+        }
+    }
+
+    /// Changes the tile material so surfaces can be told apart. Returns false for the default look.
+    pub fn customize_material(self, material: &mut StandardMaterial) -> bool {
+        match self {
+            SurfaceType::Green => return false,
+            SurfaceType::Ice => {
+                material.base_color = Color::rgb(0.75, 0.9, 1.0);
+                material.perceptual_roughness = 0.05;
+                material.reflectance = 0.8;
+            }
+            SurfaceType::Rubber => {
+                material.base_color = Color::rgb(0.7, 0.2, 0.15);
+                material.perceptual_roughness = 0.9;
+            }
+            SurfaceType::Carpet => {
+                material.base_color = Color::rgb(0.45, 0.1, 0.35);
+                material.perceptual_roughness = 1.0;
+                material.reflectance = 0.1;
+            }
+        }
+        true
+    }
+}
+
+/// The surfaces a ball is currently touching, the most recent contact last.
+#[derive(Component, Default)]
+pub struct SurfaceContacts(Vec<(Entity, SurfaceType)>);
+
+impl SurfaceContacts {
+    pub fn current(&self) -> SurfaceType {
+        self.0.last().map(|&(_, s)| s).unwrap_or_default()
+    }
+
+    /// True when the ball is not touching any floor.
+    pub fn is_airborne(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+pub fn track_ball_surfaces(
+    mut collision_events: EventReader<CollisionEvent>,
+    q_surface: Query<&SurfaceType>,
+    mut q_ball: Query<&mut SurfaceContacts>,
+) {
+    for event in collision_events.iter() {
+        let (e1, e2, started) = match *event {
+            CollisionEvent::Started(e1, e2, _) => (e1, e2, true),
+            CollisionEvent::Stopped(e1, e2, _) => (e1, e2, false),
+        };
+
+        for (ball, tile) in [(e1, e2), (e2, e1)] {
+            let (Ok(mut contacts), Ok(&surface)) = (q_ball.get_mut(ball), q_surface.get(tile))
+            else {
+                continue;
+            };
+            contacts.0.retain(|&(e, _)| e != tile);
+            if started {
+                contacts.0.push((tile, surface));
+            }
+        }
+    }
+}
+
+/// Gives every ball the physics of the surface it is on, scaled by the overrides of the level.
+pub fn apply_surface_physics(
+    mut q_ball: Query<(
+        Ref<SurfaceContacts>,
+        &mut Friction,
+        &mut Restitution,
+        &mut Damping,
+    )>,
+    current_level: Res<CurrentLevel>,
+) {
+    let level = &current_level.0;
+    for (contacts, mut friction, mut restitution, mut damping) in q_ball.iter_mut() {
+        // Also when the level changes, so that the overrides follow the file in dev mode
+        if !contacts.is_changed() && !current_level.is_changed() {
+            continue;
+        }
+        let surface = contacts.current();
+        friction.coefficient = surface.friction();
+        // Anything above 1 would make balls bounce higher every time
+        restitution.coefficient = (surface.restitution() * level.restitution_scale).min(1.0);
+        damping.linear_damping = surface.rolling_damping() * level.damping_scale;
+    }
+}
@@ -0,0 +1,93 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::{
+    ball::{Ball, BallState},
+    camera::CameraController,
+    chat,
+    controls::{CommonAction, KeyBindings},
+    flythrough,
+    level::TeeZone,
+    online::NetRole,
+    replay::ReplayPlayback,
+    scoring::GameState,
+    AppState, GameMode,
+};
+
+/// In meters per second.
+const MOVE_SPEED: f32 = 0.3;
+const ZONE_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.6);
+
+/// Lets the current player move their ball around its tee zone with the tee keys, the arrow keys
+/// by default, until their first shot.
+///
+/// Party play is left out, since the arrow keys belong to the second player there.
+pub struct TeePlugin;
+
+impl Plugin for TeePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            pick_tee_spot
+                .run_if(in_state(AppState::InGame))
+                .run_if(not(resource_equals(GameMode::Party)))
+                .run_if(not(resource_exists::<ReplayPlayback>()))
+                // Balls of clients are placed by the host
+                .run_if(|role: Res<NetRole>| !role.is_client())
+                .run_if(chat::not_typing)
+                .run_if(flythrough::not_flying),
+        );
+    }
+}
+
+fn pick_tee_spot(
+    mut q_ball: Query<(&Ball, &BallState, &TeeZone, &mut Transform)>,
+    q_camera: Query<&Transform, (With<CameraController>, Without<Ball>)>,
+    mut gizmos: Gizmos,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    game_state: Res<GameState>,
+    time: Res<Time>,
+) {
+    // The keys move the ball as seen from the camera
+    let Ok(camera) = q_camera.get_single() else {
+        return;
+    };
+    let forward = Vec3::new(camera.forward().x, 0.0, camera.forward().z).normalize_or_zero();
+    let right = Vec3::new(camera.right().x, 0.0, camera.right().z).normalize_or_zero();
+
+    for (ball, &state, zone, mut transform) in q_ball.iter_mut() {
+        if ball.player_id != game_state.current_player
+            || ball.hits > 0
+            || state != BallState::Aiming
+        {
+            continue;
+        }
+
+        let center = Vec3::from(zone.center);
+        gizmos.rect(
+            Vec3::new(center.x, transform.translation.y, center.z),
+            Quat::from_rotation_x(FRAC_PI_2),
+            Vec2::from(zone.size),
+            ZONE_COLOR,
+        );
+
+        let mut direction = Vec3::ZERO;
+        for (action, dir) in [
+            (CommonAction::TeeForward, forward),
+            (CommonAction::TeeBack, -forward),
+            (CommonAction::TeeLeft, -right),
+            (CommonAction::TeeRight, right),
+        ] {
+            if bindings.common.pressed(&keys, action) {
+                direction += dir;
+            }
+        }
+        if direction != Vec3::ZERO {
+            let position = transform.translation
+                + direction.normalize_or_zero() * MOVE_SPEED * time.delta_seconds();
+            transform.translation = zone.clamp(position);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_resource::TextureFormat,
+    },
+};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    level::{tile_center, CurrentLevel, LevelEntity, FLOOR_TOP},
+    surface::SurfaceType,
+    AppState,
+};
+
+/// Most heights sampled along each side of a heightmap. Larger images are sampled with gaps.
+const MAX_SAMPLES: usize = 129;
+
+/// Rolling terrain shaped by grayscale heightmap images, instead of flat floor tiles.
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), load_heightmaps)
+            .add_systems(
+                Update,
+                spawn_loaded_terrain.run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// A patch of terrain covering a rectangle of tiles.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Terrain {
+    /// Grayscale image in the assets, e.g. `heightmaps/hills.png`. Black is the floor of the
+    /// tiles at `from` and white is `height` above it.
+    pub heightmap: String,
+    /// Opposite corner tiles of the patch, both included. The height level is taken from `from`.
+    pub from: (i32, i32, i32),
+    pub to: (i32, i32, i32),
+    pub height: f32,
+}
+
+impl Terrain {
+    /// The tiles the patch covers, at the height level of `from`.
+    pub fn tiles(&self) -> impl Iterator<Item = (i32, i32, i32)> + '_ {
+        let (x0, x1) = (self.from.0.min(self.to.0), self.from.0.max(self.to.0));
+        let (z0, z1) = (self.from.2.min(self.to.2), self.from.2.max(self.to.2));
+        (x0..=x1).flat_map(move |x| (z0..=z1).map(move |z| (x, self.from.1, z)))
+    }
+
+    /// Center of the lowest point of the patch and its size in world space.
+    fn bounds(&self) -> (Vec3, Vec2) {
+        let a = tile_center(self.from.0, self.from.1, self.from.2);
+        let b = tile_center(self.to.0, self.from.1, self.to.2);
+        let min = a.min(b) - Vec3::new(0.2, 0.0, 0.2);
+        let max = a.max(b) + Vec3::new(0.2, 0.0, 0.2);
+        let center = (min + max) / 2.0 + Vec3::Y * FLOOR_TOP;
+        (center, Vec2::new(max.x - min.x, max.z - min.z))
+    }
+}
+
+/// A terrain patch waiting for its heightmap to load.
+#[derive(Component)]
+struct PendingTerrain {
+    terrain: Terrain,
+    heightmap: Handle<Image>,
+}
+
+fn load_heightmaps(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    current_level: Res<CurrentLevel>,
+) {
+    for terrain in current_level.0.terrain.iter() {
+        commands.spawn((
+            LevelEntity,
+            PendingTerrain {
+                terrain: terrain.clone(),
+                heightmap: asset_server.load(terrain.heightmap.as_str()),
+            },
+        ));
+    }
+}
+
+fn spawn_loaded_terrain(
+    mut commands: Commands,
+    q_pending: Query<(Entity, &PendingTerrain)>,
+    images: Res<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, pending) in q_pending.iter() {
+        let Some(image) = images.get(&pending.heightmap) else {
+            continue;
+        };
+        let (center, size) = pending.terrain.bounds();
+        let heights = Heights::sample(image);
+
+        commands.entity(entity).remove::<PendingTerrain>().insert((
+            PbrBundle {
+                mesh: meshes.add(heights.mesh(size, pending.terrain.height)),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::rgb(0.3, 0.6, 0.25),
+                    perceptual_roughness: 0.9,
+                    ..default()
+                }),
+                transform: Transform::from_translation(center),
+                ..default()
+            },
+            Collider::heightfield(
+                heights.values.clone(),
+                heights.rows,
+                heights.columns,
+                Vec3::new(size.x, pending.terrain.height, size.y),
+            ),
+            Friction::new(1.0),
+            SurfaceType::default(),
+            ActiveEvents::COLLISION_EVENTS,
+        ));
+    }
+}
+
+/// Heights from 0 to 1 in the column-major order of a Rapier heightfield, where rows go along z
+/// and columns along x.
+struct Heights {
+    values: Vec<f32>,
+    rows: usize,
+    columns: usize,
+}
+
+impl Heights {
+    fn sample(image: &Image) -> Self {
+        let width = image.texture_descriptor.size.width as usize;
+        let height = image.texture_descriptor.size.height as usize;
+        let columns = width.clamp(2, MAX_SAMPLES);
+        let rows = height.clamp(2, MAX_SAMPLES);
+
+        let mut values = Vec::with_capacity(rows * columns);
+        for column in 0..columns {
+            for row in 0..rows {
+                let x = column * (width - 1) / (columns - 1);
+                let y = row * (height - 1) / (rows - 1);
+                values.push(pixel_value(image, x + y * width));
+            }
+        }
+        Heights {
+            values,
+            rows,
+            columns,
+        }
+    }
+
+    fn get(&self, row: usize, column: usize) -> f32 {
+        self.values[row + column * self.rows]
+    }
+
+    /// A mesh with the same shape as the heightfield collider of the given size.
+    fn mesh(&self, size: Vec2, height: f32) -> Mesh {
+        let position = |row: usize, column: usize| {
+            Vec3::new(
+                (column as f32 / (self.columns - 1) as f32 - 0.5) * size.x,
+                self.get(row, column) * height,
+                (row as f32 / (self.rows - 1) as f32 - 0.5) * size.y,
+            )
+        };
+
+        let mut positions = Vec::with_capacity(self.rows * self.columns);
+        let mut normals = Vec::with_capacity(self.rows * self.columns);
+        let mut uvs = Vec::with_capacity(self.rows * self.columns);
+        for column in 0..self.columns {
+            for row in 0..self.rows {
+                let left = position(row, column.saturating_sub(1));
+                let right = position(row, (column + 1).min(self.columns - 1));
+                let back = position(row.saturating_sub(1), column);
+                let front = position((row + 1).min(self.rows - 1), column);
+                let normal = (front - back).cross(right - left).normalize_or_zero();
+
+                positions.push(position(row, column).to_array());
+                normals.push(normal.to_array());
+                uvs.push([
+                    column as f32 / (self.columns - 1) as f32,
+                    row as f32 / (self.rows - 1) as f32,
+                ]);
+            }
+        }
+
+        let index = |row: usize, column: usize| (row + column * self.rows) as u32;
+        let mut indices = Vec::new();
+        for column in 0..self.columns - 1 {
+            for row in 0..self.rows - 1 {
+                let (a, b) = (index(row, column), index(row, column + 1));
+                let (c, d) = (index(row + 1, column), index(row + 1, column + 1));
+                indices.extend([a, c, b, b, c, d]);
+            }
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+}
+
+/// Brightness of a pixel from 0 to 1, from the first channel of the image.
+fn pixel_value(image: &Image, pixel: usize) -> f32 {
+    match image.texture_descriptor.format {
+        TextureFormat::R16Uint | TextureFormat::R16Unorm => {
+            let bytes = [image.data[pixel * 2], image.data[pixel * 2 + 1]];
+            u16::from_le_bytes(bytes) as f32 / u16::MAX as f32
+        }
+        format => {
+            let bytes_per_pixel = format.block_size(None).unwrap_or(4) as usize;
+            image.data[pixel * bytes_per_pixel] as f32 / u8::MAX as f32
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    f32::consts::PI,
+    io::Cursor,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_asset::RenderAssets,
+        render_resource::*,
+        renderer::{RenderDevice, RenderQueue},
+        Extract, Render, RenderApp, RenderSet,
+    },
+};
+use image::{DynamicImage, ImageOutputFormat, RgbaImage};
+
+use crate::{
+    level::{tile_center, CurrentLevel},
+    library,
+};
+
+/// Size of the saved pictures, twice the size they are shown at in the library.
+const PICTURE_WIDTH: u32 = 192;
+const PICTURE_HEIGHT: u32 = 128;
+/// Frames to wait after spawning the camera, so that its picture has been drawn.
+const SETTLE_FRAMES: u32 = 2;
+
+/// Takes a picture of the lane from above for the course library.
+///
+/// The picture is drawn by its own camera into an image, which is then copied back from the
+/// GPU, so the view on screen and the UI are left alone.
+pub struct ThumbnailPlugin;
+
+impl Plugin for ThumbnailPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TakeThumbnail>()
+            .add_systems(Update, (start_capture, capture_thumbnail).chain());
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<PendingReadbacks>()
+            .add_systems(ExtractSchedule, extract_readback)
+            .add_systems(
+                Render,
+                read_thumbnail
+                    .after(RenderSet::Render)
+                    .before(RenderSet::Cleanup),
+            );
+    }
+}
+
+/// Asks for a picture of the current level, saved as the thumbnail of the course with this id.
+#[derive(Event)]
+pub struct TakeThumbnail(pub String);
+
+/// A picture being taken by a camera of its own.
+#[derive(Resource)]
+pub struct Capture {
+    id: String,
+    camera: Entity,
+    image: Handle<Image>,
+    frames: u32,
+}
+
+/// Run condition for systems that draw on top of the level, which would end up in the picture.
+pub fn not_capturing(capture: Option<Res<Capture>>) -> bool {
+    capture.is_none()
+}
+
+/// Tells the render world to copy the picture back on this frame.
+#[derive(Resource, Clone)]
+struct Readback {
+    id: String,
+    image: Handle<Image>,
+}
+
+fn start_capture(
+    mut commands: Commands,
+    mut requests: EventReader<TakeThumbnail>,
+    mut images: ResMut<Assets<Image>>,
+    capture: Option<Res<Capture>>,
+    current_level: Res<CurrentLevel>,
+) {
+    let Some(TakeThumbnail(id)) = requests.iter().last() else {
+        return;
+    };
+    if capture.is_some() {
+        return;
+    }
+
+    let tiles = &current_level.0.lanes.0;
+    if tiles.is_empty() {
+        return;
+    }
+    let (min, max) = tiles.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), &((x, y, z), _)| {
+            let center = tile_center(x, y, z);
+            (min.min(center), max.max(center))
+        },
+    );
+    let center = (min + max) / 2.0;
+    let size = max - min + Vec3::splat(0.4);
+
+    // Look straight down, with the long side of the lane across the picture
+    let up = if size.z > size.x {
+        Vec3::X
+    } else {
+        Vec3::NEG_Z
+    };
+    let half_extent = size.x.max(size.z) / 2.0;
+    let height = half_extent / (PI / 8.0).tan() + 0.5;
+    let transform = Transform::from_translation(Vec3::new(center.x, max.y + height, center.z))
+        .looking_at(center, up);
+
+    let extent = Extent3d {
+        width: PICTURE_WIDTH,
+        height: PICTURE_HEIGHT,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("thumbnail"),
+            size: extent,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(extent);
+    let image = images.add(image);
+
+    let camera = commands
+        .spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    target: RenderTarget::Image(image.clone()),
+                    // Drawn before the view on screen
+                    order: -1,
+                    ..default()
+                },
+                transform,
+                ..default()
+            },
+            UiCameraConfig { show_ui: false },
+        ))
+        .id();
+    commands.insert_resource(Capture {
+        id: id.clone(),
+        camera,
+        image,
+        frames: 0,
+    });
+}
+
+fn capture_thumbnail(mut commands: Commands, capture: Option<ResMut<Capture>>) {
+    let Some(mut capture) = capture else {
+        return;
+    };
+    capture.frames += 1;
+
+    if capture.frames == SETTLE_FRAMES {
+        commands.insert_resource(Readback {
+            id: capture.id.clone(),
+            image: capture.image.clone(),
+        });
+    } else if capture.frames > SETTLE_FRAMES {
+        // The copy of the picture was started while the last frame was drawn
+        commands.remove_resource::<Readback>();
+        commands.entity(capture.camera).despawn_recursive();
+        commands.remove_resource::<Capture>();
+    }
+}
+
+fn extract_readback(mut commands: Commands, readback: Extract<Option<Res<Readback>>>) {
+    if let Some(readback) = &*readback {
+        commands.insert_resource(Readback::clone(readback));
+    }
+}
+
+/// Pictures being copied back from the GPU, in the render world.
+#[derive(Resource, Default)]
+struct PendingReadbacks(Vec<PendingReadback>);
+
+struct PendingReadback {
+    id: String,
+    buffer: Buffer,
+    /// Set once the buffer can be read, to whether mapping it worked.
+    mapped: Arc<Mutex<Option<bool>>>,
+}
+
+/// Copies the finished picture from the GPU, and saves pictures whose copies have arrived.
+fn read_thumbnail(
+    mut commands: Commands,
+    readback: Option<Res<Readback>>,
+    mut pending: ResMut<PendingReadbacks>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    // Rows in the buffer have to start at multiples of 256 bytes
+    let row_bytes = PICTURE_WIDTH as usize * 4;
+    let padded_row_bytes = RenderDevice::align_copy_bytes_per_row(row_bytes);
+
+    pending.0.retain(|readback| {
+        let Some(mapped) = *readback.mapped.lock().unwrap() else {
+            return true;
+        };
+        let id = &readback.id;
+        if !mapped {
+            warn!("Could not copy the thumbnail of {} from the GPU", id);
+            return false;
+        }
+        let pixels: Vec<u8> = readback
+            .buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks(padded_row_bytes)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect();
+        readback.buffer.unmap();
+        match encode_thumbnail(pixels).and_then(|png| library::save_thumbnail(id, &png)) {
+            Ok(()) => info!("Saved the thumbnail of {}", id),
+            Err(e) => warn!("Could not save the thumbnail of {}: {}", id, e),
+        }
+        false
+    });
+
+    let Some(readback) = readback else {
+        return;
+    };
+    commands.remove_resource::<Readback>();
+    let Some(gpu_image) = images.get(&readback.image) else {
+        warn!("The picture of the course was not drawn");
+        return;
+    };
+
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("thumbnail_buffer"),
+        size: (padded_row_bytes * PICTURE_HEIGHT as usize) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        gpu_image.texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes as u32),
+                rows_per_image: None,
+            },
+        },
+        Extent3d {
+            width: PICTURE_WIDTH,
+            height: PICTURE_HEIGHT,
+            depth_or_array_layers: 1,
+        },
+    );
+    render_queue.submit([encoder.finish()]);
+
+    // The map finishes on a later frame, when the queue is submitted again
+    let mapped = Arc::new(Mutex::new(None));
+    let on_mapped = mapped.clone();
+    render_device.map_buffer(&buffer.slice(..), MapMode::Read, move |result| {
+        *on_mapped.lock().unwrap() = Some(result.is_ok());
+    });
+    pending.0.push(PendingReadback {
+        id: readback.id.clone(),
+        buffer,
+        mapped,
+    });
+}
+
+/// Encodes the RGBA pixels of a picture as PNG.
+fn encode_thumbnail(pixels: Vec<u8>) -> Result<Vec<u8>, String> {
+    let image = RgbaImage::from_raw(PICTURE_WIDTH, PICTURE_HEIGHT, pixels)
+        .ok_or_else(|| "The picture has the wrong size".to_string())?;
+    let thumbnail = DynamicImage::ImageRgba8(image).to_rgb8();
+    let mut png = Vec::new();
+    thumbnail
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
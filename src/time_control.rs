@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ball::{Ball, BallState},
+    level::Hole,
+    scoring::GameState,
+};
+
+const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+const SLOW_MOTION_SCALE: f32 = 0.3;
+const FAST_FORWARD_SCALE: f32 = 3.0;
+
+/// Balls closer than this to a hole are slowed down for dramatic effect.
+const DRAMATIC_DISTANCE: f32 = 0.3;
+
+/// How fast game time runs compared to real time.
+#[derive(Resource)]
+pub struct TimeControl {
+    pub base_scale: f32,
+    /// Set by effects like the hole-in-one celebration, wins over fast-forwarding.
+    pub slow_motion: bool,
+    /// Set when a ball is about to drop into a hole.
+    pub dramatic_shot: bool,
+    pub fast_forward: bool,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            base_scale: 1.0,
+            slow_motion: false,
+            dramatic_shot: false,
+            fast_forward: false,
+        }
+    }
+}
+
+impl TimeControl {
+    pub fn scale(&self) -> f32 {
+        if self.slow_motion || self.dramatic_shot {
+            self.base_scale * SLOW_MOTION_SCALE
+        } else if self.fast_forward {
+            self.base_scale * FAST_FORWARD_SCALE
+        } else {
+            self.base_scale
+        }
+    }
+}
+
+/// Holding F fast-forwards while waiting for balls to stop, i.e. when nobody can aim.
+pub fn fast_forward_input(
+    keys: Res<Input<KeyCode>>,
+    q_ball: Query<&BallState>,
+    mut time_control: ResMut<TimeControl>,
+) {
+    let waiting = !q_ball.iter().any(|state| *state == BallState::Aiming);
+    let fast_forward = waiting && keys.pressed(KeyCode::F);
+    if time_control.fast_forward != fast_forward {
+        time_control.fast_forward = fast_forward;
+    }
+}
+
+pub fn detect_dramatic_shots(
+    q_ball: Query<(&Transform, &Velocity, &Ball, &BallState)>,
+    q_hole: Query<&GlobalTransform, With<Hole>>,
+    mut time_control: ResMut<TimeControl>,
+    game_state: Res<GameState>,
+) {
+    let dramatic = q_ball.iter().any(|(transform, velocity, ball, state)| {
+        ball.player_id == game_state.current_player
+            && *state == BallState::Moving
+            && velocity.linvel.length() > 0.1
+            && q_hole
+                .iter()
+                .any(|hole| hole.translation().distance(transform.translation) < DRAMATIC_DISTANCE)
+    });
+    if time_control.dramatic_shot != dramatic {
+        time_control.dramatic_shot = dramatic;
+    }
+}
+
+pub fn sync_time_scale(
+    time_control: Res<TimeControl>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut fixed_time: ResMut<FixedTime>,
+) {
+    if !time_control.is_changed() {
+        return;
+    }
+    let scale = time_control.scale();
+
+    if let TimestepMode::Variable {
+        time_scale,
+        substeps,
+        ..
+    } = &mut rapier_config.timestep_mode
+    {
+        *time_scale = scale;
+        // Keep the size of each physics step the same when speeding up
+        *substeps = scale.ceil().max(1.0) as usize;
+    }
+    fixed_time.period = std::time::Duration::from_secs_f32(FIXED_TIMESTEP / scale);
+}
@@ -0,0 +1,288 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    level::{CurrentLevel, Level, LevelEntity, Levels},
+    locale::Locale,
+    menu::{BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    procgen, save,
+    scoring::{self, BallHoled, GameState, Handicaps},
+    AppState, GameMode, GameSeed,
+};
+
+const SAVE_FILE: &str = "tournament.ron";
+
+/// Random courses played after the first level.
+const NUM_RANDOM_COURSES: usize = 3;
+
+/// Several courses played one after another, with the strokes of all of them adding up.
+pub struct TournamentPlugin;
+
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Standings), setup_standings_screen)
+            .add_systems(OnExit(AppState::Standings), cleanup_standings_screen)
+            .add_systems(
+                Update,
+                (
+                    tournament_buttons.run_if(in_state(AppState::Menu)),
+                    (
+                        record_course.after(scoring::check_ball_in_hole),
+                        show_standings_when_done,
+                    )
+                        .run_if(in_state(AppState::InGame))
+                        .run_if(resource_exists::<Tournament>()),
+                    continue_tournament.run_if(in_state(AppState::Standings)),
+                ),
+            );
+    }
+}
+
+/// The tournament being played. It is saved after every course so that it can be resumed later.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Tournament {
+    pub courses: Vec<Level>,
+    pub num_players: u32,
+    /// How many courses have been played to the end.
+    pub played: usize,
+    /// Net strokes of each player over the courses played so far.
+    pub totals: Vec<u32>,
+    /// Value of the coins and stars of each player, which breaks ties.
+    pub pickups: Vec<u32>,
+}
+
+impl Tournament {
+    /// The first level followed by random courses from the seed.
+    fn new(levels: &Levels, seed: &GameSeed, num_players: u32) -> Self {
+        let mut rng = seed.rng(GameSeed::COURSE_STREAM);
+        let mut courses = vec![levels.level1.clone()];
+        for _ in 0..NUM_RANDOM_COURSES {
+            courses.push(procgen::generate_level(
+                rng.gen(),
+                &procgen::CourseSettings::default(),
+            ));
+        }
+        Tournament {
+            courses,
+            num_players,
+            played: 0,
+            totals: vec![0; num_players as usize],
+            pickups: vec![0; num_players as usize],
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.played >= self.courses.len()
+    }
+
+    /// Player ids from best to worst.
+    pub fn ranking(&self) -> Vec<u32> {
+        let mut ranking: Vec<u32> = (0..self.num_players).collect();
+        ranking.sort_by_key(|&player_id| {
+            (
+                self.totals[player_id as usize],
+                std::cmp::Reverse(self.pickups[player_id as usize]),
+            )
+        });
+        ranking
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum TournamentButton {
+    New,
+    Resume,
+}
+
+#[derive(Component)]
+struct StandingsRoot;
+
+/// Sets up the game for the next course of `tournament`, clearing away whatever is left of the
+/// previous one.
+fn start_course(
+    commands: &mut Commands,
+    q_level: &Query<Entity, With<LevelEntity>>,
+    tournament: Tournament,
+    next_state: &mut NextState<AppState>,
+) {
+    for entity in q_level.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.insert_resource(CurrentLevel(tournament.courses[tournament.played].clone()));
+    commands.insert_resource(GameState::new(tournament.num_players));
+    commands.insert_resource(GameMode::Standard);
+    commands.insert_resource(tournament);
+    next_state.set(AppState::InGame);
+}
+
+fn tournament_buttons(
+    mut commands: Commands,
+    mut q_button: Query<
+        (&Interaction, &TournamentButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    q_level: Query<Entity, With<LevelEntity>>,
+    levels: Res<Levels>,
+    seed: Res<GameSeed>,
+    game_state: Res<GameState>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button, mut background) in q_button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match button {
+                TournamentButton::New => {
+                    let tournament = Tournament::new(&levels, &seed, game_state.num_players);
+                    save::store(SAVE_FILE, &tournament);
+                    start_course(&mut commands, &q_level, tournament, &mut next_state);
+                }
+                TournamentButton::Resume => match save::try_load::<Tournament>(SAVE_FILE) {
+                    Some(tournament) if !tournament.is_over() => {
+                        start_course(&mut commands, &q_level, tournament, &mut next_state);
+                    }
+                    _ => info!("There is no tournament to resume"),
+                },
+            },
+            Interaction::Hovered => *background = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *background = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+/// Adds the strokes of the course to the totals once everybody has finished it.
+fn record_course(
+    mut holed: EventReader<BallHoled>,
+    mut tournament: ResMut<Tournament>,
+    game_state: Res<GameState>,
+    handicaps: Res<Handicaps>,
+) {
+    if holed.is_empty() {
+        return;
+    }
+    holed.clear();
+    if !(0..game_state.num_players).all(|p| game_state.is_finished(p)) {
+        return;
+    }
+
+    for player_id in 0..tournament.num_players {
+        let index = player_id as usize;
+        tournament.totals[index] += game_state.net_score(player_id, &handicaps);
+        tournament.pickups[index] += game_state.players[index].pickups;
+    }
+    tournament.played += 1;
+    save::store(SAVE_FILE, &*tournament);
+}
+
+fn show_standings_when_done(
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let done = (0..game_state.num_players).all(|p| game_state.is_finished(p));
+    if done && keys.just_pressed(KeyCode::Return) {
+        next_state.set(AppState::Standings);
+    }
+}
+
+fn setup_standings_screen(
+    mut commands: Commands,
+    tournament: Res<Tournament>,
+    locale: Res<Locale>,
+) {
+    let title = if tournament.is_over() {
+        locale.get("standings.final").to_string()
+    } else {
+        locale.format(
+            "standings.after",
+            &[
+                ("played", &tournament.played),
+                ("total", &tournament.courses.len()),
+            ],
+        )
+    };
+
+    let mut lines = Vec::new();
+    for (place, player_id) in tournament.ranking().into_iter().enumerate() {
+        lines.push(locale.format(
+            "standings.line",
+            &[
+                ("place", &(place + 1)),
+                ("player", &(player_id + 1)),
+                ("strokes", &tournament.totals[player_id as usize]),
+                ("coins", &tournament.pickups[player_id as usize]),
+            ],
+        ));
+    }
+    lines.push(String::new());
+    lines.push(
+        locale
+            .get(if tournament.is_over() {
+                "standings.to_menu"
+            } else {
+                "standings.next_course"
+            })
+            .to_string(),
+    );
+
+    commands
+        .spawn((
+            StandingsRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                lines.join("\n"),
+                TextStyle {
+                    font_size: 26.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn continue_tournament(
+    mut commands: Commands,
+    q_level: Query<Entity, With<LevelEntity>>,
+    keys: Res<Input<KeyCode>>,
+    tournament: Res<Tournament>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keys.just_pressed(KeyCode::Return) {
+        return;
+    }
+    if tournament.is_over() {
+        commands.remove_resource::<Tournament>();
+        save::remove(SAVE_FILE);
+        next_state.set(AppState::Menu);
+    } else {
+        start_course(&mut commands, &q_level, tournament.clone(), &mut next_state);
+    }
+}
+
+fn cleanup_standings_screen(mut commands: Commands, q_root: Query<Entity, With<StandingsRoot>>) {
+    for entity in q_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
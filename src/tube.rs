@@ -0,0 +1,132 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::level::Direction;
+
+/// Radius of tunnels and half-pipes, so that they are exactly one tile wide.
+pub const TUBE_RADIUS: f32 = 0.2;
+
+const TILE_LENGTH: f32 = 0.4;
+const SEGMENTS: u32 = 24;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum TubeShape {
+    /// A closed tube the ball rolls through.
+    Tunnel,
+    /// The lower half of a tube, open at the top.
+    HalfPipe,
+}
+
+impl TubeShape {
+    /// The range of angles covered by the profile, measured from the bottom of the tube.
+    fn arc(self) -> (f32, f32) {
+        match self {
+            TubeShape::Tunnel => (-PI, PI),
+            TubeShape::HalfPipe => (-PI / 2.0, PI / 2.0),
+        }
+    }
+
+    /// Points of the profile in the xy-plane, together with their inward facing normals.
+    fn profile(self) -> Vec<(Vec2, Vec2)> {
+        let (start, end) = self.arc();
+        (0..=SEGMENTS)
+            .map(|i| {
+                let angle = start + (end - start) * i as f32 / SEGMENTS as f32;
+                let (sin, cos) = angle.sin_cos();
+                (Vec2::new(sin, -cos) * TUBE_RADIUS, Vec2::new(-sin, cos))
+            })
+            .collect()
+    }
+
+    /// Vertices, normals and triangles of the profile extruded along the z-axis.
+    fn geometry(self) -> (Vec<Vec3>, Vec<Vec3>, Vec<[u32; 3]>) {
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        for (point, normal) in self.profile() {
+            for z in [-TILE_LENGTH / 2.0, TILE_LENGTH / 2.0] {
+                positions.push(point.extend(z));
+                normals.push(normal.extend(0.0));
+            }
+        }
+
+        let mut triangles = Vec::new();
+        for i in 0..SEGMENTS {
+            let a = i * 2;
+            let b = a + 1;
+            let c = a + 2;
+            let d = a + 3;
+            triangles.push([a, c, b]);
+            triangles.push([b, c, d]);
+        }
+
+        (positions, normals, triangles)
+    }
+
+    pub fn mesh(self) -> Mesh {
+        let (positions, normals, triangles) = self.geometry();
+        let uvs: Vec<[f32; 2]> = (0..positions.len())
+            .map(|i| [(i / 2) as f32 / SEGMENTS as f32, (i % 2) as f32])
+            .collect();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(
+            triangles.into_iter().flatten().collect(),
+        )));
+        mesh
+    }
+
+    pub fn collider(self) -> Collider {
+        let (positions, _, triangles) = self.geometry();
+        Collider::trimesh(positions, triangles)
+    }
+
+    /// Tunnels are see-through so the ball can be followed from outside.
+    pub fn material(self) -> StandardMaterial {
+        let mut material = StandardMaterial {
+            base_color: Color::rgb(0.55, 0.6, 0.65),
+            perceptual_roughness: 0.4,
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        };
+        if self == TubeShape::Tunnel {
+            material.base_color.set_a(0.45);
+            material.alpha_mode = AlphaMode::Blend;
+        }
+        material
+    }
+}
+
+/// Placement of a tube on a tile whose floor is centered at `tile_center`.
+///
+/// Tubes along `Up`/`Down` run along the z-axis, and along `Left`/`Right` along the x-axis.
+pub fn tube_transform(tile_center: Vec3, dir: Direction) -> Transform {
+    let rotation = match dir {
+        Direction::Up | Direction::Down => Quat::IDENTITY,
+        Direction::Left | Direction::Right => Quat::from_rotation_y(PI / 2.0),
+    };
+    Transform::from_translation(tile_center + Vec3::Y * (0.05 + TUBE_RADIUS))
+        .with_rotation(rotation)
+}
+
+/// A dim light inside a tunnel so the ball stays visible while it rolls through.
+pub fn interior_light() -> PointLightBundle {
+    PointLightBundle {
+        point_light: PointLight {
+            color: Color::rgb(1.0, 0.9, 0.7),
+            intensity: 2.0,
+            range: TUBE_RADIUS * 3.0,
+            shadows_enabled: false,
+            ..default()
+        },
+        ..default()
+    }
+}
@@ -0,0 +1,120 @@
+use std::{path::Path, time::SystemTime};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::hot_reload;
+
+/// Where the tuning is read from in dev mode. Otherwise the copy built into the game is used.
+const TUNING_PATH: &str = "assets/tuning.ron";
+/// How often the tuning file is checked for changes in dev mode.
+const POLL_SECONDS: f32 = 0.5;
+
+/// Loads the numbers that decide how shots feel, and reloads them when their file is saved in
+/// dev mode.
+pub struct TuningPlugin {
+    pub hot_reload: bool,
+}
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        if !self.hot_reload {
+            app.insert_resource(ShotTuning::parse(include_str!("../assets/tuning.ron")));
+            return;
+        }
+
+        let path = Path::new(TUNING_PATH);
+        let tuning = std::fs::read_to_string(path)
+            .map(|text| ShotTuning::parse(&text))
+            .unwrap_or_else(|e| {
+                warn!("Could not read {}: {}", path.display(), e);
+                ShotTuning::default()
+            });
+        app.insert_resource(tuning)
+            .insert_resource(WatchedTuning {
+                modified: hot_reload::modified_time(path),
+                timer: Timer::from_seconds(POLL_SECONDS, TimerMode::Repeating),
+            })
+            .add_systems(Update, reload_changed_tuning);
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShotTuning {
+    /// Strongest regular shot, as the speed given to the ball.
+    pub max_power: f32,
+    /// How the power picked when aiming turns into shot speed. 1 is linear, and above 1 gives
+    /// finer control over soft shots.
+    pub power_curve: f32,
+    /// Turning impulse per unit of mass of a shot with full spin.
+    pub spin_torque: f32,
+    /// Upwards impulse per unit of mass of a jump without any charge.
+    pub min_jump_impulse: f32,
+    /// Upwards impulse per unit of mass of a fully charged jump.
+    pub max_jump_impulse: f32,
+}
+
+impl Default for ShotTuning {
+    fn default() -> Self {
+        Self {
+            max_power: 10.0,
+            power_curve: 1.0,
+            spin_torque: 1.0,
+            min_jump_impulse: 3.0,
+            max_jump_impulse: 8.0,
+        }
+    }
+}
+
+impl ShotTuning {
+    fn parse(text: &str) -> Self {
+        ron::from_str(text).unwrap_or_else(|e| {
+            warn!("Could not parse the shot tuning: {}", e);
+            ShotTuning::default()
+        })
+    }
+
+    /// Speed given to the ball for the power picked when aiming.
+    pub fn shot_speed(&self, power: f32) -> f32 {
+        self.max_power * (power.max(0.0) / self.max_power).powf(self.power_curve)
+    }
+
+    /// Upwards impulse per unit of mass for a jump charged from 0 to 1.
+    pub fn jump_impulse(&self, charge: f32) -> f32 {
+        self.min_jump_impulse + (self.max_jump_impulse - self.min_jump_impulse) * charge
+    }
+}
+
+#[derive(Resource)]
+struct WatchedTuning {
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+fn reload_changed_tuning(
+    mut watched: ResMut<WatchedTuning>,
+    mut tuning: ResMut<ShotTuning>,
+    time: Res<Time>,
+) {
+    if !watched.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let path = Path::new(TUNING_PATH);
+    let modified = hot_reload::modified_time(path);
+    if modified == watched.modified {
+        return;
+    }
+    watched.modified = modified;
+
+    // Editors often save in several steps, so a broken file is only reported
+    let reloaded = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| ron::from_str::<ShotTuning>(&text).map_err(|e| e.to_string()));
+    match reloaded {
+        Ok(reloaded) => {
+            info!("Reloaded {}", path.display());
+            *tuning = reloaded;
+        }
+        Err(e) => warn!("Could not load {}: {}", path.display(), e),
+    }
+}
@@ -0,0 +1,348 @@
+use bevy::prelude::*;
+
+use crate::{
+    ball::{Ball, BallColor, BallSpin, BallState, Jump, ShootSettings},
+    camera::CameraController,
+    level::{CurrentLevel, LevelEntity},
+    locale::Locale,
+    putting::Putting,
+    scoring::{GameState, StrokeAdded},
+};
+
+/// How far above the ball its label is shown.
+const LABEL_HEIGHT: f32 = 0.06;
+/// Labels are invisible when the camera is closer to the ball than this, and fade in over
+/// `LABEL_FADE_DISTANCE` beyond it.
+const LABEL_HIDE_DISTANCE: f32 = 0.3;
+const LABEL_FADE_DISTANCE: f32 = 0.3;
+
+/// The in-game HUD and short-lived toast messages.
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, (setup_hud, setup_jump_meter))
+            .add_systems(
+                Update,
+                (
+                    update_hud,
+                    update_jump_meter,
+                    (spawn_ball_labels, update_ball_labels).chain(),
+                    show_stroke_toasts,
+                    fade_toasts,
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+struct HudText;
+
+/// Shown while the current player's ball can jump.
+#[derive(Component)]
+struct JumpMeter;
+
+#[derive(Component)]
+struct JumpMeterFill;
+
+/// Shows whose ball it is, floating above it.
+#[derive(Component)]
+struct BallLabel {
+    ball: Entity,
+}
+
+#[derive(Component)]
+struct Toast {
+    timer: Timer,
+}
+
+fn setup_hud(mut commands: Commands) {
+    commands.spawn((
+        HudText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+fn setup_jump_meter(mut commands: Commands) {
+    commands
+        .spawn((
+            JumpMeter,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.0),
+                    left: Val::Px(10.0),
+                    width: Val::Px(160.0),
+                    height: Val::Px(16.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                JumpMeterFill,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.3, 0.9, 0.4).into(),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn update_hud(
+    mut q_text: Query<(&mut Text, &mut Visibility), With<HudText>>,
+    q_ball: Query<(&Ball, &BallState, &ShootSettings, Option<&Putting>)>,
+    game_state: Res<GameState>,
+    current_level: Res<CurrentLevel>,
+    locale: Res<Locale>,
+) {
+    let Ok((mut text, mut visibility)) = q_text.get_single_mut() else {
+        return;
+    };
+
+    let aiming_ball = q_ball.iter().find(|(ball, state, _, _)| {
+        ball.player_id == game_state.current_player && **state == BallState::Aiming
+    });
+
+    if let Some((ball, _, shoot, putting)) = aiming_ball {
+        let spin = match shoot.spin {
+            Some(BallSpin::Left(strength)) => locale.format(
+                "hud.spin_left",
+                &[("percent", &format!("{:.0}", strength * 100.0))],
+            ),
+            Some(BallSpin::Right(strength)) => locale.format(
+                "hud.spin_right",
+                &[("percent", &format!("{:.0}", strength * 100.0))],
+            ),
+            None => locale.get("hud.spin_none").to_string(),
+        };
+        text.sections[0].value = locale.format(
+            "hud.aiming",
+            &[
+                ("player", &(ball.player_id + 1)),
+                ("stroke", &(ball.hits + 1)),
+                ("par", &current_level.0.par),
+                (
+                    "coins",
+                    &game_state.players[ball.player_id as usize].pickups,
+                ),
+                ("angle", &format!("{:.1}", shoot.angle.to_degrees())),
+                (
+                    "power",
+                    // Putting changes the power in finer steps
+                    &if putting.is_some() {
+                        format!("{:.2}", shoot.power)
+                    } else {
+                        format!("{:.1}", shoot.power)
+                    },
+                ),
+                ("spin", &spin),
+            ],
+        );
+        if putting.is_some() {
+            text.sections[0].value += "\n";
+            text.sections[0].value += locale.get("hud.putting");
+        }
+        *visibility = Visibility::Inherited;
+    } else {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_stroke_toasts(
+    mut commands: Commands,
+    mut strokes: EventReader<StrokeAdded>,
+    locale: Res<Locale>,
+) {
+    for stroke in strokes.iter() {
+        let message = if stroke.penalty {
+            locale.format("hud.penalty_stroke", &[("player", &(stroke.player_id + 1))])
+        } else {
+            locale.get("hud.stroke").to_string()
+        };
+        spawn_toast(&mut commands, message, Color::YELLOW, 0);
+    }
+}
+
+/// Shows a message in the middle of the screen that fades out after a moment.
+///
+/// Toasts with a higher `row` are placed further down, so that they don't overlap.
+pub fn spawn_toast(commands: &mut Commands, message: String, color: Color, row: u32) {
+    commands.spawn((
+        Toast {
+            timer: Timer::from_seconds(1.5, TimerMode::Once),
+        },
+        TextBundle::from_section(
+            message,
+            TextStyle {
+                font_size: 32.0,
+                color,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0 + row as f32 * 40.0),
+            left: Val::Percent(45.0),
+            ..default()
+        }),
+    ));
+}
+
+fn fade_toasts(
+    mut commands: Commands,
+    mut q_toast: Query<(Entity, &mut Toast, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (entity, mut toast, mut text) in q_toast.iter_mut() {
+        toast.timer.tick(time.delta());
+        if toast.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let alpha = toast.timer.percent_left();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+fn update_jump_meter(
+    mut q_meter: Query<&mut Visibility, With<JumpMeter>>,
+    mut q_fill: Query<&mut Style, With<JumpMeterFill>>,
+    q_ball: Query<(&Ball, &BallState, &Jump)>,
+    game_state: Res<GameState>,
+) {
+    let jump = q_ball
+        .iter()
+        .find(|(ball, _, _)| ball.player_id == game_state.current_player)
+        .filter(|(_, state, jump)| jump.can_jump(**state))
+        .map(|(_, _, jump)| jump);
+
+    for mut visibility in q_meter.iter_mut() {
+        *visibility = if jump.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for mut style in q_fill.iter_mut() {
+        style.width = Val::Percent(jump.map_or(0.0, |jump| jump.charge * 100.0));
+    }
+}
+
+/// Moves a UI node so that it is centered just above `position` in the world, hiding it when
+/// that point is off screen.
+pub fn place_above_in_world(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    position: Vec3,
+    node: &Node,
+    ui_scale: &UiScale,
+    style: &mut Style,
+    visibility: &mut Visibility,
+) {
+    let Some(screen) = camera.world_to_viewport(camera_transform, position) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    // Positions in the style are scaled along with the rest of the UI
+    let scale = ui_scale.scale as f32;
+    let size = node.size();
+    style.left = Val::Px((screen.x - size.x / 2.0) / scale);
+    style.top = Val::Px((screen.y - size.y) / scale);
+    *visibility = Visibility::Inherited;
+}
+
+fn spawn_ball_labels(
+    mut commands: Commands,
+    q_ball: Query<(Entity, &Ball, &BallColor), Added<Ball>>,
+    locale: Res<Locale>,
+) {
+    for (entity, ball, color) in q_ball.iter() {
+        commands.spawn((
+            LevelEntity,
+            BallLabel { ball: entity },
+            TextBundle::from_section(
+                locale.format("hud.player", &[("player", &(ball.player_id + 1))]),
+                TextStyle {
+                    font_size: 18.0,
+                    color: color.0,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            }),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+fn update_ball_labels(
+    mut commands: Commands,
+    mut q_label: Query<(
+        Entity,
+        &BallLabel,
+        &mut Text,
+        &mut Style,
+        &mut Visibility,
+        &Node,
+    )>,
+    q_ball: Query<&GlobalTransform, With<Ball>>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<CameraController>>,
+    ui_scale: Res<UiScale>,
+) {
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+
+    for (entity, label, mut text, mut style, mut visibility, node) in q_label.iter_mut() {
+        let Ok(ball_transform) = q_ball.get(label.ball) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        let position = ball_transform.translation() + Vec3::Y * LABEL_HEIGHT;
+        place_above_in_world(
+            camera,
+            camera_transform,
+            position,
+            node,
+            &ui_scale,
+            &mut style,
+            &mut visibility,
+        );
+
+        // The label would only be in the way when looking at the ball up close
+        let distance = camera_transform.translation().distance(position);
+        let alpha = ((distance - LABEL_HIDE_DISTANCE) / LABEL_FADE_DISTANCE).clamp(0.0, 1.0);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
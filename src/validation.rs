@@ -0,0 +1,188 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    level::{nearest_tile, tile_center, Direction, LanePart, Level, TeeZone},
+    locale::Locale,
+};
+
+type Pos = (i32, i32, i32);
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Left,
+    Direction::Down,
+    Direction::Right,
+];
+
+/// Something in a course that is likely a mistake.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// There is no floor under the tee, so balls fall out right away.
+    NoTee,
+    NoHole,
+    /// No path of floor leads from the tee to this hole.
+    UnreachableHole(Pos),
+    /// The end of a tube leads out of the course, where no walls will be added.
+    OpenBorder(Pos),
+    /// Several tiles that are not walls take up the same place.
+    Overlap(Pos),
+}
+
+impl Problem {
+    pub fn describe(&self, locale: &Locale) -> String {
+        let at = |key: &str, (x, y, z): Pos| locale.format(key, &[("x", &x), ("y", &y), ("z", &z)]);
+        match *self {
+            Problem::NoTee => locale.get("validation.no_tee").to_string(),
+            Problem::NoHole => locale.get("validation.no_hole").to_string(),
+            Problem::UnreachableHole(pos) => at("validation.unreachable_hole", pos),
+            Problem::OpenBorder(pos) => at("validation.open_border", pos),
+            Problem::Overlap(pos) => at("validation.overlap", pos),
+        }
+    }
+}
+
+/// Checks the shared lane of a level as authored, i.e. before walls are added around it.
+pub fn validate(level: &Level) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let mut parts: HashMap<Pos, Vec<LanePart>> = HashMap::new();
+    for &(pos, part) in level.lanes.0.iter() {
+        parts.entry(pos).or_default().push(part);
+    }
+    let mut overlaps: Vec<Pos> = parts
+        .iter()
+        .filter(|(_, parts)| {
+            parts
+                .iter()
+                .filter(|p| !matches!(p, LanePart::Wall(_)))
+                .count()
+                > 1
+        })
+        .map(|(&pos, _)| pos)
+        .collect();
+    overlaps.sort();
+    problems.extend(overlaps.into_iter().map(Problem::Overlap));
+
+    let terrain: HashSet<Pos> = level.terrain.iter().flat_map(|t| t.tiles()).collect();
+    let passable = |pos: &Pos| {
+        terrain.contains(pos)
+            || parts
+                .get(pos)
+                .is_some_and(|parts| parts.iter().any(|p| p.is_floor() || p.tube().is_some()))
+    };
+    let has_part = |pos: &Pos, part: LanePart| parts.get(pos).is_some_and(|p| p.contains(&part));
+    let tube_at = |pos: &Pos| {
+        parts
+            .get(pos)
+            .and_then(|parts| parts.iter().find_map(|p| p.tube()))
+    };
+
+    // Where a ball can roll to from a tile: the tiles next to it at the same height, or a level
+    // above or below, unless a wall is in the way or a tube leads elsewhere
+    let along_tube = |pos: &Pos, dir: Direction| {
+        tube_at(pos).map_or(true, |(_, tube_dir)| same_axis(dir, tube_dir))
+    };
+    let neighbours = |pos @ (x, y, z): Pos| {
+        let mut next = Vec::new();
+        for dir in DIRECTIONS {
+            if !along_tube(&pos, dir) || has_part(&pos, LanePart::Wall(dir)) {
+                continue;
+            }
+            let step = dir.vector();
+            for dy in -1..=1 {
+                let to = (x + step.x as i32, y + dy, z + step.z as i32);
+                if passable(&to)
+                    && along_tube(&to, dir)
+                    && !has_part(&to, LanePart::Wall(opposite(dir)))
+                {
+                    next.push(to);
+                }
+            }
+        }
+        next
+    };
+
+    let mut tees = Vec::new();
+    let zones = if level.tee_zones.is_empty() {
+        vec![TeeZone::default()]
+    } else {
+        level.tee_zones.clone()
+    };
+    for zone in zones {
+        for (u, v) in [(0.5, 0.5), (0.1, 0.1), (0.9, 0.1), (0.1, 0.9), (0.9, 0.9)] {
+            let point = zone.point(u, v);
+            let (x, _, z) = nearest_tile(point);
+            // The highest floor below where balls are dropped
+            let tee = parts
+                .keys()
+                .chain(terrain.iter())
+                .filter(|&&(tx, ty, tz)| {
+                    (tx, tz) == (x, z)
+                        && tile_center(tx, ty, tz).y < point.y
+                        && passable(&(tx, ty, tz))
+                })
+                .max_by_key(|&&(_, ty, _)| ty);
+            tees.extend(tee.copied());
+        }
+    }
+    if tees.is_empty() {
+        problems.push(Problem::NoTee);
+    }
+
+    let mut reached: HashSet<Pos> = tees.iter().copied().collect();
+    let mut queue: VecDeque<Pos> = tees.into_iter().collect();
+    while let Some(pos) = queue.pop_front() {
+        for next in neighbours(pos) {
+            if reached.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let holes: Vec<Pos> = level
+        .holes()
+        .into_iter()
+        .filter(|(_, owner)| owner.is_none())
+        .map(|(hole, _)| hole.position)
+        .collect();
+    if holes.is_empty() {
+        problems.push(Problem::NoHole);
+    } else if !reached.is_empty() {
+        problems.extend(
+            holes
+                .into_iter()
+                .filter(|hole| !reached.contains(hole))
+                .map(Problem::UnreachableHole),
+        );
+    }
+
+    let mut open_ends: Vec<Pos> = parts
+        .keys()
+        .filter_map(|&pos| Some((pos, tube_at(&pos)?)))
+        .filter(|&((x, y, z), (_, tube_dir))| {
+            [tube_dir, opposite(tube_dir)].into_iter().any(|dir| {
+                let step = dir.vector();
+                let (nx, nz) = (x + step.x as i32, z + step.z as i32);
+                (-1..=1).all(|dy| !passable(&(nx, y + dy, nz)))
+            })
+        })
+        .map(|(pos, _)| pos)
+        .collect();
+    open_ends.sort();
+    problems.extend(open_ends.into_iter().map(Problem::OpenBorder));
+
+    problems
+}
+
+fn opposite(dir: Direction) -> Direction {
+    match dir {
+        Direction::Up => Direction::Down,
+        Direction::Left => Direction::Right,
+        Direction::Down => Direction::Up,
+        Direction::Right => Direction::Left,
+    }
+}
+
+fn same_axis(a: Direction, b: Direction) -> bool {
+    a == b || a == opposite(b)
+}